@@ -2,17 +2,20 @@ extern crate proc_macro;
 
 use std::{collections::VecDeque, iter};
 
-use proc_macro2::Span;
-use quote::{format_ident, quote, ToTokens};
+use proc_macro2::{Delimiter, Group, Span, TokenStream as TokenStream2, TokenTree};
+use quote::{format_ident, quote, quote_spanned, ToTokens};
 use syn::{
-    parse::{Parse, ParseStream},
-    parse_macro_input,
+    ext::IdentExt,
+    parse::{Parse, ParseStream, Parser},
+    parse_macro_input, parse_quote,
     punctuated::{Pair, Punctuated},
+    spanned::Spanned,
     token::{Brace, Comma, FatArrow, Paren},
-    visit::Visit,
+    visit::{self, Visit},
     visit_mut::{self, VisitMut},
-    Arm, Expr, ExprLit, ExprMatch, ExprTuple, Ident, Lit, LitStr, Local, Pat, PatIdent, PatLit,
-    PatSlice, PatTuple, PatWild, Stmt, Token,
+    Arm, Expr, ExprLit, ExprMatch, ExprTuple, ExprUnary, FieldPat, FnArg, Ident, ItemFn, Lit,
+    LitInt, LitStr, Local, Macro, MacroDelimiter, Member, Pat, PatIdent, PatLit, PatMacro, PatOr,
+    PatRange, PatSlice, PatTuple, PatTupleStruct, PatType, PatWild, Stmt, Token, Type, UnOp,
 };
 
 #[proc_macro]
@@ -22,28 +25,71 @@ pub fn assert_body_matches(input: proc_macro::TokenStream) -> proc_macro::TokenS
     proc_macro::TokenStream::from(input.expand().to_token_stream())
 }
 
-impl BodyMatchCall {
-    fn expand(mut self) -> Stmt {
-        // We need to do three things:
-        //
-        //   - extract the identifier that are brought in scope by the macro
-        //     call,
-        //
-        //   - alter the pattern so that string literals allow to match String,
-        //
-        //   - transform the pattern in a nested match expression, with one
-        //     level of nesting for each slice pattern.
+#[proc_macro]
+pub fn body_matches(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as BodyMatchCall);
+
+    proc_macro::TokenStream::from(input.expand_result().to_token_stream())
+}
+
+#[proc_macro_attribute]
+pub fn test(
+    attr: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let args = parse_macro_input!(attr as TestArgs);
+    let function = parse_macro_input!(item as ItemFn);
+
+    let expanded = TestFunction::new(args, function)
+        .map(TestFunction::expand)
+        .unwrap_or_else(syn::Error::into_compile_error);
+
+    proc_macro::TokenStream::from(expanded)
+}
+
+#[proc_macro]
+pub fn pattern(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as NamedPatterns);
+
+    proc_macro::TokenStream::from(input.expand())
+}
+
+#[proc_macro]
+pub fn cases(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as CasesCall);
+
+    let expanded = input
+        .expand()
+        .unwrap_or_else(syn::Error::into_compile_error);
+
+    proc_macro::TokenStream::from(expanded)
+}
 
+impl BodyMatchCall {
+    fn expand(self) -> Stmt {
         let let_token = Token![let](Span::call_site());
         let equal = Token![=](Span::call_site());
         let semi_token = Token![;](Span::call_site());
 
-        let (bindings, return_expr) =
-            BindingPatternsExtractor::new(&self.pat).expand_bindings_and_return_expr();
-        let guard_condition = StringLiteralPatternModifier::new(&mut self.pat).expand_guard_expr();
-        let match_expr =
-            SlicePatternModifier::new(self.value, self.pat, guard_condition, return_expr.into())
-                .expand();
+        let (bindings, match_expr) = self.expand_match(
+            |return_expr| {
+                Expr::Verbatim(quote! {
+                    {
+                        ::restest::__private::match_succeeded();
+                        #return_expr
+                    }
+                })
+            },
+            |bindings| {
+                // Without bindings, there is no value to produce, so the
+                // failure can be accumulated in a soft assertion scope.
+                if bindings.elems.is_empty() {
+                    Expr::Verbatim(quote! { ::restest::__private::match_failed_soft() })
+                } else {
+                    Expr::Verbatim(quote! { ::restest::__private::match_failed() })
+                }
+            },
+        );
 
         let pat = bindings.into();
         let match_expr = Box::new(match_expr.into());
@@ -56,19 +102,257 @@ impl BodyMatchCall {
             semi_token,
         })
     }
+
+    /// Expands to an expression that evaluates to either the bindings, or a
+    /// `MatchError` if the value does not match the pattern.
+    fn expand_result(self) -> Expr {
+        let pattern = self.pat.to_token_stream().to_string();
+        let failure_expr = Expr::Verbatim(quote! {
+            ::std::result::Result::Err(::restest::pattern::MatchError::new(#pattern))
+        });
+
+        let (_, match_expr) = self.expand_match(
+            |return_expr| Expr::Verbatim(quote! { ::std::result::Result::Ok(#return_expr) }),
+            |_| failure_expr,
+        );
+
+        match_expr.into()
+    }
+
+    fn expand_match(
+        mut self,
+        wrap_return_expr: impl FnOnce(Expr) -> Expr,
+        failure_expr: impl FnOnce(&PatTuple) -> Expr,
+    ) -> (PatTuple, ExprMatch) {
+        if let Pat::Or(PatOr { cases, .. }) = &self.pat {
+            if !NativePatternChecker::is_native(&self.pat) {
+                let cases = cases.iter().cloned().collect();
+                return Self::expand_alternatives(
+                    self.value,
+                    cases,
+                    wrap_return_expr,
+                    failure_expr,
+                );
+            }
+        }
+
+        // We need to do five things:
+        //
+        //   - move the alternatives that are not native patterns to the
+        //     guard, so that the next steps don't see them,
+        //
+        //   - encode the `map!` patterns as regular patterns, so that the
+        //     next steps can visit the patterns of their values,
+        //
+        //   - extract the identifier that are brought in scope by the macro
+        //     call,
+        //
+        //   - alter the pattern so that literals allow to match String and
+        //     untyped values,
+        //     and so that ranges and comparisons are checked in the guard,
+        //
+        //   - transform the pattern in a nested match expression, with one
+        //     level of nesting for each slice and map pattern.
+
+        let or_modifier = OrPatternModifier::new(&mut self.pat);
+        MapPatternModifier::alter_pattern(&mut self.pat);
+
+        let (bindings, return_expr) =
+            BindingPatternsExtractor::new(&self.pat).expand_bindings_and_return_expr();
+        let literal_guard = LiteralPatternModifier::new(&mut self.pat).expand_guard_expr();
+        let literal_guard = or_modifier.expand_guard_expr(literal_guard);
+        let guard_modifier = GuardPatternModifier::new(&mut self.pat);
+        let return_expr = guard_modifier.expand_return_expr(return_expr.into());
+        let guard_condition = guard_modifier.expand_guard_expr(literal_guard);
+        let return_expr = wrap_return_expr(return_expr);
+
+        let failure_expr = failure_expr(&bindings);
+        let slice_modifier =
+            SlicePatternModifier::new(self.value, self.pat, guard_condition, return_expr)
+                .with_failure_expr(failure_expr);
+
+        (bindings, slice_modifier.expand())
+    }
+}
+
+impl BodyMatchCall {
+    /// Expands a pattern whose alternatives are not all native patterns.
+    ///
+    /// Each alternative is checked by a nested `body_matches` call, until one
+    /// of them matches. The alternatives must bring the same values in scope,
+    /// which are returned in the order of the first alternative. Every
+    /// alternative but the last one is checked on a clone of the value.
+    ///
+    /// # Example
+    ///
+    /// The following pattern:
+    ///
+    /// ```none
+    /// { status: "cached", data } | { status: "fresh", data, .. }
+    /// ```
+    ///
+    /// Will be transformed to:
+    ///
+    /// ```none
+    /// match {
+    ///     let __restest__value = <expr>;
+    ///
+    ///     if let Ok((data,)) = ::restest::body_matches!(
+    ///         ::std::clone::Clone::clone(&__restest__value),
+    ///         { status: "cached", data }
+    ///     ) {
+    ///         Some((data,))
+    ///     } else if let Ok((data,)) = ::restest::body_matches!(
+    ///         __restest__value,
+    ///         { status: "fresh", data, .. }
+    ///     ) {
+    ///         Some((data,))
+    ///     } else {
+    ///         None
+    ///     }
+    /// } {
+    ///     Some((data,)) => { /* final expression */ },
+    ///     None => { /* failure expression */ },
+    /// }
+    /// ```
+    fn expand_alternatives(
+        value: Expr,
+        cases: Vec<Pat>,
+        wrap_return_expr: impl FnOnce(Expr) -> Expr,
+        failure_expr: impl FnOnce(&PatTuple) -> Expr,
+    ) -> (PatTuple, ExprMatch) {
+        let names = cases
+            .iter()
+            .map(BindingPatternsExtractor::all_bindings)
+            .collect::<Vec<_>>();
+
+        let sorted = |names: &[Ident]| {
+            let mut names = names.iter().map(Ident::to_string).collect::<Vec<_>>();
+            names.sort();
+            names
+        };
+
+        if let Some((case, _)) = cases
+            .iter()
+            .zip(&names)
+            .find(|(_, case_names)| sorted(case_names) != sorted(&names[0]))
+        {
+            let err = syn::Error::new_spanned(
+                case,
+                "all the alternatives must bring the same values in scope",
+            )
+            .to_compile_error();
+
+            let (bindings, _) =
+                BindingPatternsExtractor::default().expand_bindings_and_return_expr();
+            return (bindings, parse_quote! { match () { _ => #err } });
+        }
+
+        let shared = &names[0];
+        let last = cases.len() - 1;
+        let checks = cases
+            .iter()
+            .zip(&names)
+            .enumerate()
+            .map(|(i, (case, names))| {
+                let value = if i == last {
+                    quote! { __restest__value }
+                } else {
+                    quote! { ::std::clone::Clone::clone(&__restest__value) }
+                };
+
+                quote! {
+                    if let ::std::result::Result::Ok(( #( #names, )* )) =
+                        ::restest::body_matches!(#value, #case)
+                    {
+                        ::std::option::Option::Some(( #( #shared, )* ))
+                    }
+                }
+            });
+
+        let (bindings, return_expr) = BindingPatternsExtractor {
+            bindings: shared.iter().collect(),
+        }
+        .expand_bindings_and_return_expr();
+        let return_expr = wrap_return_expr(return_expr.into());
+        let failure_expr = failure_expr(&bindings);
+
+        let match_expr = parse_quote! {
+            match {
+                let __restest__value = #value;
+
+                #( #checks else )* {
+                    ::std::option::Option::None
+                }
+            } {
+                ::std::option::Option::Some(#bindings) => #return_expr,
+                ::std::option::Option::None => #failure_expr,
+            }
+        };
+
+        (bindings, match_expr)
+    }
 }
 
 impl Parse for BodyMatchCall {
     fn parse(input: ParseStream) -> syn::Result<BodyMatchCall> {
+        let value = input.parse()?;
+        let _comma1 = input.parse()?;
+
+        let tokens = input.parse()?;
+        PatternValidator::check_tokens(&tokens)?;
+
+        let parse_pat = |input: ParseStream| -> syn::Result<(Pat, Option<Token![,]>)> {
+            Ok((parse_alternatives(input)?, input.parse()?))
+        };
+        let (pat, _comma2) = parse_pat.parse2(UntypedObjectRewriter::rewrite(tokens))?;
+        PatternValidator::check(&pat)?;
+
         Ok(BodyMatchCall {
-            value: input.parse()?,
-            _comma1: input.parse()?,
-            pat: input.parse()?,
-            _comma2: input.parse()?,
+            value,
+            _comma1,
+            pat,
+            _comma2,
         })
     }
 }
 
+/// Returns a span located at `span`, for the code generated from the tokens
+/// at `span`.
+///
+/// Errors in the generated code then point at these tokens, while the
+/// generated code is still ignored by the lints, unlike user code.
+fn generated(span: Span) -> Span {
+    Span::call_site().located_at(span)
+}
+
+/// Parses a pattern which may have several alternatives (`a | b`), as `Pat`
+/// only parses the nested ones.
+fn parse_alternatives(input: ParseStream) -> syn::Result<Pat> {
+    let peek_vert = |input: ParseStream| input.peek(Token![|]) && !input.peek(Token![||]);
+
+    let leading_vert = input.parse::<Option<Token![|]>>()?;
+    let pat = input.parse()?;
+
+    if leading_vert.is_none() && !peek_vert(input) {
+        return Ok(pat);
+    }
+
+    let mut cases = Punctuated::new();
+    cases.push_value(pat);
+
+    while peek_vert(input) {
+        cases.push_punct(input.parse()?);
+        cases.push_value(input.parse()?);
+    }
+
+    Ok(Pat::Or(PatOr {
+        attrs: Vec::new(),
+        leading_vert,
+        cases,
+    }))
+}
+
 struct BodyMatchCall {
     value: Expr,
     _comma1: Token![,],
@@ -76,521 +360,2880 @@ struct BodyMatchCall {
     _comma2: Option<Token![,]>,
 }
 
-/// Allows to extract a list of all the identifiers that are brought in scope
-/// by a given pattern.
-///
-/// This allows us to generate the final body of the innermost match pattern
-/// that `assert_body_matches` will expand to.
-///
-/// # How
+/// The arguments of the `test` attribute: `context = <expr>`, and any number
+/// of `depends_on = "<test>"`.
+struct TestArgs {
+    context: Option<Expr>,
+    depends_on: Vec<LitStr>,
+}
+
+impl Parse for TestArgs {
+    fn parse(input: ParseStream) -> syn::Result<TestArgs> {
+        let mut args = TestArgs {
+            context: None,
+            depends_on: Vec::new(),
+        };
+
+        while !input.is_empty() {
+            let name = input.parse::<Ident>()?;
+            input.parse::<Token![=]>()?;
+
+            if name == "context" && args.context.is_none() {
+                args.context = Some(input.parse()?);
+            } else if name == "depends_on" {
+                args.depends_on.push(input.parse()?);
+            } else if name == "context" {
+                return Err(syn::Error::new(name.span(), "duplicate `context` argument"));
+            } else {
+                return Err(syn::Error::new(
+                    name.span(),
+                    "unknown argument, expected `context = <expr>` or `depends_on = \"<test>\"`",
+                ));
+            }
+
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(args)
+    }
+}
+
+/// An `async` test function, turned into a regular `#[test]` function which
+/// runs it in a runtime.
 ///
-/// [`BindingPatternsExtractor`] implements [`Visit`], which allows to
-/// recursively visit the entire AST. We use this to visit a given pattern
-/// and ensure extract every binding pattern.
+/// Each dependency is passed as a function calling the dependency test
+/// function, so that the dependency can be run first even if it is filtered
+/// out by `cargo test`.
 ///
 /// # Example
 ///
-/// The following pattern:
+/// The following function:
 ///
 /// ```none
-/// Foo {
-///     field,
-///     inner: Bar {
-///         value: 42,
-///         other_value,
-///     },
-///     final_value,
+/// #[restest::test(depends_on = "post_user")]
+/// async fn get_user(context: Context) {
+///     // ...
 /// }
 /// ```
 ///
-/// Brings the following identifiers in scope:
-///   - `field`,
-///   - `other_value`,
-///   - `final_value`.
-#[derive(Default)]
-struct BindingPatternsExtractor<'pat> {
-    bindings: Vec<&'pat Ident>,
+/// Will be transformed to:
+///
+/// ```none
+/// #[::core::prelude::v1::test]
+/// fn get_user() {
+///     ::restest::__private::run_test(
+///         ::core::module_path!(),
+///         "get_user",
+///         &[("post_user", || ::restest::__private::run_dependency(post_user))],
+///         ::restest::Context::from_env(),
+///         |context: Context| async move {
+///             // ...
+///         },
+///     )
+/// }
+/// ```
+struct TestFunction {
+    context: Expr,
+    dependencies: Vec<(LitStr, syn::Path)>,
+    function: ItemFn,
+    parameter: Option<PatType>,
 }
 
-impl<'pat> BindingPatternsExtractor<'pat> {
-    fn new(pat: &'pat Pat) -> BindingPatternsExtractor<'pat> {
-        let mut this = BindingPatternsExtractor::default();
-        this.visit_pat(pat);
-        this
-    }
-
-    fn expand_bindings_and_return_expr(self) -> (PatTuple, ExprTuple) {
-        let paren_token = Paren {
-            span: Span::call_site(),
-        };
-
-        let bindings = self.mk_bindings(paren_token);
-        let return_expr = self.mk_return_expr(paren_token);
-
-        (bindings, return_expr)
-    }
-
-    fn mk_bindings(&self, paren_token: Paren) -> PatTuple {
-        let elems = self
-            .bindings
-            .iter()
-            .copied()
-            .cloned()
-            .map(Self::mk_ident_pat)
-            .map(|i| Pair::Punctuated(i, Comma::default()))
-            .collect();
+impl TestFunction {
+    fn new(args: TestArgs, mut function: ItemFn) -> syn::Result<TestFunction> {
+        let sig = &function.sig;
 
-        PatTuple {
-            attrs: Vec::new(),
-            paren_token,
-            elems,
+        if sig.asyncness.is_none() {
+            return Err(syn::Error::new_spanned(
+                sig.fn_token,
+                "the `async` keyword is missing from the function declaration",
+            ));
         }
-    }
 
-    fn mk_return_expr(self, paren_token: Paren) -> ExprTuple {
-        let elems = self
-            .bindings
-            .into_iter()
-            .cloned()
-            .map(Self::mk_ident_expr)
-            .map(|i| Pair::Punctuated(i, Comma::default()))
-            .collect::<Punctuated<_, _>>();
+        if !sig.generics.params.is_empty() || sig.generics.where_clause.is_some() {
+            return Err(syn::Error::new_spanned(
+                &sig.generics,
+                "test functions can not be generic",
+            ));
+        }
 
-        ExprTuple {
-            attrs: Vec::new(),
-            paren_token,
-            elems,
+        if sig.inputs.len() > 1 {
+            return Err(syn::Error::new_spanned(
+                &sig.inputs,
+                "test functions take at most one argument, the `Context`",
+            ));
         }
-    }
 
-    fn mk_ident_expr(ident: Ident) -> Expr {
-        Expr::Verbatim(quote! { #ident })
-    }
+        let parameter = match function.sig.inputs.pop().map(Pair::into_value) {
+            Some(FnArg::Typed(parameter)) => Some(parameter),
+            Some(receiver) => {
+                return Err(syn::Error::new_spanned(
+                    receiver,
+                    "test functions can not take `self`",
+                ))
+            }
+            None => None,
+        };
 
-    fn mk_ident_pat(ident: Ident) -> Pat {
-        Pat::Ident(PatIdent {
-            attrs: Vec::new(),
-            by_ref: None,
-            mutability: None,
-            ident,
-            subpat: None,
+        let context = args
+            .context
+            .unwrap_or_else(|| parse_quote! { ::restest::Context::from_env() });
+
+        let dependencies = args
+            .depends_on
+            .into_iter()
+            .map(|name| Ok((name.clone(), name.parse()?)))
+            .collect::<syn::Result<_>>()?;
+
+        Ok(TestFunction {
+            context,
+            dependencies,
+            function,
+            parameter,
         })
     }
-}
 
-impl<'pat> Visit<'pat> for BindingPatternsExtractor<'pat> {
-    fn visit_pat_ident(&mut self, i: &'pat PatIdent) {
-        self.bindings.push(&i.ident);
+    fn expand(self) -> TokenStream2 {
+        let TestFunction {
+            context,
+            dependencies,
+            function,
+            parameter,
+        } = self;
+
+        let ItemFn {
+            attrs,
+            vis,
+            sig,
+            block,
+        } = function;
+
+        let name = sig.ident.to_string();
+        let ident = sig.ident;
+        let output = sig.output;
+
+        let parameter = match parameter {
+            Some(parameter) => parameter.into_token_stream(),
+            None => quote! { _: ::restest::Context },
+        };
+
+        let dependencies = dependencies.into_iter().map(|(name, path)| {
+            quote! { (#name, || ::restest::__private::run_dependency(#path)) }
+        });
+
+        quote! {
+            #(#attrs)*
+            #[::core::prelude::v1::test]
+            #vis fn #ident() #output {
+                ::restest::__private::run_test(
+                    ::core::module_path!(),
+                    #name,
+                    &[#(#dependencies),*],
+                    #context,
+                    |#parameter| async move #block,
+                )
+            }
+        }
     }
 }
 
-/// Allows to perform pattern matching over `String` using literals.
-///
-/// To do so, we need to alter the pattern and change every instance of string
-/// literal pattern into a binding and check for equality in the final guard.
+/// Patterns defined once, and referenced in other patterns with
+/// `pattern!(NAME)`.
 ///
-/// # How
-///
-/// [`StringLiteralPatternModifier`] implements [`VisitMut`], which allows us to
-/// recursively visit and alter the AST. Here, we use this to visit and alter a
-/// given pattern. Specifically, we change all the string literal pattern to be
-/// bindings of a given identifier and keep track of which identifier
-/// corresponds to which string literal.
+/// Each pattern becomes a `macro_rules!` macro, which checks a value against
+/// the pattern with a nested `body_matches` call. The macro is re-exported
+/// under the name of the pattern, so that it can be imported in other
+/// modules.
 ///
 /// # Example
 ///
-/// The following pattern:
+/// The following definition:
 ///
 /// ```none
-/// Foo {
-///     field: "string literal 1",
-///     inner: Bar {
-///         other_field: "string literal 2",
-///     },
-///     final: "string literal 3",
+/// pattern! {
+///     USER_SHAPE = User { id: uuid!(), .. };
 /// }
 /// ```
 ///
 /// Will be transformed to:
 ///
 /// ```none
-/// Foo {
-///     field: __restest__str_0,
-///     inner: Bar {
-///         other_field: __restest__str_1,
-///     },
-///     final: __restest__str_2,
+/// #[allow(unused_macros)]
+/// macro_rules! USER_SHAPE {
+///     ($value:expr) => {
+///         ::restest::body_matches!($value, User { id: uuid!(), .. }).is_ok()
+///     };
 /// }
-/// ```
 ///
-/// And will generate the following conditions:
-///   - `__restest__str_0 == "string literal 1"`,
-///   - `__restest__str_1 == "string literal 2"`,
-///   - `__restest__str_2 == "string literal 3"`.
-#[derive(Default)]
-struct StringLiteralPatternModifier {
-    conditions: Vec<(Ident, LitStr)>,
+/// #[allow(unused_imports)]
+/// pub(crate) use USER_SHAPE;
+/// ```
+struct NamedPatterns {
+    patterns: Vec<(Ident, Pat)>,
 }
 
-impl StringLiteralPatternModifier {
-    fn new(pat: &mut Pat) -> StringLiteralPatternModifier {
-        let mut this = StringLiteralPatternModifier::default();
+impl Parse for NamedPatterns {
+    fn parse(input: ParseStream) -> syn::Result<NamedPatterns> {
+        let mut patterns = Vec::new();
 
-        this.visit_pat_mut(pat);
-        this
-    }
+        while !input.is_empty() {
+            let name = input.parse::<Ident>()?;
+            input.parse::<Token![=]>()?;
 
-    fn expand_guard_expr(self) -> Expr {
-        let (names, values): (Vec<_>, Vec<_>) = self.conditions.into_iter().unzip();
-        Expr::Verbatim(quote! {
-            true #( && #names == #values )*
-        })
-    }
+            let mut tokens = TokenStream2::new();
+            while !input.is_empty() && !input.peek(Token![;]) {
+                tokens.extend(iter::once(input.parse::<TokenTree>()?));
+            }
+            PatternValidator::check_tokens(&tokens)?;
 
-    fn add_literal_pattern(&mut self, lit: LitStr) -> Ident {
-        let name = self.mk_ident();
-        self.conditions.push((name.clone(), lit));
-        name
-    }
+            let pat = parse_alternatives.parse2(UntypedObjectRewriter::rewrite(tokens))?;
+            PatternValidator::check(&pat)?;
 
-    fn alter_pattern(pat: &mut Pat, ident: Ident) {
-        *pat = Pat::Ident(PatIdent {
-            attrs: Vec::new(),
-            by_ref: None,
-            mutability: None,
-            ident,
-            subpat: None,
-        })
-    }
+            patterns.push((name, pat));
+
+            if !input.is_empty() {
+                input.parse::<Token![;]>()?;
+            }
+        }
 
-    fn mk_ident(&self) -> Ident {
-        format_ident!("__restest__str_{}", self.conditions.len())
+        Ok(NamedPatterns { patterns })
     }
 }
 
-impl VisitMut for StringLiteralPatternModifier {
-    fn visit_pat_mut(&mut self, pat: &mut Pat) {
-        match pat {
-            Pat::Lit(PatLit { expr, .. }) => match expr.as_ref() {
-                Expr::Lit(ExprLit {
-                    lit: Lit::Str(lit), ..
-                }) => {
-                    let ident = self.add_literal_pattern(lit.clone());
-                    Self::alter_pattern(pat, ident);
+impl NamedPatterns {
+    fn expand(self) -> TokenStream2 {
+        let definitions = self.patterns.into_iter().map(|(name, pat)| {
+            quote! {
+                #[allow(unused_macros)]
+                macro_rules! #name {
+                    ($value:expr) => {
+                        ::restest::body_matches!($value, #pat).is_ok()
+                    };
                 }
 
-                _ => visit_mut::visit_pat_mut(self, pat),
-            },
+                #[allow(unused_imports)]
+                pub(crate) use #name;
+            }
+        });
 
-            _ => visit_mut::visit_pat_mut(self, pat),
-        }
+        quote! { #(#definitions)* }
     }
 }
 
-/// Allows to encode and expand a match expression that accepts slices patterns
-/// for `Vec`.
-///
-/// # How
-///
-/// We use [`VisitMut`] to visit and alter the pattern. We transform every slice
-/// pattern into a binding of a unique identifier, and match on its content in
-/// an inner expression.
-///
-/// This results in multiple, nested match expressions, each of them matching
-/// over exactly one slice pattern.
+/// The maximum length of a test name derived from the inputs of a case.
+const MAX_CASE_NAME_LEN: usize = 48;
+
+/// A test body, and the inputs it is run with.
 ///
 /// # Example
 ///
-/// Given the following pattern:
+/// The following call:
 ///
 /// ```none
-/// [                   // (1)
-///     [a, 2, 3],          // (2)
-///     [b, 5, 6],          // (3)
-/// ]                   // (1)
-/// ```
-///
-/// In the first iteration, we only alter the slice pattern delimited by `(1)`.
-/// This will produce the following match expression:
-///
-/// ```none
-/// match <expr> {
-///     __restest__slice_0 => match __restest__slice_0[..] {
-///         [__restest__slice_1, __restest__slice_2] => { /* ... */ },
+/// cases! {
+///     async fn get_user(path: &str, status: StatusCode) {
+///         // ...
 ///     }
+///
+///     ("users/ghopper", StatusCode::OK),
+///     missing: ("users/nobody", StatusCode::NOT_FOUND),
 /// }
 /// ```
 ///
-/// Next iteration alters slice pattern `(2)` and leads us to:
+/// Will be transformed to:
 ///
 /// ```none
-/// match <expr> {
-///     __restest__slice_0 => match __restest__slice_0[..] {
-///         [__restest__slice_1, __restest__slice_2] => match __restest__slice_1[..] {
-///             [a, 2, 3] => { /* ... */ },
-///         }
-///     }
+/// #[allow(dead_code)]
+/// async fn get_user(path: &str, status: StatusCode) {
+///     // ...
 /// }
-/// ```
 ///
-/// The last iteration alters slice pattern `(3)` and expands to:
+/// mod get_user {
+///     use super::*;
 ///
-/// ```none
-/// match <expr> {
-///     __restest__slice_0 => match __restest__slice_0[..] {
-///         [__restest__slice_1, __restest__slice_2] => match __restest__slice_1[..] {
-///             [a, 2, 3] => match __restest__slice_2[..] {
-///                 [b, 5, 6] => { /* final expression */ }
+///     #[::core::prelude::v1::test]
+///     fn users_ghopper_statuscode_ok() {
+///         ::restest::__private::run_test(
+///             ::core::module_path!(),
+///             "get_user::users_ghopper_statuscode_ok",
+///             &[],
+///             ::restest::Context::from_env(),
+///             |_: ::restest::Context| async move {
+///                 super::get_user("users/ghopper", StatusCode::OK).await
 ///             },
-///         }
+///         )
 ///     }
+///
+///     // Same for `missing`.
 /// }
 /// ```
-struct SlicePatternModifier {
-    first_expr: Expr,
-    first_pat: Pat,
-    nested_matches: Vec<(Expr, Pat)>,
-    final_guard_condition: Expr,
-    return_expr: Expr,
+struct CasesCall {
+    function: ItemFn,
+    cases: Vec<Case>,
 }
 
-impl SlicePatternModifier {
-    fn new(
-        matched_expr: Expr,
-        pat: Pat,
-        final_guard_condition: Expr,
-        return_expr: Expr,
-    ) -> SlicePatternModifier {
-        let mut sub_slice_patterns = Vec::new();
+struct Case {
+    name: Option<Ident>,
+    inputs: Punctuated<Expr, Comma>,
+}
 
-        let mut replacer = SlicePatternReplacer::new();
-        let pat = replacer.alter_initial_pattern(pat);
+impl Parse for CasesCall {
+    fn parse(input: ParseStream) -> syn::Result<CasesCall> {
+        let function = input.parse::<ItemFn>()?;
+        let mut cases = Vec::new();
+
+        while !input.is_empty() {
+            let name = if input.peek(Ident) && input.peek2(Token![:]) {
+                let name = input.parse()?;
+                input.parse::<Token![:]>()?;
+                Some(name)
+            } else {
+                None
+            };
 
-        let mut unaltered_slice_patterns = VecDeque::from_iter(replacer.extracted_slice_patterns());
+            let content;
+            syn::parenthesized!(content in input);
+            let inputs = content.parse_terminated(Expr::parse)?;
 
-        while let Some((ident, pat)) = unaltered_slice_patterns.pop_front() {
-            let mut replacer = SlicePatternReplacer::new();
-            let expr = Self::mk_match_expr(ident);
-            let pat = replacer.alter_pat_slice(pat).into();
+            cases.push(Case { name, inputs });
 
-            sub_slice_patterns.push((expr, pat));
-            unaltered_slice_patterns.extend(replacer.extracted_slice_patterns());
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
         }
 
-        SlicePatternModifier {
-            first_expr: matched_expr,
-            first_pat: pat,
-            nested_matches: sub_slice_patterns,
-            final_guard_condition,
-            return_expr,
-        }
+        Ok(CasesCall { function, cases })
     }
+}
 
-    fn expand(self) -> ExprMatch {
-        let mut nesting = iter::once((self.first_expr, self.first_pat))
-            .chain(self.nested_matches)
-            .rev();
+impl CasesCall {
+    fn expand(self) -> syn::Result<TokenStream2> {
+        let CasesCall {
+            mut function,
+            cases,
+        } = self;
+
+        if function.sig.asyncness.is_none() {
+            return Err(syn::Error::new_spanned(
+                function.sig.fn_token,
+                "the `async` keyword is missing from the function declaration",
+            ));
+        }
 
-        let (innermost_expr, innermost_pat) = nesting.next().unwrap();
+        if cases.is_empty() {
+            return Err(syn::Error::new_spanned(
+                &function.sig.ident,
+                "at least one case is required",
+            ));
+        }
 
-        let guard = (
-            <Token![if]>::default(),
-            Box::new(self.final_guard_condition),
-        );
+        // The attributes, such as `#[ignore]`, apply to every case.
+        let attrs = std::mem::take(&mut function.attrs);
+        let ident = &function.sig.ident;
+        let output = &function.sig.output;
+
+        let mut names = Vec::<Ident>::new();
+        for case in &cases {
+            let name = match &case.name {
+                Some(name) => name.clone(),
+                None => {
+                    let base = case_name(&case.inputs);
+                    let mut name = base.clone();
+                    let mut suffix = 2usize;
+
+                    while names.iter().any(|other| other == &name) {
+                        name = format_ident!("{}_{}", base, suffix);
+                        suffix += 1;
+                    }
+
+                    name
+                }
+            };
 
-        let arms = vec![
-            Self::mk_arm(innermost_pat, Some(guard), self.return_expr),
-            Self::catchall_arm(),
-        ];
+            names.push(name);
+        }
 
-        let innermost_match = ExprMatch {
-            attrs: Vec::new(),
-            match_token: <Token![match]>::default(),
-            expr: Box::new(innermost_expr),
-            brace_token: Brace::default(),
-            arms,
-        };
+        let tests = cases.iter().zip(&names).map(|(case, name)| {
+            let test_name = format!("{}::{}", ident, name);
+            let inputs = &case.inputs;
+
+            quote! {
+                #(#attrs)*
+                #[::core::prelude::v1::test]
+                fn #name() #output {
+                    ::restest::__private::run_test(
+                        ::core::module_path!(),
+                        #test_name,
+                        &[],
+                        ::restest::Context::from_env(),
+                        |_: ::restest::Context| async move {
+                            super::#ident(#inputs).await
+                        },
+                    )
+                }
+            }
+        });
+
+        Ok(quote! {
+            #[allow(dead_code)]
+            #function
 
-        nesting.fold(innermost_match, Self::nest_match)
+            mod #ident {
+                use super::*;
+
+                #(#tests)*
+            }
+        })
     }
+}
 
-    fn nest_match(inner: ExprMatch, (expr, pat): (Expr, Pat)) -> ExprMatch {
-        let match_token = <Token![match]>::default();
-        let expr = Box::new(expr);
-        let brace_token = Brace::default();
-        let arms = vec![Self::mk_arm(pat, None, inner.into()), Self::catchall_arm()];
+/// Derives the name of a test from the inputs of a case, such as
+/// `users_ghopper_statuscode_ok` for `("users/ghopper", StatusCode::OK)`.
+fn case_name(inputs: &Punctuated<Expr, Comma>) -> Ident {
+    let mut name = String::new();
 
-        ExprMatch {
-            attrs: Vec::new(),
-            match_token,
-            expr,
-            brace_token,
-            arms,
+    for c in inputs.to_token_stream().to_string().chars() {
+        if c.is_ascii_alphanumeric() {
+            name.push(c.to_ascii_lowercase());
+        } else if !name.is_empty() && !name.ends_with('_') {
+            name.push('_');
         }
     }
 
-    fn mk_arm(pat: Pat, guard: Option<(Token![if], Box<Expr>)>, body: Expr) -> Arm {
-        let body = Box::new(body);
-        Arm {
-            attrs: Vec::new(),
-            pat,
-            guard,
-            fat_arrow_token: FatArrow::default(),
-            body,
-            comma: Some(Comma::default()),
-        }
+    name.truncate(MAX_CASE_NAME_LEN);
+    let name = name.trim_end_matches('_');
+
+    // Keywords and names starting with a digit are not valid identifiers.
+    match syn::parse_str::<Ident>(name) {
+        Ok(_) => format_ident!("{}", name),
+        Err(_) if name.is_empty() => format_ident!("case"),
+        Err(_) => format_ident!("case_{}", name),
     }
+}
 
-    fn catchall_arm() -> Arm {
-        Arm {
-            attrs: Vec::new(),
-            pat: Pat::Wild(PatWild {
-                attrs: Vec::new(),
-                underscore_token: Token![_](Span::mixed_site()),
-            }),
-            guard: None,
-            fat_arrow_token: Token![=>](Span::mixed_site()),
-            body: Box::new(Self::mk_panic_expr()),
-            comma: Some(Token![,](Span::mixed_site())),
+/// Reports the malformed patterns with errors pointing at the offending
+/// tokens, instead of letting them fail deep inside the generated code.
+///
+/// Casts and unsupported literals are reported before the pattern is parsed,
+/// as `syn` fails to parse them, or panics. The other errors are reported by
+/// visiting the parsed pattern. The tokens of the matcher macros are left to
+/// the corresponding modifiers, which report their own errors.
+#[derive(Default)]
+struct PatternValidator {
+    errors: Vec<syn::Error>,
+}
+
+impl PatternValidator {
+    fn check(pat: &Pat) -> syn::Result<()> {
+        let mut this = PatternValidator::default();
+        this.visit_pat(pat);
+
+        let mut errors = this.errors.into_iter();
+        match errors.next() {
+            Some(mut error) => {
+                errors.for_each(|other| error.combine(other));
+                Err(error)
+            }
+
+            None => Ok(()),
         }
     }
 
-    fn mk_match_expr(ident: Ident) -> Expr {
-        Expr::Verbatim(quote! { #ident[..] })
+    /// Rejects the `as` casts outside of macros, such as `1 as u8`, and the
+    /// C string literals. Casts in qualified paths (`<T as Trait>::VALUE`)
+    /// are allowed.
+    fn check_tokens(tokens: &TokenStream2) -> syn::Result<()> {
+        let mut angle_brackets = 0usize;
+        let mut previous: Option<&TokenTree> = None;
+        let tokens = tokens.clone().into_iter().collect::<Vec<_>>();
+
+        for tree in &tokens {
+            match tree {
+                TokenTree::Punct(punct) if punct.as_char() == '<' => angle_brackets += 1,
+
+                // `=>` and `->` are not closing angle brackets.
+                TokenTree::Punct(punct) if punct.as_char() == '>' => match previous {
+                    Some(TokenTree::Punct(previous)) if "=-".contains(previous.as_char()) => {}
+                    _ => angle_brackets = angle_brackets.saturating_sub(1),
+                },
+
+                TokenTree::Ident(ident) if ident == "as" && angle_brackets == 0 => {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "casts are not allowed in patterns, use a suffixed literal such as `1u8` instead",
+                    ));
+                }
+
+                TokenTree::Literal(lit) if lit.to_string().starts_with('c') => {
+                    return Err(syn::Error::new(
+                        lit.span(),
+                        "C string literals are not supported",
+                    ));
+                }
+
+                TokenTree::Group(group) => {
+                    let is_macro = matches!(
+                        previous,
+                        Some(TokenTree::Punct(punct)) if punct.as_char() == '!'
+                    );
+
+                    if !is_macro {
+                        Self::check_tokens(&group.stream())?;
+                    }
+                }
+
+                _ => {}
+            }
+
+            previous = Some(tree);
+        }
+
+        Ok(())
     }
+}
 
-    fn mk_panic_expr() -> Expr {
-        Expr::Verbatim(quote! { panic!("Matching failed")})
+impl<'pat> Visit<'pat> for PatternValidator {
+    fn visit_pat(&mut self, pat: &'pat Pat) {
+        match pat {
+            Pat::Verbatim(tokens) => self
+                .errors
+                .push(syn::Error::new_spanned(tokens, "unsupported pattern")),
+
+            _ => visit::visit_pat(self, pat),
+        }
     }
+
+    // The tokens of the matcher macros are checked by their modifier.
+    fn visit_macro(&mut self, _: &'pat Macro) {}
 }
 
-/// Helper struct for [`SlicePatternReplacer`].
+/// Allows to match JSON objects with untyped object patterns, such as
+/// `{ type: "card", .. }`.
 ///
-/// Alters slice pattern, stores it in memory and stores it internally.
+/// Rust has no pattern for an object whose type is not known, and a brace
+/// group is not a valid pattern on its own. Before the pattern is parsed, we
+/// rewrite every untyped object pattern as a `map!` pattern on
+/// `serde_json::Value::Object`.
 ///
-/// We only alter outermost slice patterns. This process is repeated multiple
-/// times.
-struct SlicePatternReplacer {
-    slices: Vec<(Ident, PatSlice)>,
+/// # How
+///
+/// We walk the tokens of the pattern, and rewrite every brace group that is
+/// not the body of a struct pattern or of a macro. A brace group that follows
+/// an identifier or a `>` is the body of a struct pattern, and a brace group
+/// that follows a `!` is the body of a macro. The tokens of the `map!`,
+/// `contains!` and `unordered!` macros are patterns, and are rewritten as
+/// well.
+///
+/// Keys are either identifiers, keywords included, or string literals. A key
+/// alone binds the value to an identifier of the same name, just like struct
+/// patterns.
+///
+/// # Example
+///
+/// The following pattern:
+///
+/// ```none
+/// {
+///     type: "card",
+///     details: { last4, .. },
+/// }
+/// ```
+///
+/// Will be transformed to:
+///
+/// ```none
+/// ::restest::__private::Value::Object(map! {
+///     "type" => "card",
+///     "details" => ::restest::__private::Value::Object(map! {
+///         "last4" => last4,
+///         ..
+///     }),
+/// })
+/// ```
+struct UntypedObjectRewriter {
+    entries: Vec<(LitStr, TokenStream2)>,
+    rest: Option<Token![..]>,
 }
 
-impl SlicePatternReplacer {
-    fn new() -> SlicePatternReplacer {
-        SlicePatternReplacer { slices: Vec::new() }
-    }
+impl UntypedObjectRewriter {
+    /// The macros whose tokens are patterns.
+    const PATTERN_MACROS: [&'static str; 3] = ["map", "contains", "unordered"];
 
-    fn alter_initial_pattern(&mut self, mut pat: Pat) -> Pat {
-        self.visit_pat_mut(&mut pat);
-        pat
+    fn rewrite(tokens: TokenStream2) -> TokenStream2 {
+        let mut output = Vec::<TokenTree>::new();
+
+        for tree in tokens {
+            let group = match tree {
+                TokenTree::Group(group) => group,
+                tree => {
+                    output.push(tree);
+                    continue;
+                }
+            };
+
+            let (is_object, is_macro) = match output.last() {
+                Some(TokenTree::Ident(_)) => (false, false),
+                Some(TokenTree::Punct(punct)) if punct.as_char() == '>' => (false, false),
+                Some(TokenTree::Punct(punct)) if punct.as_char() == '!' => (false, true),
+                _ => (group.delimiter() == Delimiter::Brace, false),
+            };
+
+            if is_object {
+                output.extend(Self::rewrite_object(&group));
+                continue;
+            }
+
+            let is_pattern_macro = match output.len().checked_sub(2).map(|i| &output[i]) {
+                Some(TokenTree::Ident(name)) => Self::PATTERN_MACROS.iter().any(|m| name == m),
+                _ => false,
+            };
+
+            if is_macro && !is_pattern_macro {
+                output.push(TokenTree::Group(group));
+                continue;
+            }
+
+            let mut rewritten = Group::new(group.delimiter(), Self::rewrite(group.stream()));
+            rewritten.set_span(group.span());
+            output.push(TokenTree::Group(rewritten));
+        }
+
+        output.into_iter().collect()
     }
 
-    fn alter_pat_slice(&mut self, mut pat: PatSlice) -> PatSlice {
-        self.visit_pat_slice_mut(&mut pat);
-        pat
+    fn rewrite_object(group: &Group) -> TokenStream2 {
+        let object = match syn::parse2::<UntypedObjectRewriter>(group.stream()) {
+            Ok(object) => object,
+            Err(err) => return err.to_compile_error(),
+        };
+
+        let (keys, values): (Vec<_>, Vec<_>) = object.entries.into_iter().unzip();
+        let values = values.into_iter().map(Self::rewrite);
+        let rest = object.rest;
+
+        quote_spanned! {generated(group.span())=>
+            ::restest::__private::Value::Object(map! { #( #keys => #values, )* #rest })
+        }
     }
+}
 
-    fn extracted_slice_patterns(self) -> Vec<(Ident, PatSlice)> {
-        self.slices
+impl Parse for UntypedObjectRewriter {
+    fn parse(input: ParseStream) -> syn::Result<UntypedObjectRewriter> {
+        let mut entries = Vec::new();
+
+        while !input.is_empty() {
+            // Misplaced `..` are reported by `MapPattern`.
+            if input.peek(Token![..]) {
+                let rest = input.parse()?;
+                input.parse::<Option<Token![,]>>()?;
+
+                if !input.is_empty() {
+                    return Err(input.error("`..` must be at the end of an object pattern"));
+                }
+
+                return Ok(UntypedObjectRewriter {
+                    entries,
+                    rest: Some(rest),
+                });
+            }
+
+            let (key, ident) = if input.peek(LitStr) {
+                (input.parse::<LitStr>()?, None)
+            } else {
+                let ident = input
+                    .call(Ident::parse_any)
+                    .map_err(|err| syn::Error::new(err.span(), "expected a key or `..`"))?;
+                let key = LitStr::new(&ident.unraw().to_string(), ident.span());
+                (key, Some(ident))
+            };
+
+            let value = if input.peek(Token![:]) && !input.peek(Token![::]) {
+                input.parse::<Token![:]>()?;
+
+                let mut value = TokenStream2::new();
+                while !input.is_empty() && !input.peek(Token![,]) {
+                    value.extend(iter::once(input.parse::<TokenTree>()?));
+                }
+                value
+            } else {
+                match ident {
+                    Some(ident) => ident.unraw().into_token_stream(),
+                    None => {
+                        return Err(syn::Error::new(
+                            key.span(),
+                            "expected `:` after a string key",
+                        ))
+                    }
+                }
+            };
+
+            entries.push((key, value));
+
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(UntypedObjectRewriter {
+            entries,
+            rest: None,
+        })
     }
+}
 
-    fn add_slice_pattern(&mut self, pat: &mut Pat, slice: PatSlice) {
-        let ident = self.mk_internal_slice_ident();
-        self.slices.push((ident.clone(), slice));
+/// Allows to combine alternatives (`a | b`) with the patterns that are not
+/// native Rust patterns, such as most literals, ranges, slices, maps and
+/// matcher macros.
+///
+/// The next visitors replace such patterns with bindings of unique
+/// identifiers. The alternatives of an or-pattern would then bind different
+/// identifiers, which Rust rejects. Instead, we check the alternatives in the
+/// final guard.
+///
+/// # How
+///
+/// Just like [`GuardPatternModifier`], we use [`VisitMut`] to replace every
+/// or-pattern that contains such a pattern with a binding of a unique
+/// identifier. Each alternative is checked by a nested `body_matches` call, on
+/// a clone of the value, as it is only borrowed in the guard. For this reason,
+/// these alternatives can't bring values in scope.
+///
+/// Or-patterns made of native patterns only are left as is.
+///
+/// # Example
+///
+/// The following pattern:
+///
+/// ```none
+/// Payment {
+///     method: "card" | "iban",
+///     amount,
+/// }
+/// ```
+///
+/// Will be transformed to:
+///
+/// ```none
+/// Payment {
+///     method: __restest__or_0,
+///     amount,
+/// }
+/// ```
+///
+/// And will generate the following condition:
+///
+/// ```none
+/// (
+///     ::restest::body_matches!(
+///         ::std::clone::Clone::clone(&__restest__or_0),
+///         "card"
+///     ).is_ok()
+///     || ::restest::body_matches!(
+///         ::std::clone::Clone::clone(&__restest__or_0),
+///         "iban"
+///     ).is_ok()
+/// )
+/// ```
+#[derive(Default)]
+struct OrPatternModifier {
+    conditions: Vec<TokenStream2>,
+}
 
-        let pat_ident = PatIdent {
-            attrs: Vec::new(),
-            by_ref: None,
-            mutability: None,
-            ident,
-            subpat: None,
-        };
+impl OrPatternModifier {
+    fn new(pat: &mut Pat) -> OrPatternModifier {
+        let mut this = OrPatternModifier::default();
 
-        *pat = Pat::Ident(pat_ident);
+        this.visit_pat_mut(pat);
+        this
+    }
+
+    /// Appends the generated conditions to `previous_guard`.
+    fn expand_guard_expr(self, previous_guard: Expr) -> Expr {
+        let conditions = self.conditions;
+        Expr::Verbatim(quote! {
+            #previous_guard #( && #conditions )*
+        })
     }
 
-    fn mk_internal_slice_ident(&self) -> Ident {
-        format_ident!("__restest__array_{}", self.slices.len())
-    }
-}
+    fn mk_condition(cases: &Punctuated<Pat, Token![|]>, ident: &Ident) -> TokenStream2 {
+        if let Some(binding) = cases
+            .iter()
+            .find_map(BindingPatternsExtractor::first_binding)
+        {
+            return syn::Error::new_spanned(
+                binding,
+                "alternatives that are not native patterns can not bring values in scope",
+            )
+            .to_compile_error();
+        }
+
+        let cases = cases.iter();
+        quote_spanned! {ident.span()=>
+            (false #( || ::restest::body_matches!(
+                ::std::clone::Clone::clone(&#ident),
+                #cases
+            ).is_ok() )*)
+        }
+    }
+
+    fn mk_ident(&self, span: Span) -> Ident {
+        format_ident!(
+            "__restest__or_{}",
+            self.conditions.len(),
+            span = generated(span)
+        )
+    }
+}
+
+impl VisitMut for OrPatternModifier {
+    fn visit_pat_mut(&mut self, pat: &mut Pat) {
+        if let Pat::Or(or) = &*pat {
+            if !NativePatternChecker::is_native(pat) {
+                let ident = self.mk_ident(or.span());
+                let condition = Self::mk_condition(&or.cases, &ident);
+
+                self.conditions.push(condition);
+                LiteralPatternModifier::alter_pattern(pat, ident);
+                return;
+            }
+        }
+
+        visit_mut::visit_pat_mut(self, pat);
+    }
+}
+
+/// Checks whether a pattern is a native Rust pattern, which is left untouched
+/// by the visitors.
+struct NativePatternChecker {
+    native: bool,
+}
+
+impl NativePatternChecker {
+    fn is_native(pat: &Pat) -> bool {
+        let mut this = NativePatternChecker { native: true };
+        this.visit_pat(pat);
+        this.native
+    }
+}
+
+impl<'pat> Visit<'pat> for NativePatternChecker {
+    fn visit_pat(&mut self, pat: &'pat Pat) {
+        match pat {
+            _ if LiteralPatternModifier::literal(pat).is_some() => self.native = false,
+
+            Pat::Macro(_) | Pat::Range(_) | Pat::Slice(_) => self.native = false,
+
+            _ => visit::visit_pat(self, pat),
+        }
+    }
+}
+
+/// Allows to match maps, such as `HashMap` or `BTreeMap`, with the `map!`
+/// pattern macro.
+///
+/// Rust patterns can't look into maps. Instead, the value associated to each
+/// key is removed from the map and matched in a nested match expression (see
+/// [`SlicePatternModifier`]). Unless the pattern ends with `..`, the map must
+/// not contain any other key.
+///
+/// # How
+///
+/// Other visitors must be able to see the patterns of the values, which they
+/// can't do while these are hidden in a macro. We use [`VisitMut`] to encode
+/// every `map!` pattern in a tuple struct pattern whose fields are
+/// `(key, value)` tuples. The key is itself hidden in a macro so that it is
+/// left untouched by the other visitors.
+///
+/// # Example
+///
+/// The following pattern:
+///
+/// ```none
+/// map! {
+///     "name" => "Grace Hopper",
+///     "age" => age,
+///     ..
+/// }
+/// ```
+///
+/// Will be transformed to:
+///
+/// ```none
+/// __restest__map(
+///     (__restest__key!("name"), "Grace Hopper"),
+///     (__restest__key!("age"), age),
+///     ..
+/// )
+/// ```
+struct MapPatternModifier;
+
+impl MapPatternModifier {
+    fn alter_pattern(pat: &mut Pat) {
+        MapPatternModifier.visit_pat_mut(pat);
+    }
+}
+
+impl VisitMut for MapPatternModifier {
+    fn visit_pat_mut(&mut self, pat: &mut Pat) {
+        if let Pat::Macro(PatMacro { mac, .. }) = pat {
+            if mac.path.is_ident("map") {
+                *pat = match mac.parse_body::<MapPattern>() {
+                    Ok(map) => map.into_pat(),
+                    Err(err) => Pat::Verbatim(err.to_compile_error()),
+                };
+            }
+        }
+
+        visit_mut::visit_pat_mut(self, pat);
+    }
+}
+
+/// The content of a `map!` pattern.
+struct MapPattern {
+    entries: Vec<(Expr, Pat)>,
+    rest: bool,
+}
+
+impl MapPattern {
+    fn into_pat(self) -> Pat {
+        let (keys, values): (Vec<_>, Vec<_>) = self.entries.into_iter().unzip();
+        let rest = self.rest.then(|| quote! { .. });
+
+        parse_quote! {
+            __restest__map( #( (__restest__key!(#keys), #values), )* #rest )
+        }
+    }
+}
+
+impl Parse for MapPattern {
+    fn parse(input: ParseStream) -> syn::Result<MapPattern> {
+        let mut entries = Vec::new();
+
+        while !input.is_empty() {
+            if input.peek(Token![..]) {
+                input.parse::<Token![..]>()?;
+                input.parse::<Option<Token![,]>>()?;
+
+                if !input.is_empty() {
+                    return Err(input.error("`..` must be at the end of a map pattern"));
+                }
+
+                return Ok(MapPattern {
+                    entries,
+                    rest: true,
+                });
+            }
+
+            let key = input.parse()?;
+            input.parse::<Token![=>]>()?;
+            let value = input.parse()?;
+            entries.push((key, value));
+
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(MapPattern {
+            entries,
+            rest: false,
+        })
+    }
+}
+
+/// Allows to extract a list of all the identifiers that are brought in scope
+/// by a given pattern.
+///
+/// This allows us to generate the final body of the innermost match pattern
+/// that `assert_body_matches` will expand to.
+///
+/// # How
+///
+/// [`BindingPatternsExtractor`] implements [`Visit`], which allows to
+/// recursively visit the entire AST. We use this to visit a given pattern
+/// and ensure extract every binding pattern.
+///
+/// # Example
+///
+/// The following pattern:
+///
+/// ```none
+/// Foo {
+///     field,
+///     inner: Bar {
+///         value: 42,
+///         other_value,
+///     },
+///     final_value,
+/// }
+/// ```
+///
+/// Brings the following identifiers in scope:
+///   - `field`,
+///   - `other_value`,
+///   - `final_value`.
+#[derive(Default)]
+struct BindingPatternsExtractor<'pat> {
+    bindings: Vec<&'pat Ident>,
+}
+
+impl<'pat> BindingPatternsExtractor<'pat> {
+    fn new(pat: &'pat Pat) -> BindingPatternsExtractor<'pat> {
+        let mut this = BindingPatternsExtractor::default();
+        this.visit_pat(pat);
+        this
+    }
+
+    /// Returns the first identifier brought in scope by `pat`, including the
+    /// ones of its `map!` patterns.
+    fn first_binding(pat: &Pat) -> Option<Ident> {
+        Self::all_bindings(pat).into_iter().next()
+    }
+
+    /// Returns the identifiers brought in scope by `pat`, including the ones
+    /// of its `map!` patterns.
+    fn all_bindings(pat: &Pat) -> Vec<Ident> {
+        let mut pat = pat.clone();
+        MapPatternModifier::alter_pattern(&mut pat);
+
+        let bindings = BindingPatternsExtractor::new(&pat).bindings;
+        bindings.into_iter().cloned().collect()
+    }
+
+    fn expand_bindings_and_return_expr(self) -> (PatTuple, ExprTuple) {
+        let paren_token = Paren {
+            span: Span::call_site(),
+        };
+
+        let bindings = self.mk_bindings(paren_token);
+        let return_expr = self.mk_return_expr(paren_token);
+
+        (bindings, return_expr)
+    }
+
+    fn mk_bindings(&self, paren_token: Paren) -> PatTuple {
+        let elems = self
+            .bindings
+            .iter()
+            .copied()
+            .cloned()
+            .map(Self::mk_ident_pat)
+            .map(|i| Pair::Punctuated(i, Comma::default()))
+            .collect();
+
+        PatTuple {
+            attrs: Vec::new(),
+            paren_token,
+            elems,
+        }
+    }
+
+    fn mk_return_expr(self, paren_token: Paren) -> ExprTuple {
+        let elems = self
+            .bindings
+            .into_iter()
+            .cloned()
+            .map(Self::mk_ident_expr)
+            .map(|i| Pair::Punctuated(i, Comma::default()))
+            .collect::<Punctuated<_, _>>();
+
+        ExprTuple {
+            attrs: Vec::new(),
+            paren_token,
+            elems,
+        }
+    }
+
+    fn mk_ident_expr(ident: Ident) -> Expr {
+        Expr::Verbatim(quote! { #ident })
+    }
+
+    fn mk_ident_pat(ident: Ident) -> Pat {
+        Pat::Ident(PatIdent {
+            attrs: Vec::new(),
+            by_ref: None,
+            mutability: None,
+            ident,
+            subpat: None,
+        })
+    }
+}
+
+impl<'pat> Visit<'pat> for BindingPatternsExtractor<'pat> {
+    fn visit_pat_ident(&mut self, i: &'pat PatIdent) {
+        // Identifiers generated by `OrPatternModifier` are not user bindings.
+        if !i.ident.to_string().starts_with("__restest__") {
+            self.bindings.push(&i.ident);
+        }
+    }
+}
+
+/// Allows to perform pattern matching over `String`, floats and untyped
+/// values using literals.
+///
+/// Rust only allows a string literal to match a `&str`, and a literal can't
+/// match a `serde_json::Value`. To do so, we need to alter the pattern and
+/// change every string, integer, float, boolean, character and byte string
+/// literal pattern into a binding, and check for equality in the final guard.
+/// Integer literals could be matched natively, but not against untyped values.
+///
+/// # How
+///
+/// [`LiteralPatternModifier`] implements [`VisitMut`], which allows us to
+/// recursively visit and alter the AST. Here, we use this to visit and alter a
+/// given pattern. Specifically, we change all the literal patterns to be
+/// bindings of a given identifier and keep track of which identifier
+/// corresponds to which literal.
+///
+/// # Example
+///
+/// The following pattern:
+///
+/// ```none
+/// Foo {
+///     field: "string literal",
+///     inner: Bar {
+///         ratio: -0.5,
+///     },
+///     final: true,
+/// }
+/// ```
+///
+/// Will be transformed to:
+///
+/// ```none
+/// Foo {
+///     field: __restest__lit_0,
+///     inner: Bar {
+///         ratio: __restest__lit_1,
+///     },
+///     final: __restest__lit_2,
+/// }
+/// ```
+///
+/// And will generate the following conditions:
+///   - `__restest__lit_0 == "string literal"`,
+///   - `__restest__lit_1 == -0.5`,
+///   - `__restest__lit_2 == true`.
+#[derive(Default)]
+struct LiteralPatternModifier {
+    conditions: Vec<(Ident, Expr)>,
+}
+
+impl LiteralPatternModifier {
+    fn new(pat: &mut Pat) -> LiteralPatternModifier {
+        let mut this = LiteralPatternModifier::default();
+
+        this.visit_pat_mut(pat);
+        this
+    }
+
+    /// Returns the literal of `pat`, if it must be checked in the guard.
+    fn literal(pat: &Pat) -> Option<&Expr> {
+        let expr = match pat {
+            Pat::Lit(PatLit { expr, .. }) => expr.as_ref(),
+            _ => return None,
+        };
+
+        let lit = match expr {
+            Expr::Lit(ExprLit { lit, .. }) => lit,
+            Expr::Unary(ExprUnary {
+                op: UnOp::Neg(_),
+                expr,
+                ..
+            }) => match expr.as_ref() {
+                Expr::Lit(ExprLit {
+                    lit: lit @ (Lit::Int(_) | Lit::Float(_)),
+                    ..
+                }) => lit,
+                _ => return None,
+            },
+            _ => return None,
+        };
+
+        match lit {
+            Lit::Str(_)
+            | Lit::Int(_)
+            | Lit::Float(_)
+            | Lit::Bool(_)
+            | Lit::Char(_)
+            | Lit::ByteStr(_) => Some(expr),
+            _ => None,
+        }
+    }
+
+    fn expand_guard_expr(self) -> Expr {
+        let conditions = self
+            .conditions
+            .iter()
+            .map(|(name, value)| quote_spanned! {generated(value.span())=> #name == #value });
+
+        Expr::Verbatim(quote! {
+            true #( && #conditions )*
+        })
+    }
+
+    fn add_literal_pattern(&mut self, lit: Expr) -> Ident {
+        let name = self.mk_ident(lit.span());
+        self.conditions.push((name.clone(), lit));
+        name
+    }
+
+    fn alter_pattern(pat: &mut Pat, ident: Ident) {
+        *pat = Pat::Ident(PatIdent {
+            attrs: Vec::new(),
+            by_ref: None,
+            mutability: None,
+            ident,
+            subpat: None,
+        })
+    }
+
+    fn mk_ident(&self, span: Span) -> Ident {
+        format_ident!(
+            "__restest__lit_{}",
+            self.conditions.len(),
+            span = generated(span)
+        )
+    }
+}
+
+impl VisitMut for LiteralPatternModifier {
+    fn visit_pat_mut(&mut self, pat: &mut Pat) {
+        match Self::literal(pat) {
+            Some(lit) => {
+                let ident = self.add_literal_pattern(lit.clone());
+                Self::alter_pattern(pat, ident);
+            }
+
+            None => visit_mut::visit_pat_mut(self, pat),
+        }
+    }
+}
+
+/// Allows to match values against conditions that can't be expressed with
+/// native patterns.
+///
+/// Rust range patterns only accept integer and `char` literals and paths as
+/// bounds, and there is no pattern for "greater than". We alter the pattern so
+/// that such conditions are checked in the final guard instead.
+///
+/// # How
+///
+/// Just like [`LiteralPatternModifier`], we use [`VisitMut`] to replace
+/// every range pattern and every matcher macro with a binding of a unique
+/// identifier, and generate the condition that must be checked on this
+/// identifier. The supported matcher macros are:
+///   - `gt!`, `ge!`, `lt!` and `le!`, which compare the value to a bound,
+///   - `iso8601!`, which checks that the value is a RFC 3339 datetime,
+///   - `within_last!`, which checks that the value is a RFC 3339 datetime
+///     close to the current time,
+///   - `uuid!`, which checks that the value is a UUID, optionally of a given
+///     version,
+///   - `contains!`, `starts_with!`, `ends_with!` and `eq_ignore_case!`,
+///     which compare the value to a string loosely,
+///   - `satisfies!`, which calls a predicate with a reference to the value,
+///   - `custom!`, which checks the value with a `restest::pattern::Matcher`,
+///   - `pattern!`, which checks the value against a named pattern (see
+///     [`NamedPatterns`]),
+///   - `deserialize!`, which checks that the value deserializes to a type.
+///
+/// A matcher can be bound to an identifier (`id @ uuid!()`). In this case, the
+/// condition is checked on the identifier itself. Matchers that parse the
+/// value (`uuid!`, `deserialize!`) bind the parsed value instead of the
+/// original one.
+///
+/// Conditions that need runtime support call functions of the hidden
+/// `restest::__private` module. Comparisons, string matchers and element
+/// matchers wrap the value in one of its `*Of` types, so that they accept
+/// untyped values as well as typed ones.
+///
+/// # Example
+///
+/// The following pattern:
+///
+/// ```none
+/// Foo {
+///     age: 18..=99,
+///     count: gt!(0),
+/// }
+/// ```
+///
+/// Will be transformed to:
+///
+/// ```none
+/// Foo {
+///     age: __restest__guard_0,
+///     count: __restest__guard_1,
+/// }
+/// ```
+///
+/// And will generate the following conditions (without the `use` items
+/// which bring the `AsOrd` trait in scope):
+///   - `OrdOf(&__restest__guard_0).in_range(&(18..=99))`,
+///   - `OrdOf(&__restest__guard_1).compare(&(0)).is_some_and(Ordering::is_gt)`.
+#[derive(Default)]
+struct GuardPatternModifier {
+    conditions: Vec<Expr>,
+    conversions: Vec<TokenStream2>,
+}
+
+impl GuardPatternModifier {
+    fn new(pat: &mut Pat) -> GuardPatternModifier {
+        let mut this = GuardPatternModifier::default();
+
+        this.visit_pat_mut(pat);
+        this
+    }
+
+    /// Converts the values bound to matchers that produce a value of another
+    /// type (such as `id @ uuid!()`) before `return_expr` is evaluated.
+    fn expand_return_expr(&self, return_expr: Expr) -> Expr {
+        if self.conversions.is_empty() {
+            return return_expr;
+        }
+
+        let conversions = &self.conversions;
+        Expr::Verbatim(quote! {
+            {
+                #( #conversions )*
+                #return_expr
+            }
+        })
+    }
+
+    /// Appends the generated conditions to `previous_guard`.
+    fn expand_guard_expr(self, previous_guard: Expr) -> Expr {
+        let conditions = self.conditions;
+        Expr::Verbatim(quote! {
+            #previous_guard #( && #conditions )*
+        })
+    }
+
+    /// Returns the condition that must be checked on `ident` in place of
+    /// `pat`, or `None` if `pat` must be left as is.
+    fn mk_condition(pat: &Pat, ident: &Ident) -> Option<Expr> {
+        match pat {
+            Pat::Range(PatRange { lo, limits, hi, .. }) => {
+                let call = quote_spanned! {generated(pat.span())=>
+                    in_range(&(#lo #limits #hi))
+                };
+                Some(Expr::Verbatim(Self::mk_ord(ident, call, pat.span())))
+            }
+
+            Pat::Macro(PatMacro { mac, .. }) => {
+                let condition = match mac.path.get_ident()?.to_string().as_str() {
+                    "gt" => Self::mk_comparison(mac, ident, "is_gt"),
+                    "ge" => Self::mk_comparison(mac, ident, "is_ge"),
+                    "lt" => Self::mk_comparison(mac, ident, "is_lt"),
+                    "le" => Self::mk_comparison(mac, ident, "is_le"),
+                    "iso8601" => Self::mk_iso8601(mac, ident),
+                    "within_last" => Self::mk_within_last(mac, ident),
+                    "uuid" => Self::mk_uuid(mac, ident),
+                    "contains" if matches!(mac.delimiter, MacroDelimiter::Bracket(_)) => {
+                        Self::mk_elements(mac, ident, true)
+                    }
+                    "contains" | "starts_with" | "ends_with" => Self::mk_str_method(mac, ident),
+                    "eq_ignore_case" => Self::mk_eq_ignore_case(mac, ident),
+                    "satisfies" => Self::mk_satisfies(mac, ident),
+                    "custom" => Self::mk_custom(mac, ident),
+                    "pattern" => Self::mk_named(mac, ident),
+                    "deserialize" => Self::mk_deserialize(mac, ident),
+                    "unordered" => Self::mk_elements(mac, ident, false),
+                    "len" => Self::mk_len(mac, ident),
+                    "keys" => Self::mk_keys(mac, ident, true),
+                    "only_keys" => Self::mk_keys(mac, ident, false),
+                    _ => return None,
+                };
+
+                Some(Expr::Verbatim(
+                    condition.unwrap_or_else(|err| err.to_compile_error()),
+                ))
+            }
+
+            _ => None,
+        }
+    }
+
+    /// Returns the statement that converts the value bound to `ident`, if the
+    /// matcher `pat` produces a value of another type.
+    fn mk_conversion(pat: &Pat, ident: &Ident) -> Option<TokenStream2> {
+        match pat {
+            Pat::Macro(PatMacro { mac, .. }) if mac.path.is_ident("uuid") => {
+                let text = Self::mk_text(ident, mac.span());
+                Some(quote_spanned! {generated(mac.span())=>
+                    let #ident = ::restest::__private::parse_uuid(#text);
+                })
+            }
+
+            Pat::Macro(PatMacro { mac, .. }) if mac.path.is_ident("deserialize") => {
+                let ty = mac.parse_body::<Type>().ok()?;
+                Some(quote_spanned! {generated(mac.span())=>
+                    let #ident = ::restest::__private::deserialize::<#ty>(&#ident);
+                })
+            }
+
+            _ => None,
+        }
+    }
+
+    /// Wraps the value bound to `ident` so that it can be compared, whether
+    /// it is typed or untyped, and applies `call` to it. Refer to
+    /// `restest::__private::OrdOf`.
+    fn mk_ord(ident: &Ident, call: TokenStream2, span: Span) -> TokenStream2 {
+        quote_spanned! {generated(span)=>
+            {
+                use ::restest::__private::AsOrd as _;
+                ::restest::__private::OrdOf(&#ident).#call
+            }
+        }
+    }
+
+    /// Borrows the value bound to `ident` as an `Option<&str>`, whether it is
+    /// typed or untyped. Refer to `restest::__private::TextOf`.
+    fn mk_text(ident: &Ident, span: Span) -> TokenStream2 {
+        quote_spanned! {generated(span)=>
+            {
+                use ::restest::__private::AsText as _;
+                ::restest::__private::TextOf(&#ident).text()
+            }
+        }
+    }
+
+    /// `ordering` is the method of `Ordering` that must accept the result of
+    /// the comparison of the value to the bound.
+    fn mk_comparison(mac: &Macro, ident: &Ident, ordering: &str) -> syn::Result<TokenStream2> {
+        let span = generated(mac.span());
+        let bound = mac.parse_body::<Expr>()?;
+        let ordering = Ident::new(ordering, span);
+        let call = quote_spanned! {span=> compare(&(#bound)) };
+        let comparison = Self::mk_ord(ident, call, mac.span());
+        Ok(quote_spanned! {span=>
+            #comparison.is_some_and(::std::cmp::Ordering::#ordering)
+        })
+    }
+
+    fn mk_str_method(mac: &Macro, ident: &Ident) -> syn::Result<TokenStream2> {
+        let span = generated(mac.span());
+        let method = &mac.path;
+        let needle = mac.parse_body::<Expr>()?;
+        let text = Self::mk_text(ident, mac.span());
+        Ok(quote_spanned! {span=>
+            #text.is_some_and(|__restest__text| __restest__text.#method(#needle))
+        })
+    }
+
+    fn mk_eq_ignore_case(mac: &Macro, ident: &Ident) -> syn::Result<TokenStream2> {
+        let span = generated(mac.span());
+        let expected = mac.parse_body::<Expr>()?;
+        let text = Self::mk_text(ident, mac.span());
+        Ok(quote_spanned! {span=>
+            #text.is_some_and(|__restest__text| {
+                ::restest::__private::eq_ignore_case(__restest__text, #expected)
+            })
+        })
+    }
+
+    fn mk_satisfies(mac: &Macro, ident: &Ident) -> syn::Result<TokenStream2> {
+        let span = generated(mac.span());
+        let predicate = mac.parse_body::<Expr>()?;
+        Ok(quote_spanned! {span=> (#predicate)(&#ident) })
+    }
+
+    fn mk_custom(mac: &Macro, ident: &Ident) -> syn::Result<TokenStream2> {
+        let span = generated(mac.span());
+        let matcher = mac.parse_body::<Expr>()?;
+        Ok(quote_spanned! {span=> ::restest::__private::matches_custom(&#ident, &#matcher) })
+    }
+
+    /// Like element patterns, the named pattern is checked on a clone of the
+    /// value, as it is only borrowed in the guard.
+    fn mk_named(mac: &Macro, ident: &Ident) -> syn::Result<TokenStream2> {
+        let span = generated(mac.span());
+        let name = mac.parse_body::<syn::Path>()?;
+        Ok(quote_spanned! {span=> #name!(::std::clone::Clone::clone(&#ident)) })
+    }
+
+    fn mk_deserialize(mac: &Macro, ident: &Ident) -> syn::Result<TokenStream2> {
+        let span = generated(mac.span());
+        let ty = mac.parse_body::<Type>()?;
+        Ok(quote_spanned! {span=> ::restest::__private::deserializes::<#ty>(&#ident) })
+    }
+
+    /// Each element pattern is checked by a nested `body_matches` call, on a
+    /// clone of the element, as elements are only borrowed in the guard.
+    ///
+    /// With `partial`, the value may contain elements that match none of the
+    /// patterns.
+    fn mk_elements(mac: &Macro, ident: &Ident, partial: bool) -> syn::Result<TokenStream2> {
+        let span = generated(mac.span());
+        let patterns = mac.parse_body_with(Punctuated::<Pat, Token![,]>::parse_terminated)?;
+
+        if let Some(binding) = patterns
+            .iter()
+            .find_map(BindingPatternsExtractor::first_binding)
+        {
+            let name = mac.path.to_token_stream();
+            return Err(syn::Error::new_spanned(
+                binding,
+                format!("`{}!` patterns can not bring values in scope", name),
+            ));
+        }
+
+        let count = patterns.len();
+        let indices = 0..count;
+        let patterns = patterns.iter();
+
+        Ok(quote_spanned! {span=>
+            {
+                use ::restest::__private::AsSlice as _;
+                ::restest::__private::SliceOf(&#ident).slice()
+            }
+            .is_some_and(|__restest__items| ::restest::__private::matches_elements(
+                &__restest__items,
+                #count,
+                #partial,
+                |__restest__index, __restest__element| match __restest__index {
+                    #( #indices => ::restest::body_matches!(
+                        ::std::clone::Clone::clone(__restest__element),
+                        #patterns
+                    ).is_ok(), )*
+                    _ => false,
+                },
+            ))
+        })
+    }
+
+    /// Accepts either an exact length, or a range of lengths. Refer to
+    /// `restest::__private::LenOf` for the values that have a length.
+    fn mk_len(mac: &Macro, ident: &Ident) -> syn::Result<TokenStream2> {
+        let span = generated(mac.span());
+        let condition = match mac.parse_body::<Expr>()? {
+            Expr::Range(range) => quote_spanned! {span=> (#range).contains(&__restest__len) },
+            len => quote_spanned! {span=> __restest__len == (#len) },
+        };
+
+        Ok(quote_spanned! {span=>
+            ::restest::__private::IntoLen::into_len(::restest::__private::LenOf(&#ident).len())
+                .is_some_and(|__restest__len| #condition)
+        })
+    }
+
+    /// With `exact`, the object must contain every key. Otherwise, it must
+    /// only contain keys from the list.
+    fn mk_keys(mac: &Macro, ident: &Ident, exact: bool) -> syn::Result<TokenStream2> {
+        let span = generated(mac.span());
+        let keys = mac.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated)?;
+        let keys = keys.iter();
+
+        Ok(quote_spanned! {span=>
+            ::restest::__private::has_keys(&#ident, &[ #( #keys ),* ], #exact)
+        })
+    }
+
+    fn mk_iso8601(mac: &Macro, ident: &Ident) -> syn::Result<TokenStream2> {
+        let span = generated(mac.span());
+        if !mac.tokens.is_empty() {
+            return Err(syn::Error::new_spanned(
+                &mac.tokens,
+                "`iso8601!` does not take any argument",
+            ));
+        }
+
+        let text = Self::mk_text(ident, mac.span());
+        Ok(quote_spanned! {span=> #text.is_some_and(::restest::__private::is_rfc3339) })
+    }
+
+    fn mk_uuid(mac: &Macro, ident: &Ident) -> syn::Result<TokenStream2> {
+        let span = generated(mac.span());
+        let version = if mac.tokens.is_empty() {
+            quote_spanned! {span=> ::std::option::Option::None }
+        } else {
+            let version = mac.parse_body::<LitInt>()?;
+            quote_spanned! {span=> ::std::option::Option::Some(#version) }
+        };
+
+        let text = Self::mk_text(ident, mac.span());
+        Ok(quote_spanned! {span=>
+            #text.is_some_and(|__restest__text| ::restest::__private::is_uuid(__restest__text, #version))
+        })
+    }
+
+    fn mk_within_last(mac: &Macro, ident: &Ident) -> syn::Result<TokenStream2> {
+        let span = generated(mac.span());
+        let duration = mac.parse_body_with(Self::parse_duration)?;
+        let text = Self::mk_text(ident, mac.span());
+        Ok(quote_spanned! {span=>
+            #text.is_some_and(|__restest__text| ::restest::__private::is_within(__restest__text, #duration))
+        })
+    }
+
+    /// Parses either a duration literal (`5 s`, `100 ms`, `2 min`, `1 h`,
+    /// `1 d`) or an expression evaluating to a `std::time::Duration`.
+    fn parse_duration(input: ParseStream) -> syn::Result<TokenStream2> {
+        if input.peek(LitInt) && input.peek2(Ident) {
+            let amount = input.parse::<LitInt>()?;
+            let unit = input.parse::<Ident>()?;
+
+            let secs = match unit.to_string().as_str() {
+                "ms" => return Ok(quote! { ::std::time::Duration::from_millis(#amount) }),
+                "s" => quote! { #amount },
+                "min" => quote! { #amount * 60 },
+                "h" => quote! { #amount * 60 * 60 },
+                "d" => quote! { #amount * 60 * 60 * 24 },
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        unit,
+                        "expected one of `ms`, `s`, `min`, `h` or `d`",
+                    ))
+                }
+            };
+
+            return Ok(quote! { ::std::time::Duration::from_secs(#secs) });
+        }
+
+        let duration = input.parse::<Expr>()?;
+        Ok(duration.into_token_stream())
+    }
+
+    fn mk_ident(&self, span: Span) -> Ident {
+        format_ident!(
+            "__restest__guard_{}",
+            self.conditions.len(),
+            span = generated(span)
+        )
+    }
+}
+
+impl VisitMut for GuardPatternModifier {
+    fn visit_pat_mut(&mut self, pat: &mut Pat) {
+        // `ident @ matcher!()` patterns: the condition is checked on `ident`
+        // directly, as a by-move binding can't be bound again after `@`.
+        if let Pat::Ident(PatIdent {
+            ident,
+            subpat: Some((_, subpat)),
+            ..
+        }) = pat
+        {
+            if let Some(condition) = Self::mk_condition(subpat, ident) {
+                self.conversions.extend(Self::mk_conversion(subpat, ident));
+                self.conditions.push(condition);
+
+                if let Pat::Ident(pat) = pat {
+                    pat.subpat = None;
+                }
+
+                return;
+            }
+        }
+
+        let ident = self.mk_ident(pat.span());
+
+        match Self::mk_condition(pat, &ident) {
+            Some(condition) => {
+                self.conditions.push(condition);
+                LiteralPatternModifier::alter_pattern(pat, ident);
+            }
+
+            None => visit_mut::visit_pat_mut(self, pat),
+        }
+    }
+
+    fn visit_field_pat_mut(&mut self, field: &mut FieldPat) {
+        visit_mut::visit_field_pat_mut(self, field);
+
+        // `id: id @ uuid!()` is now `id: id`, which triggers the
+        // `non_shorthand_field_patterns` lint.
+        if let (Member::Named(member), Pat::Ident(pat)) = (&field.member, &*field.pat) {
+            if pat.subpat.is_none() && pat.ident == *member {
+                field.colon_token = None;
+            }
+        }
+    }
+}
+
+/// Allows to encode and expand a match expression that accepts slices patterns
+/// for `Vec`, `VecDeque`, arrays and any `AsRef<[T]>`, and map patterns encoded
+/// by [`MapPatternModifier`].
+///
+/// # How
+///
+/// We use [`VisitMut`] to visit and alter the pattern. We transform every slice
+/// pattern into a binding of a unique identifier, and match on its content in
+/// an inner expression.
+///
+/// This results in multiple, nested match expressions, each of them matching
+/// over exactly one slice pattern. The examples below write `ident[..]` for
+/// readability, the actual expression borrows the value with
+/// `restest::__private::SliceOf`, which supports more types than indexing,
+/// and the actual pattern is wrapped in `Some(&..)`, as untyped values may not
+/// be arrays.
+///
+/// The elements of a slice can't be moved out of it. The bindings of a slice
+/// pattern, including the generated ones, are therefore bound by reference,
+/// and cloned in the body of the arm (and in the guard of the innermost arm)
+/// when they are used there.
+///
+/// Map patterns are handled the same way: the map is bound to a mutable
+/// identifier, and the inner expression removes every key from the map. For
+/// instance, `__restest__map((__restest__key!("id"), id))` leads to:
+///
+/// ```none
+/// match <expr> {
+///     mut __restest__map_0 => match (
+///         __restest__map_0.remove("id"),
+///         __restest__map_0.len(),
+///     ) {
+///         (Some(id), 0) => { /* ... */ },
+///     }
+/// }
+/// ```
+///
+/// # Example
+///
+/// Given the following pattern:
+///
+/// ```none
+/// [                   // (1)
+///     [a, 2, 3],          // (2)
+///     [b, 5, 6],          // (3)
+/// ]                   // (1)
+/// ```
+///
+/// In the first iteration, we only alter the slice pattern delimited by `(1)`.
+/// This will produce the following match expression:
+///
+/// ```none
+/// match <expr> {
+///     __restest__slice_0 => match __restest__slice_0[..] {
+///         [__restest__slice_1, __restest__slice_2] => { /* ... */ },
+///     }
+/// }
+/// ```
+///
+/// Next iteration alters slice pattern `(2)` and leads us to:
+///
+/// ```none
+/// match <expr> {
+///     __restest__slice_0 => match __restest__slice_0[..] {
+///         [__restest__slice_1, __restest__slice_2] => match __restest__slice_1[..] {
+///             [a, 2, 3] => { /* ... */ },
+///         }
+///     }
+/// }
+/// ```
+///
+/// The last iteration alters slice pattern `(3)` and expands to:
+///
+/// ```none
+/// match <expr> {
+///     __restest__slice_0 => match __restest__slice_0[..] {
+///         [__restest__slice_1, __restest__slice_2] => match __restest__slice_1[..] {
+///             [a, 2, 3] => match __restest__slice_2[..] {
+///                 [b, 5, 6] => { /* final expression */ }
+///             },
+///         }
+///     }
+/// }
+/// ```
+struct SlicePatternModifier {
+    first_expr: Expr,
+    first_pat: Pat,
+    nested_matches: Vec<(Expr, Pat, Vec<PatIdent>)>,
+    final_guard_condition: Expr,
+    return_expr: Expr,
+    failure_expr: Expr,
+}
+
+impl SlicePatternModifier {
+    fn new(
+        matched_expr: Expr,
+        pat: Pat,
+        final_guard_condition: Expr,
+        return_expr: Expr,
+    ) -> SlicePatternModifier {
+        let mut sub_slice_patterns = Vec::new();
+
+        // The same replacer is used for every iteration, so that the
+        // generated identifiers are unique across the whole expansion.
+        let mut replacer = SlicePatternReplacer::new();
+        let pat = replacer.alter_initial_pattern(pat);
+
+        let mut unaltered_slice_patterns = VecDeque::from_iter(replacer.extracted_slice_patterns());
+
+        while let Some((ident, pat)) = unaltered_slice_patterns.pop_front() {
+            let (expr, pat, borrowed) = match pat {
+                NestedPattern::Slice(pat) => {
+                    let mut pat = replacer.alter_pat_slice(pat);
+                    let borrowed = ElementBindingsBorrower::borrow(&mut pat);
+                    (
+                        Self::mk_match_expr(ident),
+                        Self::mk_slice_pat(pat),
+                        borrowed,
+                    )
+                }
+
+                NestedPattern::Map(pat) => {
+                    let (expr, pat) = Self::mk_map_match(ident, pat);
+                    (expr, replacer.alter_initial_pattern(pat), Vec::new())
+                }
+            };
+
+            sub_slice_patterns.push((expr, pat, borrowed));
+            unaltered_slice_patterns.extend(replacer.extracted_slice_patterns());
+        }
+
+        let return_expr = Self::mk_rests_to_vec(replacer.rests, return_expr);
+
+        SlicePatternModifier {
+            first_expr: matched_expr,
+            first_pat: pat,
+            nested_matches: sub_slice_patterns,
+            final_guard_condition,
+            return_expr,
+            failure_expr: Self::mk_panic_expr(),
+        }
+    }
+
+    /// Sets the expression evaluated when the value does not match the
+    /// pattern. Defaults to a panic.
+    fn with_failure_expr(mut self, failure_expr: Expr) -> SlicePatternModifier {
+        self.failure_expr = failure_expr;
+        self
+    }
+
+    fn expand(self) -> ExprMatch {
+        let mut nesting = iter::once((self.first_expr, self.first_pat, Vec::new()))
+            .chain(self.nested_matches)
+            .rev();
+
+        let (innermost_expr, innermost_pat, borrowed) = nesting.next().unwrap();
+
+        let guard = (
+            <Token![if]>::default(),
+            Box::new(Self::mk_owned(&borrowed, self.final_guard_condition, false)),
+        );
+        let return_expr = Self::mk_owned(&borrowed, self.return_expr, true);
+
+        let arms = vec![
+            Self::mk_arm(innermost_pat, Some(guard), return_expr),
+            Self::catchall_arm(self.failure_expr.clone()),
+        ];
+
+        let innermost_match = ExprMatch {
+            attrs: Vec::new(),
+            match_token: <Token![match]>::default(),
+            expr: Box::new(innermost_expr),
+            brace_token: Brace::default(),
+            arms,
+        };
+
+        let failure_expr = self.failure_expr;
+        nesting.fold(innermost_match, |inner, nested| {
+            Self::nest_match(inner, nested, failure_expr.clone())
+        })
+    }
+
+    fn nest_match(
+        inner: ExprMatch,
+        (expr, pat, borrowed): (Expr, Pat, Vec<PatIdent>),
+        failure_expr: Expr,
+    ) -> ExprMatch {
+        let match_token = <Token![match]>::default();
+        let expr = Box::new(expr);
+        let brace_token = Brace::default();
+        let arms = vec![
+            Self::mk_arm(pat, None, Self::mk_owned(&borrowed, inner.into(), true)),
+            Self::catchall_arm(failure_expr),
+        ];
+
+        ExprMatch {
+            attrs: Vec::new(),
+            match_token,
+            expr,
+            brace_token,
+            arms,
+        }
+    }
+
+    fn mk_arm(pat: Pat, guard: Option<(Token![if], Box<Expr>)>, body: Expr) -> Arm {
+        let body = Box::new(body);
+        Arm {
+            attrs: Vec::new(),
+            pat,
+            guard,
+            fat_arrow_token: FatArrow::default(),
+            body,
+            comma: Some(Comma::default()),
+        }
+    }
+
+    fn catchall_arm(failure_expr: Expr) -> Arm {
+        Arm {
+            attrs: Vec::new(),
+            pat: Pat::Wild(PatWild {
+                attrs: Vec::new(),
+                underscore_token: Token![_](Span::mixed_site()),
+            }),
+            guard: None,
+            fat_arrow_token: Token![=>](Span::mixed_site()),
+            body: Box::new(failure_expr),
+            comma: Some(Token![,](Span::mixed_site())),
+        }
+    }
+
+    /// Creates the expression that borrows the value bound to `ident` as a
+    /// slice. Refer to `restest::__private::SliceOf` for the supported types.
+    /// Copies the rests of the slices, which are bound by reference, to
+    /// `Vec`s before `return_expr` is evaluated.
+    fn mk_rests_to_vec(rests: Vec<PatIdent>, return_expr: Expr) -> Expr {
+        if rests.is_empty() {
+            return return_expr;
+        }
+
+        let rests = rests.into_iter().map(|rest| {
+            let span = generated(rest.span());
+            let ident = rest.ident;
+
+            quote_spanned! {span=>
+                let #ident = ::std::borrow::ToOwned::to_owned(#ident);
+            }
+        });
+
+        Expr::Verbatim(quote! {
+            {
+                #( #rests )*
+                #return_expr
+            }
+        })
+    }
+
+    /// Clones the bindings of `borrowed` that `expr` uses, before it is
+    /// evaluated. Bindings which were mutable are cloned to mutable bindings
+    /// if `mutable` is set.
+    fn mk_owned(borrowed: &[PatIdent], expr: Expr, mutable: bool) -> Expr {
+        let tokens = expr.to_token_stream();
+        let clones = borrowed
+            .iter()
+            .filter(|binding| mentions(&tokens, &binding.ident))
+            .map(|binding| {
+                let span = generated(binding.span());
+                let ident = &binding.ident;
+                let mutability = binding.mutability.filter(|_| mutable);
+
+                quote_spanned! {span=>
+                    let #mutability #ident = ::std::clone::Clone::clone(#ident);
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if clones.is_empty() {
+            return expr;
+        }
+
+        Expr::Verbatim(quote! {
+            {
+                #( #clones )*
+                #expr
+            }
+        })
+    }
+
+    fn mk_match_expr(ident: Ident) -> Expr {
+        let span = ident.span();
+
+        Expr::Verbatim(quote_spanned! {span=>
+            ::std::option::Option::as_deref(&{
+                use ::restest::__private::AsSlice as _;
+                ::restest::__private::SliceOf(&#ident).slice()
+            })
+        })
+    }
+
+    /// Wraps a slice pattern so that it matches the expression created by
+    /// [`mk_match_expr`](SlicePatternModifier::mk_match_expr).
+    fn mk_slice_pat(slice: PatSlice) -> Pat {
+        parse_quote! { ::std::option::Option::Some(&#slice) }
+    }
+
+    /// Creates the expression that removes every key of an encoded map
+    /// pattern from the map bound to `ident`, and the pattern that the result
+    /// must match.
+    fn mk_map_match(ident: Ident, map: PatTupleStruct) -> (Expr, Pat) {
+        let mut exprs = Vec::new();
+        let mut pats = Vec::new();
+        let mut rest = false;
+
+        for elem in map.pat.elems {
+            match elem {
+                Pat::Tuple(entry) => {
+                    let mut entry = entry.elems.into_iter();
+                    let (key, value) = match (entry.next(), entry.next()) {
+                        (Some(Pat::Macro(key)), Some(value)) => (key.mac.tokens, value),
+                        _ => unreachable!("Map entries are encoded by MapPatternModifier"),
+                    };
+
+                    // String literals are passed as is, so that maps with
+                    // `String` keys can be queried.
+                    exprs.push(match syn::parse2::<LitStr>(key.clone()) {
+                        Ok(key) => quote_spanned! {generated(key.span())=> #ident.remove(#key) },
+                        Err(_) => quote_spanned! {generated(key.span())=> #ident.remove(&(#key)) },
+                    });
+                    pats.push(quote! { ::std::option::Option::Some(#value) });
+                }
+
+                Pat::Rest(_) => rest = true,
+
+                _ => unreachable!("Map entries are encoded by MapPatternModifier"),
+            }
+        }
+
+        if !rest {
+            exprs.push(quote! { #ident.len() });
+            pats.push(quote! { 0 });
+        }
+
+        let expr = Expr::Verbatim(quote! { ( #( #exprs, )* ) });
+        let pat = parse_quote! { ( #( #pats, )* ) };
+
+        (expr, pat)
+    }
+
+    fn mk_panic_expr() -> Expr {
+        Expr::Verbatim(quote! { panic!("Matching failed")})
+    }
+}
+
+/// Returns whether `ident` appears in `tokens`.
+fn mentions(tokens: &TokenStream2, ident: &Ident) -> bool {
+    tokens.clone().into_iter().any(|tree| match tree {
+        TokenTree::Ident(other) => other == *ident,
+        TokenTree::Group(group) => mentions(&group.stream(), ident),
+        _ => false,
+    })
+}
+
+/// Binds every value of a slice pattern by reference, as they can't be moved
+/// out of the slice. The nested slice and map patterns must already have been
+/// replaced by bindings.
+///
+/// Returns the bindings as they were written, so that the values can be
+/// cloned to bindings of the same mutability. The bindings that are already
+/// by reference, such as the rests of slices, are left as is.
+#[derive(Default)]
+struct ElementBindingsBorrower {
+    bindings: Vec<PatIdent>,
+}
+
+impl ElementBindingsBorrower {
+    fn borrow(slice: &mut PatSlice) -> Vec<PatIdent> {
+        let mut this = ElementBindingsBorrower::default();
+        this.visit_pat_slice_mut(slice);
+        this.bindings
+    }
+}
+
+impl VisitMut for ElementBindingsBorrower {
+    fn visit_pat_ident_mut(&mut self, binding: &mut PatIdent) {
+        if binding.by_ref.is_none() {
+            self.bindings.push(binding.clone());
+
+            binding.by_ref = Some(Token![ref](generated(binding.ident.span())));
+            binding.mutability = None;
+        }
+
+        visit_mut::visit_pat_ident_mut(self, binding);
+    }
+}
+
+/// A pattern that is matched in a nested match expression.
+enum NestedPattern {
+    Slice(PatSlice),
+    Map(PatTupleStruct),
+}
+
+/// Helper struct for [`SlicePatternReplacer`].
+///
+/// Alters slice pattern, stores it in memory and stores it internally.
+///
+/// We only alter outermost slice patterns. This process is repeated multiple
+/// times.
+struct SlicePatternReplacer {
+    slices: Vec<(Ident, NestedPattern)>,
+    rests: Vec<PatIdent>,
+    count: usize,
+}
+
+impl SlicePatternReplacer {
+    fn new() -> SlicePatternReplacer {
+        SlicePatternReplacer {
+            slices: Vec::new(),
+            rests: Vec::new(),
+            count: 0,
+        }
+    }
+
+    fn alter_initial_pattern(&mut self, mut pat: Pat) -> Pat {
+        self.visit_pat_mut(&mut pat);
+        pat
+    }
+
+    fn alter_pat_slice(&mut self, mut pat: PatSlice) -> PatSlice {
+        self.visit_pat_slice_mut(&mut pat);
+        pat
+    }
+
+    fn extracted_slice_patterns(&mut self) -> Vec<(Ident, NestedPattern)> {
+        std::mem::take(&mut self.slices)
+    }
+
+    fn add_slice_pattern(&mut self, pat: &mut Pat, slice: PatSlice) {
+        let ident = self.mk_internal_ident("array", slice.span());
+        self.slices
+            .push((ident.clone(), NestedPattern::Slice(slice)));
+
+        *pat = Self::mk_ident_pat(ident, None);
+    }
+
+    /// Binds the rest of a slice (`rest @ ..`) by reference, as it is
+    /// unsized. It is copied to a `Vec` before being returned.
+    fn add_rest_pattern(&mut self, rest: &mut PatIdent) {
+        self.rests.push(rest.clone());
+
+        rest.by_ref = Some(Token![ref](rest.ident.span()));
+        rest.mutability = None;
+    }
+
+    fn add_map_pattern(&mut self, pat: &mut Pat, map: PatTupleStruct) {
+        let ident = self.mk_internal_ident("map", map.span());
+        self.slices.push((ident.clone(), NestedPattern::Map(map)));
+
+        *pat = Self::mk_ident_pat(ident, Some(<Token![mut]>::default()));
+    }
+
+    fn mk_ident_pat(ident: Ident, mutability: Option<Token![mut]>) -> Pat {
+        Pat::Ident(PatIdent {
+            attrs: Vec::new(),
+            by_ref: None,
+            mutability,
+            ident,
+            subpat: None,
+        })
+    }
+
+    fn mk_internal_ident(&mut self, kind: &str, span: Span) -> Ident {
+        let ident = format_ident!("__restest__{}_{}", kind, self.count, span = generated(span));
+        self.count += 1;
+        ident
+    }
+}
+
+impl VisitMut for SlicePatternReplacer {
+    fn visit_pat_mut(&mut self, pat: &mut Pat) {
+        match pat {
+            Pat::Slice(slice) => {
+                let slice = slice.clone();
+                self.add_slice_pattern(pat, slice);
+            }
+
+            Pat::TupleStruct(map) if map.path.is_ident("__restest__map") => {
+                let map = map.clone();
+                self.add_map_pattern(pat, map);
+            }
+
+            Pat::Ident(rest)
+                if rest.by_ref.is_none()
+                    && matches!(&rest.subpat, Some((_, subpat)) if matches!(**subpat, Pat::Rest(_))) =>
+            {
+                self.add_rest_pattern(rest);
+            }
+
+            _ => visit_mut::visit_pat_mut(self, pat),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // The `test` attribute of this crate would shadow the built-in one.
+    use core::prelude::v1::test;
+
+    use quote::ToTokens;
+
+    use super::*;
+
+    mod map_modifier {
+        use super::*;
+
+        #[test]
+        fn encoding() {
+            let mut pat = parse_quote! {
+                Foo { scores: map! { "alice" => 42, KEY => map! { 1 => _ }, .. } }
+            };
+
+            MapPatternModifier::alter_pattern(&mut pat);
+
+            let left = pat.to_token_stream().to_string();
+            let right = quote! {
+                Foo {
+                    scores: __restest__map(
+                        (__restest__key!("alice"), 42),
+                        (__restest__key!(KEY), __restest__map((__restest__key!(1), _),)),
+                        ..
+                    )
+                }
+            }
+            .to_string();
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn misplaced_rest() {
+            let mut pat = parse_quote! { map! { .., "alice" => 42 } };
+
+            MapPatternModifier::alter_pattern(&mut pat);
+
+            let left = pat.to_token_stream().to_string();
+            assert!(left.contains("compile_error"));
+        }
+    }
+
+    mod pattern_validator {
+        use super::*;
+
+        #[test]
+        fn casts() {
+            let tokens = quote! { Foo { id: 1 as u32 } };
+
+            assert!(PatternValidator::check_tokens(&tokens).is_err());
+        }
+
+        #[test]
+        fn casts_in_qualified_paths_and_macros() {
+            let tokens = quote! {
+                Foo {
+                    id: <Foo as Trait>::ID,
+                    count: gt!(0 as u32),
+                    scores: map! { 1 as u32 => _ },
+                }
+            };
+
+            assert!(PatternValidator::check_tokens(&tokens).is_ok());
+        }
+    }
+
+    mod untyped_object_rewriter {
+        use super::*;
+
+        #[test]
+        fn rewriting() {
+            let tokens = quote! {
+                Foo { payment: { type: "card", "last 4": last4, details: { id }, .. } }
+            };
+
+            let left = UntypedObjectRewriter::rewrite(tokens).to_string();
+            let right = quote! {
+                Foo {
+                    payment: ::restest::__private::Value::Object(map! {
+                        "type" => "card",
+                        "last 4" => last4,
+                        "details" => ::restest::__private::Value::Object(map! { "id" => id, }),
+                        ..
+                    })
+                }
+            }
+            .to_string();
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn macro_bodies() {
+            let tokens = quote! {
+                Foo {
+                    items: contains![{ id: _ }],
+                    valid: satisfies!(|v: &Value| { v.is_object() }),
+                }
+            };
+
+            let left = UntypedObjectRewriter::rewrite(tokens).to_string();
+            let right = quote! {
+                Foo {
+                    items: contains![::restest::__private::Value::Object(map! { "id" => _, })],
+                    valid: satisfies!(|v: &Value| { v.is_object() }),
+                }
+            }
+            .to_string();
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn misplaced_rest() {
+            let tokens = quote! { { .., id: _ } };
+
+            let left = UntypedObjectRewriter::rewrite(tokens).to_string();
+            assert!(left.contains("compile_error"));
+        }
+    }
+
+    mod alternatives {
+        use super::*;
+
+        #[test]
+        fn different_bindings() {
+            let call =
+                syn::parse_str::<BodyMatchCall>(r#"body, { a: a, b: "b" } | { b }"#).unwrap();
+
+            let left = call.expand_result().to_token_stream().to_string();
+            assert!(left.contains("compile_error"));
+        }
+
+        #[test]
+        fn native_alternatives() {
+            let call = syn::parse_str::<BodyMatchCall>("body, Foo::A(a) | Foo::B(a)").unwrap();
+
+            let left = call.expand_result().to_token_stream().to_string();
+            assert!(!left.contains("__restest__value"));
+        }
+    }
+
+    mod or_modifier {
+        use super::*;
+
+        #[test]
+        fn native_alternatives() {
+            let mut pat = parse_quote! { Foo { status: Status::A | Status::B, id } };
+
+            let guard = OrPatternModifier::new(&mut pat).expand_guard_expr(parse_quote! { true });
+
+            let left = pat.to_token_stream().to_string();
+            let right = quote! { Foo { status: Status::A | Status::B, id } }.to_string();
+            assert_eq!(left, right);
+            assert_eq!(guard.to_token_stream().to_string(), "true");
+        }
+
+        #[test]
+        fn guarded_alternatives() {
+            let mut pat = parse_quote! { Foo { method: "card" | "iban", id } };
+
+            let guard = OrPatternModifier::new(&mut pat).expand_guard_expr(parse_quote! { true });
+
+            let left = pat.to_token_stream().to_string();
+            let right = quote! { Foo { method: __restest__or_0, id } }.to_string();
+            assert_eq!(left, right);
+
+            let left = guard.to_token_stream().to_string();
+            let right = quote! {
+                true && (false
+                    || ::restest::body_matches!(
+                        ::std::clone::Clone::clone(&__restest__or_0),
+                        "card"
+                    ).is_ok()
+                    || ::restest::body_matches!(
+                        ::std::clone::Clone::clone(&__restest__or_0),
+                        "iban"
+                    ).is_ok())
+            }
+            .to_string();
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn bindings_in_alternatives() {
+            let mut pat = parse_quote! { Foo { inner: map! { "a" => a } | map! { "b" => _ } } };
+
+            let guard = OrPatternModifier::new(&mut pat).expand_guard_expr(parse_quote! { true });
+
+            let left = guard.to_token_stream().to_string();
+            assert!(left.contains("compile_error"));
+        }
+    }
+
+    mod binding_patterns_extractor {
+        use super::*;
+
+        #[test]
+        fn extraction_simple() {
+            let pat = parse_quote! { foo };
+
+            let return_expr = BindingPatternsExtractor::new(&pat)
+                .expand_bindings_and_return_expr()
+                .1;
+
+            let left = return_expr.to_token_stream().to_string();
+            let right = quote! { (foo,) }.to_string();
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn extraction_in_subpattern() {
+            let pat = parse_quote! { [foo, bar, .., baz ]};
+
+            let return_expr = BindingPatternsExtractor::new(&pat)
+                .expand_bindings_and_return_expr()
+                .1;
+
+            let left = return_expr.to_token_stream().to_string();
+            let right = quote! { (foo, bar, baz,) }.to_string();
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn handles_at_pattern() {
+            let pat = parse_quote! { foo @ [] };
+
+            let return_expr = BindingPatternsExtractor::new(&pat)
+                .expand_bindings_and_return_expr()
+                .1;
+
+            let left = return_expr.to_token_stream().to_string();
+            let right = quote! { (foo,) }.to_string();
+
+            assert_eq!(left, right);
+        }
+    }
+
+    mod literal_modifier {
+        use super::*;
+
+        #[test]
+        fn other_literals() {
+            let mut pat = parse_quote! {
+                Foo { ratio: -0.5, valid: true, grade: 'A', raw: b"ok", count: 42 }
+            };
+
+            let modifier = LiteralPatternModifier::new(&mut pat);
+
+            let left = pat.to_token_stream().to_string();
+            let right = quote! {
+                Foo {
+                    ratio: __restest__lit_0,
+                    valid: __restest__lit_1,
+                    grade: __restest__lit_2,
+                    raw: __restest__lit_3,
+                    count: __restest__lit_4
+                }
+            }
+            .to_string();
+            assert_eq!(left, right);
+
+            let left = modifier.expand_guard_expr().to_token_stream().to_string();
+            let right = quote! {
+                true
+                    && __restest__lit_0 == -0.5
+                    && __restest__lit_1 == true
+                    && __restest__lit_2 == 'A'
+                    && __restest__lit_3 == b"ok"
+                    && __restest__lit_4 == 42
+            }
+            .to_string();
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn simple_alteration() {
+            let mut pat = parse_quote! { "foo" };
+
+            let _ = LiteralPatternModifier::new(&mut pat);
+
+            let left = pat.to_token_stream().to_string();
+            let right = quote! {
+                __restest__lit_0
+            }
+            .to_string();
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn simple_guard_condition() {
+            let mut pat = parse_quote! { "foo" };
+
+            let modifier = LiteralPatternModifier::new(&mut pat);
+
+            let left = modifier.expand_guard_expr().to_token_stream().to_string();
+            let right = quote! {
+                true && __restest__lit_0 == "foo"
+            }
+            .to_string();
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn alteration_in_subpatterns() {
+            let mut pat = parse_quote! {
+                [
+                    Foo { bar: "bar" },
+                    ("42"),
+                    [["hello"]],
+                ]
+            };
+
+            let _ = LiteralPatternModifier::new(&mut pat);
+
+            let left = pat.to_token_stream().to_string();
+            let right = quote! {
+                [
+                    Foo { bar: __restest__lit_0 },
+                    (__restest__lit_1),
+                    [[__restest__lit_2]],
+                ]
+            }
+            .to_string();
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn expansion_in_subpatterns() {
+            let mut pat = parse_quote! {
+                [
+                    Foo { bar: "bar" },
+                    ("42"),
+                    [["hello"]],
+                ]
+            };
+
+            let left = LiteralPatternModifier::new(&mut pat)
+                .expand_guard_expr()
+                .to_token_stream()
+                .to_string();
+
+            let right = quote! {
+                true
+                    && __restest__lit_0 == "bar"
+                    && __restest__lit_1 == "42"
+                    && __restest__lit_2 == "hello"
+            }
+            .to_string();
+
+            assert_eq!(left, right);
+        }
+    }
+
+    mod guard_modifier {
+        use super::*;
+
+        #[test]
+        fn range_alteration() {
+            let mut pat = parse_quote! { Foo { age: 18..=99, height: 1.5..2.5 } };
+
+            let modifier = GuardPatternModifier::new(&mut pat);
+
+            let left = pat.to_token_stream().to_string();
+            let right = quote! {
+                Foo { age: __restest__guard_0, height: __restest__guard_1 }
+            }
+            .to_string();
+
+            assert_eq!(left, right);
+
+            let left = modifier
+                .expand_guard_expr(parse_quote! { true })
+                .to_token_stream()
+                .to_string();
+            let right = quote! {
+                true
+                    && {
+                        use ::restest::__private::AsOrd as _;
+                        ::restest::__private::OrdOf(&__restest__guard_0).in_range(&(18..=99))
+                    }
+                    && {
+                        use ::restest::__private::AsOrd as _;
+                        ::restest::__private::OrdOf(&__restest__guard_1).in_range(&(1.5..2.5))
+                    }
+            }
+            .to_string();
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn comparison_alteration() {
+            let mut pat = parse_quote! { [gt!(0), ge!(1), lt!(MAX), le!(x + 1)] };
+
+            let modifier = GuardPatternModifier::new(&mut pat);
+
+            let left = pat.to_token_stream().to_string();
+            let right = quote! {
+                [__restest__guard_0, __restest__guard_1, __restest__guard_2, __restest__guard_3]
+            }
+            .to_string();
+
+            assert_eq!(left, right);
+
+            let left = modifier
+                .expand_guard_expr(parse_quote! { true })
+                .to_token_stream()
+                .to_string();
+            let right = quote! {
+                true
+                    && {
+                        use ::restest::__private::AsOrd as _;
+                        ::restest::__private::OrdOf(&__restest__guard_0).compare(&(0))
+                    }
+                    .is_some_and(::std::cmp::Ordering::is_gt)
+                    && {
+                        use ::restest::__private::AsOrd as _;
+                        ::restest::__private::OrdOf(&__restest__guard_1).compare(&(1))
+                    }
+                    .is_some_and(::std::cmp::Ordering::is_ge)
+                    && {
+                        use ::restest::__private::AsOrd as _;
+                        ::restest::__private::OrdOf(&__restest__guard_2).compare(&(MAX))
+                    }
+                    .is_some_and(::std::cmp::Ordering::is_lt)
+                    && {
+                        use ::restest::__private::AsOrd as _;
+                        ::restest::__private::OrdOf(&__restest__guard_3).compare(&(x + 1))
+                    }
+                    .is_some_and(::std::cmp::Ordering::is_le)
+            }
+            .to_string();
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn datetime_conditions() {
+            let mut pat = parse_quote! {
+                [iso8601!(), within_last!(5 s), within_last!(2 min), within_last!(MAX_AGE)]
+            };
+
+            let modifier = GuardPatternModifier::new(&mut pat);
+
+            let left = modifier
+                .expand_guard_expr(parse_quote! { true })
+                .to_token_stream()
+                .to_string();
+            let right = quote! {
+                true
+                    && {
+                        use ::restest::__private::AsText as _;
+                        ::restest::__private::TextOf(&__restest__guard_0).text()
+                    }
+                    .is_some_and(::restest::__private::is_rfc3339)
+                    && {
+                        use ::restest::__private::AsText as _;
+                        ::restest::__private::TextOf(&__restest__guard_1).text()
+                    }
+                    .is_some_and(|__restest__text| ::restest::__private::is_within(
+                        __restest__text,
+                        ::std::time::Duration::from_secs(5)
+                    ))
+                    && {
+                        use ::restest::__private::AsText as _;
+                        ::restest::__private::TextOf(&__restest__guard_2).text()
+                    }
+                    .is_some_and(|__restest__text| ::restest::__private::is_within(
+                        __restest__text,
+                        ::std::time::Duration::from_secs(2 * 60)
+                    ))
+                    && {
+                        use ::restest::__private::AsText as _;
+                        ::restest::__private::TextOf(&__restest__guard_3).text()
+                    }
+                    .is_some_and(|__restest__text| ::restest::__private::is_within(
+                        __restest__text,
+                        MAX_AGE
+                    ))
+            }
+            .to_string();
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn string_conditions() {
+            let mut pat = parse_quote! {
+                Foo {
+                    message: contains!("error"),
+                    token: starts_with!("Bearer "),
+                    path: ends_with!(SUFFIX),
+                    status: eq_ignore_case!("ok"),
+                }
+            };
+
+            let modifier = GuardPatternModifier::new(&mut pat);
 
-impl VisitMut for SlicePatternReplacer {
-    fn visit_pat_mut(&mut self, pat: &mut Pat) {
-        match pat {
-            Pat::Slice(slice) => {
-                let slice = slice.clone();
-                self.add_slice_pattern(pat, slice);
+            let left = modifier
+                .expand_guard_expr(parse_quote! { true })
+                .to_token_stream()
+                .to_string();
+            let right = quote! {
+                true
+                    && {
+                        use ::restest::__private::AsText as _;
+                        ::restest::__private::TextOf(&__restest__guard_0).text()
+                    }
+                    .is_some_and(|__restest__text| __restest__text.contains("error"))
+                    && {
+                        use ::restest::__private::AsText as _;
+                        ::restest::__private::TextOf(&__restest__guard_1).text()
+                    }
+                    .is_some_and(|__restest__text| __restest__text.starts_with("Bearer "))
+                    && {
+                        use ::restest::__private::AsText as _;
+                        ::restest::__private::TextOf(&__restest__guard_2).text()
+                    }
+                    .is_some_and(|__restest__text| __restest__text.ends_with(SUFFIX))
+                    && {
+                        use ::restest::__private::AsText as _;
+                        ::restest::__private::TextOf(&__restest__guard_3).text()
+                    }
+                    .is_some_and(|__restest__text| {
+                        ::restest::__private::eq_ignore_case(__restest__text, "ok")
+                    })
             }
+            .to_string();
 
-            _ => visit_mut::visit_pat_mut(self, pat),
+            assert_eq!(left, right);
         }
-    }
-}
 
-#[cfg(test)]
-mod tests {
-    use quote::ToTokens;
-    use syn::parse_quote;
+        #[test]
+        fn predicate_condition() {
+            let mut pat = parse_quote! {
+                Foo { balance: satisfies!(|b: &f64| *b >= 0.0), name: satisfies!(is_valid) }
+            };
 
-    use super::*;
+            let modifier = GuardPatternModifier::new(&mut pat);
 
-    mod binding_patterns_extractor {
-        use super::*;
+            let left = modifier
+                .expand_guard_expr(parse_quote! { true })
+                .to_token_stream()
+                .to_string();
+            let right = quote! {
+                true
+                    && (|b: &f64| *b >= 0.0)(&__restest__guard_0)
+                    && (is_valid)(&__restest__guard_1)
+            }
+            .to_string();
+
+            assert_eq!(left, right);
+        }
 
         #[test]
-        fn extraction_simple() {
-            let pat = parse_quote! { foo };
+        fn custom_condition() {
+            let mut pat = parse_quote! { Foo { iban: custom!(Iban::new("FR")) } };
 
-            let return_expr = BindingPatternsExtractor::new(&pat)
-                .expand_bindings_and_return_expr()
-                .1;
+            let modifier = GuardPatternModifier::new(&mut pat);
 
-            let left = return_expr.to_token_stream().to_string();
-            let right = quote! { (foo,) }.to_string();
+            let left = modifier
+                .expand_guard_expr(parse_quote! { true })
+                .to_token_stream()
+                .to_string();
+            let right = quote! {
+                true && ::restest::__private::matches_custom(&__restest__guard_0, &Iban::new("FR"))
+            }
+            .to_string();
 
             assert_eq!(left, right);
         }
 
         #[test]
-        fn extraction_in_subpattern() {
-            let pat = parse_quote! { [foo, bar, .., baz ]};
+        fn named_pattern_condition() {
+            let mut pat = parse_quote! { Team { owner: pattern!(shapes::USER) } };
 
-            let return_expr = BindingPatternsExtractor::new(&pat)
-                .expand_bindings_and_return_expr()
-                .1;
+            let modifier = GuardPatternModifier::new(&mut pat);
 
-            let left = return_expr.to_token_stream().to_string();
-            let right = quote! { (foo, bar, baz,) }.to_string();
+            let left = modifier
+                .expand_guard_expr(parse_quote! { true })
+                .to_token_stream()
+                .to_string();
+            let right = quote! {
+                true && shapes::USER!(::std::clone::Clone::clone(&__restest__guard_0))
+            }
+            .to_string();
 
             assert_eq!(left, right);
         }
 
         #[test]
-        fn handles_at_pattern() {
-            let pat = parse_quote! { foo @ [] };
+        fn bound_matcher() {
+            let mut pat = parse_quote! { Foo { id: id @ uuid!(4), count: count @ gt!(0) } };
 
-            let return_expr = BindingPatternsExtractor::new(&pat)
-                .expand_bindings_and_return_expr()
-                .1;
+            let modifier = GuardPatternModifier::new(&mut pat);
 
-            let left = return_expr.to_token_stream().to_string();
-            let right = quote! { (foo,) }.to_string();
+            let left = pat.to_token_stream().to_string();
+            let right = quote! { Foo { id, count } }.to_string();
 
             assert_eq!(left, right);
-        }
-    }
 
-    mod string_literal_modifier {
-        use super::*;
+            let left = modifier
+                .expand_return_expr(parse_quote! { (id, count,) })
+                .to_token_stream()
+                .to_string();
+            let right = quote! {
+                {
+                    let id = ::restest::__private::parse_uuid({
+                        use ::restest::__private::AsText as _;
+                        ::restest::__private::TextOf(&id).text()
+                    });
+                    (id, count,)
+                }
+            }
+            .to_string();
+
+            assert_eq!(left, right);
+
+            let left = modifier
+                .expand_guard_expr(parse_quote! { true })
+                .to_token_stream()
+                .to_string();
+            let right = quote! {
+                true
+                    && {
+                        use ::restest::__private::AsText as _;
+                        ::restest::__private::TextOf(&id).text()
+                    }
+                    .is_some_and(|__restest__text| ::restest::__private::is_uuid(
+                        __restest__text,
+                        ::std::option::Option::Some(4)
+                    ))
+                    && {
+                        use ::restest::__private::AsOrd as _;
+                        ::restest::__private::OrdOf(&count).compare(&(0))
+                    }
+                    .is_some_and(::std::cmp::Ordering::is_gt)
+            }
+            .to_string();
+
+            assert_eq!(left, right);
+        }
 
         #[test]
-        fn simple_alteration() {
-            let mut pat = parse_quote! { "foo" };
+        fn bound_deserialize() {
+            let mut pat = parse_quote! { Foo { data: data @ deserialize!(Vec<User>) } };
 
-            let _ = StringLiteralPatternModifier::new(&mut pat);
+            let modifier = GuardPatternModifier::new(&mut pat);
 
-            let left = pat.to_token_stream().to_string();
+            let left = modifier
+                .expand_return_expr(parse_quote! { (data,) })
+                .to_token_stream()
+                .to_string();
             let right = quote! {
-                __restest__str_0
+                {
+                    let data = ::restest::__private::deserialize::<Vec<User> >(&data);
+                    (data,)
+                }
+            }
+            .to_string();
+
+            assert_eq!(left, right);
+
+            let left = modifier
+                .expand_guard_expr(parse_quote! { true })
+                .to_token_stream()
+                .to_string();
+            let right = quote! {
+                true && ::restest::__private::deserializes::<Vec<User> >(&data)
             }
             .to_string();
 
@@ -598,14 +3241,41 @@ mod tests {
         }
 
         #[test]
-        fn simple_guard_condition() {
-            let mut pat = parse_quote! { "foo" };
+        fn unordered_condition() {
+            let mut pat = parse_quote! { Foo { roles: unordered!["admin", _] } };
 
-            let modifier = StringLiteralPatternModifier::new(&mut pat);
+            let modifier = GuardPatternModifier::new(&mut pat);
 
-            let left = modifier.expand_guard_expr().to_token_stream().to_string();
+            let left = pat.to_token_stream().to_string();
+            let right = quote! { Foo { roles: __restest__guard_0 } }.to_string();
+
+            assert_eq!(left, right);
+
+            let left = modifier
+                .expand_guard_expr(parse_quote! { true })
+                .to_token_stream()
+                .to_string();
             let right = quote! {
-                true && __restest__str_0 == "foo"
+                true && {
+                    use ::restest::__private::AsSlice as _;
+                    ::restest::__private::SliceOf(&__restest__guard_0).slice()
+                }
+                .is_some_and(|__restest__items| ::restest::__private::matches_elements(
+                    &__restest__items,
+                    2usize,
+                    false,
+                    |__restest__index, __restest__element| match __restest__index {
+                        0usize => ::restest::body_matches!(
+                            ::std::clone::Clone::clone(__restest__element),
+                            "admin"
+                        ).is_ok(),
+                        1usize => ::restest::body_matches!(
+                            ::std::clone::Clone::clone(__restest__element),
+                            _
+                        ).is_ok(),
+                        _ => false,
+                    },
+                ))
             }
             .to_string();
 
@@ -613,24 +3283,53 @@ mod tests {
         }
 
         #[test]
-        fn alteration_in_subpatterns() {
+        fn contains_and_len_conditions() {
             let mut pat = parse_quote! {
-                [
-                    Foo { bar: "bar" },
-                    ("42"),
-                    [["hello"]],
-                ]
+                Foo {
+                    users: contains![User { id: 42, .. }],
+                    name: contains!("Hopper"),
+                    items: len!(10),
+                    pages: len!(1..=3),
+                }
             };
 
-            let _ = StringLiteralPatternModifier::new(&mut pat);
+            let modifier = GuardPatternModifier::new(&mut pat);
 
-            let left = pat.to_token_stream().to_string();
+            let left = modifier
+                .expand_guard_expr(parse_quote! { true })
+                .to_token_stream()
+                .to_string();
             let right = quote! {
-                [
-                    Foo { bar: __restest__str_0 },
-                    (__restest__str_1),
-                    [[__restest__str_2]],
-                ]
+                true
+                    && {
+                        use ::restest::__private::AsSlice as _;
+                        ::restest::__private::SliceOf(&__restest__guard_0).slice()
+                    }
+                    .is_some_and(|__restest__items| ::restest::__private::matches_elements(
+                        &__restest__items,
+                        1usize,
+                        true,
+                        |__restest__index, __restest__element| match __restest__index {
+                            0usize => ::restest::body_matches!(
+                                ::std::clone::Clone::clone(__restest__element),
+                                User { id: 42, .. }
+                            ).is_ok(),
+                            _ => false,
+                        },
+                    ))
+                    && {
+                        use ::restest::__private::AsText as _;
+                        ::restest::__private::TextOf(&__restest__guard_1).text()
+                    }
+                    .is_some_and(|__restest__text| __restest__text.contains("Hopper"))
+                    && ::restest::__private::IntoLen::into_len(
+                        ::restest::__private::LenOf(&__restest__guard_2).len()
+                    )
+                    .is_some_and(|__restest__len| __restest__len == (10))
+                    && ::restest::__private::IntoLen::into_len(
+                        ::restest::__private::LenOf(&__restest__guard_3).len()
+                    )
+                    .is_some_and(|__restest__len| (1..=3).contains(&__restest__len))
             }
             .to_string();
 
@@ -638,30 +3337,56 @@ mod tests {
         }
 
         #[test]
-        fn expansion_in_subpatterns() {
+        fn keys_conditions() {
             let mut pat = parse_quote! {
-                [
-                    Foo { bar: "bar" },
-                    ("42"),
-                    [["hello"]],
-                ]
+                Foo {
+                    user: keys!["id", "name"],
+                    meta: only_keys!["page", "next"],
+                }
             };
 
-            let left = StringLiteralPatternModifier::new(&mut pat)
-                .expand_guard_expr()
+            let modifier = GuardPatternModifier::new(&mut pat);
+
+            let left = modifier
+                .expand_guard_expr(parse_quote! { true })
                 .to_token_stream()
                 .to_string();
-
             let right = quote! {
                 true
-                    && __restest__str_0 == "bar"
-                    && __restest__str_1 == "42"
-                    && __restest__str_2 == "hello"
+                    && ::restest::__private::has_keys(&__restest__guard_0, &["id", "name"], true)
+                    && ::restest::__private::has_keys(&__restest__guard_1, &["page", "next"], false)
             }
             .to_string();
 
             assert_eq!(left, right);
         }
+
+        #[test]
+        fn unordered_rejects_bindings() {
+            let mut pat = parse_quote! { Foo { roles: unordered!["admin", role] } };
+
+            let modifier = GuardPatternModifier::new(&mut pat);
+
+            let guard = modifier
+                .expand_guard_expr(parse_quote! { true })
+                .to_token_stream()
+                .to_string();
+
+            assert!(guard.contains("compile_error"));
+        }
+
+        #[test]
+        fn unknown_macro_is_untouched() {
+            let mut pat = parse_quote! { Foo { bar: my_pattern!() } };
+
+            let modifier = GuardPatternModifier::new(&mut pat);
+
+            let left = pat.to_token_stream().to_string();
+            let right = quote! { Foo { bar: my_pattern!() } }.to_string();
+
+            assert_eq!(left, right);
+            assert!(modifier.conditions.is_empty());
+        }
     }
 
     #[test]
@@ -675,11 +3400,22 @@ mod tests {
 
         let right = quote! {
             let (a, b, c,) = match foo {
-                __restest__array_0 => match __restest__array_0[..] {
-                    [a, b, c] if true => (a, b, c,),
-                    _ => panic!("Matching failed"),
+                __restest__array_0 => match ::std::option::Option::as_deref(&{
+                    use ::restest::__private::AsSlice as _;
+                    ::restest::__private::SliceOf(&__restest__array_0).slice()
+                }) {
+                    ::std::option::Option::Some(&[ref a, ref b, ref c]) if true => {
+                        let a = ::std::clone::Clone::clone(a);
+                        let b = ::std::clone::Clone::clone(b);
+                        let c = ::std::clone::Clone::clone(c);
+                        {
+                            ::restest::__private::match_succeeded();
+                            (a, b, c,)
+                        }
+                    },
+                    _ => ::restest::__private::match_failed(),
                 },
-                _ => panic!("Matching failed"),
+                _ => ::restest::__private::match_failed(),
             };
         }
         .to_string();
@@ -698,14 +3434,107 @@ mod tests {
 
         let right = quote! {
             let (a, b, c,) = match foo {
-                __restest__array_0 => match __restest__array_0[..] {
-                    [__restest__array_0, b, c] => match __restest__array_0[..] {
-                        [a] if true => (a, b, c,),
-                        _ => panic!("Matching failed"),
+                __restest__array_0 => match ::std::option::Option::as_deref(&{
+                    use ::restest::__private::AsSlice as _;
+                    ::restest::__private::SliceOf(&__restest__array_0).slice()
+                }) {
+                    ::std::option::Option::Some(&[ref __restest__array_1, ref b, ref c]) => {
+                        let __restest__array_1 = ::std::clone::Clone::clone(__restest__array_1);
+                        let b = ::std::clone::Clone::clone(b);
+                        let c = ::std::clone::Clone::clone(c);
+                        match ::std::option::Option::as_deref(&{
+                            use ::restest::__private::AsSlice as _;
+                            ::restest::__private::SliceOf(&__restest__array_1).slice()
+                        }) {
+                            ::std::option::Option::Some(&[ref a]) if true => {
+                                let a = ::std::clone::Clone::clone(a);
+                                {
+                                    ::restest::__private::match_succeeded();
+                                    (a, b, c,)
+                                }
+                            },
+                            _ => ::restest::__private::match_failed(),
+                        }
+                    },
+                    _ => ::restest::__private::match_failed(),
+                },
+                _ => ::restest::__private::match_failed(),
+            };
+        }
+        .to_string();
+
+        assert_eq!(left, right);
+    }
+
+    #[test]
+    fn expand_2_with_rest() {
+        let call: BodyMatchCall = parse_quote! {
+            foo,
+            [a, rest @ ..],
+        };
+
+        let left = call.expand().to_token_stream().to_string();
+
+        let right = quote! {
+            let (a, rest,) = match foo {
+                __restest__array_0 => match ::std::option::Option::as_deref(&{
+                    use ::restest::__private::AsSlice as _;
+                    ::restest::__private::SliceOf(&__restest__array_0).slice()
+                }) {
+                    ::std::option::Option::Some(&[ref a, ref rest @ ..]) if true => {
+                        let a = ::std::clone::Clone::clone(a);
+                        {
+                            let rest = ::std::borrow::ToOwned::to_owned(rest);
+                            {
+                                ::restest::__private::match_succeeded();
+                                (a, rest,)
+                            }
+                        }
+                    },
+                    _ => ::restest::__private::match_failed(),
+                },
+                _ => ::restest::__private::match_failed(),
+            };
+        }
+        .to_string();
+
+        assert_eq!(left, right);
+    }
+
+    #[test]
+    fn expand_map() {
+        let call: BodyMatchCall = parse_quote! {
+            foo,
+            map! { "name" => "Grace", "tags" => [tag], .. },
+        };
+
+        let left = call.expand().to_token_stream().to_string();
+
+        let right = quote! {
+            let (tag,) = match foo {
+                mut __restest__map_0 => match (
+                    __restest__map_0.remove("name"),
+                    __restest__map_0.remove("tags"),
+                ) {
+                    (
+                        ::std::option::Option::Some(__restest__lit_0),
+                        ::std::option::Option::Some(__restest__array_1),
+                    ) => match ::std::option::Option::as_deref(&{
+                        use ::restest::__private::AsSlice as _;
+                        ::restest::__private::SliceOf(&__restest__array_1).slice()
+                    }) {
+                        ::std::option::Option::Some(&[ref tag]) if true && __restest__lit_0 == "Grace" => {
+                            let tag = ::std::clone::Clone::clone(tag);
+                            {
+                                ::restest::__private::match_succeeded();
+                                (tag,)
+                            }
+                        },
+                        _ => ::restest::__private::match_failed(),
                     },
-                    _ => panic!("Matching failed"),
+                    _ => ::restest::__private::match_failed(),
                 },
-                _ => panic!("Matching failed"),
+                _ => ::restest::__private::match_failed(),
             };
         }
         .to_string();
@@ -713,6 +3542,30 @@ mod tests {
         assert_eq!(left, right);
     }
 
+    #[test]
+    fn expand_result() {
+        let call: BodyMatchCall = parse_quote! {
+            foo,
+            Foo { bar, baz: "ok" },
+        };
+
+        let left = call.expand_result().to_token_stream().to_string();
+
+        #[rustfmt::skip]
+        let right = quote! {
+            match foo {
+                Foo { bar, baz: __restest__lit_0 } if true && __restest__lit_0 == "ok" =>
+                    ::std::result::Result::Ok((bar,)),
+                _ => ::std::result::Result::Err(
+                    ::restest::pattern::MatchError::new("Foo { bar , baz : \"ok\" }")
+                ),
+            }
+        }
+        .to_string();
+
+        assert_eq!(left, right);
+    }
+
     #[test]
     fn expand_2_more_than_one() {
         let call: BodyMatchCall = parse_quote! {
@@ -724,18 +3577,199 @@ mod tests {
 
         let right = quote! {
             let (foo, bar,) = match foo {
-                (__restest__array_0, __restest__array_1) => match __restest__array_0[..] {
-                    [foo] => match __restest__array_1[..] {
-                        [bar] if true => (foo, bar,),
-                        _ => panic!("Matching failed"),
+                (__restest__array_0, __restest__array_1) => match ::std::option::Option::as_deref(&{
+                    use ::restest::__private::AsSlice as _;
+                    ::restest::__private::SliceOf(&__restest__array_0).slice()
+                }) {
+                    ::std::option::Option::Some(&[ref foo]) => {
+                        let foo = ::std::clone::Clone::clone(foo);
+                        match ::std::option::Option::as_deref(&{
+                            use ::restest::__private::AsSlice as _;
+                            ::restest::__private::SliceOf(&__restest__array_1).slice()
+                        }) {
+                            ::std::option::Option::Some(&[ref bar]) if true => {
+                                let bar = ::std::clone::Clone::clone(bar);
+                                {
+                                    ::restest::__private::match_succeeded();
+                                    (foo, bar,)
+                                }
+                            },
+                            _ => ::restest::__private::match_failed(),
+                        }
                     },
-                    _ => panic!("Matching failed"),
+                    _ => ::restest::__private::match_failed(),
                 },
-                _ => panic!("Matching failed"),
+                _ => ::restest::__private::match_failed(),
             };
         }
         .to_string();
 
         assert_eq!(left, right);
     }
+
+    mod test_function {
+        use super::*;
+
+        #[test]
+        fn expand_with_context_argument() {
+            let args = parse_quote! { depends_on = "post_user", depends_on = "users::post_avatar" };
+            let function = parse_quote! {
+                async fn get_user(context: Context) -> Result<(), String> {
+                    Ok(())
+                }
+            };
+
+            let left = TestFunction::new(args, function)
+                .unwrap()
+                .expand()
+                .to_string();
+            let right = quote! {
+                #[::core::prelude::v1::test]
+                fn get_user() -> Result<(), String> {
+                    ::restest::__private::run_test(
+                        ::core::module_path!(),
+                        "get_user",
+                        &[
+                            ("post_user", || ::restest::__private::run_dependency(post_user)),
+                            (
+                                "users::post_avatar",
+                                || ::restest::__private::run_dependency(users::post_avatar)
+                            )
+                        ],
+                        ::restest::Context::from_env(),
+                        |context: Context| async move {
+                            Ok(())
+                        },
+                    )
+                }
+            }
+            .to_string();
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn expand_with_explicit_context() {
+            let args = parse_quote! { context = CONTEXT };
+            let function = parse_quote! {
+                #[ignore]
+                pub async fn get_user() {}
+            };
+
+            let left = TestFunction::new(args, function)
+                .unwrap()
+                .expand()
+                .to_string();
+            let right = quote! {
+                #[ignore]
+                #[::core::prelude::v1::test]
+                pub fn get_user() {
+                    ::restest::__private::run_test(
+                        ::core::module_path!(),
+                        "get_user",
+                        &[],
+                        CONTEXT,
+                        |_: ::restest::Context| async move {},
+                    )
+                }
+            }
+            .to_string();
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn not_async() {
+            let args = parse_quote! {};
+            let function = parse_quote! { fn get_user() {} };
+
+            assert!(TestFunction::new(args, function).is_err());
+        }
+    }
+
+    mod cases {
+        use super::*;
+
+        #[test]
+        fn derived_names() {
+            let names = [
+                case_name(&parse_quote! { "users/ghopper", StatusCode::OK }),
+                case_name(&parse_quote! { 42 }),
+                case_name(&parse_quote! { true }),
+                case_name(&parse_quote! {}),
+            ];
+
+            let left = quote! { #(#names)* }.to_string();
+            let right = quote! { users_ghopper_statuscode_ok case_42 case_true case }.to_string();
+
+            assert_eq!(left, right);
+        }
+
+        #[test]
+        fn expand() {
+            let call: CasesCall = parse_quote! {
+                #[ignore]
+                async fn get_user(path: &str) {}
+
+                ("users/ghopper"),
+                ("users/ghopper"),
+                missing: ("users/nobody"),
+            };
+
+            let left = call.expand().unwrap().to_string();
+            let right = quote! {
+                #[allow(dead_code)]
+                async fn get_user(path: &str) {}
+
+                mod get_user {
+                    use super::*;
+
+                    #[ignore]
+                    #[::core::prelude::v1::test]
+                    fn users_ghopper() {
+                        ::restest::__private::run_test(
+                            ::core::module_path!(),
+                            "get_user::users_ghopper",
+                            &[],
+                            ::restest::Context::from_env(),
+                            |_: ::restest::Context| async move {
+                                super::get_user("users/ghopper").await
+                            },
+                        )
+                    }
+
+                    #[ignore]
+                    #[::core::prelude::v1::test]
+                    fn users_ghopper_2() {
+                        ::restest::__private::run_test(
+                            ::core::module_path!(),
+                            "get_user::users_ghopper_2",
+                            &[],
+                            ::restest::Context::from_env(),
+                            |_: ::restest::Context| async move {
+                                super::get_user("users/ghopper").await
+                            },
+                        )
+                    }
+
+                    #[ignore]
+                    #[::core::prelude::v1::test]
+                    fn missing() {
+                        ::restest::__private::run_test(
+                            ::core::module_path!(),
+                            "get_user::missing",
+                            &[],
+                            ::restest::Context::from_env(),
+                            |_: ::restest::Context| async move {
+                                super::get_user("users/nobody").await
+                            },
+                        )
+                    }
+                }
+            }
+            .to_string();
+
+            assert_eq!(left, right);
+        }
+    }
 }
@@ -0,0 +1,764 @@
+//! Runtime support for the code generated by [`assert_body_matches`] and
+//! [`test`].
+//!
+//! Nothing in this module is part of the public API.
+//!
+//! [`assert_body_matches`]: crate::assert_body_matches
+//! [`test`]: crate::test
+
+use std::{
+    borrow::Cow,
+    cell::Cell,
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    future::Future,
+    ops::{Bound, Deref, RangeBounds},
+    panic::{self, AssertUnwindSafe},
+    sync::{Condvar, Mutex, PoisonError},
+    thread::{self, ThreadId},
+    time::Duration,
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use uuid::Uuid;
+
+use crate::{context::TEST_NAME, pattern::Matcher, report, Context};
+
+/// The type matched by untyped object patterns (`{ key: pattern }`).
+pub use serde_json::Value;
+
+/// Checks that `value` is equal to `expected`, ignoring case.
+pub fn eq_ignore_case(value: impl AsRef<str>, expected: impl AsRef<str>) -> bool {
+    value.as_ref().to_lowercase() == expected.as_ref().to_lowercase()
+}
+
+/// Checks that `value` is a RFC 3339 datetime.
+pub fn is_rfc3339(value: impl AsRef<str>) -> bool {
+    OffsetDateTime::parse(value.as_ref(), &Rfc3339).is_ok()
+}
+
+/// Checks that `value` is a RFC 3339 datetime that is at most `tolerance`
+/// away from the current time.
+pub fn is_within(value: impl AsRef<str>, tolerance: Duration) -> bool {
+    let datetime = match OffsetDateTime::parse(value.as_ref(), &Rfc3339) {
+        Ok(datetime) => datetime,
+        Err(_) => return false,
+    };
+
+    (OffsetDateTime::now_utc() - datetime).unsigned_abs() <= tolerance
+}
+
+/// Checks that `value` is a UUID, and that its version is `version` if
+/// specified.
+pub fn is_uuid(value: impl AsRef<str>, version: Option<usize>) -> bool {
+    match Uuid::parse_str(value.as_ref()) {
+        Ok(uuid) => version.is_none_or(|version| uuid.get_version_num() == version),
+        Err(_) => false,
+    }
+}
+
+/// Parses a value that has already been checked with [`is_uuid`].
+pub fn parse_uuid(value: Option<&str>) -> Uuid {
+    value
+        .and_then(|value| Uuid::parse_str(value).ok())
+        .expect("Value has already been checked")
+}
+
+/// Checks that `value`, serialized to JSON, can be deserialized to `T`.
+pub fn deserializes<T>(value: &impl Serialize) -> bool
+where
+    T: DeserializeOwned,
+{
+    serde_json::to_value(value)
+        .and_then(serde_json::from_value::<T>)
+        .is_ok()
+}
+
+/// Deserializes a value that has already been checked with [`deserializes`].
+pub fn deserialize<T>(value: &impl Serialize) -> T
+where
+    T: DeserializeOwned,
+{
+    serde_json::to_value(value)
+        .and_then(serde_json::from_value)
+        .expect("Value has already been checked")
+}
+
+/// Checks that every pattern accepts a distinct item, in any order. Unless
+/// `partial` is set, every item must be accepted by a pattern too.
+///
+/// `matches(pattern, item)` tells whether the pattern at index `pattern`
+/// accepts `item`. Items are paired with patterns with augmenting paths, so
+/// that a pattern accepting several items does not take the only item
+/// another pattern accepts.
+pub fn matches_elements<T>(
+    items: &[T],
+    patterns: usize,
+    partial: bool,
+    matches: impl Fn(usize, &T) -> bool,
+) -> bool {
+    if items.len() < patterns || !partial && items.len() > patterns {
+        return false;
+    }
+
+    let accepted = (0..patterns)
+        .map(|pattern| {
+            (0..items.len())
+                .filter(|&item| matches(pattern, &items[item]))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    // The pattern each item is currently paired with.
+    let mut pairs = vec![None; items.len()];
+
+    (0..patterns).all(|pattern| {
+        pair(
+            pattern,
+            &accepted,
+            &mut pairs,
+            &mut vec![false; items.len()],
+        )
+    })
+}
+
+/// Pairs `pattern` with an item it accepts, pairing the patterns of
+/// previously paired items with other items if needed.
+fn pair(
+    pattern: usize,
+    accepted: &[Vec<usize>],
+    pairs: &mut [Option<usize>],
+    visited: &mut [bool],
+) -> bool {
+    for &item in &accepted[pattern] {
+        if visited[item] {
+            continue;
+        }
+        visited[item] = true;
+
+        if pairs[item].is_none_or(|other| pair(other, accepted, pairs, visited)) {
+            pairs[item] = Some(pattern);
+            return true;
+        }
+    }
+
+    false
+}
+
+/// The value matched by a slice pattern.
+///
+/// The generated code matches `SliceOf(&value).slice()` against
+/// `Some(&[..])`, which resolves to the inherent methods of `VecDeque`s and
+/// untyped values, and to [`AsSlice`] for the values implementing
+/// `AsRef<[T]>`, such as `Vec`s, arrays and slices. Untyped values only have
+/// elements if they are arrays.
+pub struct SliceOf<'a, S>(pub &'a S);
+
+impl<'a, T> SliceOf<'a, VecDeque<T>>
+where
+    T: Clone,
+{
+    /// Borrows the elements of the deque, or clones them if they are not
+    /// contiguous in memory.
+    pub fn slice(self) -> Option<Cow<'a, [T]>> {
+        match self.0.as_slices() {
+            (front, []) => Some(Cow::Borrowed(front)),
+            _ => Some(Cow::Owned(self.0.iter().cloned().collect())),
+        }
+    }
+}
+
+impl<'a, T> SliceOf<'a, &VecDeque<T>>
+where
+    T: Clone,
+{
+    pub fn slice(self) -> Option<Cow<'a, [T]>> {
+        SliceOf(*self.0).slice()
+    }
+}
+
+impl<'a> SliceOf<'a, Value> {
+    pub fn slice(self) -> Option<Cow<'a, [Value]>> {
+        self.0
+            .as_array()
+            .map(|array| Cow::Borrowed(array.as_slice()))
+    }
+}
+
+impl<'a> SliceOf<'a, &Value> {
+    pub fn slice(self) -> Option<Cow<'a, [Value]>> {
+        SliceOf(*self.0).slice()
+    }
+}
+
+/// Borrows the value matched by a slice pattern with `AsRef<[T]>`.
+pub trait AsSlice<'a, T>
+where
+    T: Clone,
+{
+    fn slice(self) -> Option<Cow<'a, [T]>>;
+}
+
+impl<'a, S, T> AsSlice<'a, T> for SliceOf<'a, S>
+where
+    S: AsRef<[T]>,
+    T: Clone,
+{
+    fn slice(self) -> Option<Cow<'a, [T]>> {
+        Some(Cow::Borrowed(self.0.as_ref()))
+    }
+}
+
+/// The value checked by the string matchers, such as `starts_with!` or
+/// `uuid!`.
+///
+/// The generated code calls `TextOf(&value).text()`, which resolves to the
+/// inherent methods of untyped values, and to [`AsText`] for the values
+/// implementing `AsRef<str>`. Untyped values are only text if they are
+/// strings.
+pub struct TextOf<'a, S>(pub &'a S);
+
+impl<'a> TextOf<'a, Value> {
+    pub fn text(self) -> Option<&'a str> {
+        self.0.as_str()
+    }
+}
+
+impl<'a> TextOf<'a, &Value> {
+    pub fn text(self) -> Option<&'a str> {
+        TextOf(*self.0).text()
+    }
+}
+
+/// Borrows the value checked by a string matcher with `AsRef<str>`.
+pub trait AsText<'a> {
+    fn text(self) -> Option<&'a str>;
+}
+
+impl<'a, S> AsText<'a> for TextOf<'a, S>
+where
+    S: AsRef<str>,
+{
+    fn text(self) -> Option<&'a str> {
+        Some(self.0.as_ref())
+    }
+}
+
+/// The value compared by `gt!`, `ge!`, `lt!`, `le!` and range patterns.
+///
+/// The generated code calls `OrdOf(&value).compare(&bound)` and
+/// `OrdOf(&value).in_range(&range)`, which resolve to the inherent methods of
+/// untyped values, and to [`AsOrd`] for the values implementing `PartialOrd`.
+/// Untyped values can only be compared if they are numbers.
+pub struct OrdOf<'a, T>(pub &'a T);
+
+impl OrdOf<'_, Value> {
+    pub fn compare<B>(self, bound: &B) -> Option<Ordering>
+    where
+        B: Number,
+    {
+        bound.compare(self.0).map(Ordering::reverse)
+    }
+
+    pub fn in_range<R, B>(self, range: &R) -> bool
+    where
+        R: RangeBounds<B>,
+        B: Number,
+    {
+        in_range(range, |bound| OrdOf(self.0).compare(bound))
+    }
+}
+
+impl OrdOf<'_, &Value> {
+    pub fn compare<B>(self, bound: &B) -> Option<Ordering>
+    where
+        B: Number,
+    {
+        OrdOf(*self.0).compare(bound)
+    }
+
+    pub fn in_range<R, B>(self, range: &R) -> bool
+    where
+        R: RangeBounds<B>,
+        B: Number,
+    {
+        OrdOf(*self.0).in_range(range)
+    }
+}
+
+/// Compares the value of a comparison or of a range pattern with
+/// `PartialOrd`.
+pub trait AsOrd<B> {
+    fn compare(self, bound: &B) -> Option<Ordering>;
+
+    fn in_range<R>(self, range: &R) -> bool
+    where
+        R: RangeBounds<B>;
+}
+
+impl<T, B> AsOrd<B> for OrdOf<'_, T>
+where
+    T: PartialOrd<B>,
+{
+    fn compare(self, bound: &B) -> Option<Ordering> {
+        self.0.partial_cmp(bound)
+    }
+
+    fn in_range<R>(self, range: &R) -> bool
+    where
+        R: RangeBounds<B>,
+    {
+        in_range(range, |bound| self.0.partial_cmp(bound))
+    }
+}
+
+/// Checks that a value is in `range`, `compare` comparing the value to a
+/// bound.
+fn in_range<B>(range: &impl RangeBounds<B>, compare: impl Fn(&B) -> Option<Ordering>) -> bool {
+    let above = match range.start_bound() {
+        Bound::Included(start) => compare(start).is_some_and(Ordering::is_ge),
+        Bound::Excluded(start) => compare(start).is_some_and(Ordering::is_gt),
+        Bound::Unbounded => true,
+    };
+
+    let below = match range.end_bound() {
+        Bound::Included(end) => compare(end).is_some_and(Ordering::is_le),
+        Bound::Excluded(end) => compare(end).is_some_and(Ordering::is_lt),
+        Bound::Unbounded => true,
+    };
+
+    above && below
+}
+
+/// A number which an untyped value can be compared to.
+pub trait Number {
+    /// Compares the number to `value`, if it is a number.
+    fn compare(&self, value: &Value) -> Option<Ordering>;
+}
+
+macro_rules! integer_numbers {
+    ($($ty:ty)*) => {
+        $(
+            impl Number for $ty {
+                fn compare(&self, value: &Value) -> Option<Ordering> {
+                    let value = value.as_number()?;
+                    let this = *self as i128;
+
+                    match (value.as_i64(), value.as_u64()) {
+                        (Some(value), _) => Some(this.cmp(&i128::from(value))),
+                        (_, Some(value)) => Some(this.cmp(&i128::from(value))),
+                        _ => (this as f64).partial_cmp(&value.as_f64()?),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+integer_numbers!(i8 i16 i32 i64 isize u8 u16 u32 u64 usize);
+
+macro_rules! float_numbers {
+    ($($ty:ty)*) => {
+        $(
+            impl Number for $ty {
+                fn compare(&self, value: &Value) -> Option<Ordering> {
+                    f64::from(*self).partial_cmp(&value.as_f64()?)
+                }
+            }
+        )*
+    };
+}
+
+float_numbers!(f32 f64);
+
+/// The value whose length is checked by `len!`.
+///
+/// The generated code calls `LenOf(&value).len()`, which resolves to the
+/// inherent methods of untyped values, and to the `len` method of the value
+/// itself otherwise, through `Deref`. Untyped values only have a length if
+/// they are arrays, objects or strings. [`IntoLen`] then turns both results
+/// into an `Option`.
+pub struct LenOf<'a, T>(pub &'a T);
+
+impl<T> Deref for LenOf<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.0
+    }
+}
+
+#[allow(clippy::len_without_is_empty)]
+impl LenOf<'_, Value> {
+    pub fn len(&self) -> Option<usize> {
+        match self.0 {
+            Value::Array(array) => Some(array.len()),
+            Value::Object(object) => Some(object.len()),
+            Value::String(string) => Some(string.len()),
+            _ => None,
+        }
+    }
+}
+
+#[allow(clippy::len_without_is_empty)]
+impl LenOf<'_, &Value> {
+    pub fn len(&self) -> Option<usize> {
+        LenOf(*self.0).len()
+    }
+}
+
+/// The length returned by [`LenOf`].
+pub trait IntoLen {
+    fn into_len(self) -> Option<usize>;
+}
+
+impl IntoLen for usize {
+    fn into_len(self) -> Option<usize> {
+        Some(self)
+    }
+}
+
+impl IntoLen for Option<usize> {
+    fn into_len(self) -> Option<usize> {
+        self
+    }
+}
+
+/// Checks that `value`, serialized to JSON, is an object whose keys are
+/// exactly `keys`, or are all in `keys` if `exact` is not set.
+pub fn has_keys(value: &impl Serialize, keys: &[&str], exact: bool) -> bool {
+    let object = match serde_json::to_value(value) {
+        Ok(serde_json::Value::Object(object)) => object,
+        _ => return false,
+    };
+
+    object.keys().all(|key| keys.contains(&key.as_str()))
+        && (!exact || keys.iter().all(|key| object.contains_key(*key)))
+}
+
+/// Checks that `value`, serialized to JSON, is accepted by `matcher`.
+pub fn matches_custom(value: &impl Serialize, matcher: &dyn Matcher) -> bool {
+    match serde_json::to_value(value) {
+        Ok(value) => matcher.matches(&value).is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// Gives a closure the signature of a [`Matcher`], so that its argument type
+/// does not need to be written by `assert_jsonpath`.
+#[cfg(feature = "jsonpath")]
+pub fn value_matcher<F>(matcher: F) -> F
+where
+    F: Fn(&serde_json::Value) -> Result<(), crate::pattern::MatchError>,
+{
+    matcher
+}
+
+/// Records that a value matched the pattern of an `assert_body_matches`
+/// call.
+pub fn match_succeeded() {
+    report::record("assert_body_matches", &report::last_tags(), None);
+}
+
+/// Records that a value did not match the pattern of an
+/// `assert_body_matches` call, and panics.
+#[track_caller]
+pub fn match_failed() -> ! {
+    report::record(
+        "assert_body_matches",
+        &report::last_tags(),
+        Some("Matching failed".to_string()),
+    );
+    panic!("Matching failed")
+}
+
+/// Records that a value did not match the pattern of an
+/// `assert_body_matches` call which binds no variable, and panics unless
+/// run in a soft assertion scope.
+#[track_caller]
+pub fn match_failed_soft() {
+    report::check_soft(
+        "assert_body_matches",
+        &report::last_tags(),
+        Err("Matching failed".to_string()),
+    );
+}
+
+/// Records the outcome of an `assert_body_not_matches` call, and panics if
+/// the value matched the pattern, unless run in a soft assertion scope.
+#[track_caller]
+pub fn not_matched(matched: bool, pattern: &str) {
+    let result = match matched {
+        true => Err(format!("Body unexpectedly matches pattern `{}`", pattern)),
+        false => Ok(()),
+    };
+
+    report::check_soft("assert_body_not_matches", &report::last_tags(), result);
+}
+
+/// The name of a dependency of a `#[restest::test]` function, and the
+/// function which runs it and returns whether it succeeded.
+pub type Dependency = (&'static str, fn() -> bool);
+
+/// Runs the body of a `#[restest::test]` function in a new runtime, with
+/// the name of the test attached to the description of its requests.
+///
+/// The test runs at most once per process: it may already have been run as
+/// the dependency of another test, in which case its outcome is reused. It is
+/// skipped if one of its `dependencies` does not succeed.
+pub fn run_test<F, Fut>(
+    module: &'static str,
+    name: &'static str,
+    dependencies: &[Dependency],
+    context: Context,
+    test: F,
+) -> Fut::Output
+where
+    F: FnOnce(Context) -> Fut,
+    Fut: Future,
+    Fut::Output: TestOutput,
+{
+    let as_dependency = AS_DEPENDENCY.replace(false);
+    let key = format!("{}::{}", module, name);
+
+    if let Some(state) = start_test(&key) {
+        DEPENDENCY_STATE.set(Some(state));
+
+        match state {
+            _ if as_dependency => {}
+            TestState::Failed => panic!(
+                "Test '{}' already failed as a dependency of another test",
+                name
+            ),
+            TestState::Skipped => eprintln!(
+                "Test '{}' was skipped as a dependency of another test",
+                name
+            ),
+            _ => {}
+        }
+
+        return TestOutput::skipped();
+    }
+
+    let outcome = panic::catch_unwind(AssertUnwindSafe(|| {
+        for (dependency, run) in dependencies {
+            if !run() {
+                eprintln!(
+                    "Skipping test '{}': its dependency '{}' did not succeed",
+                    name, dependency
+                );
+                return (TestOutput::skipped(), TestState::Skipped);
+            }
+        }
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("Failed to build the test runtime");
+
+        let output = runtime.block_on(TEST_NAME.scope(name, test(context)));
+        let state = match output.is_success() {
+            true => TestState::Passed,
+            false => TestState::Failed,
+        };
+
+        (output, state)
+    }));
+
+    let state = match &outcome {
+        Ok((_, state)) => *state,
+        Err(_) => TestState::Failed,
+    };
+    finish_test(&key, state);
+    DEPENDENCY_STATE.set(Some(state));
+
+    match outcome {
+        Ok((output, _)) => output,
+        Err(payload) => {
+            if !as_dependency {
+                CIRCULAR.set(false);
+            }
+
+            panic::resume_unwind(payload)
+        }
+    }
+}
+
+/// Runs a `#[restest::test]` function as the dependency of another test, and
+/// returns whether it succeeded.
+pub fn run_dependency<T>(test: fn() -> T) -> bool
+where
+    T: TestOutput,
+{
+    AS_DEPENDENCY.set(true);
+    DEPENDENCY_STATE.set(None);
+
+    let output = panic::catch_unwind(test);
+    AS_DEPENDENCY.set(false);
+
+    match output {
+        // A circular dependency fails every test of the cycle, instead of
+        // skipping them.
+        Err(payload) if CIRCULAR.get() => panic::resume_unwind(payload),
+        output => match DEPENDENCY_STATE.take() {
+            Some(state) => state == TestState::Passed,
+            None => output.is_ok_and(|output| output.is_success()),
+        },
+    }
+}
+
+/// The return type of a `#[restest::test]` function.
+pub trait TestOutput {
+    /// Returns the value of a test that did not run.
+    fn skipped() -> Self;
+
+    /// Returns whether the test succeeded.
+    fn is_success(&self) -> bool;
+}
+
+impl TestOutput for () {
+    fn skipped() {}
+
+    fn is_success(&self) -> bool {
+        true
+    }
+}
+
+impl<T, E> TestOutput for Result<T, E>
+where
+    T: TestOutput,
+{
+    fn skipped() -> Self {
+        Ok(T::skipped())
+    }
+
+    fn is_success(&self) -> bool {
+        self.as_ref().is_ok_and(T::is_success)
+    }
+}
+
+/// The state of a `#[restest::test]` function in the current process.
+#[derive(Clone, Copy, PartialEq)]
+enum TestState {
+    Running(ThreadId),
+    Passed,
+    Failed,
+    Skipped,
+}
+
+/// The `#[restest::test]` functions of the current process.
+struct Tests {
+    states: BTreeMap<String, TestState>,
+    /// The test each thread is waiting for, because it runs on another
+    /// thread.
+    waiting: Vec<(ThreadId, String)>,
+    /// The tests which are part of a circular dependency.
+    circular: BTreeSet<String>,
+}
+
+static TESTS: Mutex<Tests> = Mutex::new(Tests {
+    states: BTreeMap::new(),
+    waiting: Vec::new(),
+    circular: BTreeSet::new(),
+});
+
+thread_local! {
+    /// Whether the next test run on this thread is run as a dependency.
+    static AS_DEPENDENCY: Cell<bool> = const { Cell::new(false) };
+
+    /// The final state of the last test run on this thread.
+    static DEPENDENCY_STATE: Cell<Option<TestState>> = const { Cell::new(None) };
+
+    /// Whether the panic unwinding on this thread comes from a circular
+    /// dependency.
+    static CIRCULAR: Cell<bool> = const { Cell::new(false) };
+}
+
+static TEST_FINISHED: Condvar = Condvar::new();
+
+/// Marks a test as running on the current thread, unless it already ran, in
+/// which case its final state is returned.
+///
+/// If the test is running on another thread, this waits until it finishes,
+/// unless that thread is itself waiting, directly or not, for a test running
+/// on the current thread. Such a circular dependency fails every test of the
+/// cycle.
+fn start_test(key: &str) -> Option<TestState> {
+    let current = thread::current().id();
+    let mut tests = TESTS.lock().unwrap_or_else(PoisonError::into_inner);
+
+    loop {
+        if tests.circular.contains(key) {
+            drop(tests);
+            CIRCULAR.set(true);
+            panic!("Circular dependency between tests, involving '{}'", key)
+        }
+
+        match tests.states.get(key) {
+            None => {
+                tests
+                    .states
+                    .insert(key.to_string(), TestState::Running(current));
+                return None;
+            }
+            Some(TestState::Running(_)) => {
+                if let Some(cycle) = tests.cycle(key, current) {
+                    tests.circular.extend(cycle);
+                    TEST_FINISHED.notify_all();
+                    continue;
+                }
+
+                tests.waiting.push((current, key.to_string()));
+                tests = TEST_FINISHED
+                    .wait(tests)
+                    .unwrap_or_else(PoisonError::into_inner);
+                tests.waiting.retain(|(thread, _)| *thread != current);
+            }
+            Some(state) => return Some(*state),
+        }
+    }
+}
+
+impl Tests {
+    /// Returns the tests of the cycle formed by the current thread waiting
+    /// for `key`, if any.
+    fn cycle(&self, key: &str, current: ThreadId) -> Option<Vec<String>> {
+        let mut cycle = vec![key.to_string()];
+        let mut key = key;
+
+        loop {
+            let thread = match self.states.get(key) {
+                Some(TestState::Running(thread)) => *thread,
+                _ => return None,
+            };
+
+            if thread == current {
+                return Some(cycle);
+            }
+
+            key = self
+                .waiting
+                .iter()
+                .find_map(|(waiting, key)| (*waiting == thread).then_some(key.as_str()))?;
+
+            // The waiting threads form a chain, which is at most as long as
+            // the number of running tests.
+            if cycle.len() > self.states.len() {
+                return None;
+            }
+
+            cycle.push(key.to_string());
+        }
+    }
+}
+
+fn finish_test(key: &str, state: TestState) {
+    TESTS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .states
+        .insert(key.to_string(), state);
+
+    TEST_FINISHED.notify_all();
+}
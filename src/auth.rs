@@ -0,0 +1,16 @@
+//! Helpers to obtain credentials for authenticated APIs.
+//!
+//! Each helper is gated behind a cargo feature, so that its dependencies are
+//! only compiled when needed:
+//!   - `jwt`: JSON Web Token minting and claim decoding,
+//!   - `oauth2`: OAuth2 token acquisition with the client credentials or
+//!     password grant,
+//!   - `oidc`: OpenID Connect discovery and authorization code flow
+//!     with PKCE.
+
+#[cfg(feature = "jwt")]
+pub mod jwt;
+#[cfg(feature = "oauth2")]
+pub mod oauth2;
+#[cfg(feature = "oidc")]
+pub mod oidc;
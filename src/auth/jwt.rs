@@ -0,0 +1,145 @@
+//! JSON Web Token minting and claim decoding.
+//!
+//! This module allows to sign test tokens with a known key, so that requests
+//! can be authenticated without a running identity provider, and to decode
+//! the claims of tokens returned by the server, so that they can be checked
+//! with [`assert_body_matches`](crate::assert_body_matches).
+//!
+//! This module is available with the `jwt` feature.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use http::StatusCode;
+//! use restest::{assert_body_matches, auth::jwt, Context, Request};
+//! use serde::{Deserialize, Serialize};
+//!
+//! const CONTEXT: Context = Context::new().with_port(8080);
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let token = jwt::hs256(
+//!     &Claims {
+//!         sub: "ghopper".to_string(),
+//!         exp: jwt::expires_in(3600),
+//!     },
+//!     b"secret",
+//! );
+//!
+//! let session: Session = CONTEXT
+//!     .run(Request::post("sessions").with_header("authorization", format!("Bearer {}", token)))
+//!     .await
+//!     .expect_status(StatusCode::CREATED)
+//!     .await;
+//!
+//! let claims: Claims = jwt::expect_claims(&session.token);
+//!
+//! assert_body_matches! {
+//!     claims,
+//!     Claims { sub: "ghopper", .. },
+//! }
+//! # }
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Claims {
+//!     sub: String,
+//!     exp: u64,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct Session {
+//!     token: String,
+//! }
+//! ```
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::report;
+
+/// Signs a token with the `HS256` algorithm.
+///
+/// # Panics
+///
+/// This function panics if the claims can not be serialized.
+pub fn hs256<C>(claims: &C, secret: &[u8]) -> String
+where
+    C: Serialize,
+{
+    jsonwebtoken::encode(
+        &Header::new(Algorithm::HS256),
+        claims,
+        &EncodingKey::from_secret(secret),
+    )
+    .expect("Claims serialization failed")
+}
+
+/// Signs a token with the `RS256` algorithm, using a PEM-encoded RSA private
+/// key.
+///
+/// # Error
+///
+/// This function returns an error if the key is not a valid RSA private key,
+/// or if the claims can not be serialized.
+pub fn rs256<C>(claims: &C, private_key_pem: &[u8]) -> Result<String, String>
+where
+    C: Serialize,
+{
+    let key = EncodingKey::from_rsa_pem(private_key_pem)
+        .map_err(|err| format!("Invalid RSA private key: {}", err))?;
+
+    jsonwebtoken::encode(&Header::new(Algorithm::RS256), claims, &key)
+        .map_err(|err| format!("Failed to sign token: {}", err))
+}
+
+/// Returns the Unix timestamp `seconds` seconds from now, to be used as the
+/// `exp` claim of a token.
+pub fn expires_in(seconds: u64) -> u64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("System time is before the Unix epoch");
+
+    now.as_secs() + seconds
+}
+
+/// Decodes the claims of a token, without checking its signature.
+///
+/// # Panics
+///
+/// This function panics if the token is malformed, or if its claims can not
+/// be deserialized.
+#[track_caller]
+pub fn expect_claims<T>(token: &str) -> T
+where
+    T: DeserializeOwned,
+{
+    report::check("expect_claims", &report::last_tags(), ensure_claims(token))
+}
+
+/// Decodes the claims of a token, without checking its signature.
+///
+/// # Error
+///
+/// This function returns an error if the token is malformed, or if its claims
+/// can not be deserialized.
+pub fn ensure_claims<T>(token: &str) -> Result<T, String>
+where
+    T: DeserializeOwned,
+{
+    let token = token.trim();
+    let token = token.strip_prefix("Bearer ").unwrap_or(token);
+
+    let payload = match token.split('.').collect::<Vec<_>>()[..] {
+        [_, payload, _] => payload,
+        _ => return Err(format!("Malformed JWT: {}", token)),
+    };
+
+    let payload = URL_SAFE_NO_PAD
+        .decode(payload)
+        .map_err(|err| format!("Malformed JWT payload: {}", err))?;
+
+    serde_json::from_slice(&payload).map_err(|err| format!("Failed to deserialize claims: {}", err))
+}
@@ -0,0 +1,300 @@
+//! OAuth2 token acquisition with the client credentials or password grant.
+//!
+//! The [`OAuth2`] type fetches an access token from a token endpoint, caches
+//! it for the whole test process, and fetches a new one when it expires. It
+//! implements [`Middleware`], so that it can be added to a
+//! [`Context`](crate::Context): the `Authorization` header is then added to
+//! every request that does not set it.
+//!
+//! This module is available with the `oauth2` feature.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use restest::{auth::oauth2::OAuth2, Context, Request};
+//!
+//! static AUTH: OAuth2 = OAuth2::client_credentials(
+//!     "http://localhost:8180/realms/test/protocol/openid-connect/token",
+//!     "test-client",
+//!     "secret",
+//! )
+//! .with_scope("users:read");
+//!
+//! const CONTEXT: Context = Context::new().with_port(8080).with_middleware(&AUTH);
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! CONTEXT.run(Request::get("users")).await;
+//! # }
+//! ```
+
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use futures_util::future::BoxFuture;
+use http::{header::AUTHORIZATION, HeaderValue};
+use serde::Deserialize;
+
+use crate::middleware::{Middleware, OutgoingRequest};
+
+/// Tokens expiring within this delay are considered expired, so that they do
+/// not expire while a request is in flight.
+const EXPIRY_MARGIN: Duration = Duration::from_secs(30);
+
+/// The tokens of every client, by cache key.
+static CACHE: OnceLock<Mutex<HashMap<String, Cached>>> = OnceLock::new();
+
+/// An OAuth2 client, which fetches and caches access tokens.
+#[derive(Clone, Copy)]
+pub struct OAuth2 {
+    token_endpoint: &'static str,
+    client_id: &'static str,
+    client_secret: Option<&'static str>,
+    grant: Grant,
+    scope: Option<&'static str>,
+}
+
+#[derive(Clone, Copy)]
+enum Grant {
+    ClientCredentials,
+    Password {
+        username: &'static str,
+        password: &'static str,
+    },
+}
+
+struct Cached {
+    token: Token,
+    expires_at: Option<Instant>,
+}
+
+/// The token returned by the token endpoint.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Token {
+    /// The access token.
+    pub access_token: String,
+    /// The token type, usually `Bearer`.
+    pub token_type: String,
+    /// The refresh token, if any.
+    pub refresh_token: Option<String>,
+    /// The lifetime of the access token, in seconds, if specified.
+    pub expires_in: Option<u64>,
+}
+
+impl Token {
+    /// Returns the value of an `Authorization` header carrying the access
+    /// token.
+    pub fn bearer(&self) -> String {
+        format!("Bearer {}", self.access_token)
+    }
+}
+
+impl OAuth2 {
+    /// Creates a client which uses the client credentials grant.
+    pub const fn client_credentials(
+        token_endpoint: &'static str,
+        client_id: &'static str,
+        client_secret: &'static str,
+    ) -> OAuth2 {
+        OAuth2::new(token_endpoint, client_id, Grant::ClientCredentials)
+            .with_client_secret(client_secret)
+    }
+
+    /// Creates a client which uses the resource owner password grant.
+    ///
+    /// The client secret can be set with
+    /// [`with_client_secret`](OAuth2::with_client_secret), for confidential
+    /// clients.
+    pub const fn password(
+        token_endpoint: &'static str,
+        client_id: &'static str,
+        username: &'static str,
+        password: &'static str,
+    ) -> OAuth2 {
+        OAuth2::new(
+            token_endpoint,
+            client_id,
+            Grant::Password { username, password },
+        )
+    }
+
+    const fn new(token_endpoint: &'static str, client_id: &'static str, grant: Grant) -> OAuth2 {
+        OAuth2 {
+            token_endpoint,
+            client_id,
+            client_secret: None,
+            grant,
+            scope: None,
+        }
+    }
+
+    /// Sets the client secret.
+    pub const fn with_client_secret(self, client_secret: &'static str) -> OAuth2 {
+        OAuth2 {
+            client_secret: Some(client_secret),
+            ..self
+        }
+    }
+
+    /// Sets the requested scopes, separated by spaces.
+    ///
+    /// No scope is requested by default.
+    pub const fn with_scope(self, scope: &'static str) -> OAuth2 {
+        OAuth2 {
+            scope: Some(scope),
+            ..self
+        }
+    }
+
+    /// Returns a valid token.
+    ///
+    /// The token is fetched once, then reused by every client with the same
+    /// configuration until it expires. An expired
+    /// token is refreshed with its refresh token if any, and fetched again
+    /// otherwise.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the token can not be fetched.
+    pub async fn token(&self) -> Result<Token, String> {
+        let key = self.cache_key();
+
+        let refresh_token = {
+            let cache = cache().lock().unwrap();
+
+            match cache.get(&key) {
+                Some(cached) if !cached.is_expired() => return Ok(cached.token.clone()),
+                Some(cached) => cached.token.refresh_token.clone(),
+                None => None,
+            }
+        };
+
+        let token = match refresh_token {
+            Some(refresh_token) => match self.refresh(&refresh_token).await {
+                Ok(token) => token,
+                Err(_) => self.fetch().await?,
+            },
+            None => self.fetch().await?,
+        };
+
+        let expires_at = token
+            .expires_in
+            .map(|expires_in| Instant::now() + Duration::from_secs(expires_in));
+
+        cache().lock().unwrap().insert(
+            key,
+            Cached {
+                token: token.clone(),
+                expires_at,
+            },
+        );
+
+        Ok(token)
+    }
+
+    fn cache_key(&self) -> String {
+        let username = match self.grant {
+            Grant::ClientCredentials => "",
+            Grant::Password { username, .. } => username,
+        };
+
+        format!(
+            "{} {} {} {}",
+            self.token_endpoint,
+            self.client_id,
+            username,
+            self.scope.unwrap_or_default()
+        )
+    }
+
+    async fn fetch(&self) -> Result<Token, String> {
+        let mut params = match self.grant {
+            Grant::ClientCredentials => vec![("grant_type", "client_credentials")],
+            Grant::Password { username, password } => vec![
+                ("grant_type", "password"),
+                ("username", username),
+                ("password", password),
+            ],
+        };
+
+        if let Some(scope) = self.scope {
+            params.push(("scope", scope));
+        }
+
+        self.request(params).await
+    }
+
+    async fn refresh(&self, refresh_token: &str) -> Result<Token, String> {
+        self.request(vec![
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token),
+        ])
+        .await
+    }
+
+    async fn request(&self, mut params: Vec<(&str, &str)>) -> Result<Token, String> {
+        params.push(("client_id", self.client_id));
+        if let Some(secret) = self.client_secret {
+            params.push(("client_secret", secret));
+        }
+
+        let response = reqwest::Client::new()
+            .post(self.token_endpoint)
+            .form(&params)
+            .send()
+            .await
+            .map_err(|err| format!("Failed to fetch OAuth2 token: {}", err))?;
+
+        if !response.status().is_success() {
+            return Err(format!(
+                "Failed to fetch OAuth2 token: server responded {}. Body is {}",
+                response.status(),
+                response.text().await.unwrap_or_default()
+            ));
+        }
+
+        response
+            .json()
+            .await
+            .map_err(|err| format!("Failed to deserialize OAuth2 token: {}", err))
+    }
+}
+
+fn cache() -> &'static Mutex<HashMap<String, Cached>> {
+    CACHE.get_or_init(Default::default)
+}
+
+impl Cached {
+    fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| Instant::now() + EXPIRY_MARGIN >= expires_at)
+    }
+}
+
+impl Middleware for OAuth2 {
+    /// Adds the `Authorization` header to the request, unless it is already
+    /// set.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the token can not be fetched.
+    fn on_request<'a>(&'a self, request: &'a mut OutgoingRequest) -> BoxFuture<'a, ()> {
+        Box::pin(async move {
+            if request.headers.contains_key(AUTHORIZATION) {
+                return;
+            }
+
+            let token = match self.token().await {
+                Ok(token) => token,
+                Err(err) => panic!("{}", err),
+            };
+
+            let value =
+                HeaderValue::from_str(&token.bearer()).expect("Header value conversion failed");
+            request.headers.insert(AUTHORIZATION, value);
+        })
+    }
+}
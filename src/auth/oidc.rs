@@ -0,0 +1,410 @@
+//! OpenID Connect discovery and authorization code flow with PKCE.
+//!
+//! This module allows to obtain tokens from a test identity provider (such as
+//! Keycloak) without any manual browser step. The [`AuthorizationCodeFlow`]
+//! type performs the whole authorization code flow: it fetches the login page,
+//! submits the user credentials to the login form, and exchanges the
+//! authorization code for tokens using a
+//! [PKCE](https://datatracker.ietf.org/doc/html/rfc7636) code verifier.
+//!
+//! This module is available with the `oidc` feature.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use restest::{auth::oidc::{AuthorizationCodeFlow, Discovery}, Request};
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let discovery = Discovery::fetch("http://localhost:8180/realms/test")
+//!     .await
+//!     .unwrap();
+//!
+//! let tokens = AuthorizationCodeFlow::new(discovery, "test-client", "http://localhost/callback")
+//!     .with_credentials("ghopper", "cobol")
+//!     .run()
+//!     .await
+//!     .unwrap();
+//!
+//! let request = Request::get("users/me").with_header("authorization", tokens.bearer());
+//! # }
+//! ```
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use rand::{distributions::Alphanumeric, Rng};
+use reqwest::{header::LOCATION, redirect::Policy, Client, Response};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use url::Url;
+
+/// The maximum number of redirections followed during the flow.
+const MAX_REDIRECTIONS: usize = 10;
+
+/// The OpenID Connect provider metadata.
+///
+/// Only the fields that are needed to run the authorization code flow are
+/// stored.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Discovery {
+    /// The issuer identifier.
+    pub issuer: String,
+    /// The URL of the authorization endpoint.
+    pub authorization_endpoint: String,
+    /// The URL of the token endpoint.
+    pub token_endpoint: String,
+    /// The URL of the user info endpoint, if any.
+    pub userinfo_endpoint: Option<String>,
+    /// The URL of the JSON Web Key Set document, if any.
+    pub jwks_uri: Option<String>,
+}
+
+impl Discovery {
+    /// Fetches the discovery document of an issuer.
+    ///
+    /// The document is fetched from `{issuer}/.well-known/openid-configuration`.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the document can not be fetched or
+    /// deserialized.
+    pub async fn fetch(issuer: &str) -> Result<Discovery, String> {
+        let url = format!(
+            "{}/.well-known/openid-configuration",
+            issuer.trim_end_matches('/')
+        );
+
+        let response = reqwest::get(&url)
+            .await
+            .map_err(|err| format!("Failed to fetch discovery document '{}': {}", url, err))?;
+
+        if !response.status().is_success() {
+            return Err(format!(
+                "Failed to fetch discovery document '{}': server responded {}",
+                url,
+                response.status()
+            ));
+        }
+
+        response.json().await.map_err(|err| {
+            format!(
+                "Failed to deserialize discovery document '{}': {}",
+                url, err
+            )
+        })
+    }
+}
+
+/// A PKCE code verifier and its `S256` code challenge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pkce {
+    /// The code verifier, sent to the token endpoint.
+    pub verifier: String,
+    /// The code challenge, sent to the authorization endpoint.
+    pub challenge: String,
+}
+
+impl Pkce {
+    /// Generates a random code verifier and computes its code challenge.
+    pub fn new() -> Pkce {
+        Pkce::from_verifier(random_string(64))
+    }
+
+    /// Computes the code challenge of a given code verifier.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use restest::auth::oidc::Pkce;
+    ///
+    /// // Example taken from RFC 7636, appendix B.
+    /// let pkce = Pkce::from_verifier("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk");
+    ///
+    /// assert_eq!(pkce.challenge, "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM");
+    /// ```
+    pub fn from_verifier(verifier: impl ToString) -> Pkce {
+        let verifier = verifier.to_string();
+        let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+
+        Pkce {
+            verifier,
+            challenge,
+        }
+    }
+}
+
+impl Default for Pkce {
+    fn default() -> Pkce {
+        Pkce::new()
+    }
+}
+
+/// The tokens returned by the token endpoint.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Tokens {
+    /// The access token.
+    pub access_token: String,
+    /// The token type, usually `Bearer`.
+    pub token_type: String,
+    /// The ID token, if the `openid` scope was requested.
+    pub id_token: Option<String>,
+    /// The refresh token, if any.
+    pub refresh_token: Option<String>,
+    /// The lifetime of the access token, in seconds, if specified.
+    pub expires_in: Option<u64>,
+}
+
+impl Tokens {
+    /// Returns the value of an `Authorization` header carrying the access
+    /// token.
+    pub fn bearer(&self) -> String {
+        format!("Bearer {}", self.access_token)
+    }
+}
+
+/// An automated authorization code flow with PKCE.
+///
+/// The flow submits the user credentials to the first HTML form of the login
+/// page, using the `username` and `password` field names. This matches the
+/// login pages of the usual test identity providers, such as Keycloak.
+pub struct AuthorizationCodeFlow {
+    discovery: Discovery,
+    client_id: String,
+    client_secret: Option<String>,
+    redirect_uri: String,
+    scope: String,
+    username: String,
+    password: String,
+}
+
+impl AuthorizationCodeFlow {
+    /// Creates a new flow for a given client.
+    ///
+    /// The redirect URI must be registered for the client by the identity
+    /// provider. It is never actually requested.
+    pub fn new(
+        discovery: Discovery,
+        client_id: impl ToString,
+        redirect_uri: impl ToString,
+    ) -> AuthorizationCodeFlow {
+        AuthorizationCodeFlow {
+            discovery,
+            client_id: client_id.to_string(),
+            client_secret: None,
+            redirect_uri: redirect_uri.to_string(),
+            scope: "openid".to_string(),
+            username: String::new(),
+            password: String::new(),
+        }
+    }
+
+    /// Sets the client secret, for confidential clients.
+    pub fn with_client_secret(mut self, client_secret: impl ToString) -> AuthorizationCodeFlow {
+        self.client_secret = Some(client_secret.to_string());
+
+        self
+    }
+
+    /// Sets the requested scopes, separated by spaces.
+    ///
+    /// The default scope is `openid`.
+    pub fn with_scope(mut self, scope: impl ToString) -> AuthorizationCodeFlow {
+        self.scope = scope.to_string();
+
+        self
+    }
+
+    /// Sets the credentials submitted to the login form.
+    pub fn with_credentials(
+        mut self,
+        username: impl ToString,
+        password: impl ToString,
+    ) -> AuthorizationCodeFlow {
+        self.username = username.to_string();
+        self.password = password.to_string();
+
+        self
+    }
+
+    /// Runs the flow and returns the tokens issued by the identity provider.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if any step of the flow fails: the login
+    /// page can not be fetched or does not contain a form, the credentials are
+    /// rejected, the state does not match, or the code exchange fails.
+    pub async fn run(&self) -> Result<Tokens, String> {
+        let client = Client::builder()
+            .cookie_store(true)
+            .redirect(Policy::none())
+            .build()
+            .map_err(|err| format!("Failed to create client: {}", err))?;
+
+        let pkce = Pkce::new();
+        let state = random_string(32);
+
+        let mut authorization_url = Url::parse(&self.discovery.authorization_endpoint)
+            .map_err(|err| format!("Invalid authorization endpoint: {}", err))?;
+        authorization_url
+            .query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.client_id)
+            .append_pair("redirect_uri", &self.redirect_uri)
+            .append_pair("scope", &self.scope)
+            .append_pair("state", &state)
+            .append_pair("code_challenge", &pkce.challenge)
+            .append_pair("code_challenge_method", "S256");
+
+        let response = self
+            .follow(&client, client.get(authorization_url.clone()))
+            .await?;
+
+        let callback = match response {
+            Step::Callback(callback) => callback,
+            Step::Page(page_url, page) => {
+                let action = form_action(&page)
+                    .ok_or_else(|| format!("No login form found at '{}'", page_url))?;
+                let action = page_url
+                    .join(&action)
+                    .map_err(|err| format!("Invalid login form action '{}': {}", action, err))?;
+
+                let login = client.post(action).form(&[
+                    ("username", self.username.as_str()),
+                    ("password", self.password.as_str()),
+                ]);
+
+                match self.follow(&client, login).await? {
+                    Step::Callback(callback) => callback,
+                    Step::Page(url, _) => {
+                        return Err(format!(
+                            "Login failed: stopped at '{}' instead of redirecting to '{}'",
+                            url, self.redirect_uri
+                        ))
+                    }
+                }
+            }
+        };
+
+        let mut code = None;
+        let mut returned_state = None;
+        for (key, value) in callback.query_pairs() {
+            match key.as_ref() {
+                "code" => code = Some(value.into_owned()),
+                "state" => returned_state = Some(value.into_owned()),
+                "error" => return Err(format!("Authorization failed: {}", value)),
+                _ => {}
+            }
+        }
+
+        if returned_state.as_deref() != Some(state.as_str()) {
+            return Err("Authorization failed: state mismatch".to_string());
+        }
+
+        let code = code.ok_or_else(|| format!("No authorization code in '{}'", callback))?;
+
+        let mut params = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code.as_str()),
+            ("redirect_uri", self.redirect_uri.as_str()),
+            ("client_id", self.client_id.as_str()),
+            ("code_verifier", pkce.verifier.as_str()),
+        ];
+        if let Some(secret) = &self.client_secret {
+            params.push(("client_secret", secret.as_str()));
+        }
+
+        let response = client
+            .post(&self.discovery.token_endpoint)
+            .form(&params)
+            .send()
+            .await
+            .map_err(|err| format!("Failed to exchange authorization code: {}", err))?;
+
+        if !response.status().is_success() {
+            return Err(format!(
+                "Failed to exchange authorization code: server responded {}. Body is {}",
+                response.status(),
+                response.text().await.unwrap_or_default()
+            ));
+        }
+
+        response
+            .json()
+            .await
+            .map_err(|err| format!("Failed to deserialize tokens: {}", err))
+    }
+
+    /// Sends a request and follows redirections until either a page is
+    /// returned or the identity provider redirects to the redirect URI.
+    async fn follow(
+        &self,
+        client: &Client,
+        request: reqwest::RequestBuilder,
+    ) -> Result<Step, String> {
+        let mut response = send(request).await?;
+
+        for _ in 0..MAX_REDIRECTIONS {
+            let url = response.url().clone();
+
+            let location = match response.headers().get(LOCATION) {
+                Some(location) if response.status().is_redirection() => location,
+                _ => {
+                    let page = response
+                        .text()
+                        .await
+                        .map_err(|err| format!("Failed to read page '{}': {}", url, err))?;
+                    return Ok(Step::Page(url, page));
+                }
+            };
+
+            let location = location
+                .to_str()
+                .ok()
+                .and_then(|location| url.join(location).ok())
+                .ok_or_else(|| format!("Invalid redirection from '{}'", url))?;
+
+            if location.as_str().starts_with(&self.redirect_uri) {
+                return Ok(Step::Callback(location));
+            }
+
+            response = send(client.get(location)).await?;
+        }
+
+        Err(format!(
+            "Too many redirections while authenticating against '{}'",
+            self.discovery.issuer
+        ))
+    }
+}
+
+enum Step {
+    Page(Url, String),
+    Callback(Url),
+}
+
+async fn send(request: reqwest::RequestBuilder) -> Result<Response, String> {
+    request
+        .send()
+        .await
+        .map_err(|err| format!("Request to identity provider failed: {}", err))
+}
+
+/// Extracts the `action` attribute of the first form of an HTML page.
+fn form_action(page: &str) -> Option<String> {
+    let form = &page[page.find("<form")?..];
+    let form = &form[..form.find('>')?];
+    let action = &form[form.find("action=")? + "action=".len()..];
+
+    let quote = action.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let action = &action[1..];
+    let action = &action[..action.find(quote)?];
+
+    Some(action.replace("&amp;", "&"))
+}
+
+fn random_string(len: usize) -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(len)
+        .map(char::from)
+        .collect()
+}
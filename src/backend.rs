@@ -0,0 +1,387 @@
+//! Run the service under test as a child process.
+//!
+//! This module provides the [`Backend`] type, which spawns the service under
+//! test, for instance with `cargo run`, waits until it is ready, and returns
+//! a [`BackendProcess`]. The output of the process is captured: its last
+//! lines are appended to the failure message of the assertions following a
+//! request sent with the context of the backend, and to the error returned
+//! when the service does not become ready.
+//!
+//! The process is killed when the [`BackendProcess`] is dropped or stopped
+//! with [`BackendProcess::stop`]. Otherwise, it is killed when the test
+//! process exits, even if it crashes, by a small shell process which waits
+//! for the test process to exit. The whole process group is killed, so that
+//! the service itself is killed along with the `cargo` process that spawned
+//! it.
+//!
+//! This module requires a Unix system.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//!
+//! use http::StatusCode;
+//! use restest::{backend::Backend, Context, Request};
+//!
+//! const CONTEXT: Context = Context::new().with_port(8080);
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let backend = Backend::cargo_run("--bin api-server")
+//!     .with_env("PORT", "8080")
+//!     .with_context(CONTEXT)
+//!     .with_readiness("health", Duration::from_secs(120))
+//!     .start()
+//!     .await;
+//!
+//! backend
+//!     .context()
+//!     .run(Request::get("users"))
+//!     .await
+//!     .expect_status::<Vec<String>>(StatusCode::OK)
+//!     .await;
+//!
+//! backend.stop();
+//! # }
+//! ```
+
+use std::{
+    collections::VecDeque,
+    io::{BufRead, BufReader, Read},
+    os::unix::process::CommandExt,
+    pin::pin,
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    thread,
+    time::Duration,
+};
+
+use futures_util::future::{self, Either};
+
+use crate::{report, watchdog, Context};
+
+/// The number of output lines kept for failure messages.
+const OUTPUT_LINES: usize = 20;
+
+/// The time between two checks of whether a starting service has exited.
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The output of the running backends, appended to failure messages.
+static OUTPUTS: Mutex<Vec<Output>> = Mutex::new(Vec::new());
+
+/// A service to run as a child process.
+#[derive(Clone)]
+pub struct Backend {
+    program: String,
+    args: Vec<String>,
+    env: Vec<(String, String)>,
+    context: Context,
+    readiness: Option<(String, Duration)>,
+}
+
+impl Backend {
+    /// Creates a backend which runs `program` with `args`.
+    pub fn command<I, S>(program: impl ToString, args: I) -> Backend
+    where
+        I: IntoIterator<Item = S>,
+        S: ToString,
+    {
+        Backend {
+            program: program.to_string(),
+            args: args.into_iter().map(|arg| arg.to_string()).collect(),
+            env: Vec::new(),
+            context: Context::new(),
+            readiness: None,
+        }
+    }
+
+    /// Creates a backend which runs `cargo run --quiet`, followed by
+    /// whitespace-separated `args`, such as `--bin api-server`.
+    pub fn cargo_run(args: &str) -> Backend {
+        let args = ["run", "--quiet"]
+            .into_iter()
+            .chain(args.split_whitespace());
+
+        Backend::command("cargo", args)
+    }
+
+    /// Sets an environment variable of the process.
+    pub fn with_env(mut self, name: impl ToString, value: impl ToString) -> Backend {
+        self.env.push((name.to_string(), value.to_string()));
+
+        self
+    }
+
+    /// Sets the context which sends requests to the backend.
+    ///
+    /// The default context is [`Context::new`].
+    pub fn with_context(mut self, context: Context) -> Backend {
+        self.context = context;
+
+        self
+    }
+
+    /// Waits until `path` responds with a success status, for at most
+    /// `timeout`, before returning the process.
+    ///
+    /// Refer to [`Context::wait_until_ready`] for more details. The timeout
+    /// should include the time it takes to compile the service. By default,
+    /// the process is returned as soon as it is spawned.
+    pub fn with_readiness(mut self, path: impl ToString, timeout: Duration) -> Backend {
+        self.readiness = Some((path.to_string(), timeout));
+
+        self
+    }
+
+    /// Spawns the process, and waits until the service is ready.
+    ///
+    /// This function performs I/O, therefore it is marked as `async`.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the process can not be spawned, or if the
+    /// service is not ready in time.
+    pub async fn start(self) -> BackendProcess {
+        match self.ensure_start().await {
+            Ok(process) => process,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Spawns the process, and waits until the service is ready.
+    ///
+    /// This function performs I/O, therefore it is marked as `async`.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the process can not be spawned, if it
+    /// exits, or if the service is not ready in time. The process is killed
+    /// in the latter case, and the error includes its last output lines.
+    pub async fn ensure_start(self) -> Result<BackendProcess, String> {
+        let mut command = Command::new(&self.program);
+        command
+            .args(&self.args)
+            .envs(self.env.iter().map(|(name, value)| (name, value)))
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            // The service gets its own process group, so that it can be
+            // killed along with its children.
+            .process_group(0);
+
+        let mut child = command
+            .spawn()
+            .map_err(|err| format!("Failed to spawn backend '{}': {}", self.description(), err))?;
+
+        watchdog::watch(&kill_group(child.id()));
+
+        let output = Output::new(self.description(), self.context.base_url());
+        if let Some(stdout) = child.stdout.take() {
+            output.capture(stdout);
+        }
+        if let Some(stderr) = child.stderr.take() {
+            output.capture(stderr);
+        }
+
+        let process = BackendProcess {
+            child: Mutex::new(Some(child)),
+            output,
+            context: self.context,
+        };
+
+        process.output.register();
+
+        if let Some((path, timeout)) = self.readiness {
+            let ready = pin!(process.context.ensure_ready(path, timeout));
+            let exited = pin!(process.exited());
+
+            let err = match future::select(ready, exited).await {
+                Either::Left((Ok(()), _)) => None,
+                Either::Left((Err(err), _)) => Some(err),
+                Either::Right((status, _)) => Some(format!(
+                    "Backend '{}' exited before being ready ({})",
+                    process.output.description, status
+                )),
+            };
+
+            if let Some(err) = err {
+                let lines = process.output.lines();
+                process.stop();
+
+                return Err(format!("{}\n\n{}", err, lines));
+            }
+        }
+
+        Ok(process)
+    }
+
+    fn description(&self) -> String {
+        format!("{} {}", self.program, self.args.join(" "))
+    }
+}
+
+/// A running service, which is killed when dropped.
+pub struct BackendProcess {
+    /// The process, until it is stopped.
+    child: Mutex<Option<Child>>,
+    output: Output,
+    context: Context,
+}
+
+impl BackendProcess {
+    /// Returns the context which sends requests to the service.
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
+    /// Returns the last lines written by the service to its standard output
+    /// and its standard error.
+    pub fn output(&self) -> Vec<String> {
+        self.output.buffer().iter().cloned().collect()
+    }
+
+    /// Kills the service now, instead of when the process is dropped.
+    ///
+    /// This method does not wait for the service to exit, so that it does
+    /// not block the runtime when called from an `async` test. Calling it
+    /// again has no effect.
+    pub fn stop(&self) {
+        let Some(mut child) = self.lock().take() else {
+            return;
+        };
+
+        self.output.unregister();
+
+        // The service may already have exited. As it is not reaped yet, its
+        // process group can't be reused by another process.
+        let kill = Command::new("sh")
+            .arg("-c")
+            .arg(kill_group(child.id()))
+            .stderr(Stdio::null())
+            .spawn();
+
+        thread::spawn(move || {
+            if let Ok(mut kill) = kill {
+                let _ = kill.wait();
+            }
+
+            let _ = child.wait();
+        });
+    }
+
+    /// Waits until the service exits.
+    async fn exited(&self) -> ExitStatus {
+        loop {
+            if let Some(Ok(Some(status))) = self.lock().as_mut().map(Child::try_wait) {
+                return status;
+            }
+
+            tokio::time::sleep(EXIT_POLL_INTERVAL).await;
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<Child>> {
+        self.child.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Drop for BackendProcess {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Returns the shell command which kills the process group led by `pid`.
+fn kill_group(pid: u32) -> String {
+    format!("kill -TERM -{}", pid)
+}
+
+/// The last output lines of a service, shared with the threads reading them.
+#[derive(Clone)]
+struct Output {
+    description: Arc<String>,
+    /// The base URL of the context which sends requests to the service.
+    base_url: Arc<String>,
+    lines: Arc<Mutex<VecDeque<String>>>,
+}
+
+impl Output {
+    fn new(description: String, base_url: String) -> Output {
+        Output {
+            description: Arc::new(description),
+            base_url: Arc::new(base_url),
+            lines: Arc::new(Mutex::new(VecDeque::new())),
+        }
+    }
+
+    /// Reads a stream of the service in a thread, until it is closed.
+    fn capture(&self, stream: impl Read + Send + 'static) {
+        let output = self.clone();
+
+        thread::spawn(move || {
+            for line in BufReader::new(stream).lines() {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(_) => return,
+                };
+
+                let mut lines = output.buffer();
+                if lines.len() == OUTPUT_LINES {
+                    lines.pop_front();
+                }
+                lines.push_back(line);
+            }
+        });
+    }
+
+    fn buffer(&self) -> MutexGuard<'_, VecDeque<String>> {
+        self.lines.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Formats the last output lines, for a failure message.
+    fn lines(&self) -> String {
+        let lines = self.buffer();
+
+        if lines.is_empty() {
+            return format!("Backend '{}' wrote nothing", self.description);
+        }
+
+        let lines = lines
+            .iter()
+            .map(|line| format!("  | {}", line))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        format!("Last output of backend '{}':\n{}", self.description, lines)
+    }
+
+    fn register(&self) {
+        report::set_annotator(annotate);
+        lock_outputs().push(self.clone());
+    }
+
+    fn unregister(&self) {
+        lock_outputs().retain(|output| !Arc::ptr_eq(&output.lines, &self.lines));
+    }
+}
+
+fn lock_outputs() -> MutexGuard<'static, Vec<Output>> {
+    OUTPUTS.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Appends the last output lines of the running backends which `base_url`
+/// points to to a failure message.
+fn annotate(message: String, base_url: &str) -> String {
+    let lines = lock_outputs()
+        .iter()
+        .filter(|output| *output.base_url == base_url)
+        .map(Output::lines)
+        .collect::<Vec<_>>();
+
+    if lines.is_empty() {
+        return message;
+    }
+
+    format!("{}\n\n{}", message, lines.join("\n\n"))
+}
@@ -0,0 +1,179 @@
+//! Parse the caching headers of a response.
+//!
+//! This module provides the [`CachePolicy`] type, which is returned by
+//! [`RequestResult::expect_cache_policy`](crate::request::RequestResult::expect_cache_policy).
+//! It gathers the directives of the `Cache-Control` header, the `Age` header
+//! and the `Vary` header, so that the caching contract of an endpoint, as
+//! seen by a CDN or a browser, can be checked by a test. The `Expires` and
+//! `Pragma` headers, which are superseded by `Cache-Control`, are ignored.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//!
+//! use http::StatusCode;
+//! use restest::{Context, Request};
+//!
+//! const CONTEXT: Context = Context::new().with_port(8080);
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let result = CONTEXT
+//!     .run(Request::get("catalog"))
+//!     .await
+//!     .expect_cacheable(Duration::from_secs(60));
+//!
+//! assert!(result.expect_cache_policy().varies_on("accept-language"));
+//!
+//! CONTEXT
+//!     .run(Request::get("users/me"))
+//!     .await
+//!     .expect_no_store();
+//! # }
+//! ```
+
+use std::time::Duration;
+
+use http::{
+    header::{AGE, CACHE_CONTROL, VARY},
+    HeaderMap,
+};
+
+/// The caching information of a response.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use http::{
+///     header::{CACHE_CONTROL, VARY},
+///     HeaderMap, HeaderValue,
+/// };
+/// use restest::cache::CachePolicy;
+///
+/// let mut headers = HeaderMap::new();
+/// headers.insert(CACHE_CONTROL, HeaderValue::from_static("public, max-age=60, s-maxage=3600"));
+/// headers.insert(VARY, HeaderValue::from_static("Accept-Encoding"));
+///
+/// let policy = CachePolicy::from_headers(&headers).unwrap();
+///
+/// assert!(policy.is_cacheable());
+/// assert_eq!(policy.freshness_lifetime(), Some(Duration::from_secs(3600)));
+/// assert!(policy.varies_on("accept-encoding"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CachePolicy {
+    /// The `no-store` directive: the response must not be stored by any
+    /// cache.
+    pub no_store: bool,
+    /// The `no-cache` directive: the response must be revalidated before
+    /// being reused.
+    pub no_cache: bool,
+    /// The `private` directive: the response must not be stored by shared
+    /// caches.
+    pub private: bool,
+    /// The `public` directive.
+    pub public: bool,
+    /// The `must-revalidate` directive.
+    pub must_revalidate: bool,
+    /// The `immutable` directive.
+    pub immutable: bool,
+    /// The `max-age` directive.
+    pub max_age: Option<Duration>,
+    /// The `s-maxage` directive, which applies to shared caches only.
+    pub s_maxage: Option<Duration>,
+    /// The time the response has spent in caches, from the `Age` header.
+    pub age: Option<Duration>,
+    /// The request headers the response varies on, from the `Vary` header,
+    /// in lowercase.
+    pub vary: Vec<String>,
+}
+
+impl CachePolicy {
+    /// Parses the caching headers. A response without caching headers has
+    /// the default policy.
+    ///
+    /// Unknown `Cache-Control` directives are ignored.
+    pub fn from_headers(headers: &HeaderMap) -> Result<CachePolicy, String> {
+        let mut policy = CachePolicy::default();
+
+        for directive in list(headers, CACHE_CONTROL.as_str()) {
+            let (name, value) = match directive.split_once('=') {
+                Some((name, value)) => (name.trim(), Some(value.trim().trim_matches('"'))),
+                None => (directive.as_str(), None),
+            };
+
+            match name.to_ascii_lowercase().as_str() {
+                "no-store" => policy.no_store = true,
+                "no-cache" => policy.no_cache = true,
+                "private" => policy.private = true,
+                "public" => policy.public = true,
+                "must-revalidate" => policy.must_revalidate = true,
+                "immutable" => policy.immutable = true,
+                "max-age" => policy.max_age = Some(seconds(&directive, value)?),
+                "s-maxage" => policy.s_maxage = Some(seconds(&directive, value)?),
+                _ => {}
+            }
+        }
+
+        if let Some(age) = headers.get(AGE) {
+            let age = String::from_utf8_lossy(age.as_bytes());
+            policy.age = Some(seconds(&format!("Age: {}", age), Some(age.trim()))?);
+        }
+
+        policy.vary = list(headers, VARY.as_str())
+            .map(|name| name.to_ascii_lowercase())
+            .collect();
+
+        Ok(policy)
+    }
+
+    /// Returns how long the response can be reused by a shared cache, such
+    /// as a CDN, from the `s-maxage` or the `max-age` directive.
+    pub fn freshness_lifetime(&self) -> Option<Duration> {
+        self.s_maxage.or(self.max_age)
+    }
+
+    /// Returns whether the response can be stored by a shared cache.
+    ///
+    /// The response must not have the `no-store` nor the `private`
+    /// directive, and must have a non-zero freshness lifetime.
+    pub fn is_cacheable(&self) -> bool {
+        !self.no_store
+            && !self.private
+            && self
+                .freshness_lifetime()
+                .is_some_and(|lifetime| !lifetime.is_zero())
+    }
+
+    /// Returns whether the response varies on a request header, which is
+    /// always the case when the `Vary` header is `*`.
+    pub fn varies_on(&self, header: &str) -> bool {
+        self.vary
+            .iter()
+            .any(|name| name == "*" || name.eq_ignore_ascii_case(header))
+    }
+}
+
+/// Returns the comma-separated items of all the `name` headers.
+fn list<'a>(headers: &'a HeaderMap, name: &str) -> impl Iterator<Item = String> + 'a {
+    headers
+        .get_all(name)
+        .iter()
+        .flat_map(|value| {
+            String::from_utf8_lossy(value.as_bytes())
+                .split(',')
+                .map(|item| item.trim().to_string())
+                .collect::<Vec<_>>()
+        })
+        .filter(|item| !item.is_empty())
+}
+
+fn seconds(directive: &str, value: Option<&str>) -> Result<Duration, String> {
+    value
+        .and_then(|value| value.parse().ok())
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("Invalid caching header: {}", directive))
+}
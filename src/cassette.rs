@@ -0,0 +1,368 @@
+//! Record responses to a file, and replay them on subsequent runs.
+//!
+//! A cassette is a JSON file containing a list of interactions. Each
+//! interaction is a request (method, URL, headers and body) and the response
+//! that was received for it (status, headers and body).
+//!
+//! When a cassette file does not exist, it is created and every request is
+//! sent to the backend and recorded. When it exists, the requests are never
+//! sent: each of them is answered with the first recorded interaction which
+//! matches its method, URL and body, and which was not replayed yet.
+//!
+//! Request bodies are compared structurally when both are valid JSON, so that
+//! a change of key order or whitespace does not prevent a match. Request
+//! headers are recorded for reference only, they are not compared.
+//!
+//! Cassettes are meant to be committed, therefore the values of the sensitive
+//! request and response headers, such as `Authorization` and `Set-Cookie`, are
+//! redacted when recording, as in the [logs](crate::log). So are the values
+//! of the [`REDACTED_FIELDS`] of JSON request bodies, at any depth. The
+//! redacted fields of a sent body are ignored when looking for a match.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+};
+
+use http::{HeaderMap, HeaderName, HeaderValue, StatusCode};
+use serde_json::{json, Value};
+
+use crate::log;
+
+/// The fields of JSON request bodies whose values are redacted when recording.
+/// Field names are compared case-insensitively.
+const REDACTED_FIELDS: &[&str] = &[
+    "password",
+    "secret",
+    "client_secret",
+    "token",
+    "access_token",
+    "refresh_token",
+    "api_key",
+];
+
+/// The cassettes that are loaded in memory, indexed by path.
+///
+/// Tests run concurrently and may share a cassette, so every access goes
+/// through this map instead of reading and writing the file independently.
+static CASSETTES: OnceLock<Mutex<HashMap<PathBuf, Cassette>>> = OnceLock::new();
+
+/// What to do with a request.
+pub(crate) enum Lookup {
+    /// The request must not be sent, a recorded response is available.
+    Replay(Response),
+    /// The request must be sent, and its response recorded.
+    Record,
+}
+
+/// A recorded response.
+pub(crate) struct Response {
+    pub(crate) status: StatusCode,
+    pub(crate) headers: HeaderMap,
+    pub(crate) body: Vec<u8>,
+}
+
+struct Cassette {
+    recording: bool,
+    interactions: Vec<Interaction>,
+}
+
+struct Interaction {
+    method: String,
+    url: String,
+    request_headers: HeaderMap,
+    request_body: Vec<u8>,
+    response: Response,
+    replayed: bool,
+}
+
+impl Interaction {
+    fn matches(&self, method: &str, url: &str, body: &[u8]) -> bool {
+        self.method == method && self.url == url && bodies_match(&self.request_body, body)
+    }
+}
+
+/// Finds the response recorded for a request.
+///
+/// Returns an error if the cassette can not be loaded, or if it was recorded
+/// during a previous run and does not contain the request.
+pub(crate) fn lookup(path: &str, method: &str, url: &str, body: &[u8]) -> Result<Lookup, String> {
+    let mut cassettes = cassettes();
+    let cassette = load(&mut cassettes, path)?;
+
+    if cassette.recording {
+        return Ok(Lookup::Record);
+    }
+
+    let interaction = cassette
+        .interactions
+        .iter_mut()
+        .find(|interaction| !interaction.replayed && interaction.matches(method, url, body))
+        .ok_or_else(|| {
+            format!(
+                "No interaction for request {} {} in cassette `{}`. Delete the cassette to record it again",
+                method, url, path
+            )
+        })?;
+
+    interaction.replayed = true;
+
+    Ok(Lookup::Replay(Response {
+        status: interaction.response.status,
+        headers: interaction.response.headers.clone(),
+        body: interaction.response.body.clone(),
+    }))
+}
+
+/// Adds an interaction to a cassette, and writes the cassette file.
+///
+/// The values of the sensitive request and response headers are redacted, as
+/// in the logs, and so are the values of the [`REDACTED_FIELDS`] of the
+/// request body.
+pub(crate) fn record(
+    path: &str,
+    method: &str,
+    url: &str,
+    mut request_headers: HeaderMap,
+    request_body: &[u8],
+    mut response: Response,
+    redacted: &[&str],
+) -> Result<(), String> {
+    let mut cassettes = cassettes();
+    let cassette = load(&mut cassettes, path)?;
+
+    redact_headers(&mut request_headers, redacted);
+    redact_headers(&mut response.headers, redacted);
+
+    let request_body = match serde_json::from_slice::<Value>(request_body) {
+        Ok(mut body) => {
+            if redact_fields(&mut body) {
+                serde_json::to_vec(&body).unwrap()
+            } else {
+                request_body.to_vec()
+            }
+        }
+        Err(_) => request_body.to_vec(),
+    };
+
+    cassette.interactions.push(Interaction {
+        method: method.to_string(),
+        url: url.to_string(),
+        request_headers,
+        request_body,
+        response,
+        replayed: false,
+    });
+
+    let content = serde_json::to_string_pretty(&encode(cassette)).unwrap();
+
+    if let Some(parent) = Path::new(path).parent() {
+        fs::create_dir_all(parent)
+            .map_err(|err| format!("Failed to create cassette `{}`: {}", path, err))?;
+    }
+
+    fs::write(path, content).map_err(|err| format!("Failed to write cassette `{}`: {}", path, err))
+}
+
+fn cassettes() -> std::sync::MutexGuard<'static, HashMap<PathBuf, Cassette>> {
+    CASSETTES
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn load<'a>(
+    cassettes: &'a mut HashMap<PathBuf, Cassette>,
+    path: &str,
+) -> Result<&'a mut Cassette, String> {
+    let key = PathBuf::from(path);
+
+    if !cassettes.contains_key(&key) {
+        let cassette = match fs::read_to_string(path) {
+            Ok(content) => decode(&content)
+                .map_err(|err| format!("Failed to load cassette `{}`: {}", path, err))?,
+            Err(_) => Cassette {
+                recording: true,
+                interactions: Vec::new(),
+            },
+        };
+
+        cassettes.insert(key.clone(), cassette);
+    }
+
+    Ok(cassettes.get_mut(&key).unwrap())
+}
+
+fn redact_headers(headers: &mut HeaderMap, redacted: &[&str]) {
+    for (name, value) in headers.iter_mut() {
+        if log::is_redacted(name.as_str(), redacted) {
+            *value = HeaderValue::from_static(log::REDACTED);
+        }
+    }
+}
+
+/// Redacts the values of the [`REDACTED_FIELDS`] of a JSON value, at any
+/// depth. Returns whether a value was redacted.
+fn redact_fields(value: &mut Value) -> bool {
+    let mut redacted = false;
+
+    match value {
+        Value::Object(fields) => {
+            for (name, value) in fields {
+                if REDACTED_FIELDS
+                    .iter()
+                    .any(|field| field.eq_ignore_ascii_case(name))
+                {
+                    *value = Value::from(log::REDACTED);
+                    redacted = true;
+                } else {
+                    redacted |= redact_fields(value);
+                }
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                redacted |= redact_fields(value);
+            }
+        }
+        _ => {}
+    }
+
+    redacted
+}
+
+fn bodies_match(recorded: &[u8], sent: &[u8]) -> bool {
+    let parse = serde_json::from_slice::<Value>;
+
+    match (parse(recorded), parse(sent)) {
+        // The recorded body is already redacted.
+        (Ok(recorded), Ok(mut sent)) => {
+            redact_fields(&mut sent);
+            recorded == sent
+        }
+        _ => recorded == sent,
+    }
+}
+
+fn encode(cassette: &Cassette) -> Value {
+    let interactions = cassette
+        .interactions
+        .iter()
+        .map(|interaction| {
+            json!({
+                "request": {
+                    "method": interaction.method,
+                    "url": interaction.url,
+                    "headers": encode_headers(&interaction.request_headers),
+                    "body": encode_body(&interaction.request_body),
+                },
+                "response": {
+                    "status": interaction.response.status.as_u16(),
+                    "headers": encode_headers(&interaction.response.headers),
+                    "body": encode_body(&interaction.response.body),
+                },
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({ "interactions": interactions })
+}
+
+fn encode_headers(headers: &HeaderMap) -> Value {
+    headers
+        .iter()
+        .map(|(name, value)| json!([name.as_str(), String::from_utf8_lossy(value.as_bytes())]))
+        .collect()
+}
+
+/// Bodies are stored as strings when they are valid UTF-8, and as arrays of
+/// bytes otherwise.
+fn encode_body(body: &[u8]) -> Value {
+    match std::str::from_utf8(body) {
+        Ok(text) => Value::from(text),
+        Err(_) => Value::from(body),
+    }
+}
+
+fn decode(content: &str) -> Result<Cassette, String> {
+    let content = serde_json::from_str::<Value>(content).map_err(|err| err.to_string())?;
+
+    let interactions = content["interactions"]
+        .as_array()
+        .ok_or("missing `interactions` array")?
+        .iter()
+        .map(decode_interaction)
+        .collect::<Result<_, _>>()?;
+
+    Ok(Cassette {
+        recording: false,
+        interactions,
+    })
+}
+
+fn decode_interaction(interaction: &Value) -> Result<Interaction, String> {
+    let request = &interaction["request"];
+    let response = &interaction["response"];
+
+    let status = response["status"]
+        .as_u64()
+        .and_then(|status| StatusCode::from_u16(status as u16).ok())
+        .ok_or("invalid response status")?;
+
+    Ok(Interaction {
+        method: request["method"]
+            .as_str()
+            .ok_or("invalid request method")?
+            .to_string(),
+        url: request["url"]
+            .as_str()
+            .ok_or("invalid request url")?
+            .to_string(),
+        // Request headers are not recorded in older cassettes.
+        request_headers: match &request["headers"] {
+            Value::Null => HeaderMap::new(),
+            headers => decode_headers(headers).map_err(|err| format!("invalid request {}", err))?,
+        },
+        request_body: decode_body(&request["body"])?,
+        response: Response {
+            status,
+            headers: decode_headers(&response["headers"])
+                .map_err(|err| format!("invalid response {}", err))?,
+            body: decode_body(&response["body"])?,
+        },
+        replayed: false,
+    })
+}
+
+fn decode_headers(headers: &Value) -> Result<HeaderMap, String> {
+    let mut decoded = HeaderMap::new();
+    for header in headers.as_array().ok_or("headers")? {
+        let (name, value) = match header.as_array().map(Vec::as_slice) {
+            Some([Value::String(name), Value::String(value)]) => (name, value),
+            _ => return Err(format!("header {}", header)),
+        };
+
+        decoded.append(
+            name.parse::<HeaderName>().map_err(|err| err.to_string())?,
+            value
+                .parse::<HeaderValue>()
+                .map_err(|err| err.to_string())?,
+        );
+    }
+
+    Ok(decoded)
+}
+
+fn decode_body(body: &Value) -> Result<Vec<u8>, String> {
+    match body {
+        Value::String(text) => Ok(text.clone().into_bytes()),
+        Value::Array(bytes) => bytes
+            .iter()
+            .map(|byte| byte.as_u64().and_then(|byte| u8::try_from(byte).ok()))
+            .collect::<Option<_>>()
+            .ok_or_else(|| "invalid body".to_string()),
+        _ => Err("invalid body".to_string()),
+    }
+}
@@ -0,0 +1,171 @@
+//! Inject network faults before sending requests.
+//!
+//! A [`Chaos`] layer, set with [`Context::with_chaos`](crate::Context::with_chaos),
+//! delays every request by a fixed amount plus a random jitter, and drops a
+//! fraction of them before they are sent. A dropped request fails with
+//! [`TransportError::Dropped`](crate::context::TransportError::Dropped), as if
+//! the network had lost it.
+//!
+//! This is meant for tests where restest drives a proxy or a gateway, in order
+//! to exercise the timeout and retry logic of the services under test.
+//!
+//! The faults only depend on the seed and on the number of requests that went
+//! through the layer, so that a test which sends its requests sequentially
+//! sees the same faults at each run. Requests sent concurrently share the
+//! sequence, in the order in which they are sent.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//!
+//! use http::StatusCode;
+//! use restest::{chaos::Chaos, context::TransportError, Context, Request};
+//!
+//! static SLOW: Chaos = Chaos::new(42)
+//!     .with_delay(Duration::from_millis(100))
+//!     .with_jitter(Duration::from_millis(400));
+//!
+//! static LOSSY: Chaos = Chaos::new(42).with_drop_rate(1.0);
+//!
+//! const SLOW_CONTEXT: Context = Context::new().with_port(8080).with_chaos(&SLOW);
+//! const LOSSY_CONTEXT: Context = Context::new().with_port(8080).with_chaos(&LOSSY);
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! SLOW_CONTEXT
+//!     .run(Request::get("orders"))
+//!     .await
+//!     .expect_status::<Vec<String>>(StatusCode::OK)
+//!     .await;
+//!
+//! let error = LOSSY_CONTEXT
+//!     .expect_transport_error(Request::get("orders"))
+//!     .await;
+//!
+//! assert!(matches!(error, TransportError::Dropped(_)));
+//! # }
+//! ```
+
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+/// The fractional part of the golden ratio, used to spread the seeds of
+/// consecutive requests.
+const GOLDEN_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Delays and drops the requests of a context.
+///
+/// A layer is usually placed in a `static`, so that its sequence of faults is
+/// shared by every request of the context.
+#[derive(Debug)]
+pub struct Chaos {
+    seed: u64,
+    delay: Duration,
+    jitter: Duration,
+    drop_rate: f64,
+    sent: AtomicU64,
+}
+
+impl Chaos {
+    /// Creates a layer which injects no fault, whose random faults are drawn
+    /// from `seed`.
+    pub const fn new(seed: u64) -> Chaos {
+        Chaos {
+            seed,
+            delay: Duration::ZERO,
+            jitter: Duration::ZERO,
+            drop_rate: 0.0,
+            sent: AtomicU64::new(0),
+        }
+    }
+
+    /// Delays every request by `delay` before sending it.
+    ///
+    /// The previously-set delay is discarded.
+    pub const fn with_delay(self, delay: Duration) -> Chaos {
+        Chaos { delay, ..self }
+    }
+
+    /// Delays every request by a random duration between zero and `jitter`,
+    /// in addition to the fixed delay.
+    ///
+    /// The previously-set jitter is discarded.
+    pub const fn with_jitter(self, jitter: Duration) -> Chaos {
+        Chaos { jitter, ..self }
+    }
+
+    /// Drops requests with probability `rate`, between `0.0` (never) and `1.0`
+    /// (always).
+    ///
+    /// The previously-set rate is discarded.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `rate` is not between `0.0` and `1.0`.
+    pub const fn with_drop_rate(self, rate: f64) -> Chaos {
+        if !(rate >= 0.0 && rate <= 1.0) {
+            panic!("Drop rate must be between 0.0 and 1.0");
+        }
+
+        Chaos {
+            drop_rate: rate,
+            ..self
+        }
+    }
+
+    /// Returns the seed of the layer.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Restarts the sequence of faults, so that the next requests see the
+    /// same faults as the first ones.
+    pub fn reset(&self) {
+        self.sent.store(0, Ordering::SeqCst);
+    }
+
+    /// Draws the fault injected in the next request.
+    pub(crate) fn next(&self) -> Fault {
+        let number = self.sent.fetch_add(1, Ordering::SeqCst);
+
+        let mut state = self
+            .seed
+            .wrapping_add(number.wrapping_add(1).wrapping_mul(GOLDEN_GAMMA));
+
+        let dropped = uniform(&mut state) < self.drop_rate;
+        let delay = self.delay + self.jitter.mul_f64(uniform(&mut state));
+
+        Fault {
+            number,
+            delay,
+            dropped,
+        }
+    }
+}
+
+/// The fault injected in a request.
+pub(crate) struct Fault {
+    /// The position of the request in the sequence of the layer, starting at
+    /// zero.
+    pub(crate) number: u64,
+    pub(crate) delay: Duration,
+    pub(crate) dropped: bool,
+}
+
+/// Returns a number uniformly distributed in `[0, 1)`, and advances `state`.
+///
+/// This is the SplitMix64 generator, which is good enough for fault injection
+/// and does not require an additional dependency.
+fn uniform(state: &mut u64) -> f64 {
+    *state = state.wrapping_add(GOLDEN_GAMMA);
+
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^= z >> 31;
+
+    (z >> 11) as f64 / (1u64 << 53) as f64
+}
@@ -0,0 +1,135 @@
+//! Body formats other than JSON.
+//!
+//! Request and response bodies are encoded as JSON by default. The
+//! [`Codec`] of a request can be changed with
+//! [`Request::with_codec`](crate::Request::with_codec): the request body is
+//! then encoded with this codec, the `Content-Type` and `Accept` headers are
+//! set accordingly, and the response body is decoded with the same codec by
+//! [`RequestResult::expect_status`](crate::request::RequestResult::expect_status).
+//!
+//! The following codecs are available, each behind a cargo feature:
+//!   - `msgpack`: [MessagePack](https://msgpack.org/), with `rmp-serde`,
+//!   - `cbor`: [CBOR](https://cbor.io/), with `ciborium`.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use http::StatusCode;
+//! use restest::{codec::Codec, Context, Request};
+//! use serde::{Deserialize, Serialize};
+//!
+//! const CONTEXT: Context = Context::new().with_port(8080);
+//!
+//! # #[cfg(feature = "msgpack")]
+//! # #[tokio::main]
+//! # async fn main() {
+//! let request = Request::post("users")
+//!     .with_codec(Codec::MsgPack)
+//!     .with_body(UserInput {
+//!         name: "Grace Hopper".to_string(),
+//!     });
+//!
+//! let user: User = CONTEXT
+//!     .run(request)
+//!     .await
+//!     .expect_status(StatusCode::CREATED)
+//!     .await;
+//! # }
+//! # #[cfg(not(feature = "msgpack"))]
+//! # fn main() {}
+//!
+//! #[derive(Serialize)]
+//! struct UserInput {
+//!     name: String,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct User {
+//!     id: u64,
+//!     name: String,
+//! }
+//! ```
+
+use serde::{de::DeserializeOwned, Serialize};
+
+/// The format of request and response bodies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Codec {
+    /// JSON, the default.
+    #[default]
+    Json,
+    /// MessagePack.
+    ///
+    /// This codec is available with the `msgpack` feature.
+    #[cfg(feature = "msgpack")]
+    MsgPack,
+    /// CBOR.
+    ///
+    /// This codec is available with the `cbor` feature.
+    #[cfg(feature = "cbor")]
+    Cbor,
+}
+
+impl Codec {
+    /// Returns the media type of the codec, which is sent in the
+    /// `Content-Type` and `Accept` headers.
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Codec::Json => "application/json; charset=utf-8",
+            #[cfg(feature = "msgpack")]
+            Codec::MsgPack => "application/msgpack",
+            #[cfg(feature = "cbor")]
+            Codec::Cbor => "application/cbor",
+        }
+    }
+
+    /// Encodes a value.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the value can not be serialized.
+    pub fn encode<T>(self, value: &T) -> Result<Vec<u8>, String>
+    where
+        T: Serialize + ?Sized,
+    {
+        let encoded = match self {
+            Codec::Json => serde_json::to_vec(value).map_err(|err| err.to_string()),
+            #[cfg(feature = "msgpack")]
+            Codec::MsgPack => rmp_serde::to_vec_named(value).map_err(|err| err.to_string()),
+            #[cfg(feature = "cbor")]
+            Codec::Cbor => {
+                let mut encoded = Vec::new();
+                ciborium::into_writer(value, &mut encoded)
+                    .map(|()| encoded)
+                    .map_err(|err| err.to_string())
+            }
+        };
+
+        encoded.map_err(|err| format!("Failed to encode body as {:?}: {}", self, err))
+    }
+
+    /// Returns whether an encoded body stands for the absence of a body, that
+    /// is, whether it is the encoding of `()` or `None`.
+    pub(crate) fn is_empty(self, encoded: &[u8]) -> bool {
+        self.encode(&()).is_ok_and(|unit| unit == encoded)
+    }
+
+    /// Decodes a value.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the data can not be deserialized to the
+    /// specified type.
+    pub fn decode<T>(self, data: &[u8]) -> Result<T, String>
+    where
+        T: DeserializeOwned,
+    {
+        match self {
+            Codec::Json => serde_json::from_slice(data).map_err(|err| err.to_string()),
+            #[cfg(feature = "msgpack")]
+            Codec::MsgPack => rmp_serde::from_slice(data).map_err(|err| err.to_string()),
+            #[cfg(feature = "cbor")]
+            Codec::Cbor => ciborium::from_reader(data).map_err(|err| err.to_string()),
+        }
+    }
+}
@@ -0,0 +1,240 @@
+//! Compare the behavior of multiple environments.
+//!
+//! This module provides the [`Comparison`] type, which runs the same requests
+//! against multiple [`Context`]s (for instance a development, a staging and a
+//! read-only production environment) and records the status code and latency
+//! of each response. The resulting matrix can be printed, and divergences
+//! between environments can be asserted.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use restest::{compare::Comparison, path, Context, Request};
+//!
+//! const DEV: Context = Context::new().with_port(8080);
+//! const STAGING: Context = Context::new().with_host("http://staging.local");
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let mut comparison = Comparison::new()
+//!     .with_environment("dev", &DEV)
+//!     .with_environment("staging", &STAGING);
+//!
+//! comparison.run(Request::get("users")).await;
+//! comparison.run(Request::get(path!["users", "ghopper"])).await;
+//!
+//! println!("{}", comparison);
+//! comparison.assert_no_divergence();
+//! # }
+//! ```
+
+use std::{
+    fmt::{self, Display, Formatter},
+    time::{Duration, Instant},
+};
+
+use http::StatusCode;
+use serde::Serialize;
+
+use crate::{report, Context, Request};
+
+/// Runs requests against multiple environments and stores a comparison matrix
+/// of the responses.
+///
+/// Each request that is run adds a row to the matrix. Each environment adds a
+/// column.
+#[derive(Default)]
+pub struct Comparison<'a> {
+    environments: Vec<(String, &'a Context)>,
+    rows: Vec<ComparisonRow>,
+}
+
+impl<'a> Comparison<'a> {
+    /// Creates a new comparison, with no environment.
+    pub fn new() -> Comparison<'a> {
+        Comparison::default()
+    }
+
+    /// Adds an environment to the comparison.
+    ///
+    /// Environments must be added before any request is run.
+    pub fn with_environment(mut self, name: impl ToString, context: &'a Context) -> Comparison<'a> {
+        assert!(
+            self.rows.is_empty(),
+            "Attempt to add an environment after a request was run"
+        );
+        self.environments.push((name.to_string(), context));
+
+        self
+    }
+
+    /// Runs a request against every environment, and records the responses
+    /// status and latency.
+    ///
+    /// This function performs I/O, therefore it is marked as `async`.
+    pub async fn run<I, R>(&mut self, request: R) -> &ComparisonRow
+    where
+        I: Serialize,
+        R: AsRef<Request<I>>,
+    {
+        let request = request.as_ref();
+        let mut outcomes = Vec::with_capacity(self.environments.len());
+        let mut tags = Vec::new();
+
+        for (_, context) in &self.environments {
+            let start = Instant::now();
+            let result = context.run(request).await;
+
+            outcomes.push(Outcome {
+                status: result.status,
+                latency: start.elapsed(),
+            });
+            tags.extend_from_slice(result.tags());
+        }
+
+        self.rows.push(ComparisonRow {
+            endpoint: request.description(),
+            outcomes,
+            tags: report::merge_tags(&tags),
+        });
+
+        self.rows.last().unwrap()
+    }
+
+    /// Returns the name of the environments, in the order they were added.
+    pub fn environments(&self) -> impl Iterator<Item = &str> {
+        self.environments.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Returns every row of the matrix, in the order the requests were run.
+    pub fn rows(&self) -> &[ComparisonRow] {
+        &self.rows
+    }
+
+    /// Returns the rows for which at least two environments returned a
+    /// different status code.
+    pub fn divergences(&self) -> impl Iterator<Item = &ComparisonRow> {
+        self.rows.iter().filter(|row| row.is_divergent())
+    }
+
+    /// Checks that every environment returned the same status code for each
+    /// request.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if at least one request led to different status
+    /// codes. The panic message contains the whole comparison matrix.
+    #[track_caller]
+    pub fn assert_no_divergence(&self) {
+        let tags = self
+            .rows
+            .iter()
+            .flat_map(|row| &row.tags)
+            .collect::<Vec<_>>();
+
+        report::check_soft(
+            "assert_no_divergence",
+            &report::merge_tags(tags),
+            self.ensure_no_divergence(),
+        );
+    }
+
+    /// Checks that every environment returned the same status code for each
+    /// request.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if at least one request led to different
+    /// status codes. The error message contains the whole comparison matrix.
+    pub fn ensure_no_divergence(&self) -> Result<(), String> {
+        let count = self.divergences().count();
+
+        if count == 0 {
+            return Ok(());
+        }
+
+        Err(format!(
+            "{} request(s) diverged across environments:\n{}",
+            count, self
+        ))
+    }
+}
+
+/// Renders the comparison matrix, one line per request.
+///
+/// Divergent rows are prefixed with `!`.
+impl Display for Comparison<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "  endpoint")?;
+        for name in self.environments() {
+            write!(f, " | {}", name)?;
+        }
+        writeln!(f)?;
+
+        for row in &self.rows {
+            let marker = if row.is_divergent() { '!' } else { ' ' };
+            write!(f, "{} {}", marker, row.endpoint)?;
+
+            for outcome in &row.outcomes {
+                write!(f, " | {}", outcome)?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The responses of every environment for a given request.
+pub struct ComparisonRow {
+    endpoint: String,
+    outcomes: Vec<Outcome>,
+    tags: Vec<String>,
+}
+
+impl ComparisonRow {
+    /// Returns the context description of the request.
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Returns the outcome of the request for each environment, in the order
+    /// the environments were added.
+    pub fn outcomes(&self) -> &[Outcome] {
+        &self.outcomes
+    }
+
+    /// Returns the tags of the request, including the tags of the contexts.
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    /// Returns whether at least two environments returned a different status
+    /// code.
+    pub fn is_divergent(&self) -> bool {
+        self.outcomes
+            .windows(2)
+            .any(|pair| pair[0].status != pair[1].status)
+    }
+}
+
+/// The status code and latency of a response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Outcome {
+    /// The response status code.
+    pub status: StatusCode,
+    /// The time elapsed between the moment the request was sent and the
+    /// moment the response headers were received.
+    pub latency: Duration,
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}ms)",
+            self.status.as_u16(),
+            self.latency.as_millis()
+        )
+    }
+}
@@ -0,0 +1,63 @@
+//! Generate `curl` commands that reproduce a request.
+
+use std::net::IpAddr;
+
+use http::{header::CONTENT_LENGTH, HeaderMap};
+
+use crate::log;
+
+/// Returns a shell command which sends the same request with `curl`.
+///
+/// The `Content-Length` header is omitted, as `curl` computes it from the
+/// body. The values of the sensitive headers are redacted, refer to
+/// [`log::header_value`].
+pub(crate) fn command(
+    method: &str,
+    url: &str,
+    headers: &HeaderMap,
+    body: &[u8],
+    redacted: &[&str],
+) -> String {
+    let mut command = format!("curl -X {} {}", method, quote(url));
+
+    for (name, value) in headers {
+        if name == CONTENT_LENGTH {
+            continue;
+        }
+
+        let header = format!(
+            "{}: {}",
+            name,
+            log::header_value(name.as_str(), value, redacted)
+        );
+        command.push_str(" -H ");
+        command.push_str(&quote(&header));
+    }
+
+    if !body.is_empty() {
+        command.push_str(" --data-raw ");
+        command.push_str(&quote(&String::from_utf8_lossy(body)));
+    }
+
+    command
+}
+
+/// Returns the `--resolve` option which makes `curl` connect to `address`
+/// instead of resolving `host`, for requests on `port`.
+pub(crate) fn resolve(host: &str, port: u16, address: IpAddr) -> String {
+    let address = match address {
+        IpAddr::V4(address) => address.to_string(),
+        IpAddr::V6(address) => format!("[{}]", address),
+    };
+
+    format!(
+        " --resolve {}",
+        quote(&format!("{}:{}:{}", host, port, address))
+    )
+}
+
+/// Quotes a string so that a POSIX shell passes it verbatim as a single
+/// argument.
+pub(crate) fn quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
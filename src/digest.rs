@@ -0,0 +1,71 @@
+//! Compute the digest of bodies and fixture files.
+//!
+//! Large binary responses, such as exports or images, are best checked by
+//! comparing their digest with the one of a reference file, with
+//! [`RequestResult::expect_body_sha256`](crate::request::RequestResult::expect_body_sha256).
+//! This avoids loading the expected content in the test.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use http::StatusCode;
+//! use restest::{digest, Context, Request};
+//!
+//! const CONTEXT: Context = Context::new().with_port(8080);
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let expected = digest::sha256_file("tests/fixtures/export.csv").unwrap();
+//!
+//! CONTEXT
+//!     .run(Request::get("users/export"))
+//!     .await
+//!     .expect_status_code(StatusCode::OK)
+//!     .await
+//!     .expect_body_sha256(&expected)
+//!     .await;
+//! # }
+//! ```
+
+use std::{fs::File, io, path::Path};
+
+use sha2::{Digest, Sha256};
+
+/// Returns the SHA-256 digest of some data, as a lowercase hexadecimal string.
+///
+/// # Example
+///
+/// ```rust
+/// use restest::digest;
+///
+/// assert_eq!(
+///     digest::sha256(b"abc"),
+///     "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+/// );
+/// ```
+pub fn sha256(data: &[u8]) -> String {
+    hex(&Sha256::digest(data))
+}
+
+/// Returns the SHA-256 digest of a file, as a lowercase hexadecimal string.
+///
+/// The file is read in chunks, so that large fixtures are never loaded in
+/// memory at once.
+///
+/// # Error
+///
+/// This function returns an error if the file can not be read.
+pub fn sha256_file(path: impl AsRef<Path>) -> Result<String, String> {
+    let path = path.as_ref();
+
+    let mut hasher = Sha256::new();
+    File::open(path)
+        .and_then(|mut file| io::copy(&mut file, &mut hasher))
+        .map_err(|err| format!("Failed to read '{}': {}", path.display(), err))?;
+
+    Ok(hex(&hasher.finalize()))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
@@ -0,0 +1,412 @@
+//! Start the backend in Docker before the tests, and stop it afterwards.
+//!
+//! This module provides the [`Container`] and [`Compose`] types, which start
+//! a single container or a `docker compose` project, wait until the backend
+//! is ready, and return an [`Environment`]. The environment exposes the port
+//! Docker mapped on the host as a runtime [`Context`], so that a suite does
+//! not depend on a backend started beforehand.
+//!
+//! The containers are removed when the test process exits, even if it
+//! crashes, by a small shell process which waits for the test process to
+//! exit. They can also be removed earlier with [`Environment::stop`].
+//!
+//! The `docker` command must be available, along with the `docker compose`
+//! plugin to start compose projects, and a POSIX shell.
+//!
+//! Starting a backend takes a while: an environment is usually shared by
+//! the tests of a process, for instance in a `static` initialized with
+//! `tokio::sync::OnceCell`.
+//!
+//! This module is available with the `docker` feature.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//!
+//! use http::StatusCode;
+//! use restest::{env::Container, Request};
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let backend = Container::new("ghcr.io/acme/users-api:latest", 8080)
+//!     .with_env("DATABASE_URL", "sqlite::memory:")
+//!     .with_readiness("health", Duration::from_secs(60))
+//!     .start()
+//!     .await;
+//!
+//! backend
+//!     .context()
+//!     .run(Request::get("users"))
+//!     .await
+//!     .expect_status::<Vec<String>>(StatusCode::OK)
+//!     .await;
+//!
+//! backend.stop().await;
+//! # }
+//! ```
+
+use std::{process::Stdio, time::Duration};
+
+use tokio::process::Command;
+
+use crate::{context::ContextBuilder, curl, gen, watchdog, Context};
+
+/// A container to start from an image.
+#[derive(Clone)]
+pub struct Container {
+    image: String,
+    port: u16,
+    env: Vec<(String, String)>,
+    startup: Startup,
+}
+
+impl Container {
+    /// Creates a container running `image`, whose backend listens on `port`
+    /// inside the container.
+    pub fn new(image: impl ToString, port: u16) -> Container {
+        Container {
+            image: image.to_string(),
+            port,
+            env: Vec::new(),
+            startup: Startup::new(),
+        }
+    }
+
+    /// Sets an environment variable of the container.
+    pub fn with_env(mut self, name: impl ToString, value: impl ToString) -> Container {
+        self.env.push((name.to_string(), value.to_string()));
+
+        self
+    }
+
+    /// Sets the context the environment context is created from.
+    ///
+    /// Its port is replaced with the port mapped by Docker. The default
+    /// context is [`Context::new`].
+    pub fn with_context(mut self, context: Context) -> Container {
+        self.startup.context = context;
+
+        self
+    }
+
+    /// Waits until `path` responds with a success status, for at most
+    /// `timeout`, before returning the environment.
+    ///
+    /// Refer to [`Context::wait_until_ready`] for more details. By default,
+    /// the environment is returned as soon as the container is started.
+    pub fn with_readiness(mut self, path: impl ToString, timeout: Duration) -> Container {
+        self.startup.readiness = Some((path.to_string(), timeout));
+
+        self
+    }
+
+    /// Starts the container, and waits until the backend is ready.
+    ///
+    /// This function performs I/O, therefore it is marked as `async`.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the container can not be started, or if the
+    /// backend is not ready in time.
+    pub async fn start(self) -> Environment {
+        match self.ensure_start().await {
+            Ok(environment) => environment,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Starts the container, and waits until the backend is ready.
+    ///
+    /// This function performs I/O, therefore it is marked as `async`.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the container can not be started, or
+    /// if the backend is not ready in time. The container is removed in both
+    /// cases.
+    pub async fn ensure_start(self) -> Result<Environment, String> {
+        let mut args = vec![
+            "run".to_string(),
+            "--detach".to_string(),
+            "--publish".to_string(),
+            format!("127.0.0.1::{}", self.port),
+        ];
+
+        for (name, value) in &self.env {
+            args.push("--env".to_string());
+            args.push(format!("{}={}", name, value));
+        }
+
+        args.push(self.image.clone());
+
+        let id = docker(&args)
+            .await
+            .map_err(|err| format!("Failed to start container '{}': {}", self.image, err))?;
+
+        let teardown = Teardown::new(vec![
+            "rm".to_string(),
+            "--force".to_string(),
+            "--volumes".to_string(),
+            id.clone(),
+        ]);
+
+        let mapping = docker(&["port".to_string(), id, format!("{}/tcp", self.port)]).await;
+
+        self.startup.finish(&self.image, mapping, teardown).await
+    }
+}
+
+/// A `docker compose` project.
+#[derive(Clone)]
+pub struct Compose {
+    file: String,
+    service: String,
+    port: u16,
+    project: String,
+    startup: Startup,
+}
+
+impl Compose {
+    /// Creates a project from a compose file, whose backend is `service`,
+    /// listening on `port` inside its container.
+    ///
+    /// The port must be published in the compose file, for instance with
+    /// `ports: ["8080"]`, so that Docker maps it on the host.
+    ///
+    /// The project gets a unique name, so that concurrent test processes do
+    /// not share containers.
+    pub fn new(file: impl ToString, service: impl ToString, port: u16) -> Compose {
+        Compose {
+            file: file.to_string(),
+            service: service.to_string(),
+            port,
+            project: gen::unique_username("restest"),
+            startup: Startup::new(),
+        }
+    }
+
+    /// Sets the context the environment context is created from.
+    ///
+    /// Its port is replaced with the port mapped by Docker. The default
+    /// context is [`Context::new`].
+    pub fn with_context(mut self, context: Context) -> Compose {
+        self.startup.context = context;
+
+        self
+    }
+
+    /// Waits until `path` responds with a success status, for at most
+    /// `timeout`, before returning the environment.
+    ///
+    /// Refer to [`Context::wait_until_ready`] for more details. By default,
+    /// the environment is returned as soon as the containers are started.
+    pub fn with_readiness(mut self, path: impl ToString, timeout: Duration) -> Compose {
+        self.startup.readiness = Some((path.to_string(), timeout));
+
+        self
+    }
+
+    /// Starts the project, and waits until the backend is ready.
+    ///
+    /// This function performs I/O, therefore it is marked as `async`.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the project can not be started, or if the
+    /// backend is not ready in time.
+    pub async fn start(self) -> Environment {
+        match self.ensure_start().await {
+            Ok(environment) => environment,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Starts the project, and waits until the backend is ready.
+    ///
+    /// This function performs I/O, therefore it is marked as `async`.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the project can not be started, or if
+    /// the backend is not ready in time. The project is taken down in both
+    /// cases.
+    pub async fn ensure_start(self) -> Result<Environment, String> {
+        let compose = |command: &[&str]| {
+            let mut args = vec![
+                "compose".to_string(),
+                "--file".to_string(),
+                self.file.clone(),
+                "--project-name".to_string(),
+                self.project.clone(),
+            ];
+            args.extend(command.iter().map(ToString::to_string));
+            args
+        };
+
+        // The project is taken down even if it is only partially started.
+        let teardown = Teardown::new(compose(&["down", "--volumes"]));
+
+        if let Err(err) = docker(&compose(&["up", "--detach"])).await {
+            teardown.run().await;
+            return Err(format!(
+                "Failed to start compose project '{}': {}",
+                self.file, err
+            ));
+        }
+
+        let port = self.port.to_string();
+        let mapping = docker(&compose(&["port", &self.service, &port])).await;
+
+        self.startup.finish(&self.file, mapping, teardown).await
+    }
+}
+
+/// A backend started in Docker.
+pub struct Environment {
+    context: Context,
+    port: u16,
+    teardown: Teardown,
+}
+
+impl Environment {
+    /// Returns a context which sends requests to the backend.
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
+    /// Returns the port Docker mapped on the host.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Removes the containers now, instead of when the test process exits.
+    ///
+    /// This function performs I/O, therefore it is marked as `async`.
+    pub async fn stop(self) {
+        self.teardown.run().await;
+    }
+}
+
+/// The settings shared by containers and compose projects.
+#[derive(Clone)]
+struct Startup {
+    context: Context,
+    readiness: Option<(String, Duration)>,
+}
+
+impl Startup {
+    fn new() -> Startup {
+        Startup {
+            context: Context::new(),
+            readiness: None,
+        }
+    }
+
+    /// Creates the environment once the containers are started, from the
+    /// output of `docker port`, and waits until the backend is ready.
+    async fn finish(
+        self,
+        name: &str,
+        mapping: Result<String, String>,
+        teardown: Teardown,
+    ) -> Result<Environment, String> {
+        let port = mapping.and_then(|mapping| mapped_port(&mapping));
+
+        let port = match port {
+            Ok(port) => port,
+            Err(err) => {
+                teardown.run().await;
+                return Err(format!("Failed to find the port of '{}': {}", name, err));
+            }
+        };
+
+        let context = ContextBuilder::from_context(self.context)
+            .with_port(port)
+            .build();
+
+        if let Some((path, timeout)) = self.readiness {
+            if let Err(err) = context.ensure_ready(path, timeout).await {
+                teardown.run().await;
+                return Err(err);
+            }
+        }
+
+        Ok(Environment {
+            context,
+            port,
+            teardown,
+        })
+    }
+}
+
+/// Parses the output of `docker port`, such as `127.0.0.1:49153`.
+///
+/// Docker prints one line per address the port is bound to, which all share
+/// the same port.
+fn mapped_port(mapping: &str) -> Result<u16, String> {
+    mapping
+        .lines()
+        .next()
+        .and_then(|line| line.rsplit_once(':'))
+        .and_then(|(_, port)| port.trim().parse::<u16>().ok())
+        .ok_or_else(|| format!("Unexpected port mapping '{}'", mapping))
+}
+
+/// The `docker` command which removes the containers of an environment.
+struct Teardown {
+    args: Vec<String>,
+}
+
+impl Teardown {
+    /// Creates a teardown command, and makes sure that it is run when the
+    /// test process exits.
+    fn new(args: Vec<String>) -> Teardown {
+        watchdog::watch(&shell_command("docker", &args));
+
+        Teardown { args }
+    }
+
+    /// Runs the teardown command now. The watchdog command then does
+    /// nothing, as the containers are already removed.
+    async fn run(&self) {
+        if let Err(err) = docker(&self.args).await {
+            eprintln!("{}", err);
+        }
+    }
+}
+
+/// Runs a `docker` command, and returns its trimmed output.
+async fn docker(args: &[String]) -> Result<String, String> {
+    let command = format!("docker {}", args.join(" "));
+
+    let output = Command::new("docker")
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .map_err(|err| format!("Failed to run `{}`: {}", command, err))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "`{}` failed ({}): {}",
+            command,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Quotes a command and its arguments for [`watchdog::watch`].
+pub(crate) fn shell_command(program: &str, args: &[String]) -> String {
+    let mut command = program.to_string();
+
+    for arg in args {
+        command.push(' ');
+        command.push_str(&curl::quote(arg));
+    }
+
+    command
+}
@@ -0,0 +1,685 @@
+//! Fill request bodies with random, plausible data.
+//!
+//! The [`fake`] function generates a value of any type implementing
+//! [`Deserialize`](serde::Deserialize): the type is deserialized from random
+//! data instead of a JSON document. The generated strings and numbers depend on
+//! the field names, so that an `email` field contains an email address, an
+//! `age` field contains a plausible age, and so on.
+//!
+//! The data is generated from a seed, which is random unless the
+//! `RESTEST_SEED` environment variable is set. The seed is written to the
+//! standard error the first time a test generates data. As the Rust test
+//! harness captures this output, the seed is only displayed when the test
+//! fails, and the failure can be reproduced by setting `RESTEST_SEED`.
+//!
+//! This module is available with the `fake` feature.
+//!
+//! # Example
+//!
+//! ```rust
+//! use restest::{fake::fake, Request};
+//! use serde::{Deserialize, Serialize};
+//!
+//! let user: UserInput = fake();
+//! assert!(user.email.contains('@'));
+//! assert!((18..=90).contains(&user.age));
+//!
+//! let request = Request::post("users").with_body(user);
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct UserInput {
+//!     first_name: String,
+//!     email: String,
+//!     age: u8,
+//!     tags: Vec<String>,
+//! }
+//! ```
+
+use std::{any, cell::RefCell, env, slice};
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::de::{
+    self,
+    value::{Error, StrDeserializer},
+    DeserializeOwned, DeserializeSeed, Deserializer, EnumAccess, IntoDeserializer, MapAccess,
+    SeqAccess, VariantAccess, Visitor,
+};
+use time::OffsetDateTime;
+use uuid::Builder;
+
+/// The name of the environment variable which sets the seed.
+pub const SEED_ENV_VAR: &str = "RESTEST_SEED";
+
+/// The depth after which collections are empty and options are `None`, so
+/// that recursive types can be generated.
+const MAX_DEPTH: usize = 4;
+
+const FIRST_NAMES: &[&str] = &[
+    "Ada", "Alan", "Barbara", "Dennis", "Donald", "Edsger", "Frances", "Grace", "John", "Ken",
+    "Margaret", "Niklaus", "Radia", "Robin", "Tony",
+];
+
+const LAST_NAMES: &[&str] = &[
+    "Allen",
+    "Dijkstra",
+    "Hamilton",
+    "Hoare",
+    "Hopper",
+    "Kernighan",
+    "Knuth",
+    "Liskov",
+    "Lovelace",
+    "McCarthy",
+    "Milner",
+    "Perlman",
+    "Ritchie",
+    "Turing",
+    "Wirth",
+];
+
+const CITIES: &[&str] = &[
+    "Amsterdam",
+    "Berlin",
+    "Lisbon",
+    "London",
+    "Montreal",
+    "Nairobi",
+    "Osaka",
+    "Paris",
+    "Santiago",
+    "Sydney",
+];
+
+const COUNTRIES: &[&str] = &[
+    "Australia",
+    "Canada",
+    "Chile",
+    "France",
+    "Germany",
+    "Japan",
+    "Kenya",
+    "Netherlands",
+    "Portugal",
+    "United Kingdom",
+];
+
+const WORDS: &[&str] = &[
+    "alpha", "bravo", "charlie", "delta", "echo", "foxtrot", "golf", "hotel", "india", "juliet",
+    "kilo", "lima", "mike", "november", "oscar", "papa",
+];
+
+thread_local! {
+    /// The seed and the random number generator of the current thread.
+    static RNG: RefCell<Option<(u64, StdRng)>> = const { RefCell::new(None) };
+}
+
+/// Generates a random value, using the seed of the current thread.
+///
+/// # Panics
+///
+/// This function panics if the type can not be generated, for instance if its
+/// `Deserialize` implementation validates the generated data.
+pub fn fake<T>() -> T
+where
+    T: DeserializeOwned,
+{
+    with_rng(|seed, rng| generate(seed, rng))
+}
+
+/// Generates a random value from a specific seed.
+///
+/// The same seed always generates the same value.
+///
+/// # Panics
+///
+/// This function panics if the type can not be generated, for instance if its
+/// `Deserialize` implementation validates the generated data.
+pub fn fake_with_seed<T>(seed: u64) -> T
+where
+    T: DeserializeOwned,
+{
+    generate(seed, &mut StdRng::seed_from_u64(seed))
+}
+
+/// Returns the seed of the current thread.
+pub fn seed() -> u64 {
+    with_rng(|seed, _| seed)
+}
+
+fn with_rng<T>(f: impl FnOnce(u64, &mut StdRng) -> T) -> T {
+    RNG.with(|rng| {
+        let mut rng = rng.borrow_mut();
+        let (seed, rng) = rng.get_or_insert_with(|| {
+            let seed = env::var(SEED_ENV_VAR)
+                .ok()
+                .and_then(|seed| seed.trim().parse().ok())
+                .unwrap_or_else(rand::random);
+
+            eprintln!(
+                "[restest] Generating fake data with seed {0}. Set {1}={0} to reproduce",
+                seed, SEED_ENV_VAR
+            );
+
+            (seed, StdRng::seed_from_u64(seed))
+        });
+
+        f(*seed, rng)
+    })
+}
+
+fn generate<T>(seed: u64, rng: &mut StdRng) -> T
+where
+    T: DeserializeOwned,
+{
+    let deserializer = Fake {
+        rng,
+        field: None,
+        depth: 0,
+    };
+
+    T::deserialize(deserializer).unwrap_or_else(|err| {
+        panic!(
+            "Failed to generate a fake `{}` with seed {}: {}",
+            any::type_name::<T>(),
+            seed,
+            err
+        )
+    })
+}
+
+/// A deserializer which produces random data.
+struct Fake<'a> {
+    rng: &'a mut StdRng,
+    /// The name of the field being generated, if any.
+    field: Option<&'static str>,
+    depth: usize,
+}
+
+impl Fake<'_> {
+    fn field(&self) -> String {
+        self.field.unwrap_or_default().to_ascii_lowercase()
+    }
+
+    /// Returns the bounds of a plausible integer, which are then restricted to
+    /// the range of the requested type.
+    fn integer_bounds(&self) -> (i64, i64) {
+        let field = self.field();
+
+        if field.contains("age") {
+            (18, 90)
+        } else if field.contains("year") {
+            (1950, 2010)
+        } else if field.contains("month") {
+            (1, 12)
+        } else if field.contains("day") {
+            (1, 28)
+        } else if field.contains("count") || field.contains("quantity") {
+            (1, 100)
+        } else {
+            (0, 1000)
+        }
+    }
+
+    fn string(&mut self) -> String {
+        let field = self.field();
+        let first = *FIRST_NAMES.choose(self.rng).unwrap();
+        let last = *LAST_NAMES.choose(self.rng).unwrap();
+
+        if field.contains("email") || field.contains("mail") {
+            format!(
+                "{}.{}{}@example.com",
+                first.to_ascii_lowercase(),
+                last.to_ascii_lowercase(),
+                self.rng.gen_range(1..1000)
+            )
+        } else if field.contains("first") {
+            first.to_string()
+        } else if field.contains("last") || field.contains("surname") {
+            last.to_string()
+        } else if field.contains("user") || field.contains("login") {
+            format!(
+                "{}{}",
+                first.to_ascii_lowercase().chars().next().unwrap(),
+                last.to_ascii_lowercase()
+            )
+        } else if field.contains("name") {
+            format!("{} {}", first, last)
+        } else if field.contains("city") {
+            CITIES.choose(self.rng).unwrap().to_string()
+        } else if field.contains("country") {
+            COUNTRIES.choose(self.rng).unwrap().to_string()
+        } else if field.contains("phone") {
+            format!("+1555{:07}", self.rng.gen_range(0..10_000_000))
+        } else if field.contains("url") || field.contains("website") {
+            format!("https://example.com/{}", self.words(1))
+        } else if field == "id" || field.ends_with("_id") || field.contains("uuid") {
+            Builder::from_random_bytes(self.rng.gen())
+                .into_uuid()
+                .to_string()
+        } else if field.ends_with("_at") || field.contains("time") {
+            let date = self.date();
+            format!(
+                "{}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+                date.year(),
+                date.month() as u8,
+                date.day(),
+                date.hour(),
+                date.minute(),
+                date.second()
+            )
+        } else if field.contains("date") {
+            let date = self.date();
+            format!(
+                "{}-{:02}-{:02}",
+                date.year(),
+                date.month() as u8,
+                date.day()
+            )
+        } else {
+            let count = self.rng.gen_range(1..=3);
+            self.words(count)
+        }
+    }
+
+    fn words(&mut self, count: usize) -> String {
+        (0..count)
+            .map(|_| *WORDS.choose(self.rng).unwrap())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Returns a date between 2000 and 2030.
+    fn date(&mut self) -> OffsetDateTime {
+        let timestamp = self.rng.gen_range(946_684_800..1_893_456_000);
+        OffsetDateTime::from_unix_timestamp(timestamp).unwrap()
+    }
+
+    fn len(&mut self) -> usize {
+        if self.depth >= MAX_DEPTH {
+            0
+        } else {
+            self.rng.gen_range(1..=3)
+        }
+    }
+
+    fn nested(&mut self, field: Option<&'static str>) -> Fake<'_> {
+        Fake {
+            rng: self.rng,
+            field,
+            depth: self.depth + 1,
+        }
+    }
+}
+
+macro_rules! deserialize_integers {
+    ( $( $method:ident => $visit:ident: $ty:ty ),* $(,)? ) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Error>
+            where
+                V: Visitor<'de>,
+            {
+                let (low, high) = self.integer_bounds();
+                let (min, max) = (<$ty>::MIN as i128, <$ty>::MAX as i128);
+
+                // The type may be too small for the plausible bounds, in
+                // which case any value of the type is used.
+                let (low, high) = match (i128::from(low).max(min), i128::from(high).min(max)) {
+                    (low, high) if low <= high => (low, high),
+                    _ => (min, max),
+                };
+
+                visitor.$visit(self.rng.gen_range(low..=high) as $ty)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for Fake<'_> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_bool(self.rng.gen())
+    }
+
+    deserialize_integers! {
+        deserialize_i8 => visit_i8: i8,
+        deserialize_i16 => visit_i16: i16,
+        deserialize_i32 => visit_i32: i32,
+        deserialize_i64 => visit_i64: i64,
+        deserialize_i128 => visit_i128: i128,
+        deserialize_u8 => visit_u8: u8,
+        deserialize_u16 => visit_u16: u16,
+        deserialize_u32 => visit_u32: u32,
+        deserialize_u64 => visit_u64: u64,
+        deserialize_u128 => visit_u128: u128,
+    }
+
+    fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_f32(f32::from(self.rng.gen_range(0_u16..10_000)) / 100.)
+    }
+
+    fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_f64(f64::from(self.rng.gen_range(0_u32..100_000)) / 100.)
+    }
+
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_char(self.rng.gen_range('a'..='z'))
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_string<V>(mut self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_string(self.string())
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        let len = self.rng.gen_range(1..=32);
+        visitor.visit_byte_buf((0..len).map(|_| self.rng.gen()).collect())
+    }
+
+    fn deserialize_option<V>(mut self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        if self.depth < MAX_DEPTH && self.rng.gen_bool(0.8) {
+            let field = self.field;
+            visitor.visit_some(self.nested(field))
+        } else {
+            visitor.visit_none()
+        }
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V>(mut self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        let remaining = self.len();
+        self.deserialize_tuple(remaining, visitor)
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_seq(FakeSeq {
+            fake: self,
+            remaining: len,
+        })
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V>(mut self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        let remaining = self.len();
+        visitor.visit_map(FakeMap {
+            fake: self,
+            remaining,
+        })
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_map(FakeStruct {
+            fake: self,
+            fields: fields.iter(),
+            current: None,
+        })
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        let variant = *variants
+            .choose(self.rng)
+            .ok_or_else(|| de::Error::custom("enum has no variant"))?;
+
+        visitor.visit_enum(FakeEnum {
+            fake: self,
+            variant,
+        })
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+}
+
+struct FakeSeq<'a> {
+    fake: Fake<'a>,
+    remaining: usize,
+}
+
+impl<'de> SeqAccess<'de> for FakeSeq<'_> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+
+        self.remaining -= 1;
+        let field = self.fake.field;
+        seed.deserialize(self.fake.nested(field)).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
+
+struct FakeMap<'a> {
+    fake: Fake<'a>,
+    remaining: usize,
+}
+
+impl<'de> MapAccess<'de> for FakeMap<'_> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+
+        self.remaining -= 1;
+        seed.deserialize(self.fake.nested(None)).map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        seed.deserialize(self.fake.nested(None))
+    }
+}
+
+struct FakeStruct<'a> {
+    fake: Fake<'a>,
+    fields: slice::Iter<'static, &'static str>,
+    current: Option<&'static str>,
+}
+
+impl<'de> MapAccess<'de> for FakeStruct<'_> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        self.current = self.fields.next().copied();
+
+        match self.current {
+            Some(field) => {
+                let key: StrDeserializer<Error> = field.into_deserializer();
+                seed.deserialize(key).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let field = self.current;
+        seed.deserialize(self.fake.nested(field))
+    }
+}
+
+struct FakeEnum<'a> {
+    fake: Fake<'a>,
+    variant: &'static str,
+}
+
+impl<'de, 'a> EnumAccess<'de> for FakeEnum<'a> {
+    type Error = Error;
+    type Variant = FakeEnum<'a>;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, FakeEnum<'a>), Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let variant: StrDeserializer<Error> = self.variant.into_deserializer();
+        Ok((seed.deserialize(variant)?, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for FakeEnum<'_> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(mut self, seed: T) -> Result<T::Value, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let field = self.fake.field;
+        seed.deserialize(self.fake.nested(field))
+    }
+
+    fn tuple_variant<V>(mut self, len: usize, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        let field = self.fake.field;
+        self.fake.nested(field).deserialize_tuple(len, visitor)
+    }
+
+    fn struct_variant<V>(
+        mut self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.fake
+            .nested(None)
+            .deserialize_struct("", fields, visitor)
+    }
+}
@@ -0,0 +1,158 @@
+//! Create resources before a test, and delete them afterwards.
+//!
+//! This module provides the [`Fixture`] type, which runs a setup request (for
+//! instance, creating a user) and holds the deserialized response body. The
+//! teardown request (deleting the user) is run when the fixture is dropped,
+//! including when the test panics, so that failed tests don't leak resources
+//! on the backend.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use http::StatusCode;
+//! use restest::{fixture::Fixture, path, Context, Request};
+//! use serde::{Deserialize, Serialize};
+//!
+//! const CONTEXT: Context = Context::new().with_port(8080);
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let user = Fixture::setup(
+//!     &CONTEXT,
+//!     Request::post("users").with_body(UserInput { year_of_birth: 2000 }),
+//!     StatusCode::CREATED,
+//!     |user: &User| Request::delete(path!["users", user.id]),
+//! )
+//! .await;
+//!
+//! CONTEXT
+//!     .run(Request::get(path!["users", user.id]))
+//!     .await
+//!     .expect_status::<User>(StatusCode::OK)
+//!     .await;
+//!
+//! // The user is deleted here, or earlier if the test panics.
+//! user.teardown().await;
+//! # }
+//!
+//! #[derive(Serialize)]
+//! struct UserInput {
+//!     year_of_birth: usize,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct User {
+//!     id: u32,
+//! }
+//! ```
+
+use std::{ops::Deref, thread};
+
+use http::StatusCode;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use crate::{request::RequestResult, Context, Request};
+
+/// A resource created by a setup request, which is deleted by a teardown
+/// request when the fixture is dropped.
+///
+/// The fixture dereferences to the body of the setup response.
+pub struct Fixture<T> {
+    value: T,
+    teardown: Option<(Context, Request<Value>)>,
+}
+
+impl<T> Fixture<T> {
+    /// Runs a setup request, and checks its response status.
+    ///
+    /// The response body is deserialized and passed to `teardown`, which
+    /// returns the request that deletes the resource. This request is run when
+    /// the fixture is dropped, or when [`teardown`](Fixture::teardown) is
+    /// called.
+    ///
+    /// This function performs I/O, therefore it is marked as `async`.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the setup response status is not equal to
+    /// `status`, or if its body can not be deserialized. No teardown request
+    /// is run in this case.
+    pub async fn setup<I, R, D, F>(
+        context: &Context,
+        setup: R,
+        status: StatusCode,
+        teardown: F,
+    ) -> Fixture<T>
+    where
+        T: DeserializeOwned,
+        I: Serialize,
+        R: AsRef<Request<I>>,
+        D: Serialize,
+        F: FnOnce(&T) -> Request<D>,
+    {
+        let value = context.run(setup).await.expect_status::<T>(status).await;
+
+        let teardown = teardown(&value);
+        let body = serde_json::to_value(&teardown.body).expect("Body serialization failed");
+        let raw_body = teardown.raw_body.clone();
+
+        let mut teardown = teardown.with_body(body);
+        teardown.raw_body = raw_body;
+
+        // The teardown request may run on another thread, which must not be
+        // mistaken for a new test and reset the backend.
+        Fixture {
+            value,
+            teardown: Some((context.without_reset(), teardown)),
+        }
+    }
+
+    /// Returns the body of the setup response.
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// Runs the teardown request, and returns its result.
+    ///
+    /// Calling this method at the end of a test is preferred to dropping the
+    /// fixture: the teardown request is run on the test runtime, and its
+    /// response can be checked.
+    ///
+    /// This function performs I/O, therefore it is marked as `async`.
+    pub async fn teardown(mut self) -> RequestResult {
+        let (context, request) = self.teardown.take().unwrap();
+
+        context.run(request).await
+    }
+}
+
+impl<T> Deref for Fixture<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+/// Runs the teardown request, unless [`Fixture::teardown`] was called.
+///
+/// As `drop` can not be `async`, the request is run on a separate thread with
+/// its own runtime, and the current thread is blocked until it completes.
+/// Errors are ignored, since the test may already be panicking.
+impl<T> Drop for Fixture<T> {
+    fn drop(&mut self) {
+        let Some((context, request)) = self.teardown.take() else {
+            return;
+        };
+
+        let _ = thread::spawn(move || {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .expect("Runtime creation failed")
+                .block_on(context.run(request));
+        })
+        .join();
+    }
+}
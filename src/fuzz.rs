@@ -0,0 +1,373 @@
+//! Send randomly mutated requests, and check an invariant on the responses.
+//!
+//! This module provides the [`Fuzzer`] type, which takes a request template
+//! and repeatedly sends it with a mutated JSON body: fields are removed,
+//! replaced with values of another type, with empty or huge strings, with
+//! extreme numbers, and so on. An invariant is checked on every response, the
+//! most common one being "the server never answers with a `5xx` status".
+//!
+//! When the invariant does not hold, the failing body is shrunk: fields,
+//! array elements and values are simplified as long as the invariant still
+//! fails, and the minimal failing body is reported along with the seed, so
+//! that the run can be reproduced with [`Fuzzer::with_seed`].
+//!
+//! This module is available with the `fuzz` feature.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use restest::{fuzz::Fuzzer, Context, Request};
+//! use serde::Serialize;
+//!
+//! const CONTEXT: Context = Context::new().with_port(8080);
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let template = Request::post("users").with_body(UserInput {
+//!     name: "Grace Hopper".to_string(),
+//!     year_of_birth: 1906,
+//! });
+//!
+//! Fuzzer::new(&CONTEXT, template)
+//!     .with_iterations(200)
+//!     .assert_no_server_error()
+//!     .await;
+//! # }
+//!
+//! #[derive(Serialize)]
+//! struct UserInput {
+//!     name: String,
+//!     year_of_birth: u16,
+//! }
+//! ```
+
+use http::StatusCode;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::{report, Context, Request};
+
+/// The default number of mutated requests sent by a fuzzer.
+const DEFAULT_ITERATIONS: usize = 100;
+
+/// The maximum number of requests sent while shrinking a failing body.
+const MAX_SHRINK_REQUESTS: usize = 200;
+
+/// Strings that commonly trigger bugs in input handling.
+const INTERESTING_STRINGS: &[&str] = &[
+    "",
+    " ",
+    "0",
+    "-1",
+    "null",
+    "true",
+    "' OR '1'='1",
+    "<script>alert(1)</script>",
+    "../../../../etc/passwd",
+    "%00",
+    "\u{0}",
+    "\u{202e}evil",
+    "😀👍🏽",
+    "Ω≈ç√∫",
+];
+
+/// Numbers that commonly trigger bugs in input handling.
+const INTERESTING_NUMBERS: &[f64] = &[0., -1., 0.5, -0.5, 1e308, -1e308, 4294967296.];
+
+/// Sends randomly mutated versions of a request, and checks an invariant on
+/// the responses.
+pub struct Fuzzer<'a> {
+    context: &'a Context,
+    template: Request<Value>,
+    iterations: usize,
+    seed: Option<u64>,
+}
+
+impl<'a> Fuzzer<'a> {
+    /// Creates a fuzzer from a request template.
+    ///
+    /// The body of the template is the starting point of every mutation.
+    pub fn new<I>(context: &'a Context, template: Request<I>) -> Fuzzer<'a>
+    where
+        I: Serialize,
+    {
+        let body = serde_json::to_value(&template.body).expect("Body serialization failed");
+
+        Fuzzer {
+            context,
+            template: template.with_body(body),
+            iterations: DEFAULT_ITERATIONS,
+            seed: None,
+        }
+    }
+
+    /// Sets the number of mutated requests that are sent.
+    ///
+    /// The default is 100.
+    pub fn with_iterations(self, iterations: usize) -> Fuzzer<'a> {
+        Fuzzer { iterations, ..self }
+    }
+
+    /// Sets the seed of the mutations, so that a failing run can be
+    /// reproduced.
+    ///
+    /// By default, a random seed is used. It is included in the failure
+    /// message.
+    pub fn with_seed(self, seed: u64) -> Fuzzer<'a> {
+        Fuzzer {
+            seed: Some(seed),
+            ..self
+        }
+    }
+
+    /// Checks that the server never answers a mutated request with a server
+    /// error (`5xx`) status.
+    ///
+    /// This function performs I/O, therefore it is marked as `async`.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if a mutated request leads to a server error. The
+    /// panic message contains the minimal failing body and the seed.
+    pub async fn assert_no_server_error(&self) {
+        report::check_soft(
+            "assert_no_server_error",
+            &self.context.tags_of(&self.template),
+            self.ensure_no_server_error().await,
+        );
+    }
+
+    /// Checks that the server never answers a mutated request with a server
+    /// error (`5xx`) status.
+    ///
+    /// This function performs I/O, therefore it is marked as `async`.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if a mutated request leads to a server
+    /// error. The error message contains the minimal failing body and the
+    /// seed.
+    pub async fn ensure_no_server_error(&self) -> Result<(), String> {
+        self.ensure_invariant(|status, _| {
+            if status.is_server_error() {
+                Err(format!("server error {}", status))
+            } else {
+                Ok(())
+            }
+        })
+        .await
+    }
+
+    /// Checks an invariant on the responses to the mutated requests.
+    ///
+    /// The invariant receives the response status and body, and returns an
+    /// error describing the violation, if any.
+    ///
+    /// This function performs I/O, therefore it is marked as `async`.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the invariant does not hold for a mutated
+    /// request. The panic message contains the minimal failing body and the
+    /// seed.
+    pub async fn assert_invariant<F>(&self, invariant: F)
+    where
+        F: Fn(StatusCode, &[u8]) -> Result<(), String>,
+    {
+        report::check_soft(
+            "assert_invariant",
+            &self.context.tags_of(&self.template),
+            self.ensure_invariant(invariant).await,
+        );
+    }
+
+    /// Checks an invariant on the responses to the mutated requests.
+    ///
+    /// Refer to the [`assert_invariant`](Fuzzer::assert_invariant) method
+    /// documentation for more details.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the invariant does not hold for a
+    /// mutated request. The error message contains the minimal failing body
+    /// and the seed.
+    pub async fn ensure_invariant<F>(&self, invariant: F) -> Result<(), String>
+    where
+        F: Fn(StatusCode, &[u8]) -> Result<(), String>,
+    {
+        let seed = self.seed.unwrap_or_else(rand::random);
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        for iteration in 1..=self.iterations {
+            let mut body = self.template.body.clone();
+            for _ in 0..rng.gen_range(1..=3) {
+                mutate(&mut body, &mut rng);
+            }
+
+            if let Err(reason) = self.check(&body, &invariant).await {
+                let (minimal, reason) = self.shrink(body, reason, &invariant).await;
+
+                return Err(format!(
+                    "Invariant violated for request '{}' after {} iteration(s) (seed {}): {}\nMinimal failing body: {}",
+                    self.context.description_of(&self.template), iteration, seed, reason, minimal
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn check<F>(&self, body: &Value, invariant: &F) -> Result<(), String>
+    where
+        F: Fn(StatusCode, &[u8]) -> Result<(), String>,
+    {
+        let request = self.template.clone().with_body(body.clone());
+        let mut result = self.context.run(&request).await;
+        let status = result.status;
+
+        invariant(status, result.ensure_bytes().await?)
+    }
+
+    /// Simplifies a failing body as long as the invariant still fails.
+    async fn shrink<F>(&self, mut body: Value, mut reason: String, invariant: &F) -> (Value, String)
+    where
+        F: Fn(StatusCode, &[u8]) -> Result<(), String>,
+    {
+        let mut requests = 0;
+
+        'shrink: loop {
+            for candidate in simplifications(&body) {
+                if requests == MAX_SHRINK_REQUESTS {
+                    break 'shrink;
+                }
+                requests += 1;
+
+                if let Err(candidate_reason) = self.check(&candidate, invariant).await {
+                    body = candidate;
+                    reason = candidate_reason;
+                    continue 'shrink;
+                }
+            }
+
+            break;
+        }
+
+        (body, reason)
+    }
+}
+
+/// Returns the JSON pointer of every node of a value, including the root.
+fn pointers(value: &Value, prefix: String, pointers: &mut Vec<String>) {
+    match value {
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                self::pointers(item, format!("{}/{}", prefix, index), pointers);
+            }
+        }
+        Value::Object(fields) => {
+            for (key, field) in fields {
+                let key = key.replace('~', "~0").replace('/', "~1");
+                self::pointers(field, format!("{}/{}", prefix, key), pointers);
+            }
+        }
+        _ => {}
+    }
+
+    pointers.push(prefix);
+}
+
+/// Applies a random mutation to a random node of a value.
+fn mutate(body: &mut Value, rng: &mut StdRng) {
+    let mut all = Vec::new();
+    pointers(body, String::new(), &mut all);
+
+    let pointer = all.choose(rng).unwrap();
+    let node = body.pointer_mut(pointer).unwrap();
+
+    *node = match (node.take(), rng.gen_range(0..4)) {
+        (Value::Object(mut fields), 0) if !fields.is_empty() => {
+            let key = fields
+                .keys()
+                .nth(rng.gen_range(0..fields.len()))
+                .unwrap()
+                .clone();
+            fields.remove(&key);
+            Value::Object(fields)
+        }
+        (Value::Object(mut fields), 1) => {
+            fields.insert("__restest_unknown".to_string(), json!("fuzz"));
+            Value::Object(fields)
+        }
+        (Value::Array(mut items), 0) if !items.is_empty() => {
+            let item = items[rng.gen_range(0..items.len())].clone();
+            items.extend(std::iter::repeat_n(item, 100));
+            Value::Array(items)
+        }
+        (Value::String(text), 0) => {
+            Value::String(text.repeat(1000).chars().take(100_000).collect())
+        }
+        (_, 0) | (_, 1) => interesting_value(rng),
+        (_, 2) => Value::String(INTERESTING_STRINGS.choose(rng).unwrap().to_string()),
+        _ => json!(INTERESTING_NUMBERS.choose(rng).unwrap()),
+    };
+}
+
+fn interesting_value(rng: &mut StdRng) -> Value {
+    match rng.gen_range(0..6) {
+        0 => Value::Null,
+        1 => Value::Bool(rng.gen()),
+        2 => json!(i64::MAX),
+        3 => json!(i64::MIN),
+        4 => json!([]),
+        _ => json!({}),
+    }
+}
+
+/// Returns the simpler versions of a value, obtained by simplifying a single
+/// node.
+fn simplifications(body: &Value) -> Vec<Value> {
+    let mut all = Vec::new();
+    pointers(body, String::new(), &mut all);
+
+    let mut candidates = Vec::new();
+
+    for pointer in all {
+        let node = body.pointer(&pointer).unwrap();
+
+        let simpler = match node {
+            Value::Object(fields) => fields
+                .keys()
+                .map(|key| {
+                    let mut fields = fields.clone();
+                    fields.remove(key);
+                    Value::Object(fields)
+                })
+                .collect(),
+            Value::Array(items) => (0..items.len())
+                .map(|index| {
+                    let mut items = items.clone();
+                    items.remove(index);
+                    Value::Array(items)
+                })
+                .collect(),
+            Value::String(text) if !text.is_empty() => {
+                let half = text
+                    .chars()
+                    .take(text.chars().count() / 2)
+                    .collect::<String>();
+                vec![Value::String(String::new()), Value::String(half)]
+            }
+            Value::Number(number) if number.as_f64() != Some(0.) => vec![json!(0)],
+            _ => Vec::new(),
+        };
+
+        for value in simpler {
+            let mut candidate = body.clone();
+            *candidate.pointer_mut(&pointer).unwrap() = value;
+            candidates.push(candidate);
+        }
+    }
+
+    candidates
+}
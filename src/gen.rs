@@ -0,0 +1,86 @@
+//! Generate unique values for request bodies.
+//!
+//! Tests that run concurrently against a shared backend must not create
+//! resources with the same email address or user name, otherwise some of them
+//! fail with `409 Conflict`. The functions of this module return values that
+//! are unique across the tests of a process, and across processes.
+//!
+//! Uniqueness is obtained by combining the time at which the process started
+//! using this module, the process id, and a counter.
+//!
+//! As `gen` is a reserved keyword since the 2024 edition, crates using this
+//! edition must refer to this module as `restest::r#gen`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use restest::{gen, Request};
+//! use serde::Serialize;
+//!
+//! let request = Request::post("users").with_body(UserInput {
+//!     email: gen::unique_email(),
+//!     username: gen::unique_username("ghopper"),
+//! });
+//!
+//! assert_ne!(gen::unique_email(), gen::unique_email());
+//!
+//! #[derive(Serialize)]
+//! struct UserInput {
+//!     email: String,
+//!     username: String,
+//! }
+//! ```
+
+use std::{
+    process,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        OnceLock,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use uuid::Uuid;
+
+/// The domain of the generated email addresses. It is reserved for
+/// documentation and testing by RFC 2606.
+const EMAIL_DOMAIN: &str = "example.com";
+
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Returns a unique email address, such as
+/// `user_18c4f2a91b3_2f1a_0@example.com`.
+pub fn unique_email() -> String {
+    format!("{}@{}", unique_username("user"), EMAIL_DOMAIN)
+}
+
+/// Returns a unique user name starting with `prefix`, such as
+/// `ghopper_18c4f2a91b3_2f1a_0`.
+///
+/// The generated suffix only contains lowercase ASCII letters, digits and
+/// underscores.
+pub fn unique_username(prefix: &str) -> String {
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+
+    format!("{}_{}_{}", prefix, run_id(), count)
+}
+
+/// Returns a random version 4 UUID.
+pub fn uuid() -> Uuid {
+    Uuid::new_v4()
+}
+
+/// Returns an identifier of the current process, which is unique across the
+/// processes that run concurrently.
+fn run_id() -> &'static str {
+    static RUN_ID: OnceLock<String> = OnceLock::new();
+
+    RUN_ID.get_or_init(|| {
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+
+        format!("{:x}_{:x}", millis, process::id())
+    })
+}
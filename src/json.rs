@@ -0,0 +1,325 @@
+//! Extract values from untyped JSON documents.
+//!
+//! A single value deep in a large body is best pulled out with a
+//! [JSON Pointer](https://datatracker.ietf.org/doc/html/rfc6901), rather than
+//! with a full struct or pattern. Response bodies can be queried directly with
+//! [`RequestResult::expect_pointer`](crate::request::RequestResult::expect_pointer).
+//!
+//! Collections are best checked with a
+//! [JSONPath](https://datatracker.ietf.org/doc/html/rfc9535) query, such as
+//! `$.items[?@.active == true].id`, along with a
+//! [`Matcher`](crate::pattern::Matcher) every selected value must satisfy.
+//! This is done with [`expect_jsonpath`], with
+//! [`RequestResult::expect_jsonpath`](crate::request::RequestResult::expect_jsonpath),
+//! or with the [`assert_jsonpath`](crate::assert_jsonpath) macro, which takes
+//! an [`assert_body_matches`](crate::assert_body_matches) pattern. JSONPath
+//! support is available with the `jsonpath` feature.
+//!
+//! Objects with duplicate keys are accepted by most parsers, which silently
+//! keep one of the values. They are reported by [`duplicate_keys`] and
+//! [`RequestResult::expect_no_duplicate_keys`](crate::request::RequestResult::expect_no_duplicate_keys).
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use http::StatusCode;
+//! use restest::{path, Context, Request};
+//!
+//! const CONTEXT: Context = Context::new().with_port(8080);
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let id: u64 = CONTEXT
+//!     .run(Request::get("users"))
+//!     .await
+//!     .expect_status_code(StatusCode::OK)
+//!     .await
+//!     .expect_pointer("/data/items/0/id")
+//!     .await;
+//!
+//! CONTEXT
+//!     .run(Request::get(path!["users", id]))
+//!     .await
+//!     .expect_status_code(StatusCode::OK)
+//!     .await;
+//! # }
+//! ```
+
+use std::{collections::HashSet, fmt};
+
+use serde::de::{DeserializeOwned, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde_json::Value;
+
+#[cfg(feature = "jsonpath")]
+use serde_json_path::JsonPath;
+
+#[cfg(feature = "jsonpath")]
+use crate::pattern::Matcher;
+use crate::report;
+
+/// Deserializes the value found at a JSON Pointer.
+///
+/// # Example
+///
+/// ```rust
+/// use restest::json;
+/// use serde_json::json;
+///
+/// let body = json!({ "data": { "items": [{ "id": 42 }] } });
+///
+/// let id: u64 = json::expect_pointer(&body, "/data/items/0/id");
+/// assert_eq!(id, 42);
+/// ```
+///
+/// # Panics
+///
+/// This function panics if there is no value at `pointer`, or if the value
+/// can not be deserialized to the specified type.
+#[track_caller]
+pub fn expect_pointer<T>(value: &Value, pointer: &str) -> T
+where
+    T: DeserializeOwned,
+{
+    report::check(
+        "expect_pointer",
+        &report::last_tags(),
+        ensure_pointer(value, pointer),
+    )
+}
+
+/// Deserializes the value found at a JSON Pointer.
+///
+/// # Error
+///
+/// This function returns an error if there is no value at `pointer`, or if
+/// the value can not be deserialized to the specified type.
+pub fn ensure_pointer<T>(value: &Value, pointer: &str) -> Result<T, String>
+where
+    T: DeserializeOwned,
+{
+    let found = value
+        .pointer(pointer)
+        .ok_or_else(|| format!("No value at '{}' in {}", pointer, value))?;
+
+    T::deserialize(found).map_err(|err| {
+        format!(
+            "Failed to deserialize value at '{}': {}. Value is {}",
+            pointer, err, found
+        )
+    })
+}
+
+/// Checks that every value selected by a JSONPath query is accepted by a
+/// matcher.
+///
+/// The query must select at least one value, so that a typo in the query
+/// does not make the assertion pass silently.
+///
+/// This function is available with the `jsonpath` feature.
+///
+/// # Example
+///
+/// ```rust
+/// use restest::{json, pattern::MatchError};
+/// use serde_json::{json, Value};
+///
+/// let body = json!({
+///     "items": [
+///         { "id": 1, "active": true },
+///         { "id": null, "active": false },
+///     ],
+/// });
+///
+/// json::expect_jsonpath(&body, "$.items[?@.active == true].id", |id: &Value| match id {
+///     Value::Null => Err(MatchError::with_reason("id is null")),
+///     _ => Ok(()),
+/// });
+/// ```
+///
+/// # Panics
+///
+/// This function panics if the query is invalid, if it selects no value, or
+/// if any selected value is rejected by the matcher, unless it is run in a
+/// [`soft`](crate::soft) scope.
+#[cfg(feature = "jsonpath")]
+#[track_caller]
+pub fn expect_jsonpath(value: &Value, path: &str, matcher: impl Matcher) {
+    report::check_soft(
+        "expect_jsonpath",
+        &report::last_tags(),
+        ensure_jsonpath(value, path, matcher),
+    );
+}
+
+/// Checks that every value selected by a JSONPath query is accepted by a
+/// matcher.
+///
+/// This function is available with the `jsonpath` feature.
+///
+/// # Error
+///
+/// This function returns an error if the query is invalid, if it selects no
+/// value, or if any selected value is rejected by the matcher.
+#[cfg(feature = "jsonpath")]
+pub fn ensure_jsonpath(value: &Value, path: &str, matcher: impl Matcher) -> Result<(), String> {
+    let query =
+        JsonPath::parse(path).map_err(|err| format!("Invalid JSONPath '{}': {}", path, err))?;
+
+    let nodes = query.query_located(value);
+    if nodes.is_empty() {
+        return Err(format!("No value selected by '{}' in {}", path, value));
+    }
+
+    let rejected = nodes
+        .iter()
+        .filter_map(|node| {
+            let err = matcher.matches(node.node()).err()?;
+            Some(format!(
+                "  - {} = {}: {}",
+                node.location(),
+                node.node(),
+                err
+            ))
+        })
+        .collect::<Vec<_>>();
+
+    if !rejected.is_empty() {
+        return Err(format!(
+            "{} of {} values selected by '{}' do not match:\n{}",
+            rejected.len(),
+            nodes.len(),
+            path,
+            rejected.join("\n")
+        ));
+    }
+
+    Ok(())
+}
+
+/// Returns the JSON Pointer of every key that appears more than once in the
+/// same object of a JSON document.
+///
+/// # Example
+///
+/// ```rust
+/// use restest::json;
+///
+/// let document = r#"{ "id": 1, "user": { "name": "Grace", "name": "Ada" } }"#;
+///
+/// assert_eq!(json::duplicate_keys(document.as_bytes()).unwrap(), ["/user/name"]);
+/// ```
+///
+/// # Error
+///
+/// This function returns an error if the document is not valid JSON.
+pub fn duplicate_keys(document: &[u8]) -> Result<Vec<String>, String> {
+    let mut duplicates = Vec::new();
+    let mut deserializer = serde_json::Deserializer::from_slice(document);
+
+    DuplicateKeys {
+        pointer: String::new(),
+        duplicates: &mut duplicates,
+    }
+    .deserialize(&mut deserializer)
+    .and_then(|()| deserializer.end())
+    .map_err(|err| format!("Invalid JSON: {}", err))?;
+
+    Ok(duplicates)
+}
+
+/// Walks a JSON document without building it, and collects the pointers of
+/// duplicate keys.
+struct DuplicateKeys<'a> {
+    pointer: String,
+    duplicates: &'a mut Vec<String>,
+}
+
+impl<'de> DeserializeSeed<'de> for DuplicateKeys<'_> {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for DuplicateKeys<'_> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a JSON value")
+    }
+
+    fn visit_bool<E>(self, _: bool) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_i64<E>(self, _: i64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_u64<E>(self, _: u64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_f64<E>(self, _: f64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_str<E>(self, _: &str) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_unit<E>(self) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<(), A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut index = 0;
+
+        loop {
+            let element = DuplicateKeys {
+                pointer: format!("{}/{}", self.pointer, index),
+                duplicates: &mut *self.duplicates,
+            };
+
+            if seq.next_element_seed(element)?.is_none() {
+                return Ok(());
+            }
+
+            index += 1;
+        }
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<(), A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut keys = HashSet::new();
+
+        while let Some(key) = map.next_key::<String>()? {
+            // See RFC 6901, section 3.
+            let pointer = format!(
+                "{}/{}",
+                self.pointer,
+                key.replace('~', "~0").replace('/', "~1")
+            );
+
+            if !keys.insert(key) && !self.duplicates.contains(&pointer) {
+                self.duplicates.push(pointer.clone());
+            }
+
+            map.next_value_seed(DuplicateKeys {
+                pointer,
+                duplicates: &mut *self.duplicates,
+            })?;
+        }
+
+        Ok(())
+    }
+}
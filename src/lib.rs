@@ -67,7 +67,8 @@
 //!
 //! The [`Context`] object handles all the server-specific configuration:
 //!   - which base URL should be used,
-//!   - which port should be used.
+//!   - which port should be used,
+//!   - which proxy should be used, if any.
 //!
 //! It can be created with [`Context::new`]. All its setters are `const`, so
 //! it can be initialized once for all the tests of a module:
@@ -91,6 +92,20 @@
 //! As we're running `async` code under the hood, all the tests must be `async`,
 //! hence the use of `tokio::test`
 //!
+//! Alternatively, the [`test`] attribute runs an `async` test in a runtime and
+//! gives it a [`Context`] read from the `RESTEST_HOST` and `RESTEST_PORT`
+//! environment variables. The name of the test is added to the description of
+//! its requests, and therefore to every failure message:
+//!
+//! ```rust
+//! use restest::Context;
+//!
+//! #[restest::test]
+//! async fn test_first_route(context: Context) {
+//!     // Test code that use `context` for a specific route
+//! }
+//! ```
+//!
 //! # Creating a request
 //!
 //! Let's focus on the test function itself.
@@ -103,7 +118,8 @@
 //! [`with_header`](request::Request::with_header) method.
 //! A body can be specified by calling the
 //! [`with_body`](request::Request::with_body) method, which
-//! allows to add a body.
+//! allows to add a body. Requests without a body are sent without a body nor
+//! a `Content-Type` header.
 //!
 //! ```rust
 //! use restest::{path, Request};
@@ -148,6 +164,38 @@
 //! }
 //! ```
 //!
+//! # Panics or errors
+//!
+//! Every `expect_*` method has an `ensure_*` counterpart that performs the
+//! same checks, but returns a `Result` instead of panicking. The error is a
+//! `String` describing the failed check. Both flavors can be mixed, but tests
+//! written with `?` can stick to the `ensure_*` methods:
+//!
+//! ```rust,no_run
+//! use http::StatusCode;
+//! use restest::{Context, Request};
+//!
+//! const CONTEXT: Context = Context::new().with_port(8080);
+//!
+//! #[tokio::test]
+//! async fn get_user() -> Result<(), String> {
+//!     let name: String = CONTEXT
+//!         .run(Request::get("users/ghopper/name"))
+//!         .await
+//!         .ensure_content_encoding("gzip")?
+//!         .ensure_status(StatusCode::OK)
+//!         .await?;
+//!
+//!     assert_eq!(name, "Grace Hopper");
+//!
+//!     Ok(())
+//! }
+//! ```
+//!
+//! When a test fails in CI, setting the `RESTEST_LOG` environment variable to
+//! `info` or `debug` logs every request that was sent and every response that
+//! was received. Refer to the [`log`] module documentation for more details.
+//!
 //! # Checking the response body
 //!
 //! Properties about the response body can be asserted with
@@ -195,7 +243,56 @@
 ///
 /// This pattern supports all the Rust pattern syntax, with a few additions:
 ///   - matching on [`String`] can be done with string literals,
-///   - matching on [`Vec`] can be done using slice patterns,
+///   - integer, float, boolean, character and byte string literals are
+///     compared with `==`, so that they also match a [`serde_json::Value`] or
+///     a `Vec<u8>`,
+///   - ranges accept any bound that can be compared, including float literals
+///     (`ratio: 0.0..1.0`),
+///   - comparisons can be expressed with `gt!(value)`, `ge!(value)`,
+///     `lt!(value)` and `le!(value)`,
+///   - [RFC 3339](https://datatracker.ietf.org/doc/html/rfc3339) datetimes
+///     can be checked with `iso8601!()`, and checked to be close to the
+///     current time with `within_last!(5 s)` (the accepted units are `ms`,
+///     `s`, `min`, `h` and `d`, a [`Duration`](std::time::Duration)
+///     expression is accepted too),
+///   - strings can be compared loosely with `contains!("error")`,
+///     `starts_with!("Bearer ")`, `ends_with!(".json")` and
+///     `eq_ignore_case!("ok")`,
+///   - any other constraint can be expressed with a predicate, such as
+///     `satisfies!(|balance: &f64| *balance >= 0.0)`. The predicate is called
+///     with a reference to the value,
+///   - reusable checks implementing [`Matcher`](pattern::Matcher) can be
+///     used with `custom!(matcher)`,
+///   - UUIDs can be checked with `uuid!()`, or `uuid!(4)` to check the
+///     version too. Binding the matcher (`id @ uuid!()`) brings the parsed
+///     `uuid::Uuid` to scope,
+///   - subtrees can be checked to deserialize to a type with
+///     `deserialize!(Type)`. Binding the matcher
+///     (`payload @ deserialize!(Type)`) brings the deserialized value to
+///     scope. This is mostly useful with untyped
+///     [`serde_json::Value`](serde_json::Value) bodies, whose subtrees can
+///     also be bound as they are,
+///   - matching on [`Vec`] can be done using slice patterns, or regardless
+///     of the order of the elements with `unordered![pattern, ...]`. Slice
+///     patterns also match arrays, slices, any `AsRef<[T]>`, and
+///     [`VecDeque`](std::collections::VecDeque)s whose elements implement
+///     [`Clone`]. With `unordered!`, each element must match a distinct
+///     pattern. The elements must implement [`Clone`], and the patterns can
+///     not bring values in scope,
+///   - the remaining elements of a slice pattern can be brought to scope as
+///     a [`Vec`] with `rest @ ..`, which clones them,
+///   - large arrays can be checked to contain some elements with
+///     `contains![pattern, ...]`, each pattern matching a distinct element,
+///     with the same restrictions as `unordered!`,
+///   - the length of an array, a map or a string can be checked with
+///     `len!(10)`, or `len!(1..=10)` for a range of lengths,
+///   - the keys of an object or a map can be checked independently of their
+///     values with `keys!["id", "name"]`, which requires exactly these keys,
+///     or with `only_keys!["id", "name", "email"]`, which rejects any other
+///     key,
+///   - matching on maps, such as [`HashMap`](std::collections::HashMap), can
+///     be done with `map! { "key" => pattern, .. }`. Without the trailing
+///     `..`, the map must not contain any other key,
 ///   - values that are bound to variables are available in the whole scope,
 ///     allowing for later use.
 ///
@@ -269,14 +366,490 @@
 ///
 /// Bringing values to scope may allow to extract information that are required
 /// to perform a next request.
+///
+/// Numbers can be checked against ranges and comparisons, which is useful for
+/// generated values such as timestamps and counters:
+///
+/// ```rust
+/// use restest::assert_body_matches;
+///
+/// struct Stats {
+///     users: u32,
+///     ratio: f64,
+/// }
+///
+/// let stats = Stats { users: 42, ratio: 0.5 };
+///
+/// assert_body_matches! {
+///     stats,
+///     Stats {
+///         users: gt!(0),
+///         ratio: 0.0..=1.0,
+///     },
+/// }
+/// ```
+///
+/// Timestamps can be checked without parsing them beforehand:
+///
+/// ```rust
+/// use restest::assert_body_matches;
+///
+/// struct Event {
+///     created_at: String,
+///     expires_at: String,
+/// }
+///
+/// let event = Event {
+///     created_at: "1970-01-01T00:00:00Z".to_string(),
+///     expires_at: "2100-01-01T00:00:00+01:00".to_string(),
+/// };
+///
+/// assert_body_matches! {
+///     event,
+///     Event {
+///         created_at: iso8601!(),
+///         expires_at: iso8601!(),
+///     },
+/// }
+/// ```
+///
+/// Sets returned in no particular order can be matched without relying on
+/// the order of their elements:
+///
+/// ```rust
+/// use restest::assert_body_matches;
+///
+/// struct User {
+///     name: String,
+///     roles: Vec<String>,
+/// }
+///
+/// let user = User {
+///     name: "Grace Hopper".to_string(),
+///     roles: vec!["dev".to_string(), "admin".to_string()],
+/// };
+///
+/// assert_body_matches! {
+///     user,
+///     User {
+///         name: "Grace Hopper",
+///         roles: unordered!["admin", "dev"],
+///     },
+/// }
+/// ```
+///
+/// Listings can be checked without enumerating all their elements:
+///
+/// ```rust
+/// use restest::assert_body_matches;
+///
+/// #[derive(Clone)]
+/// struct User {
+///     id: u32,
+///     name: String,
+/// }
+///
+/// let users = (0..100)
+///     .map(|id| User {
+///         id,
+///         name: format!("user {}", id),
+///     })
+///     .collect::<Vec<_>>();
+///
+/// assert_body_matches!(users.clone(), len!(100));
+/// assert_body_matches!(users, contains![User { id: 42, .. }]);
+/// ```
+///
+/// Maps with dynamic keys can be matched too:
+///
+/// ```rust
+/// use std::collections::HashMap;
+///
+/// use restest::assert_body_matches;
+///
+/// let scores = HashMap::from([
+///     ("ghopper".to_string(), 42),
+///     ("aturing".to_string(), 40),
+/// ]);
+///
+/// assert_body_matches! {
+///     scores,
+///     map! {
+///         "ghopper" => 42,
+///         "aturing" => score,
+///     },
+/// }
+///
+/// assert_eq!(score, 40);
+/// ```
+///
+/// Identifiers can be checked and parsed at the same time:
+///
+/// ```rust
+/// use restest::assert_body_matches;
+///
+/// struct User {
+///     id: String,
+///     name: String,
+/// }
+///
+/// let user = User {
+///     id: "67e55044-10b1-426f-9247-bb680e5fe0c8".to_string(),
+///     name: "John Doe".to_string(),
+/// };
+///
+/// assert_body_matches! {
+///     user,
+///     User {
+///         id: id @ uuid!(4),
+///         name: "John Doe",
+///     },
+/// }
+///
+/// // id is a `uuid::Uuid`:
+/// assert_eq!(id.get_version_num(), 4);
+/// ```
+///
+/// Subtrees of untyped bodies can be bound as [`serde_json::Value`], or
+/// deserialized to a concrete type:
+///
+/// ```rust
+/// use restest::assert_body_matches;
+/// use serde::Deserialize;
+/// use serde_json::{json, Value};
+///
+/// #[derive(Deserialize)]
+/// struct User {
+///     name: String,
+/// }
+///
+/// let body = json!({
+///     "status": "ok",
+///     "data": { "name": "Grace Hopper" },
+///     "meta": { "page": 1 },
+/// });
+///
+/// assert_body_matches! {
+///     body,
+///     Value::Object(map! {
+///         "status" => "ok",
+///         "data" => user @ deserialize!(User),
+///         "meta" => meta,
+///     }),
+/// }
+///
+/// assert_eq!(user.name, "Grace Hopper");
+/// assert_eq!(meta["page"], 1);
+/// ```
+///
+/// Untyped bodies can also be matched with object patterns, whose keys are
+/// written like struct fields. Combined with alternatives, they match the
+/// tagged unions produced by serde enums, without deserializing them first:
+///
+/// ```rust
+/// use restest::assert_body_matches;
+/// use serde_json::json;
+///
+/// let body = json!({
+///     "type": "card",
+///     "details": { "last4": "4242", "brand": "visa" },
+/// });
+///
+/// assert_body_matches! {
+///     body,
+///     { type: "card", details: { last4: _, .. } } | { type: "iban", .. },
+/// }
+/// ```
+///
+/// Responses which legitimately vary can be matched with several
+/// alternatives, as long as they bring the same values in scope:
+///
+/// ```rust
+/// use restest::assert_body_matches;
+/// use serde_json::json;
+///
+/// let body = json!({ "source": "cache", "age": 12, "data": [1, 2, 3] });
+///
+/// assert_body_matches! {
+///     body,
+///     { source: "cache", data, .. } | { source: "origin", data },
+/// }
+///
+/// assert_eq!(data, json!([1, 2, 3]));
+/// ```
+///
+/// Integer literals, ranges, slice patterns and matcher macros can be used on
+/// untyped values too. A value of the wrong JSON type simply does not match:
+///
+/// ```rust
+/// use restest::assert_body_matches;
+/// use serde_json::json;
+///
+/// let body = json!({ "id": 42, "age": 36, "tags": ["admin", "ops"] });
+///
+/// assert_body_matches! {
+///     body,
+///     { id: 42, age: 18..=99, tags: [first, ..] },
+/// }
+///
+/// assert_eq!(first, "admin");
+/// ```
+///
+/// Alternatives which contain literals, ranges, slices, maps or matcher
+/// macros are checked on a clone of the value. Nested in a larger pattern,
+/// they can not bring values in scope.
 pub use restest_macros::assert_body_matches;
 
+/// Checks whether a value matches a given pattern, without panicking.
+///
+/// The pattern syntax is the same as in [`assert_body_matches`]. Instead of
+/// adding bindings to the current scope, this macro evaluates to a
+/// `Result`: if the value matches, it contains a tuple of the bound values,
+/// in the order they appear in the pattern. Otherwise, it contains a
+/// [`MatchError`](pattern::MatchError).
+///
+/// This allows to use pattern matching in retry loops and helper functions,
+/// where a panic is not appropriate.
+///
+/// # Example
+///
+/// ```rust
+/// use restest::body_matches;
+///
+/// struct Job {
+///     status: String,
+///     progress: u8,
+/// }
+///
+/// let job = Job {
+///     status: "running".to_string(),
+///     progress: 42,
+/// };
+///
+/// match body_matches!(job, Job { status: "done", progress }) {
+///     Ok((progress,)) => println!("Job done ({}%)", progress),
+///     Err(err) => println!("Job not done yet: {}", err),
+/// }
+/// ```
+pub use restest_macros::body_matches;
+
+/// Defines named patterns, which can be referenced in other patterns.
+///
+/// Each definition is a name, followed by `=` and a pattern with the syntax
+/// of [`assert_body_matches`]. Definitions are separated by `;`. A named
+/// pattern is referenced with `pattern!(NAME)` in [`assert_body_matches`],
+/// [`body_matches`] and other named patterns, so that the shape of a common
+/// entity is asserted consistently, and updated in one place.
+///
+/// Named patterns are macros: like any `macro_rules!` macro, they can be
+/// used after their definition in the same module and its submodules, and
+/// from other modules of the crate with their path, such as
+/// `pattern!(crate::shapes::USER)`. Bindings in a named pattern are not
+/// brought in scope, but the value matched by a named pattern can be bound
+/// with `user @ pattern!(USER)`.
+///
+/// The value matched by a named pattern must implement [`Clone`], as it is
+/// checked on a clone of the value.
+///
+/// # Example
+///
+/// ```rust
+/// use restest::assert_body_matches;
+///
+/// #[derive(Clone)]
+/// struct User {
+///     id: String,
+///     name: String,
+/// }
+///
+/// struct Team {
+///     owner: User,
+///     deputy: User,
+/// }
+///
+/// restest::pattern! {
+///     USER = User { id: uuid!(), .. };
+///     ADMIN = User { name: "admin", .. };
+/// }
+///
+/// # let user = |name: &str| User {
+/// #     id: "67e55044-10b1-426f-9247-bb680e5fe0c8".to_string(),
+/// #     name: name.to_string(),
+/// # };
+/// let team = Team {
+///     owner: user("admin"),
+///     deputy: user("ghopper"),
+/// };
+///
+/// assert_body_matches! {
+///     team,
+///     Team {
+///         owner: pattern!(ADMIN),
+///         deputy: pattern!(USER),
+///     },
+/// }
+/// ```
+pub use restest_macros::pattern;
+
+/// Runs an `async` test function in a runtime, with a [`Context`].
+///
+/// The function may take a single [`Context`] argument. By default, it is
+/// created by [`Context::from_env`]; another one can be specified with
+/// `#[restest::test(context = CONTEXT)]`. The function may return a `Result`,
+/// like any test function.
+///
+/// The name of the test is added to the description of every request it
+/// sends, such as `GET:users/ghopper (test get_user)`, so that failure
+/// messages and logs point to the test that sent the request. Tasks spawned
+/// by the test do not get the name.
+///
+/// # Dependencies
+///
+/// Stateful flows can be split into several tests, each declaring the tests
+/// it depends on with `depends_on = "<test>"`, which can be repeated. The
+/// value is the path of another `#[restest::test]` function, relative to
+/// the current module.
+///
+/// A test runs its dependencies before itself, even if they are filtered out
+/// by `cargo test`. Each test runs at most once per process: a test whose
+/// dependency is already running on another thread waits for it, and reuses
+/// its outcome if it already ran. When a dependency fails, or is skipped, the
+/// test is skipped: it passes without running, and says so on its output.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use http::StatusCode;
+/// use restest::{Context, Request};
+///
+/// const STAGING: Context = Context::new()
+///     .with_host("https://staging.example.com")
+///     .with_port(443);
+///
+/// #[restest::test]
+/// async fn get_user(context: Context) {
+///     context
+///         .run(Request::get("users/ghopper"))
+///         .await
+///         .expect_status::<serde_json::Value>(StatusCode::OK)
+///         .await;
+/// }
+///
+/// #[restest::test(depends_on = "get_user")]
+/// async fn delete_user(context: Context) {
+///     context
+///         .run(Request::delete("users/ghopper"))
+///         .await
+///         .expect_status::<()>(StatusCode::NO_CONTENT)
+///         .await;
+/// }
+///
+/// #[restest::test(context = STAGING)]
+/// async fn get_staging_user(context: Context) -> Result<(), String> {
+///     context
+///         .run(Request::get("users/ghopper"))
+///         .await
+///         .ensure_status::<serde_json::Value>(StatusCode::OK)
+///         .await?;
+///
+///     Ok(())
+/// }
+/// ```
+pub use restest_macros::test;
+
+/// Runs an `async` test body once per case, each case being a separate test.
+///
+/// The macro takes an `async` function, followed by a comma-separated list of
+/// cases. Each case is a parenthesized list of the arguments the function is
+/// called with, such as paths, bodies and expected statuses, optionally
+/// preceded by a name and a colon.
+///
+/// The function is kept as is, and a module of the same name is generated
+/// next to it, with one test per case. The tests run like [`test`]
+/// functions, so that each case shows up individually in the test output and
+/// in the failure messages. Unnamed cases get a name derived from their
+/// arguments, such as `users_ghopper_statuscode_ok` below. The attributes of
+/// the function, such as `#[ignore]`, are applied to every case.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use http::StatusCode;
+/// use restest::{Context, Request};
+///
+/// const CONTEXT: Context = Context::new().with_port(8080);
+///
+/// restest::cases! {
+///     async fn get_user(path: &'static str, status: StatusCode) {
+///         CONTEXT
+///             .run(Request::get(path))
+///             .await
+///             .expect_status::<serde_json::Value>(status)
+///             .await;
+///     }
+///
+///     ("users/ghopper", StatusCode::OK),
+///     missing_user: ("users/nobody", StatusCode::NOT_FOUND),
+///     invalid_name: ("users/%20", StatusCode::BAD_REQUEST),
+/// }
+/// ```
+///
+/// This generates the `get_user::users_ghopper_statuscode_ok`,
+/// `get_user::missing_user` and `get_user::invalid_name` tests.
+pub use restest_macros::cases;
+
+#[doc(hidden)]
+pub mod __private;
+
+pub mod auth;
+#[cfg(unix)]
+pub mod backend;
+pub mod cache;
+mod cassette;
+pub mod chaos;
+pub mod codec;
+pub mod compare;
 pub mod context;
+mod curl;
+pub mod digest;
+#[cfg(feature = "docker")]
+pub mod env;
+#[cfg(feature = "fake")]
+pub mod fake;
+pub mod fixture;
+#[cfg(feature = "fuzz")]
+pub mod fuzz;
+pub mod gen;
+pub mod json;
+mod link;
+pub mod log;
+pub mod malformed;
+pub mod middleware;
+pub mod paginate;
+pub mod pattern;
+pub mod problem;
+pub mod rate_limit;
+pub mod report;
 pub mod request;
+pub mod security;
+mod shaping;
+pub mod sign;
+pub mod snapshot;
+pub mod sse;
+pub mod store;
+pub mod stream;
+pub mod table;
+pub mod timeline;
 mod url;
+#[cfg(any(unix, feature = "docker"))]
+mod watchdog;
+#[cfg(feature = "xml")]
+pub mod xml;
 
 pub use context::Context;
+pub use report::soft;
 pub use request::Request;
+pub use tokio_util::sync::CancellationToken;
 
 /// Creates a path from multiple segments.
 ///
@@ -301,3 +874,195 @@ macro_rules! path {
         vec![ $( Box::new($segment) as Box<dyn ToString>, )* ]
     };
 }
+
+/// Runs a request multiple times and asserts that a field of the response body
+/// never decreases.
+///
+/// This is a shorthand for
+/// [`Context::expect_monotonic`](context::Context::expect_monotonic). The
+/// number of samples defaults to 3. It must be used in an `async` context.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use restest::{assert_monotonic, Context, Request};
+///
+/// const CONTEXT: Context = Context::new().with_port(8080);
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let request = Request::get("counter");
+///
+/// assert_monotonic!(CONTEXT, request, field = "version", samples = 5);
+/// assert_monotonic!(CONTEXT, request, field = "/meta/updated_at");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! assert_monotonic {
+    ( $context:expr, $request:expr, field = $field:expr, samples = $samples:expr $(,)? ) => {
+        $context.expect_monotonic(&$request, $field, $samples).await
+    };
+
+    ( $context:expr, $request:expr, field = $field:expr $(,)? ) => {
+        $crate::assert_monotonic!($context, $request, field = $field, samples = 3)
+    };
+}
+
+/// Fetches many resources and asserts that each of them matches a given
+/// pattern.
+///
+/// This is a shorthand for [`Context::assert_each`](context::Context::assert_each),
+/// with the check performed by [`assert_body_matches`]. The pattern can not
+/// bring values in scope. It must be used in an `async` context.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use restest::{assert_each, Context};
+/// use serde::Deserialize;
+///
+/// const CONTEXT: Context = Context::new().with_port(8080);
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let ids = ["ghopper", "alovelace", "aturing"];
+///
+/// assert_each!(CONTEXT, "users/{}", ids, User { active: true, .. });
+///
+/// #[derive(Deserialize)]
+/// struct User {
+///     name: String,
+///     active: bool,
+/// }
+/// # }
+/// ```
+#[macro_export]
+macro_rules! assert_each {
+    ( $context:expr, $path_template:expr, $ids:expr, $( $pattern:tt )+ ) => {
+        $context
+            .assert_each($path_template, $ids, |__restest__body| {
+                $crate::assert_body_matches!(__restest__body, $( $pattern )+);
+            })
+            .await
+    };
+}
+
+/// Asserts that a response body does *not* match a given pattern.
+///
+/// The pattern syntax is the same as in [`assert_body_matches`]. This is
+/// useful to check that sensitive data, such as passwords or internal
+/// identifiers, is not leaked. No value is brought in scope.
+///
+/// # Panics
+///
+/// This macro panics if the body matches the provided pattern, unless it is
+/// run in a [`soft`] scope.
+///
+/// # Example
+///
+/// ```rust
+/// use restest::assert_body_not_matches;
+/// use serde_json::{json, Value};
+///
+/// let user = json!({
+///     "name": "Grace Hopper",
+///     "email": "ghopper@example.com",
+/// });
+///
+/// assert_body_not_matches!(user, Value::Object(map! { "password" => _, .. }));
+/// ```
+#[macro_export]
+macro_rules! assert_body_not_matches {
+    ( $value:expr, $( $pattern:tt )+ ) => {
+        $crate::__private::not_matched(
+            $crate::body_matches!($value, $( $pattern )+).is_ok(),
+            stringify!($( $pattern )+),
+        )
+    };
+}
+
+/// Asserts that two [`ResponseSnapshot`](snapshot::ResponseSnapshot)s have
+/// the same body, optionally ignoring some fields.
+///
+/// This is a shorthand for [`snapshot::assert_same_body`]. An ignored field
+/// is either a key, which is ignored at any depth, or a JSON Pointer starting
+/// with `/`.
+///
+/// # Panics
+///
+/// This macro panics if the bodies differ.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use http::StatusCode;
+/// use restest::{assert_same_body, Context, Request};
+///
+/// const CONTEXT: Context = Context::new().with_port(8080);
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let mut first = CONTEXT.run(Request::get("users/ghopper")).await;
+/// let mut second = CONTEXT.run(Request::get("users/ghopper")).await;
+///
+/// let first = first.expect_snapshot().await;
+/// let second = second.expect_snapshot().await;
+///
+/// assert_same_body!(first, second);
+/// assert_same_body!(first, second, ignoring = ["updated_at"]);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! assert_same_body {
+    ( $left:expr, $right:expr, ignoring = [ $( $field:expr ),* $(,)? ] $(,)? ) => {
+        $crate::snapshot::assert_same_body(&$left, &$right, &[ $( $field ),* ])
+    };
+
+    ( $left:expr, $right:expr $(,)? ) => {
+        $crate::snapshot::assert_same_body(&$left, &$right, &[])
+    };
+}
+
+/// Asserts that every value selected by a JSONPath query matches a given
+/// pattern.
+///
+/// This is a shorthand for [`json::expect_jsonpath`], with the check
+/// performed by [`assert_body_matches`] on each selected
+/// [`serde_json::Value`]. The pattern can not bring values in scope. This
+/// macro is available with the `jsonpath` feature.
+///
+/// # Panics
+///
+/// This macro panics if the query is invalid, if it selects no value, or if
+/// any selected value does not match the pattern.
+///
+/// # Example
+///
+/// ```rust
+/// use restest::assert_jsonpath;
+/// use serde_json::{json, Value};
+///
+/// let body = json!({
+///     "items": [
+///         { "id": 1, "active": true },
+///         { "id": 2, "active": true },
+///         { "id": null, "active": false },
+///     ],
+/// });
+///
+/// assert_jsonpath!(body, "$.items[?@.active == true].id", Value::Number(_));
+/// assert_jsonpath!(body, "$.items[*]", Value::Object(map! { "active" => Value::Bool(_), .. }));
+/// ```
+#[cfg(feature = "jsonpath")]
+#[macro_export]
+macro_rules! assert_jsonpath {
+    ( $value:expr, $path:expr, $( $pattern:tt )+ ) => {
+        $crate::json::expect_jsonpath(
+            &$value,
+            $path,
+            $crate::__private::value_matcher(|__restest__node| {
+                $crate::body_matches!(__restest__node.clone(), $( $pattern )+).map(|_| ())
+            }),
+        )
+    };
+}
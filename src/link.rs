@@ -0,0 +1,98 @@
+//! Find hypermedia links in a response.
+//!
+//! Links are read from the `Link` header (RFC 8288), or from the `_links`
+//! member of a HAL body.
+
+use ::url::Url;
+use http::{header::LINK, HeaderMap};
+use serde_json::Value;
+
+/// Returns the target of the first link of the `Link` headers with the
+/// relation `rel`.
+pub(crate) fn from_headers(headers: &HeaderMap, rel: &str) -> Option<String> {
+    headers
+        .get_all(LINK)
+        .iter()
+        .find_map(|value| from_header(&String::from_utf8_lossy(value.as_bytes()), rel))
+}
+
+/// Returns the target of the link with the relation `rel` of a `Link` header
+/// value.
+fn from_header(value: &str, rel: &str) -> Option<String> {
+    split(value, ',').find_map(|link| {
+        let mut parts = split(link, ';');
+        let target = parts.next()?.trim();
+        let target = target.strip_prefix('<')?.strip_suffix('>')?;
+
+        parts
+            .filter_map(|param| param.split_once('='))
+            .any(|(key, value)| {
+                key.trim().eq_ignore_ascii_case("rel")
+                    && value
+                        .trim()
+                        .trim_matches('"')
+                        .split_whitespace()
+                        .any(|value| value.eq_ignore_ascii_case(rel))
+            })
+            .then(|| target.to_string())
+    })
+}
+
+/// Splits a `Link` header value on `separator`, except inside the target of a
+/// link (`<...>`) or a quoted parameter value, where it is part of the value.
+fn split(value: &str, separator: char) -> impl Iterator<Item = &str> {
+    let mut in_target = false;
+    let mut in_quotes = false;
+    let mut escaped = false;
+    let mut start = 0;
+    let mut parts = Vec::new();
+
+    for (index, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_quotes => escaped = true,
+            '"' if !in_target => in_quotes = !in_quotes,
+            '<' if !in_quotes => in_target = true,
+            '>' if !in_quotes => in_target = false,
+            _ if c == separator && !in_target && !in_quotes => {
+                parts.push(&value[start..index]);
+                start = index + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+
+    parts.push(&value[start..]);
+    parts.into_iter()
+}
+
+/// Returns the `href` of the link with the relation `rel` of a HAL body. The
+/// first link is used when there are several of them.
+pub(crate) fn from_hal(body: &Value, rel: &str) -> Result<Option<String>, String> {
+    let link = match body.get("_links").and_then(|links| links.get(rel)) {
+        None | Some(Value::Null) => return Ok(None),
+        Some(Value::Array(links)) => match links.first() {
+            Some(link) => link,
+            None => return Ok(None),
+        },
+        Some(link) => link,
+    };
+
+    if link.get("templated") == Some(&Value::Bool(true)) {
+        return Err(format!("Link `{}` is templated: {}", rel, link));
+    }
+
+    match link.get("href") {
+        Some(Value::String(href)) => Ok(Some(href.clone())),
+        _ => Err(format!("Link `{}` has no `href`: {}", rel, link)),
+    }
+}
+
+/// Resolves the target of a link against the URL of the response it was
+/// found in.
+pub(crate) fn resolve(current: &str, target: &str) -> Result<String, String> {
+    Url::parse(current)
+        .and_then(|current| current.join(target))
+        .map(String::from)
+        .map_err(|err| format!("Invalid link URL `{}`: {}", target, err))
+}
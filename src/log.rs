@@ -0,0 +1,259 @@
+//! Log the requests that are sent and the responses that are received.
+//!
+//! Logging is disabled by default. It can be enabled for the whole test suite
+//! with the `RESTEST_LOG` environment variable, or for a specific [`Context`]
+//! with [`Context::with_log_level`].
+//!
+//! Logs are emitted through the [`log`](::log) crate, with the `restest`
+//! target, so that the logger of the test suite can route, filter or capture
+//! them. The method, URL, status and latency of each request are emitted at the
+//! `Info` level, its headers and bodies at the `Debug` level. The log level
+//! above still decides which records are emitted in the first place.
+//!
+//! When no logger is installed, logs are written to the standard error. As the
+//! Rust test harness captures this output, the logs of a test are only
+//! displayed when it fails.
+//!
+//! The values of sensitive headers, such as `Authorization` and `Cookie`, are
+//! redacted from the logs, and from the `curl` commands and header values
+//! shown in failure messages. The redacted headers are the
+//! [`REDACTED_HEADERS`], along with the ones set with
+//! [`Context::with_redacted_headers`]. Header names are compared
+//! case-insensitively.
+//!
+//! # Example
+//!
+//! ```sh
+//! RESTEST_LOG=debug cargo test
+//! ```
+//!
+//! The values of an `Authorization` header and of a custom `X-Api-Key` header
+//! are redacted from the `curl` command of a response, here replayed from a
+//! cassette:
+//!
+//! ```rust
+//! use restest::{Context, Request};
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let path = std::env::temp_dir().join(format!("restest-redaction-{}.json", std::process::id()));
+//! std::fs::write(
+//!     &path,
+//!     r#"{ "interactions": [{
+//!         "request": { "method": "GET", "url": "http://localhost:8080/users", "body": "" },
+//!         "response": { "status": 200, "headers": [], "body": "[]" }
+//!     }] }"#,
+//! )
+//! .unwrap();
+//!
+//! let path: &'static str = Box::leak(path.to_string_lossy().into_owned().into_boxed_str());
+//! let context = Context::new()
+//!     .with_port(8080)
+//!     .with_redacted_headers(&["x-api-key"])
+//!     .with_cassette(path);
+//!
+//! let request = Request::get("users")
+//!     .with_header("AUTHORIZATION", "Bearer secret-token")
+//!     .with_header("X-Api-Key", "secret-key")
+//!     .with_header("Accept-Language", "fr-FR");
+//!
+//! let curl = context.run(request).await.curl().to_string();
+//! # std::fs::remove_file(path).unwrap();
+//!
+//! assert!(!curl.contains("secret"));
+//! assert!(curl.contains("authorization: <redacted>"));
+//! assert!(curl.contains("x-api-key: <redacted>"));
+//! assert!(curl.contains("accept-language: fr-FR"));
+//! # }
+//! ```
+//!
+//! [`Context`]: crate::Context
+//! [`Context::with_log_level`]: crate::Context::with_log_level
+//! [`Context::with_redacted_headers`]: crate::Context::with_redacted_headers
+
+use std::{borrow::Cow, env, fmt, sync::OnceLock, time::Duration};
+
+use http::{HeaderMap, HeaderValue, StatusCode};
+
+/// The name of the environment variable which sets the log level.
+pub const LOG_ENV_VAR: &str = "RESTEST_LOG";
+
+/// The target of the emitted log records.
+const TARGET: &str = "restest";
+
+/// The maximum number of bytes of a body that are logged.
+const MAX_BODY_LEN: usize = 1024;
+
+/// The headers whose values are always redacted.
+pub const REDACTED_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+];
+
+/// The text shown instead of a redacted header value.
+pub(crate) const REDACTED: &str = "<redacted>";
+
+/// How much information is logged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    /// Nothing is logged.
+    Off,
+    /// The method, URL, status and latency of each request are logged.
+    Info,
+    /// The headers and the bodies (truncated to 1 KiB) are logged as well.
+    Debug,
+}
+
+impl LogLevel {
+    /// Returns the log level set by the `RESTEST_LOG` environment variable.
+    ///
+    /// The accepted values are `off`, `info`, `debug` and `trace` (an alias of
+    /// `debug`), regardless of their case. Any other value, including an empty
+    /// one, disables logging.
+    pub fn from_env() -> LogLevel {
+        static LEVEL: OnceLock<LogLevel> = OnceLock::new();
+
+        *LEVEL.get_or_init(|| {
+            env::var(LOG_ENV_VAR)
+                .map(|value| LogLevel::parse(&value))
+                .unwrap_or(LogLevel::Off)
+        })
+    }
+
+    fn parse(value: &str) -> LogLevel {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "info" => LogLevel::Info,
+            "debug" | "trace" => LogLevel::Debug,
+            _ => LogLevel::Off,
+        }
+    }
+}
+
+/// Returns the value of a header as text, unless it is one of the
+/// [`REDACTED_HEADERS`] or of the `redacted` ones.
+pub(crate) fn header_value<'a>(
+    name: &str,
+    value: &'a HeaderValue,
+    redacted: &[&str],
+) -> Cow<'a, str> {
+    if is_redacted(name, redacted) {
+        Cow::Borrowed(REDACTED)
+    } else {
+        String::from_utf8_lossy(value.as_bytes())
+    }
+}
+
+/// Returns whether a header is one of the [`REDACTED_HEADERS`] or of the
+/// `redacted` ones.
+pub(crate) fn is_redacted(name: &str, redacted: &[&str]) -> bool {
+    REDACTED_HEADERS
+        .iter()
+        .chain(redacted)
+        .any(|redacted| redacted.eq_ignore_ascii_case(name))
+}
+
+pub(crate) fn request(
+    level: LogLevel,
+    method: &str,
+    url: &str,
+    headers: &HeaderMap,
+    body: &[u8],
+    redacted: &[&str],
+) {
+    if level < LogLevel::Info {
+        return;
+    }
+
+    emit(::log::Level::Info, format_args!("--> {} {}", method, url));
+
+    if level >= LogLevel::Debug {
+        log_headers(headers, redacted);
+        log_body(body);
+    }
+}
+
+pub(crate) fn response(
+    level: LogLevel,
+    context_description: &str,
+    status: StatusCode,
+    headers: &HeaderMap,
+    elapsed: Duration,
+    redacted: &[&str],
+) {
+    if level < LogLevel::Info {
+        return;
+    }
+
+    emit(
+        ::log::Level::Info,
+        format_args!(
+            "<-- {} {} ({}ms)",
+            context_description,
+            status,
+            elapsed.as_millis()
+        ),
+    );
+
+    if level >= LogLevel::Debug {
+        log_headers(headers, redacted);
+    }
+}
+
+pub(crate) fn response_body(level: LogLevel, context_description: &str, body: &[u8]) {
+    if level < LogLevel::Debug {
+        return;
+    }
+
+    emit(
+        ::log::Level::Debug,
+        format_args!("<-- {} body:", context_description),
+    );
+    log_body(body);
+}
+
+fn log_headers(headers: &HeaderMap, redacted: &[&str]) {
+    for (name, value) in headers {
+        emit(
+            ::log::Level::Debug,
+            format_args!(
+                "    {}: {}",
+                name,
+                header_value(name.as_str(), value, redacted)
+            ),
+        );
+    }
+}
+
+fn log_body(body: &[u8]) {
+    if body.is_empty() {
+        return;
+    }
+
+    let shown = &body[..body.len().min(MAX_BODY_LEN)];
+    let text = String::from_utf8_lossy(shown);
+
+    if shown.len() < body.len() {
+        emit(
+            ::log::Level::Debug,
+            format_args!(
+                "    {}... ({} bytes omitted)",
+                text,
+                body.len() - shown.len()
+            ),
+        );
+    } else {
+        emit(::log::Level::Debug, format_args!("    {}", text));
+    }
+}
+
+/// Emits a log record with the `restest` target, or writes it to the standard
+/// error when no logger is installed.
+fn emit(level: ::log::Level, message: fmt::Arguments<'_>) {
+    if ::log::max_level() == ::log::LevelFilter::Off {
+        eprintln!("[restest] {}", message);
+    } else {
+        ::log::log!(target: TARGET, level, "{}", message);
+    }
+}
@@ -0,0 +1,230 @@
+//! Send deliberately broken requests, and check that they are rejected.
+//!
+//! This module provides the [`MalformedRequests`] type, which takes a valid
+//! request template and sends broken versions of it: with a body which is
+//! not valid JSON, with a wrong `Content-Type`, with an oversized header, or
+//! without one of its headers. Every broken request is expected to be
+//! rejected with a client error (`4xx`) status, rather than crashing the
+//! server with a `5xx` status, or being accepted.
+//!
+//! The broken requests bypass the typed body pipeline: their bodies are
+//! sent as is, with [`Request::with_raw_json`].
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use restest::{malformed::MalformedRequests, Context, Request};
+//! use serde::Serialize;
+//!
+//! const CONTEXT: Context = Context::new().with_port(8080);
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let template = Request::post("users")
+//!     .with_header("X-Api-Version", "2")
+//!     .with_body(UserInput {
+//!         name: "Grace Hopper".to_string(),
+//!     });
+//!
+//! MalformedRequests::new(&CONTEXT, template)
+//!     .assert_client_errors()
+//!     .await;
+//! # }
+//!
+//! #[derive(Serialize)]
+//! struct UserInput {
+//!     name: String,
+//! }
+//! ```
+
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::{report, Context, Request};
+
+/// The default size of the header added by
+/// [`Malformation::OversizedHeader`], in bytes.
+pub const DEFAULT_OVERSIZED_HEADER_LEN: usize = 64 * 1024;
+
+/// The name of the header added by [`Malformation::OversizedHeader`].
+const OVERSIZED_HEADER: &str = "X-Restest-Oversized";
+
+/// The content type sent by [`Malformation::WrongContentType`].
+const WRONG_CONTENT_TYPE: &str = "text/plain; charset=utf-8";
+
+/// A way to break a request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Malformation {
+    /// The body is not valid JSON: its closing bracket is removed, or an
+    /// opening brace is prepended to it if it is not an object nor an
+    /// array.
+    InvalidJson,
+    /// The body is sent with the `text/plain` content type.
+    WrongContentType,
+    /// A header whose value is `len` bytes long is added.
+    OversizedHeader(usize),
+    /// A header of the template is removed.
+    MissingHeader(String),
+}
+
+impl fmt::Display for Malformation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Malformation::InvalidJson => write!(f, "invalid JSON body"),
+            Malformation::WrongContentType => {
+                write!(f, "wrong content type '{}'", WRONG_CONTENT_TYPE)
+            }
+            Malformation::OversizedHeader(len) => write!(f, "{} bytes long header", len),
+            Malformation::MissingHeader(name) => write!(f, "missing header '{}'", name),
+        }
+    }
+}
+
+/// Sends broken versions of a request, and checks that they are rejected.
+pub struct MalformedRequests<'a> {
+    context: &'a Context,
+    template: Request<()>,
+    malformations: Vec<Malformation>,
+}
+
+impl<'a> MalformedRequests<'a> {
+    /// Creates a set of broken requests from a valid request template.
+    ///
+    /// The body of the template is encoded as JSON. By default, the body is
+    /// made invalid and sent with a wrong content type if the template has a
+    /// body, a [`DEFAULT_OVERSIZED_HEADER_LEN`] bytes long header is added,
+    /// and each header of the template is removed in turn. The headers set
+    /// by the context are not removed.
+    pub fn new<I>(context: &'a Context, template: Request<I>) -> MalformedRequests<'a>
+    where
+        I: Serialize,
+    {
+        let body = match template.raw_body.clone() {
+            Some(raw_body) => raw_body,
+            None => match serde_json::to_vec(&template.body) {
+                Ok(body) if body == b"null" => Vec::new(),
+                Ok(body) => body,
+                Err(err) => panic!("Body serialization failed: {}", err),
+            },
+        };
+
+        let mut malformations = Vec::new();
+
+        if !body.is_empty() {
+            malformations.push(Malformation::InvalidJson);
+            malformations.push(Malformation::WrongContentType);
+        }
+
+        malformations.push(Malformation::OversizedHeader(DEFAULT_OVERSIZED_HEADER_LEN));
+
+        let mut headers = template.header.keys().cloned().collect::<Vec<_>>();
+        headers.sort();
+        malformations.extend(headers.into_iter().map(Malformation::MissingHeader));
+
+        let mut template = template.with_body(());
+        template.raw_body = Some(body);
+
+        MalformedRequests {
+            context,
+            template,
+            malformations,
+        }
+    }
+
+    /// Replaces the default malformations.
+    pub fn with_malformations<I>(self, malformations: I) -> MalformedRequests<'a>
+    where
+        I: IntoIterator<Item = Malformation>,
+    {
+        MalformedRequests {
+            malformations: malformations.into_iter().collect(),
+            ..self
+        }
+    }
+
+    /// Checks that every broken request is rejected with a client error
+    /// (`4xx`) status.
+    ///
+    /// This function performs I/O, therefore it is marked as `async`.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if a broken request is not rejected with a client
+    /// error. The panic message lists every such request, along with the
+    /// status it received.
+    pub async fn assert_client_errors(&self) {
+        report::check_soft(
+            "assert_client_errors",
+            &self.context.tags_of(&self.template),
+            self.ensure_client_errors().await,
+        );
+    }
+
+    /// Checks that every broken request is rejected with a client error
+    /// (`4xx`) status.
+    ///
+    /// This function performs I/O, therefore it is marked as `async`.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if a broken request is not rejected with
+    /// a client error. The error lists every such request, along with the
+    /// status it received.
+    pub async fn ensure_client_errors(&self) -> Result<(), String> {
+        let mut failures = Vec::new();
+
+        for malformation in &self.malformations {
+            let request = self.malformed(malformation);
+            let result = self.context.run(&request).await;
+
+            if !result.status.is_client_error() {
+                failures.push(format!("  - {}: {}", malformation, result.status));
+            }
+        }
+
+        if failures.is_empty() {
+            return Ok(());
+        }
+
+        Err(format!(
+            "Malformed versions of request '{}' were not rejected with a client error:\n{}",
+            self.context.description_of(&self.template),
+            failures.join("\n")
+        ))
+    }
+
+    /// Returns the template, broken by `malformation`.
+    fn malformed(&self, malformation: &Malformation) -> Request<()> {
+        let mut request = self.template.clone();
+
+        match malformation {
+            Malformation::InvalidJson => {
+                request.raw_body = request.raw_body.map(|body| invalid_json(&body));
+            }
+            Malformation::WrongContentType => {
+                request = request.with_content_type(WRONG_CONTENT_TYPE);
+            }
+            Malformation::OversizedHeader(len) => {
+                request
+                    .header
+                    .insert(OVERSIZED_HEADER.to_string(), "a".repeat(*len));
+            }
+            Malformation::MissingHeader(name) => {
+                request
+                    .header
+                    .retain(|key, _| !key.eq_ignore_ascii_case(name));
+            }
+        }
+
+        request
+    }
+}
+
+/// Makes a JSON document invalid.
+fn invalid_json(body: &[u8]) -> Vec<u8> {
+    match body.trim_ascii_end() {
+        [rest @ .., b'}' | b']'] => rest.to_vec(),
+        _ => [b"{", body].concat(),
+    }
+}
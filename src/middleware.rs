@@ -0,0 +1,129 @@
+//! Observe or modify the requests and responses of a context.
+//!
+//! This module provides the [`Middleware`] trait, whose hooks are called by a
+//! [`Context`](crate::Context) around every request:
+//!   - [`on_request`](Middleware::on_request) is called before the request is
+//!     sent, and can modify its URL, headers and body,
+//!   - [`on_response`](Middleware::on_response) is called once the response
+//!     headers are received, and can modify its status and headers, or read
+//!     its body.
+//!
+//! Both hooks are asynchronous, and do nothing by default. Middlewares are
+//! called in the order they are added to the context for requests, and in the
+//! reverse order for responses.
+//!
+//! This allows to add tracing headers, to log requests in a custom format, or
+//! to inject failures, without changing the tests themselves.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use futures_util::future::BoxFuture;
+//! use http::HeaderValue;
+//! use restest::{
+//!     middleware::{Middleware, OutgoingRequest},
+//!     request::RequestResult,
+//!     Context, Request,
+//! };
+//!
+//! struct Tracing;
+//!
+//! impl Middleware for Tracing {
+//!     fn on_request<'a>(&'a self, request: &'a mut OutgoingRequest) -> BoxFuture<'a, ()> {
+//!         Box::pin(async move {
+//!             let id = restest::gen::uuid().to_string();
+//!             request
+//!                 .headers
+//!                 .insert("x-trace-id", HeaderValue::from_str(&id).unwrap());
+//!         })
+//!     }
+//!
+//!     fn on_response<'a>(&'a self, result: &'a mut RequestResult) -> BoxFuture<'a, ()> {
+//!         Box::pin(async move {
+//!             println!("Received {}", result.status());
+//!         })
+//!     }
+//! }
+//!
+//! const CONTEXT: Context = Context::new().with_port(8080).with_middleware(&Tracing);
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! CONTEXT.run(Request::get("users")).await;
+//! # }
+//! ```
+
+use futures_util::future::BoxFuture;
+use http::HeaderMap;
+
+use crate::request::RequestResult;
+
+/// The maximum number of middlewares of a context.
+pub(crate) const MAX_MIDDLEWARES: usize = 8;
+
+/// Hooks which are called around every request of a context.
+pub trait Middleware: Send + Sync {
+    /// Called before a request is sent.
+    ///
+    /// The `Content-Length` header is set after every middleware is called,
+    /// so the body can be changed freely.
+    fn on_request<'a>(&'a self, request: &'a mut OutgoingRequest) -> BoxFuture<'a, ()> {
+        let _ = request;
+        Box::pin(async {})
+    }
+
+    /// Called once the response headers are received.
+    ///
+    /// Reading the body here, for instance with
+    /// [`ensure_bytes`](RequestResult::ensure_bytes), buffers it, so that it
+    /// can still be checked by the test afterwards.
+    fn on_response<'a>(&'a self, result: &'a mut RequestResult) -> BoxFuture<'a, ()> {
+        let _ = result;
+        Box::pin(async {})
+    }
+}
+
+/// A request which is about to be sent.
+#[derive(Debug)]
+pub struct OutgoingRequest {
+    method: &'static str,
+    /// The full URL of the request, including the query string.
+    pub url: String,
+    /// The request headers.
+    pub headers: HeaderMap,
+    /// The serialized request body.
+    pub body: Vec<u8>,
+    context_description: String,
+}
+
+impl OutgoingRequest {
+    pub(crate) fn new(
+        method: &'static str,
+        url: String,
+        headers: HeaderMap,
+        body: Vec<u8>,
+        context_description: String,
+    ) -> OutgoingRequest {
+        OutgoingRequest {
+            method,
+            url,
+            headers,
+            body,
+            context_description,
+        }
+    }
+
+    /// Returns the request method.
+    pub fn method(&self) -> &'static str {
+        self.method
+    }
+
+    /// Returns the context description of the request.
+    pub fn context_description(&self) -> &str {
+        &self.context_description
+    }
+
+    pub(crate) fn into_parts(self) -> (String, HeaderMap, Vec<u8>) {
+        (self.url, self.headers, self.body)
+    }
+}
@@ -0,0 +1,198 @@
+//! Walk through the pages of a listing endpoint.
+//!
+//! This module provides the [`PaginationStrategy`] type, which tells
+//! [`Context::paginate`](crate::Context::paginate) and
+//! [`Context::paginate_items`](crate::Context::paginate_items) how to find
+//! the next page of a response:
+//!   - from the `Link` header, with the `rel="next"` relation,
+//!   - from a cursor in the body, which is sent back as a query parameter,
+//!   - from a URL in the body.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use restest::{paginate::PaginationStrategy, Context, Request};
+//! use serde::Deserialize;
+//!
+//! const CONTEXT: Context = Context::new().with_port(8080);
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let strategy = PaginationStrategy::cursor("next_cursor", "cursor").with_max_pages(50);
+//!
+//! let users: Vec<User> = CONTEXT
+//!     .paginate_items(Request::get("users"), &strategy, "items")
+//!     .await;
+//!
+//! assert!(users.iter().all(|user| user.year_of_birth > 1900));
+//! # }
+//!
+//! #[derive(Deserialize)]
+//! struct User {
+//!     year_of_birth: u16,
+//! }
+//! ```
+
+use ::url::Url;
+use http::HeaderMap;
+use serde_json::Value;
+
+use crate::link;
+
+/// The default maximum number of pages fetched.
+const DEFAULT_MAX_PAGES: usize = 100;
+
+/// How to find the next page of a response.
+#[derive(Debug, Clone)]
+pub struct PaginationStrategy {
+    kind: Kind,
+    pub(crate) max_pages: usize,
+}
+
+#[derive(Debug, Clone)]
+enum Kind {
+    LinkHeader,
+    Cursor { field: String, param: String },
+    NextUrl { field: String },
+}
+
+impl PaginationStrategy {
+    /// Follows the URL of the `Link` header with the `rel="next"` relation,
+    /// until a response has no such link.
+    ///
+    /// # Example
+    ///
+    /// The targets of the links may contain commas, here in pages replayed
+    /// from a cassette:
+    ///
+    /// ```rust
+    /// use restest::{paginate::PaginationStrategy, Context, Request};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let path = std::env::temp_dir().join(format!("restest-link-{}.json", std::process::id()));
+    /// std::fs::write(
+    ///     &path,
+    ///     r#"{ "interactions": [{
+    ///         "request": { "method": "GET", "url": "http://localhost:8080/search?tags=a,b", "body": "" },
+    ///         "response": {
+    ///             "status": 200,
+    ///             "headers": [[
+    ///                 "link",
+    ///                 "<http://localhost:8080/search?tags=a,b&page=2>; rel=\"next\", <http://localhost:8080/search?tags=a,b&page=9>; rel=\"last\""
+    ///             ]],
+    ///             "body": "[1]"
+    ///         }
+    ///     }, {
+    ///         "request": { "method": "GET", "url": "http://localhost:8080/search?tags=a,b&page=2", "body": "" },
+    ///         "response": { "status": 200, "headers": [], "body": "[2]" }
+    ///     }] }"#,
+    /// )
+    /// .unwrap();
+    ///
+    /// let path: &'static str = Box::leak(path.to_string_lossy().into_owned().into_boxed_str());
+    /// let context = Context::new().with_port(8080).with_cassette(path);
+    ///
+    /// let pages: Vec<Vec<u32>> = context
+    ///     .paginate(Request::get("search?tags=a,b"), &PaginationStrategy::link_header())
+    ///     .await;
+    /// # std::fs::remove_file(path).unwrap();
+    ///
+    /// assert_eq!(pages, [[1], [2]]);
+    /// # }
+    /// ```
+    pub fn link_header() -> PaginationStrategy {
+        PaginationStrategy::new(Kind::LinkHeader)
+    }
+
+    /// Reads a cursor from a body field, and sends it in the `param` query
+    /// parameter of the next request, until the cursor is missing or `null`.
+    ///
+    /// The field is either the name of a top-level field of the body, or a
+    /// [JSON pointer](https://datatracker.ietf.org/doc/html/rfc6901) starting
+    /// with `/`.
+    pub fn cursor(field: impl ToString, param: impl ToString) -> PaginationStrategy {
+        PaginationStrategy::new(Kind::Cursor {
+            field: field.to_string(),
+            param: param.to_string(),
+        })
+    }
+
+    /// Follows the URL of a body field, until the field is missing or `null`.
+    ///
+    /// The field is either the name of a top-level field of the body, or a
+    /// JSON pointer starting with `/`. Relative URLs are resolved against the
+    /// URL of the current page.
+    pub fn next_url(field: impl ToString) -> PaginationStrategy {
+        PaginationStrategy::new(Kind::NextUrl {
+            field: field.to_string(),
+        })
+    }
+
+    /// Sets the maximum number of pages fetched. Reaching this limit is
+    /// considered an error, as it usually means the pagination never ends.
+    ///
+    /// The default is 100 pages.
+    pub fn with_max_pages(self, max_pages: usize) -> PaginationStrategy {
+        PaginationStrategy { max_pages, ..self }
+    }
+
+    fn new(kind: Kind) -> PaginationStrategy {
+        PaginationStrategy {
+            kind,
+            max_pages: DEFAULT_MAX_PAGES,
+        }
+    }
+
+    /// Returns the URL of the page after `current`, if any.
+    pub(crate) fn next_page(
+        &self,
+        first: &str,
+        current: &str,
+        headers: &HeaderMap,
+        body: &Value,
+    ) -> Result<Option<String>, String> {
+        match &self.kind {
+            Kind::LinkHeader => link::from_headers(headers, "next")
+                .map(|next| link::resolve(current, &next))
+                .transpose(),
+
+            Kind::Cursor { field, param } => {
+                let cursor = match body.pointer(&pointer(field)) {
+                    None | Some(Value::Null) => return Ok(None),
+                    Some(Value::String(cursor)) => cursor.clone(),
+                    Some(cursor) => cursor.to_string(),
+                };
+
+                let mut url = Url::parse(first).map_err(|err| err.to_string())?;
+                let pairs = url
+                    .query_pairs()
+                    .filter(|(key, _)| key != param)
+                    .map(|(key, value)| (key.into_owned(), value.into_owned()))
+                    .collect::<Vec<_>>();
+
+                url.query_pairs_mut()
+                    .clear()
+                    .extend_pairs(pairs)
+                    .append_pair(param, &cursor);
+
+                Ok(Some(url.into()))
+            }
+
+            Kind::NextUrl { field } => match body.pointer(&pointer(field)) {
+                None | Some(Value::Null) => Ok(None),
+                Some(Value::String(next)) => link::resolve(current, next).map(Some),
+                Some(other) => Err(format!("Field `{}` is not a URL: {}", field, other)),
+            },
+        }
+    }
+}
+
+/// Converts a field name to a JSON pointer, unless it already is one.
+pub(crate) fn pointer(field: &str) -> String {
+    if field.starts_with('/') || field.is_empty() {
+        field.to_string()
+    } else {
+        format!("/{}", field)
+    }
+}
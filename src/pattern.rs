@@ -0,0 +1,690 @@
+//! Support types for pattern matching.
+//!
+//! This module provides the [`MatchError`] type, which is returned by
+//! [`body_matches`](crate::body_matches) when a value does not match a
+//! pattern, and the [`Matcher`] trait, which allows to write custom matchers
+//! that can be used in patterns with `custom!(matcher)`.
+//!
+//! It also provides the [`Pattern`] type, which describes the expected shape
+//! of a JSON value at runtime, with the features of the
+//! [`assert_body_matches`](crate::assert_body_matches) patterns. This allows
+//! to build patterns programmatically, for instance from a configuration
+//! file, instead of writing them in the test code. A [`Pattern`] is a
+//! [`Matcher`], so it can be used anywhere a matcher is expected.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use http::StatusCode;
+//! use restest::{pattern::Pattern, Context, Request};
+//!
+//! const CONTEXT: Context = Context::new().with_port(8080);
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! // The fields every user must have, read from a configuration file.
+//! let required = ["id", "name", "email"];
+//!
+//! let user = required
+//!     .iter()
+//!     .fold(Pattern::object().with_rest(), |user, field| {
+//!         user.with_field(*field, Pattern::string())
+//!     });
+//!
+//! let page = Pattern::object()
+//!     .with_field("items", Pattern::each(user))
+//!     .with_field("total", Pattern::at_least(0));
+//!
+//! CONTEXT
+//!     .run(Request::get("users"))
+//!     .await
+//!     .expect_status_code(StatusCode::OK)
+//!     .await
+//!     .expect_body_matches(page)
+//!     .await;
+//! # }
+//! ```
+
+use std::{error::Error, fmt, sync::Arc};
+
+use serde_json::Value;
+
+use crate::__private;
+
+/// The error returned when a value does not match a pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchError {
+    pattern: Option<String>,
+    reason: Option<String>,
+}
+
+impl MatchError {
+    #[doc(hidden)]
+    pub fn new(pattern: impl ToString) -> MatchError {
+        MatchError {
+            pattern: Some(pattern.to_string()),
+            reason: None,
+        }
+    }
+
+    /// Creates an error explaining why a value does not match.
+    ///
+    /// This is the constructor [`Matcher`] implementations should use.
+    pub fn with_reason(reason: impl ToString) -> MatchError {
+        MatchError {
+            pattern: None,
+            reason: Some(reason.to_string()),
+        }
+    }
+
+    /// Returns the pattern that was not matched, if any.
+    pub fn pattern(&self) -> Option<&str> {
+        self.pattern.as_deref()
+    }
+
+    /// Returns the reason why the value does not match, if any.
+    pub fn reason(&self) -> Option<&str> {
+        self.reason.as_deref()
+    }
+}
+
+impl fmt::Display for MatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.pattern, &self.reason) {
+            (Some(pattern), Some(reason)) => {
+                write!(f, "Value does not match pattern `{}`: {}", pattern, reason)
+            }
+            (Some(pattern), None) => write!(f, "Value does not match pattern `{}`", pattern),
+            (None, Some(reason)) => write!(f, "Value does not match: {}", reason),
+            (None, None) => write!(f, "Value does not match"),
+        }
+    }
+}
+
+impl Error for MatchError {}
+
+/// A reusable check on a JSON value.
+///
+/// Matchers can be used in [`assert_body_matches`](crate::assert_body_matches)
+/// and [`body_matches`](crate::body_matches) patterns with
+/// `custom!(matcher)`. The matched value is serialized to a JSON value
+/// beforehand, so it must implement [`Serialize`](serde::Serialize).
+///
+/// They can also check a response directly, with
+/// [`expect_body_matches`](crate::request::RequestResult::expect_body_matches)
+/// and
+/// [`expect_pointer_matches`](crate::request::RequestResult::expect_pointer_matches).
+///
+/// This allows to share domain-specific matchers, such as IBANs or phone
+/// numbers, across test suites.
+///
+/// Closures taking a `&Value` and returning a `Result<(), MatchError>` are
+/// matchers too.
+///
+/// # Example
+///
+/// ```rust
+/// use restest::{
+///     assert_body_matches,
+///     pattern::{MatchError, Matcher},
+/// };
+/// use serde_json::Value;
+///
+/// struct Latitude;
+///
+/// impl Matcher for Latitude {
+///     fn matches(&self, value: &Value) -> Result<(), MatchError> {
+///         match value.as_f64() {
+///             Some(lat) if (-90.0..=90.0).contains(&lat) => Ok(()),
+///             _ => Err(MatchError::with_reason(format!("{} is not a latitude", value))),
+///         }
+///     }
+/// }
+///
+/// struct Place {
+///     name: String,
+///     lat: f64,
+/// }
+///
+/// let place = Place {
+///     name: "Paris".to_string(),
+///     lat: 48.85,
+/// };
+///
+/// assert_body_matches! {
+///     place,
+///     Place {
+///         name: "Paris",
+///         lat: custom!(Latitude),
+///     },
+/// }
+/// ```
+pub trait Matcher {
+    /// Checks that `value` is accepted by the matcher.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error explaining why the value is rejected.
+    fn matches(&self, value: &Value) -> Result<(), MatchError>;
+}
+
+impl<F> Matcher for F
+where
+    F: Fn(&Value) -> Result<(), MatchError>,
+{
+    fn matches(&self, value: &Value) -> Result<(), MatchError> {
+        self(value)
+    }
+}
+
+/// The expected shape of a JSON value, built at runtime.
+///
+/// Refer to the [module documentation](self) for an example.
+#[derive(Clone)]
+pub struct Pattern {
+    kind: Kind,
+}
+
+#[derive(Clone)]
+enum Kind {
+    Any,
+    Eq(Value),
+    Type(&'static str, fn(&Value) -> bool),
+    Range(Option<f64>, Option<f64>),
+    Str(&'static str, String, fn(&str, &str) -> bool),
+    Len(usize),
+    Object(ObjectPattern),
+    Array(ArrayPattern),
+    Each(Box<Pattern>),
+    AllOf(Vec<Pattern>),
+    OneOf(Vec<Pattern>),
+    Not(Box<Pattern>),
+    Custom(Arc<dyn Matcher + Send + Sync>),
+}
+
+impl Pattern {
+    /// Accepts any value, like `_`.
+    pub fn any() -> Pattern {
+        Pattern::new(Kind::Any)
+    }
+
+    /// Accepts values equal to `value`.
+    ///
+    /// Numbers are compared by value, so that `1` is equal to `1.0`.
+    pub fn eq(value: impl Into<Value>) -> Pattern {
+        Pattern::new(Kind::Eq(value.into()))
+    }
+
+    /// Accepts `null`.
+    pub fn null() -> Pattern {
+        Pattern::new(Kind::Type("null", Value::is_null))
+    }
+
+    /// Accepts booleans.
+    pub fn bool() -> Pattern {
+        Pattern::new(Kind::Type("a boolean", Value::is_boolean))
+    }
+
+    /// Accepts numbers.
+    pub fn number() -> Pattern {
+        Pattern::new(Kind::Type("a number", Value::is_number))
+    }
+
+    /// Accepts strings.
+    pub fn string() -> Pattern {
+        Pattern::new(Kind::Type("a string", Value::is_string))
+    }
+
+    /// Accepts UUID strings, like `uuid!()`.
+    pub fn uuid() -> Pattern {
+        Pattern::new(Kind::Type("a UUID", |value| {
+            value
+                .as_str()
+                .is_some_and(|value| __private::is_uuid(value, None))
+        }))
+    }
+
+    /// Accepts RFC 3339 datetime strings, like `datetime!()`.
+    pub fn datetime() -> Pattern {
+        Pattern::new(Kind::Type("an RFC 3339 datetime", |value| {
+            value.as_str().is_some_and(__private::is_rfc3339)
+        }))
+    }
+
+    /// Accepts numbers greater than or equal to `min`, like `min..`.
+    pub fn at_least(min: impl Into<f64>) -> Pattern {
+        Pattern::new(Kind::Range(Some(min.into()), None))
+    }
+
+    /// Accepts numbers less than or equal to `max`, like `..=max`.
+    pub fn at_most(max: impl Into<f64>) -> Pattern {
+        Pattern::new(Kind::Range(None, Some(max.into())))
+    }
+
+    /// Accepts numbers between `min` and `max`, inclusive, like `min..=max`.
+    pub fn between(min: impl Into<f64>, max: impl Into<f64>) -> Pattern {
+        Pattern::new(Kind::Range(Some(min.into()), Some(max.into())))
+    }
+
+    /// Accepts strings equal to `expected`, ignoring case, like
+    /// `eq_ignore_case!(expected)`.
+    pub fn eq_ignore_case(expected: impl ToString) -> Pattern {
+        Pattern::new(Kind::Str(
+            "equal, ignoring case, to",
+            expected.to_string(),
+            |value, expected| __private::eq_ignore_case(value, expected),
+        ))
+    }
+
+    /// Accepts strings containing `expected`, like `contains!(expected)`.
+    pub fn contains(expected: impl ToString) -> Pattern {
+        Pattern::new(Kind::Str(
+            "containing",
+            expected.to_string(),
+            |value, expected| value.contains(expected),
+        ))
+    }
+
+    /// Accepts strings starting with `expected`, like
+    /// `starts_with!(expected)`.
+    pub fn starts_with(expected: impl ToString) -> Pattern {
+        Pattern::new(Kind::Str(
+            "starting with",
+            expected.to_string(),
+            |value, expected| value.starts_with(expected),
+        ))
+    }
+
+    /// Accepts strings ending with `expected`, like `ends_with!(expected)`.
+    pub fn ends_with(expected: impl ToString) -> Pattern {
+        Pattern::new(Kind::Str(
+            "ending with",
+            expected.to_string(),
+            |value, expected| value.ends_with(expected),
+        ))
+    }
+
+    /// Accepts strings, arrays and objects of length `len`, like `len!(len)`.
+    ///
+    /// The length of a string is its number of characters.
+    pub fn len(len: usize) -> Pattern {
+        Pattern::new(Kind::Len(len))
+    }
+
+    /// Creates a pattern accepting objects with the given fields.
+    pub fn object() -> ObjectPattern {
+        ObjectPattern {
+            fields: Vec::new(),
+            rest: false,
+        }
+    }
+
+    /// Creates a pattern accepting arrays with the given elements.
+    pub fn array() -> ArrayPattern {
+        ArrayPattern {
+            elements: Vec::new(),
+            rest: false,
+            unordered: false,
+        }
+    }
+
+    /// Accepts arrays whose elements are all accepted by `pattern`.
+    pub fn each(pattern: impl Into<Pattern>) -> Pattern {
+        Pattern::new(Kind::Each(Box::new(pattern.into())))
+    }
+
+    /// Accepts values accepted by every pattern.
+    pub fn all_of<I>(patterns: I) -> Pattern
+    where
+        I: IntoIterator,
+        I::Item: Into<Pattern>,
+    {
+        Pattern::new(Kind::AllOf(patterns.into_iter().map(Into::into).collect()))
+    }
+
+    /// Accepts values accepted by at least one pattern, like `a | b`.
+    pub fn one_of<I>(patterns: I) -> Pattern
+    where
+        I: IntoIterator,
+        I::Item: Into<Pattern>,
+    {
+        Pattern::new(Kind::OneOf(patterns.into_iter().map(Into::into).collect()))
+    }
+
+    /// Accepts values rejected by `pattern`, like `not!(pattern)`.
+    pub fn not(pattern: impl Into<Pattern>) -> Pattern {
+        Pattern::new(Kind::Not(Box::new(pattern.into())))
+    }
+
+    /// Accepts values accepted by a custom matcher, like `custom!(matcher)`.
+    pub fn custom(matcher: impl Matcher + Send + Sync + 'static) -> Pattern {
+        Pattern::new(Kind::Custom(Arc::new(matcher)))
+    }
+
+    fn new(kind: Kind) -> Pattern {
+        Pattern { kind }
+    }
+
+    /// Checks `value`, found at `pointer` in the matched value, and returns a
+    /// description of the first mismatch.
+    fn check(&self, value: &Value, pointer: &str) -> Result<(), String> {
+        let mismatch = |expected: &dyn fmt::Display| {
+            let location = match pointer {
+                "" => "the root".to_string(),
+                pointer => format!("'{}'", pointer),
+            };
+
+            Err(format!(
+                "expected {} at {}, found {}",
+                expected, location, value
+            ))
+        };
+
+        match &self.kind {
+            Kind::Any => Ok(()),
+
+            Kind::Eq(expected) => match (expected.as_f64(), value.as_f64()) {
+                (Some(expected), Some(value)) if expected == value => Ok(()),
+                _ if expected == value => Ok(()),
+                _ => mismatch(expected),
+            },
+
+            Kind::Type(name, accepts) => match accepts(value) {
+                true => Ok(()),
+                false => mismatch(name),
+            },
+
+            Kind::Range(min, max) => {
+                let accepted = value.as_f64().is_some_and(|value| {
+                    min.is_none_or(|min| value >= min) && max.is_none_or(|max| value <= max)
+                });
+
+                match (accepted, min, max) {
+                    (true, _, _) => Ok(()),
+                    (false, Some(min), None) => mismatch(&format!("a number >= {}", min)),
+                    (false, None, Some(max)) => mismatch(&format!("a number <= {}", max)),
+                    (false, min, max) => mismatch(&format!(
+                        "a number between {} and {}",
+                        min.unwrap_or(f64::NEG_INFINITY),
+                        max.unwrap_or(f64::INFINITY)
+                    )),
+                }
+            }
+
+            Kind::Str(name, expected, accepts) => {
+                match value.as_str().is_some_and(|value| accepts(value, expected)) {
+                    true => Ok(()),
+                    false => mismatch(&format!("a string {} {:?}", name, expected)),
+                }
+            }
+
+            Kind::Len(len) => {
+                let actual = match value {
+                    Value::String(value) => Some(value.chars().count()),
+                    Value::Array(value) => Some(value.len()),
+                    Value::Object(value) => Some(value.len()),
+                    _ => None,
+                };
+
+                match actual == Some(*len) {
+                    true => Ok(()),
+                    false => mismatch(&format!("a value of length {}", len)),
+                }
+            }
+
+            Kind::Object(object) => object.check(value, pointer, mismatch),
+
+            Kind::Array(array) => array.check(value, pointer, mismatch),
+
+            Kind::Each(pattern) => match value {
+                Value::Array(elements) => {
+                    elements
+                        .iter()
+                        .enumerate()
+                        .try_for_each(|(index, element)| {
+                            pattern.check(element, &format!("{}/{}", pointer, index))
+                        })
+                }
+                _ => mismatch(&"an array"),
+            },
+
+            Kind::AllOf(patterns) => patterns
+                .iter()
+                .try_for_each(|pattern| pattern.check(value, pointer)),
+
+            Kind::OneOf(patterns) => match patterns.as_slice() {
+                [pattern] => pattern.check(value, pointer),
+                _ if patterns
+                    .iter()
+                    .any(|pattern| pattern.check(value, pointer).is_ok()) =>
+                {
+                    Ok(())
+                }
+                _ => mismatch(&format!("one of {} alternatives", patterns.len())),
+            },
+
+            Kind::Not(pattern) => match pattern.check(value, pointer) {
+                Ok(()) => mismatch(&"a value rejected by the negated pattern"),
+                Err(_) => Ok(()),
+            },
+
+            Kind::Custom(matcher) => matcher.matches(value).map_err(|err| {
+                let reason = err.reason().unwrap_or("rejected by a custom matcher");
+                match pointer {
+                    "" => reason.to_string(),
+                    pointer => format!("{} (at '{}')", reason, pointer),
+                }
+            }),
+        }
+    }
+}
+
+impl Matcher for Pattern {
+    fn matches(&self, value: &Value) -> Result<(), MatchError> {
+        self.check(value, "").map_err(MatchError::with_reason)
+    }
+}
+
+impl From<Value> for Pattern {
+    fn from(value: Value) -> Pattern {
+        Pattern::eq(value)
+    }
+}
+
+impl From<&str> for Pattern {
+    fn from(value: &str) -> Pattern {
+        Pattern::eq(value)
+    }
+}
+
+impl From<String> for Pattern {
+    fn from(value: String) -> Pattern {
+        Pattern::eq(value)
+    }
+}
+
+impl From<bool> for Pattern {
+    fn from(value: bool) -> Pattern {
+        Pattern::eq(value)
+    }
+}
+
+impl From<i64> for Pattern {
+    fn from(value: i64) -> Pattern {
+        Pattern::eq(value)
+    }
+}
+
+impl From<f64> for Pattern {
+    fn from(value: f64) -> Pattern {
+        Pattern::eq(value)
+    }
+}
+
+/// A pattern accepting objects, created with [`Pattern::object`].
+///
+/// Like a struct pattern, the object must not have any other field, unless
+/// [`with_rest`](ObjectPattern::with_rest) is called.
+#[derive(Clone)]
+pub struct ObjectPattern {
+    fields: Vec<(String, Pattern)>,
+    rest: bool,
+}
+
+impl ObjectPattern {
+    /// Requires a field accepted by `pattern`.
+    pub fn with_field(mut self, name: impl ToString, pattern: impl Into<Pattern>) -> ObjectPattern {
+        self.fields.push((name.to_string(), pattern.into()));
+
+        self
+    }
+
+    /// Allows the object to have other fields, like `..`.
+    pub fn with_rest(mut self) -> ObjectPattern {
+        self.rest = true;
+
+        self
+    }
+
+    fn check(
+        &self,
+        value: &Value,
+        pointer: &str,
+        mismatch: impl Fn(&dyn fmt::Display) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let object = match value {
+            Value::Object(object) => object,
+            _ => return mismatch(&"an object"),
+        };
+
+        for (name, pattern) in &self.fields {
+            let field_pointer = format!("{}/{}", pointer, escape(name));
+
+            match object.get(name) {
+                Some(field) => pattern.check(field, &field_pointer)?,
+                None => return Err(format!("missing field '{}'", field_pointer)),
+            }
+        }
+
+        if !self.rest {
+            let unexpected = object
+                .keys()
+                .find(|key| !self.fields.iter().any(|(name, _)| name == *key));
+
+            if let Some(key) = unexpected {
+                return Err(format!("unexpected field '{}/{}'", pointer, escape(key)));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl From<ObjectPattern> for Pattern {
+    fn from(object: ObjectPattern) -> Pattern {
+        Pattern::new(Kind::Object(object))
+    }
+}
+
+impl Matcher for ObjectPattern {
+    fn matches(&self, value: &Value) -> Result<(), MatchError> {
+        Pattern::from(self.clone()).matches(value)
+    }
+}
+
+/// A pattern accepting arrays, created with [`Pattern::array`].
+///
+/// Like a slice pattern, the array must have exactly the given elements, in
+/// order, unless [`with_rest`](ArrayPattern::with_rest) or
+/// [`unordered`](ArrayPattern::unordered) is called.
+#[derive(Clone)]
+pub struct ArrayPattern {
+    elements: Vec<Pattern>,
+    rest: bool,
+    unordered: bool,
+}
+
+impl ArrayPattern {
+    /// Requires an element accepted by `pattern`, after the previous ones.
+    pub fn with_element(mut self, pattern: impl Into<Pattern>) -> ArrayPattern {
+        self.elements.push(pattern.into());
+
+        self
+    }
+
+    /// Allows the array to have other elements after the given ones, like
+    /// `[a, b, ..]`, or anywhere if the pattern is unordered.
+    pub fn with_rest(mut self) -> ArrayPattern {
+        self.rest = true;
+
+        self
+    }
+
+    /// Accepts the elements in any order, like `unordered!`. Each pattern
+    /// must accept a distinct element.
+    pub fn unordered(mut self) -> ArrayPattern {
+        self.unordered = true;
+
+        self
+    }
+
+    fn check(
+        &self,
+        value: &Value,
+        pointer: &str,
+        mismatch: impl Fn(&dyn fmt::Display) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let elements = match value {
+            Value::Array(elements) => elements,
+            _ => return mismatch(&"an array"),
+        };
+
+        if elements.len() < self.elements.len()
+            || !self.rest && elements.len() > self.elements.len()
+        {
+            let at_least = if self.rest { "at least " } else { "" };
+            return mismatch(&format!(
+                "an array of {}{} elements",
+                at_least,
+                self.elements.len()
+            ));
+        }
+
+        if self.unordered {
+            let accepted = __private::matches_elements(
+                elements,
+                self.elements.len(),
+                self.rest,
+                |pattern, element| self.elements[pattern].check(element, pointer).is_ok(),
+            );
+
+            return match accepted {
+                true => Ok(()),
+                false => {
+                    mismatch(&"an array whose elements are accepted by the patterns, in any order")
+                }
+            };
+        }
+
+        self.elements.iter().zip(elements).enumerate().try_for_each(
+            |(index, (pattern, element))| pattern.check(element, &format!("{}/{}", pointer, index)),
+        )
+    }
+}
+
+impl From<ArrayPattern> for Pattern {
+    fn from(array: ArrayPattern) -> Pattern {
+        Pattern::new(Kind::Array(array))
+    }
+}
+
+impl Matcher for ArrayPattern {
+    fn matches(&self, value: &Value) -> Result<(), MatchError> {
+        Pattern::from(self.clone()).matches(value)
+    }
+}
+
+/// Escapes a field name for a JSON Pointer.
+fn escape(name: &str) -> String {
+    name.replace('~', "~0").replace('/', "~1")
+}
@@ -0,0 +1,125 @@
+//! Check error responses formatted as Problem Details (RFC 7807).
+//!
+//! APIs following [RFC 7807](https://datatracker.ietf.org/doc/html/rfc7807)
+//! describe their errors with a JSON object whose standard members are
+//! `type`, `title`, `status`, `detail` and `instance`. This module provides
+//! the [`ProblemDetails`] type, which is returned by
+//! [`RequestResult::expect_problem`](crate::request::RequestResult::expect_problem),
+//! so that error tests do not have to declare it. Its fields can be matched
+//! with [`assert_body_matches`](crate::assert_body_matches), the members
+//! specific to an API being collected in
+//! [`extensions`](ProblemDetails::extensions).
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use http::StatusCode;
+//! use restest::{assert_body_matches, problem::ProblemDetails, Context, Request};
+//!
+//! const CONTEXT: Context = Context::new().with_port(8080);
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let problem = CONTEXT
+//!     .run(Request::post("accounts/12345/transfers"))
+//!     .await
+//!     .expect_problem(StatusCode::FORBIDDEN)
+//!     .await;
+//!
+//! assert_body_matches! {
+//!     problem,
+//!     ProblemDetails {
+//!         r#type: "https://example.com/probs/out-of-credit",
+//!         title: Some("You do not have enough credit."),
+//!         extensions: map! { "balance" => balance, .. },
+//!         ..
+//!     }
+//! };
+//!
+//! assert_eq!(balance, 30);
+//! # }
+//! ```
+
+use serde::{de::Error, Deserialize, Deserializer};
+use serde_json::{Map, Value};
+
+/// The `type` of a problem that has no other semantics than its status.
+pub const ABOUT_BLANK: &str = "about:blank";
+
+/// An error response, as defined by RFC 7807.
+///
+/// # Example
+///
+/// ```rust
+/// use restest::problem::{ProblemDetails, ABOUT_BLANK};
+///
+/// let problem: ProblemDetails =
+///     serde_json::from_str(r#"{ "title": "Not Found", "status": 404, "resource": "users" }"#)
+///         .unwrap();
+///
+/// assert_eq!(problem.r#type, ABOUT_BLANK);
+/// assert_eq!(problem.status, Some(404));
+/// assert_eq!(problem.extensions["resource"], "users");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProblemDetails {
+    /// A URI reference identifying the problem type. Defaults to
+    /// [`ABOUT_BLANK`] when absent.
+    pub r#type: String,
+    /// A short, human-readable summary of the problem type.
+    pub title: Option<String>,
+    /// The HTTP status code of the response.
+    pub status: Option<u16>,
+    /// A human-readable explanation specific to this occurrence of the
+    /// problem.
+    pub detail: Option<String>,
+    /// A URI reference identifying this occurrence of the problem.
+    pub instance: Option<String>,
+    /// The members which are not defined by RFC 7807.
+    pub extensions: Map<String, Value>,
+}
+
+impl<'de> Deserialize<'de> for ProblemDetails {
+    fn deserialize<D>(deserializer: D) -> Result<ProblemDetails, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let mut members = Map::deserialize(deserializer)?;
+
+        let mut string = |name: &str| match members.remove(name) {
+            None | Some(Value::Null) => Ok(None),
+            Some(Value::String(value)) => Ok(Some(value)),
+            Some(value) => Err(D::Error::custom(format!(
+                "`{}` must be a string, found {}",
+                name, value
+            ))),
+        };
+
+        let r#type = string("type")?.unwrap_or_else(|| ABOUT_BLANK.to_string());
+        let title = string("title")?;
+        let detail = string("detail")?;
+        let instance = string("instance")?;
+
+        let status = match members.remove("status") {
+            None | Some(Value::Null) => None,
+            Some(value) => match value.as_u64().and_then(|status| u16::try_from(status).ok()) {
+                Some(status) => Some(status),
+                None => {
+                    return Err(D::Error::custom(format!(
+                        "`status` must be a status code, found {}",
+                        value
+                    )))
+                }
+            },
+        };
+
+        Ok(ProblemDetails {
+            r#type,
+            title,
+            status,
+            detail,
+            instance,
+            extensions: members,
+        })
+    }
+}
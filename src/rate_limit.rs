@@ -0,0 +1,167 @@
+//! Parse the rate limiting headers of a response.
+//!
+//! This module provides the [`RateLimit`] type, which is returned by
+//! [`RequestResult::expect_rate_limit`](crate::request::RequestResult::expect_rate_limit).
+//! The following headers are supported:
+//!   - the de facto standard `X-RateLimit-Limit`, `X-RateLimit-Remaining` and
+//!     `X-RateLimit-Reset` headers,
+//!   - the `RateLimit-Limit`, `RateLimit-Remaining` and `RateLimit-Reset`
+//!     headers, as well as the structured `RateLimit` header, of the IETF
+//!     drafts,
+//!   - the `Retry-After` header, either as a number of seconds or as an HTTP
+//!     date.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use http::StatusCode;
+//! use restest::{Context, Request};
+//!
+//! const CONTEXT: Context = Context::new().with_port(8080);
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let result = CONTEXT.run(Request::get("users")).await;
+//! let rate_limit = result.expect_rate_limit();
+//!
+//! assert_eq!(rate_limit.limit, Some(100));
+//!
+//! if result.status() == StatusCode::TOO_MANY_REQUESTS {
+//!     rate_limit.wait_for_reset().await;
+//! }
+//! # }
+//! ```
+
+use std::time::Duration;
+
+use http::{header::RETRY_AFTER, HeaderMap};
+use time::{format_description::well_known::Rfc2822, OffsetDateTime};
+
+/// Reset values greater than this are Unix timestamps, smaller values are
+/// numbers of seconds.
+const TIMESTAMP_THRESHOLD: u64 = 1_000_000_000;
+
+/// The rate limiting information of a response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RateLimit {
+    /// The maximum number of requests allowed in the current window.
+    pub limit: Option<u64>,
+    /// The number of requests remaining in the current window.
+    pub remaining: Option<u64>,
+    /// The time until the current window resets.
+    pub reset: Option<Duration>,
+    /// The time to wait before sending another request, from the
+    /// `Retry-After` header.
+    pub retry_after: Option<Duration>,
+}
+
+impl RateLimit {
+    /// Parses the rate limiting headers. Returns `None` if none of them is
+    /// present.
+    ///
+    /// The IETF headers take precedence over the `X-RateLimit-*` headers.
+    pub fn from_headers(headers: &HeaderMap) -> Result<Option<RateLimit>, String> {
+        let mut rate_limit = RateLimit::default();
+
+        for prefix in ["x-ratelimit-", "ratelimit-"] {
+            if let Some(limit) = number(headers, &format!("{}limit", prefix))? {
+                rate_limit.limit = Some(limit);
+            }
+            if let Some(remaining) = number(headers, &format!("{}remaining", prefix))? {
+                rate_limit.remaining = Some(remaining);
+            }
+            if let Some(reset) = number(headers, &format!("{}reset", prefix))? {
+                rate_limit.reset = Some(reset_duration(reset));
+            }
+        }
+
+        if let Some(value) = header(headers, "ratelimit") {
+            for item in value.split([',', ';']) {
+                let (key, value) = match item.split_once('=') {
+                    Some((key, value)) => (key.trim(), value.trim()),
+                    None => continue,
+                };
+
+                let value = value
+                    .parse::<u64>()
+                    .map_err(|_| format!("Invalid RateLimit header: {}", item.trim()))?;
+
+                match key {
+                    "limit" => rate_limit.limit = Some(value),
+                    "remaining" | "r" => rate_limit.remaining = Some(value),
+                    "reset" | "t" => rate_limit.reset = Some(reset_duration(value)),
+                    _ => {}
+                }
+            }
+        }
+
+        if let Some(value) = header(headers, RETRY_AFTER.as_str()) {
+            rate_limit.retry_after = Some(retry_after(&value)?);
+        }
+
+        if rate_limit == RateLimit::default() {
+            return Ok(None);
+        }
+
+        Ok(Some(rate_limit))
+    }
+
+    /// Returns the time to wait before the next request is allowed.
+    ///
+    /// This is the `Retry-After` delay if any, or the time until the window
+    /// resets if no request remains. Otherwise, no wait is needed.
+    pub fn wait_time(&self) -> Duration {
+        match (self.retry_after, self.remaining, self.reset) {
+            (Some(retry_after), _, _) => retry_after,
+            (None, Some(0), Some(reset)) => reset,
+            _ => Duration::ZERO,
+        }
+    }
+
+    /// Sleeps until the next request is allowed.
+    ///
+    /// Refer to [`wait_time`](RateLimit::wait_time) for more details.
+    pub async fn wait_for_reset(&self) {
+        tokio::time::sleep(self.wait_time()).await;
+    }
+}
+
+fn header(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get(name)
+        .map(|value| String::from_utf8_lossy(value.as_bytes()).trim().to_string())
+}
+
+fn number(headers: &HeaderMap, name: &str) -> Result<Option<u64>, String> {
+    header(headers, name)
+        .map(|value| {
+            // Some servers append a quota policy to the limit (`100, 100;w=60`).
+            let value = value.split([',', ';']).next().unwrap_or_default().trim();
+
+            value
+                .parse()
+                .map_err(|_| format!("Invalid {} header: {}", name, value))
+        })
+        .transpose()
+}
+
+fn reset_duration(reset: u64) -> Duration {
+    if reset < TIMESTAMP_THRESHOLD {
+        return Duration::from_secs(reset);
+    }
+
+    let now = OffsetDateTime::now_utc().unix_timestamp();
+    Duration::from_secs(reset.saturating_sub(now.max(0) as u64))
+}
+
+fn retry_after(value: &str) -> Result<Duration, String> {
+    if let Ok(seconds) = value.parse() {
+        return Ok(Duration::from_secs(seconds));
+    }
+
+    let date = OffsetDateTime::parse(value, &Rfc2822)
+        .map_err(|_| format!("Invalid Retry-After header: {}", value))?;
+
+    let delay = date - OffsetDateTime::now_utc();
+    Ok(Duration::try_from(delay).unwrap_or(Duration::ZERO))
+}
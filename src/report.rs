@@ -0,0 +1,505 @@
+//! Collect the outcome of every assertion, for CI dashboards.
+//!
+//! Every `expect_*` and `assert_*` method of this crate, as well as
+//! [`assert_body_matches`](crate::assert_body_matches) and
+//! [`assert_body_not_matches`](crate::assert_body_not_matches), records
+//! whether it succeeded before returning or panicking. The recorded
+//! [`Assertion`]s are grouped by test, that is, by the name of the thread they
+//! ran on, which is the name of the test when running `cargo test`.
+//!
+//! Assertions are only recorded when the `RESTEST_REPORT` environment
+//! variable is set to the path of a report. The report is then rewritten
+//! after every assertion, so that it is complete whether the tests passed or
+//! not. Each test binary writes its own report, whose name is suffixed with
+//! the name of the binary: `target/restest.xml` becomes
+//! `target/restest-api.xml` for the `api` test binary. Paths ending with
+//! `.xml` produce JUnit XML, other paths produce JSON. A summary can also be
+//! written explicitly with [`write`].
+//!
+//! Assertions can also be run in a [`soft`] scope, where failures are
+//! accumulated instead of panicking immediately, so that a single test run
+//! reveals every problem.
+//!
+//! # Example
+//!
+//! ```sh
+//! RESTEST_REPORT=target/restest.xml cargo test --test api
+//! ```
+//!
+//! ```rust,no_run
+//! use restest::report;
+//!
+//! let failures = report::assertions()
+//!     .into_iter()
+//!     .filter(|assertion| assertion.failure.is_some())
+//!     .count();
+//!
+//! report::write("target/restest.json").unwrap();
+//! ```
+
+use std::{
+    any::Any,
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+    env, fs,
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+    sync::{Mutex, MutexGuard, OnceLock, PoisonError},
+    thread,
+};
+
+use futures_util::FutureExt;
+use serde_json::json;
+
+/// The environment variable holding the path of the report, which enables
+/// the recording of assertions.
+const REPORT_PATH_VAR: &str = "RESTEST_REPORT";
+
+static ASSERTIONS: Mutex<Vec<Assertion>> = Mutex::new(Vec::new());
+
+/// Adds details to the failure messages, such as the output of the service
+/// under test.
+static ANNOTATOR: OnceLock<fn(String, &str) -> String> = OnceLock::new();
+
+tokio::task_local! {
+    /// The failures of the soft assertions of the current [`soft`] scope.
+    static SOFT_FAILURES: RefCell<Vec<String>>;
+}
+
+thread_local! {
+    /// The tags of the last request sent on this thread.
+    static LAST_TAGS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+
+    /// The base URL of the context of the last request sent on this thread.
+    static LAST_BASE_URL: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+/// The outcome of an assertion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Assertion {
+    /// The name of the test the assertion belongs to, that is, the name of
+    /// the thread it ran on.
+    pub test: String,
+    /// The name of the assertion, such as `expect_status`.
+    pub assertion: &'static str,
+    /// The tags of the request the assertion is about, set with
+    /// [`Request::with_tags`](crate::Request::with_tags) and
+    /// [`Context::with_tags`](crate::Context::with_tags). Assertions on
+    /// values which do not belong to a request, such as
+    /// [`assert_body_matches`](crate::assert_body_matches), get the tags of
+    /// the last request sent by the test.
+    pub tags: Vec<String>,
+    /// The failure message, if the assertion failed.
+    pub failure: Option<String>,
+}
+
+/// Returns every assertion recorded so far, in order.
+///
+/// Assertions are only recorded when the `RESTEST_REPORT` environment
+/// variable is set.
+pub fn assertions() -> Vec<Assertion> {
+    lock().clone()
+}
+
+/// Forgets every assertion recorded so far.
+pub fn clear() {
+    lock().clear();
+}
+
+/// Returns a JSON summary of every assertion recorded so far.
+///
+/// The summary contains the total number of assertions, the number of
+/// failures, and the list of assertions.
+pub fn to_json() -> String {
+    json_summary(&lock())
+}
+
+/// Returns a JUnit XML summary of every assertion recorded so far.
+///
+/// Each test is reported as a test case, along with its number of
+/// assertions and the tags of its requests. A test case fails if any of its
+/// assertions failed.
+pub fn to_junit() -> String {
+    junit_summary(&lock())
+}
+
+/// Writes a summary of every assertion recorded so far to a file.
+///
+/// The summary is written as JUnit XML if the path ends with `.xml`, and as
+/// JSON otherwise.
+///
+/// # Error
+///
+/// This function returns an error if the file can not be written.
+pub fn write(path: &str) -> Result<(), String> {
+    write_summary(path, &lock())
+}
+
+/// Runs a future in which failed assertions are accumulated instead of
+/// panicking immediately, then panics once with every failure if any.
+///
+/// Assertions that only check the response, such as
+/// [`expect_header_absent`](crate::request::RequestResult::expect_header_absent)
+/// or [`expect_status_code`](crate::request::RequestResult::expect_status_code),
+/// and [`assert_body_matches`](crate::assert_body_matches) calls that do not
+/// bind any variable, let the future continue when they fail. Assertions
+/// that return a value, such as
+/// [`expect_status`](crate::request::RequestResult::expect_status), can not
+/// continue: they end the scope, and the panic lists the failures
+/// accumulated so far.
+///
+/// # Panics
+///
+/// This function panics if any assertion failed, or if the future panicked.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use http::StatusCode;
+/// use restest::{assert_body_matches, Context, Request};
+/// use serde::Deserialize;
+///
+/// const CONTEXT: Context = Context::new().with_port(8080);
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// restest::soft(async {
+///     let mut response = CONTEXT
+///         .run(Request::get("users/ghopper"))
+///         .await
+///         .expect_content_type("application/json")
+///         .expect_header_absent("x-powered-by");
+///
+///     let user: User = response
+///         .expect_status_code(StatusCode::OK)
+///         .await
+///         .json()
+///         .await;
+///
+///     assert_body_matches!(user.name, "Grace Hopper");
+///     assert_body_matches!(user.admin, false);
+/// })
+/// .await;
+/// # }
+///
+/// #[derive(Deserialize)]
+/// struct User {
+///     name: String,
+///     admin: bool,
+/// }
+/// ```
+pub async fn soft<F>(future: F) -> F::Output
+where
+    F: Future,
+{
+    let (output, mut failures) = SOFT_FAILURES
+        .scope(RefCell::new(Vec::new()), async {
+            let output = AssertUnwindSafe(future).catch_unwind().await;
+            (output, SOFT_FAILURES.with(RefCell::take))
+        })
+        .await;
+
+    match output {
+        Ok(output) if failures.is_empty() => output,
+        Ok(_) => fail_soft(failures),
+        Err(payload) if failures.is_empty() => panic::resume_unwind(payload),
+        Err(payload) => {
+            failures.push(panic_message(&*payload));
+            fail_soft(failures)
+        }
+    }
+}
+
+fn fail_soft(failures: Vec<String>) -> ! {
+    let failures = failures
+        .iter()
+        .map(|failure| format!("  - {}", failure.replace('\n', "\n    ")))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    panic!("Soft assertions failed:\n{}", failures)
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    match payload.downcast_ref::<String>() {
+        Some(message) => message.clone(),
+        None => match payload.downcast_ref::<&str>() {
+            Some(message) => message.to_string(),
+            None => "Panicked".to_string(),
+        },
+    }
+}
+
+/// Records the outcome of an assertion, and returns its value.
+///
+/// # Panics
+///
+/// This function panics with the error message if the assertion failed,
+/// including in a [`soft`] scope.
+#[track_caller]
+pub(crate) fn check<T>(assertion: &'static str, tags: &[String], result: Result<T, String>) -> T {
+    match result {
+        Ok(value) => {
+            record(assertion, tags, None);
+            value
+        }
+        Err(err) => {
+            let err = annotate(err);
+
+            record(assertion, tags, Some(err.clone()));
+            panic!("{}", err)
+        }
+    }
+}
+
+/// Records the outcome of an assertion that does not return a value.
+///
+/// # Panics
+///
+/// This function panics with the error message if the assertion failed,
+/// unless it is run in a [`soft`] scope.
+#[track_caller]
+pub(crate) fn check_soft(assertion: &'static str, tags: &[String], result: Result<(), String>) {
+    let err = match result {
+        Ok(()) => return record(assertion, tags, None),
+        Err(err) => annotate(err),
+    };
+
+    record(assertion, tags, Some(err.clone()));
+
+    let accumulated = SOFT_FAILURES.try_with(|failures| failures.borrow_mut().push(err.clone()));
+    if accumulated.is_err() {
+        panic!("{}", err);
+    }
+}
+
+/// Records the outcome of an assertion, and rewrites the report, if the
+/// `RESTEST_REPORT` environment variable is set.
+pub(crate) fn record(assertion: &'static str, tags: &[String], failure: Option<String>) {
+    let Some(path) = report_path() else {
+        return;
+    };
+
+    let test = thread::current().name().unwrap_or("<unnamed>").to_string();
+
+    let mut assertions = lock();
+    assertions.push(Assertion {
+        test,
+        assertion,
+        tags: tags.to_vec(),
+        failure,
+    });
+
+    // The test harness exits without running any destructor when a test
+    // fails, so the report can't wait for the end of the process.
+    if let Err(err) = write_summary(path, &assertions) {
+        eprintln!("{}", err);
+    }
+}
+
+/// Sets the function which adds details to the failure messages of the
+/// assertions. It is given the base URL of the context of the last request
+/// sent by the test. Only the first function set is kept.
+pub(crate) fn set_annotator(annotator: fn(String, &str) -> String) {
+    let _ = ANNOTATOR.set(annotator);
+}
+
+fn annotate(message: String) -> String {
+    match ANNOTATOR.get() {
+        Some(annotator) => LAST_BASE_URL.with(|base_url| annotator(message, &base_url.borrow())),
+        None => message,
+    }
+}
+
+/// Returns the path of the report of the current test binary, if the
+/// `RESTEST_REPORT` environment variable is set.
+fn report_path() -> Option<&'static str> {
+    static PATH: OnceLock<Option<String>> = OnceLock::new();
+
+    PATH.get_or_init(|| Some(binary_report_path(&env::var(REPORT_PATH_VAR).ok()?)))
+        .as_deref()
+}
+
+/// Suffixes the file name of `path` with the name of the current binary,
+/// without the hash cargo appends to the name of test binaries.
+fn binary_report_path(path: &str) -> String {
+    let binary = env::current_exe()
+        .ok()
+        .and_then(|exe| Some(exe.file_stem()?.to_string_lossy().into_owned()))
+        .unwrap_or_default();
+
+    let binary = match binary.rsplit_once('-') {
+        Some((name, hash)) if hash.len() == 16 && hash.chars().all(|c| c.is_ascii_hexdigit()) => {
+            name
+        }
+        _ => &binary,
+    };
+
+    if binary.is_empty() {
+        return path.to_string();
+    }
+
+    let path = Path::new(path);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{}-{}.{}", stem, binary, extension.to_string_lossy()),
+        None => format!("{}-{}", stem, binary),
+    };
+
+    path.with_file_name(name).to_string_lossy().into_owned()
+}
+
+/// Remembers the tags of a request which is being sent, so that the
+/// assertions on values that do not belong to a request, such as
+/// [`assert_body_matches`](crate::assert_body_matches), are attributed to the
+/// last request of the test. The base URL of its context is given to the
+/// annotator of the failure messages.
+pub(crate) fn set_last_request(tags: &[String], base_url: String) {
+    LAST_TAGS.with(|last| *last.borrow_mut() = tags.to_vec());
+    LAST_BASE_URL.with(|last| *last.borrow_mut() = base_url);
+}
+
+/// Returns the tags of the last request sent on this thread.
+pub(crate) fn last_tags() -> Vec<String> {
+    LAST_TAGS.with(|last| last.borrow().clone())
+}
+
+/// Returns every tag of `tags`, without duplicates, in order.
+pub(crate) fn merge_tags<'a>(tags: impl IntoIterator<Item = &'a String>) -> Vec<String> {
+    let mut merged = Vec::<String>::new();
+
+    for tag in tags {
+        if !merged.contains(tag) {
+            merged.push(tag.clone());
+        }
+    }
+
+    merged
+}
+
+fn lock() -> MutexGuard<'static, Vec<Assertion>> {
+    // A failed assertion panics while the lock is not held, so the list is
+    // never left in an inconsistent state.
+    ASSERTIONS.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn write_summary(path: &str, assertions: &[Assertion]) -> Result<(), String> {
+    let summary = if path.ends_with(".xml") {
+        junit_summary(assertions)
+    } else {
+        json_summary(assertions)
+    };
+
+    fs::write(path, summary).map_err(|err| format!("Failed to write report '{}': {}", path, err))
+}
+
+fn json_summary(assertions: &[Assertion]) -> String {
+    let failures = assertions
+        .iter()
+        .filter(|assertion| assertion.failure.is_some())
+        .count();
+
+    let list = assertions
+        .iter()
+        .map(|assertion| {
+            json!({
+                "test": assertion.test,
+                "assertion": assertion.assertion,
+                "tags": assertion.tags,
+                "passed": assertion.failure.is_none(),
+                "failure": assertion.failure,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let summary = json!({
+        "assertions": assertions.len(),
+        "failures": failures,
+        "details": list,
+    });
+
+    serde_json::to_string_pretty(&summary).expect("JSON serialization failed")
+}
+
+fn junit_summary(assertions: &[Assertion]) -> String {
+    let mut tests: BTreeMap<String, Vec<&Assertion>> = BTreeMap::new();
+    for assertion in assertions {
+        tests
+            .entry(assertion.test.clone())
+            .or_default()
+            .push(assertion);
+    }
+
+    let failures = tests
+        .values()
+        .filter(|assertions| {
+            assertions
+                .iter()
+                .any(|assertion| assertion.failure.is_some())
+        })
+        .count();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites tests=\"{0}\" failures=\"{1}\">\n  <testsuite name=\"restest\" tests=\"{0}\" failures=\"{1}\">\n",
+        tests.len(),
+        failures
+    ));
+
+    for (test, assertions) in &tests {
+        xml.push_str(&format!(
+            "    <testcase name=\"{}\" assertions=\"{}\">\n",
+            escape(test),
+            assertions.len()
+        ));
+
+        let tags = assertions
+            .iter()
+            .flat_map(|assertion| &assertion.tags)
+            .collect::<BTreeSet<_>>();
+
+        if !tags.is_empty() {
+            xml.push_str("      <properties>\n");
+            for tag in tags {
+                xml.push_str(&format!(
+                    "        <property name=\"tag\" value=\"{}\"/>\n",
+                    escape(tag)
+                ));
+            }
+            xml.push_str("      </properties>\n");
+        }
+
+        let failed = assertions
+            .iter()
+            .filter_map(|assertion| Some((assertion.assertion, assertion.failure.as_ref()?)))
+            .collect::<Vec<_>>();
+
+        if let Some((assertion, message)) = failed.first() {
+            let details = failed
+                .iter()
+                .map(|(assertion, message)| format!("{}: {}", assertion, message))
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            xml.push_str(&format!(
+                "      <failure type=\"{}\" message=\"{}\">{}</failure>\n",
+                assertion,
+                escape(message),
+                escape(&details)
+            ));
+        }
+
+        xml.push_str("    </testcase>\n");
+    }
+
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
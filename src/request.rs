@@ -2,24 +2,47 @@
 //!
 //! A request has a specific lifecycle:
 //!   - a [`Request`] is created using one of [`Request::get`],
-//! [`Request::post`] and so on,
+//!     [`Request::post`] and so on,
 //!   - the request can be modified using the different methods un [`Request`], such as
-//! [`with_body`](Request::with_body) or [`with_header`](Request::with_header),
+//!     [`with_body`](Request::with_body) or [`with_header`](Request::with_header),
 //!   - the [`Request`] is passed as argument of
-//! [`Context::run`](crate::Context), returning a [`RequestResult`],
+//!     [`Context::run`](crate::Context), returning a [`RequestResult`],
 //!   - the final request body is constructed by calling
-//! [`expect_status`](RequestResult::expect_status).
+//!     [`expect_status`](RequestResult::expect_status).
 //!
 //! The documentation for [`Request`] provide more specific description.
 
 use core::panic;
-use std::collections::HashMap;
+use std::{collections::HashMap, fs::File, io::Write, path::Path, time::Duration};
 
-use http::status::StatusCode;
+use http::{
+    header::{
+        ACCEPT, CACHE_CONTROL, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE, ETAG, IF_MATCH,
+        IF_NONE_MATCH,
+    },
+    status::StatusCode,
+    HeaderMap, HeaderName, HeaderValue, Version,
+};
 use reqwest::Response;
 use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
 
-use crate::url::IntoUrl;
+use crate::{
+    cache::CachePolicy,
+    cassette,
+    codec::Codec,
+    digest, json, link, log,
+    pattern::Matcher,
+    problem::ProblemDetails,
+    rate_limit::RateLimit,
+    report,
+    security::SecurityProfile,
+    shaping,
+    snapshot::{self, ResponseSnapshot},
+    stream::{Download, ResponseStream},
+    url::IntoUrl,
+    Context,
+};
 
 /// An HTTP request we're about to run.
 ///
@@ -69,6 +92,11 @@ where
     pub(crate) method: Method,
     pub(crate) url: String,
     pub(crate) context_description: String,
+    pub(crate) tags: Vec<String>,
+    pub(crate) latency: Option<Duration>,
+    pub(crate) codec: Codec,
+    /// A JSON body sent as is, instead of the encoding of `body`.
+    pub(crate) raw_body: Option<Vec<u8>>,
 }
 
 impl Request<()> {
@@ -76,9 +104,14 @@ impl Request<()> {
     ///
     /// # Specifying an URL
     ///
-    /// The url argument must be either a string literal or the value produced
-    /// by the [`path`] macro. Only the absolute path to the resource must be
-    /// passed.
+    /// The url argument must be either a string (a literal, a [`String`] or
+    /// the output of [`format_args`]), the value produced by the [`path`]
+    /// macro, or a [`url::Url`](::url::Url). Only the absolute path to the
+    /// resource must be passed.
+    ///
+    /// A full URL, starting with `http://` or `https://`, can also be passed.
+    /// In this case, the host and port of the [`Context`](crate::Context) are
+    /// ignored.
     ///
     /// # Example
     ///
@@ -89,6 +122,10 @@ impl Request<()> {
     ///
     /// let user_name = "scrabsha";
     /// let request_2 = Request::get(path!["users", user_name]);
+    ///
+    /// let request_3 = Request::get(format!("users/{}", user_name));
+    ///
+    /// let request_4 = Request::get("http://example.com/users");
     /// ```
     pub fn get(url: impl IntoUrl) -> Request<()> {
         let url = url.into_url();
@@ -96,8 +133,12 @@ impl Request<()> {
             body: (),
             header: HashMap::new(),
             method: Method::Get,
+            latency: None,
+            codec: Codec::Json,
             context_description: format!("GET:{}", url),
+            tags: Vec::new(),
             url,
+            raw_body: None,
         }
     }
 
@@ -105,9 +146,9 @@ impl Request<()> {
     ///
     /// # Specifying an URL
     ///
-    /// The url argument must be either a string literal or the value produced
-    /// by the [`path`] macro. Only the absolute path to the resource must be
-    /// passed.
+    /// The url argument must be either a string, the value produced by the
+    /// [`path`] macro or a [`url::Url`](::url::Url). Only the absolute path to
+    /// the resource must be passed.
     ///
     /// Refer to the [`get`][Request::get] method documentation for a
     /// self-describing example.
@@ -117,8 +158,12 @@ impl Request<()> {
             body: (),
             header: HashMap::new(),
             method: Method::Post,
+            latency: None,
+            codec: Codec::Json,
             context_description: format!("POST:{}", url),
+            tags: Vec::new(),
             url,
+            raw_body: None,
         }
     }
 
@@ -126,9 +171,9 @@ impl Request<()> {
     ///
     /// # Specifying an URL
     ///
-    /// The url argument must be either a string literal or the value produced
-    /// by the [`path`] macro. Only the absolute path to the resource must be
-    /// passed.
+    /// The url argument must be either a string, the value produced by the
+    /// [`path`] macro or a [`url::Url`](::url::Url). Only the absolute path to
+    /// the resource must be passed.
     ///
     /// Refer to the [`get`][Request::get] method documentation for a
     /// self-describing example.
@@ -138,8 +183,12 @@ impl Request<()> {
             body: (),
             header: HashMap::new(),
             method: Method::Put,
+            latency: None,
+            codec: Codec::Json,
             context_description: format!("PUT:{}", url),
+            tags: Vec::new(),
             url,
+            raw_body: None,
         }
     }
 
@@ -147,9 +196,9 @@ impl Request<()> {
     ///
     /// # Specifying an URL
     ///
-    /// The url argument must be either a string literal or the value produced
-    /// by the [`path`] macro. Only the absolute path to the resource must be
-    /// passed.
+    /// The url argument must be either a string, the value produced by the
+    /// [`path`] macro or a [`url::Url`](::url::Url). Only the absolute path to
+    /// the resource must be passed.
     ///
     /// Refer to the [`get`][Request::get] method documentation for a
     /// self-describing example,.
@@ -159,8 +208,12 @@ impl Request<()> {
             body: (),
             header: HashMap::new(),
             method: Method::Delete,
+            latency: None,
+            codec: Codec::Json,
             context_description: format!("DELETE:{}", url),
+            tags: Vec::new(),
             url,
+            raw_body: None,
         }
     }
 }
@@ -196,14 +249,243 @@ where
     B: Serialize,
 {
     /// Adds a header key and value to the request.
-    pub fn with_header(mut self, key: impl ToString, value: impl ToString) -> Request<B> {
-        let previous_entry = self.header.insert(key.to_string(), value.to_string());
-        assert!(previous_entry.is_none(), "Attempt to replace a header");
+    ///
+    /// The value may only contain visible ASCII characters, spaces and tabs.
+    /// Other characters can be sent with
+    /// [`with_non_ascii_header`](Request::with_non_ascii_header).
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the header is already set, or if its name or
+    /// its value contains an invalid character. The panic message names the
+    /// header, the request and the invalid character.
+    #[track_caller]
+    pub fn with_header(self, key: impl ToString, value: impl ToString) -> Request<B> {
+        match self.ensure_with_header(key, value) {
+            Ok(request) => request,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Adds a header key and value to the request.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the header is already set, or if its
+    /// name or its value contains an invalid character.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use restest::Request;
+    ///
+    /// let err = Request::get("users")
+    ///     .ensure_with_header("X-Api Key", "secret")
+    ///     .err()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     err,
+    ///     "Invalid header name 'X-Api Key' in request 'GET:/users': invalid character ' ' at position 5"
+    /// );
+    ///
+    /// let err = Request::get("users")
+    ///     .ensure_with_header("X-Trace", "first\nsecond")
+    ///     .err()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     err,
+    ///     "Invalid value for header 'X-Trace' in request 'GET:/users': invalid character '\\n' at position 5"
+    /// );
+    ///
+    /// let err = Request::get("users")
+    ///     .ensure_with_header("X-User-Name", "Grâce")
+    ///     .err()
+    ///     .unwrap();
+    ///
+    /// assert!(err.contains("invalid character 'â' at position 2"));
+    /// assert!(err.contains("`with_non_ascii_header`"));
+    /// ```
+    pub fn ensure_with_header(
+        self,
+        key: impl ToString,
+        value: impl ToString,
+    ) -> Result<Request<B>, String> {
+        self.insert_header(key.to_string(), value.to_string(), false)
+    }
+
+    /// Adds a header key and value to the request, the value being allowed
+    /// to contain non-ASCII characters.
+    ///
+    /// Such values are sent as UTF-8, which HTTP does not define: this allows
+    /// to check how the backend handles them.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the header is already set, or if its name or
+    /// its value contains an invalid character, such as a control character.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use restest::Request;
+    ///
+    /// let request = Request::get("users").with_non_ascii_header("X-User-Name", "Grâce");
+    /// ```
+    #[track_caller]
+    pub fn with_non_ascii_header(self, key: impl ToString, value: impl ToString) -> Request<B> {
+        match self.insert_header(key.to_string(), value.to_string(), true) {
+            Ok(request) => request,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    fn insert_header(
+        mut self,
+        key: String,
+        value: String,
+        non_ascii: bool,
+    ) -> Result<Request<B>, String> {
+        let location = format!("request '{}'", self.description());
+        parse_header(&key, &value, non_ascii, &location)?;
+
+        if self.header.contains_key(&key) {
+            return Err(format!(
+                "Attempt to replace header '{}' in {}",
+                key, location
+            ));
+        }
+
+        self.header.insert(key, value);
+
+        Ok(self)
+    }
+
+    /// Overrides the `Content-Type` header of the request.
+    ///
+    /// Request bodies are sent with `application/json; charset=utf-8` by
+    /// default. Overriding it allows to check how the backend negotiates
+    /// content types, along with
+    /// [`RequestResult::expect_request_content_type_accepted`]. The body is
+    /// still encoded with the codec of the request, which is JSON unless set
+    /// with [`with_codec`](Request::with_codec).
+    ///
+    /// The previously-set content type is discarded.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use restest::Request;
+    ///
+    /// let request = Request::post("users")
+    ///     .with_content_type("application/json; charset=iso-8859-1")
+    ///     .with_body("Grace Hopper");
+    /// ```
+    pub fn with_content_type(mut self, content_type: impl ToString) -> Request<B> {
+        self.header
+            .retain(|key, _| !key.eq_ignore_ascii_case(CONTENT_TYPE.as_str()));
+        self.header
+            .insert(CONTENT_TYPE.to_string(), content_type.to_string());
+
+        self
+    }
+
+    /// Sets the `Accept` header of the request, which tells the server the
+    /// media types it may respond with.
+    ///
+    /// The response content type can then be checked with
+    /// [`RequestResult::expect_content_type`]. The previously-set `Accept`
+    /// header is discarded.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use http::StatusCode;
+    /// use restest::{Context, Request};
+    ///
+    /// const CONTEXT: Context = Context::new().with_port(8080);
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// CONTEXT
+    ///     .run(Request::get("users/ghopper").with_accept("application/xml"))
+    ///     .await
+    ///     .expect_content_type("application/xml")
+    ///     .expect_status_code(StatusCode::OK)
+    ///     .await;
+    /// # }
+    /// ```
+    pub fn with_accept(mut self, media_type: impl ToString) -> Request<B> {
+        self.header
+            .retain(|key, _| !key.eq_ignore_ascii_case(ACCEPT.as_str()));
+        self.header
+            .insert(ACCEPT.to_string(), media_type.to_string());
+
+        self
+    }
+
+    /// Makes the request conditional: the server is expected to respond with
+    /// `304 Not Modified` if the resource still matches `etag`.
+    ///
+    /// This sets the `If-None-Match` header. The entity tag is usually taken
+    /// from a previous response with
+    /// [`RequestResult::expect_etag`](crate::request::RequestResult::expect_etag).
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use http::StatusCode;
+    /// use restest::{Context, Request};
+    ///
+    /// const CONTEXT: Context = Context::new().with_port(8080);
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let etag = CONTEXT
+    ///     .run(Request::get("users/ghopper"))
+    ///     .await
+    ///     .expect_etag();
+    ///
+    /// CONTEXT
+    ///     .run(Request::get("users/ghopper").with_if_none_match(etag))
+    ///     .await
+    ///     .expect_not_modified();
+    /// # }
+    /// ```
+    pub fn with_if_none_match(self, etag: impl ToString) -> Request<B> {
+        self.with_header(IF_NONE_MATCH, etag)
+    }
+
+    /// Makes the request conditional: the server is expected to perform it
+    /// only if the resource still matches `etag`, and to respond with
+    /// `412 Precondition Failed` otherwise.
+    ///
+    /// This sets the `If-Match` header, which allows to test optimistic
+    /// concurrency control on updates.
+    pub fn with_if_match(self, etag: impl ToString) -> Request<B> {
+        self.with_header(IF_MATCH, etag)
+    }
+
+    /// Sets the format of the request and response bodies.
+    ///
+    /// The request body is encoded with `codec`, the `Content-Type` and
+    /// `Accept` headers default to its media type, and the response body is
+    /// decoded with it by [`RequestResult::expect_status`]. Refer to the
+    /// [`codec`](crate::codec) module documentation for more details.
+    ///
+    /// The default codec is JSON.
+    pub fn with_codec(mut self, codec: Codec) -> Request<B> {
+        self.codec = codec;
 
         self
     }
 
     /// Specifies a body, returns the final [`Request`] object.
+    ///
+    /// Calling this method is not required: a request without a body, or
+    /// whose body is `()` or `None`, is sent without a body nor a
+    /// `Content-Type` header.
     pub fn with_body<C>(self, body: C) -> Request<C>
     where
         C: Serialize,
@@ -213,6 +495,9 @@ where
             method,
             url,
             context_description,
+            tags,
+            latency,
+            codec,
             ..
         } = self;
 
@@ -222,15 +507,150 @@ where
             method,
             url,
             context_description,
+            tags,
+            latency,
+            codec,
+            raw_body: None,
         }
     }
 
+    /// Specifies a body which is already serialized to a JSON value, returns
+    /// the final [`Request`] object.
+    ///
+    /// The body is sent as JSON, regardless of the codec of the request. This
+    /// allows to send payloads which are constructed dynamically, without
+    /// declaring a type for them.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use restest::Request;
+    /// use serde_json::json;
+    ///
+    /// let mut user = json!({ "name": "Grace Hopper" });
+    /// user["age"] = json!(85);
+    ///
+    /// let request = Request::post("users").with_json_value(user);
+    /// ```
+    pub fn with_json_value(self, value: Value) -> Request<Value> {
+        let raw_body = serde_json::to_vec(&value).expect("JSON value serialization failed");
+        let mut request = self.with_body(value);
+        request.raw_body = Some(raw_body);
+
+        request
+    }
+
+    /// Specifies a body which is sent as is, with the
+    /// `application/json; charset=utf-8` content type. Returns the final
+    /// [`Request`] object.
+    ///
+    /// The body is not checked to be valid JSON. This allows to check that
+    /// the backend rejects malformed payloads.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use http::StatusCode;
+    /// use restest::{Context, Request};
+    ///
+    /// const CONTEXT: Context = Context::new().with_port(8080);
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// CONTEXT
+    ///     .run(Request::post("users").with_raw_json(r#"{ "name": "Grace"#))
+    ///     .await
+    ///     .expect_status_code(StatusCode::BAD_REQUEST)
+    ///     .await;
+    /// # }
+    /// ```
+    pub fn with_raw_json(self, json: impl ToString) -> Request<()> {
+        let mut request = self.with_body(());
+        request.raw_body = Some(json.to_string().into_bytes());
+
+        request
+    }
+
     /// Specifies a context description. Returns the final [`Request`] object.
     pub fn with_context(mut self, context_description: impl ToString) -> Request<B> {
         self.context_description = context_description.to_string();
 
         self
     }
+
+    /// Adds tags to the request, such as the area of the API it belongs to.
+    ///
+    /// Tags are appended to the context description in failure messages, and
+    /// are attached to the assertions of the [`report`](crate::report), so
+    /// that failures can be triaged by area.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use restest::Request;
+    ///
+    /// let request = Request::get("users").with_tags(["smoke", "users-api"]);
+    /// ```
+    pub fn with_tags<I, T>(mut self, tags: I) -> Request<B>
+    where
+        I: IntoIterator<Item = T>,
+        T: ToString,
+    {
+        self.tags
+            .extend(tags.into_iter().map(|tag| tag.to_string()));
+
+        self
+    }
+
+    /// Delays the request by a given duration before it is sent.
+    ///
+    /// This simulates a slow network on the client side. Combined with
+    /// [`Context::with_bandwidth_limit`](crate::Context::with_bandwidth_limit),
+    /// it allows to check how the backend handles slow clients.
+    ///
+    /// The previously-set latency is discarded.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    ///
+    /// use restest::Request;
+    ///
+    /// let request = Request::get("users")
+    ///     .with_simulated_latency(Duration::from_millis(500));
+    /// ```
+    pub fn with_simulated_latency(mut self, latency: Duration) -> Request<B> {
+        self.latency = Some(latency);
+
+        self
+    }
+}
+
+impl<B> Request<B>
+where
+    B: Serialize,
+{
+    /// Returns the context description of the request, followed by its tags.
+    pub(crate) fn description(&self) -> String {
+        describe(&self.context_description, &self.tags)
+    }
+
+    /// Returns a copy of the request, with a different URL and context
+    /// description, which borrows the body of the original request.
+    pub(crate) fn with_url_ref(&self, url: String, context_description: String) -> Request<&B> {
+        Request {
+            body: &self.body,
+            header: self.header.clone(),
+            method: self.method,
+            url,
+            context_description,
+            tags: self.tags.clone(),
+            latency: self.latency,
+            codec: self.codec,
+            raw_body: self.raw_body.clone(),
+        }
+    }
 }
 
 impl<B> AsRef<Request<B>> for Request<B>
@@ -253,10 +673,23 @@ where
             method: self.method,
             url: self.url.clone(),
             context_description: self.context_description.clone(),
+            tags: self.tags.clone(),
+            latency: self.latency,
+            codec: self.codec,
+            raw_body: self.raw_body.clone(),
         }
     }
 }
 
+/// Returns a context description, followed by tags.
+pub(crate) fn describe(context_description: &str, tags: &[String]) -> String {
+    if tags.is_empty() {
+        return context_description.to_string();
+    }
+
+    format!("{} [{}]", context_description, tags.join(", "))
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) enum Method {
     Get,
@@ -265,67 +698,2455 @@ pub(crate) enum Method {
     Delete,
 }
 
+impl Method {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Method::Get => "GET",
+            Method::Post => "POST",
+            Method::Put => "PUT",
+            Method::Delete => "DELETE",
+        }
+    }
+}
+
 /// The data returned by the server once the request is performed.
 ///
 /// This datatype is meant for intermediary representation. It can be converted
 /// to a concrete type by calling [`RequestResult::expect_status`].
+///
+/// The response body is read at most once, and kept in memory afterwards. This
+/// allows to perform multiple assertions on the same response, through a
+/// mutable reference:
+///
+/// ```rust,no_run
+/// use http::{header::ETAG, StatusCode};
+/// use restest::{Context, Request};
+/// use serde::Deserialize;
+///
+/// const CONTEXT: Context = Context::new().with_port(8080);
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let mut result = CONTEXT.run(Request::get("users/ghopper")).await;
+///
+/// let user: User = result.expect_status_code(StatusCode::OK).await.json().await;
+/// let raw = result.text().await;
+///
+/// assert!(result.headers().contains_key(ETAG));
+/// assert!(raw.contains(&user.name));
+///
+/// #[derive(Deserialize)]
+/// struct User {
+///     name: String,
+/// }
+/// # }
+/// ```
 pub struct RequestResult {
-    pub(crate) response: Response,
+    pub(crate) status: StatusCode,
+    pub(crate) headers: HeaderMap,
+    version: Version,
+    body: Body,
     pub(crate) context_description: String,
+    pub(crate) context: Context,
+    pub(crate) elapsed: Duration,
+    curl: String,
+    /// The URL the request was sent to, against which links are resolved.
+    url: String,
+    request_id: Option<String>,
+    tags: Vec<String>,
+    codec: Codec,
+}
+
+/// The body of a response, which is read at most once.
+enum Body {
+    Pending(Response),
+    Buffered(Vec<u8>),
+    Failed(String),
 }
 
 impl RequestResult {
-    /// Checks if the response status meets an expected status code and convert
-    /// the body to a concrete type.
+    pub(crate) fn new(
+        response: Response,
+        context_description: String,
+        context: Context,
+        elapsed: Duration,
+        curl: String,
+    ) -> RequestResult {
+        RequestResult {
+            status: response.status(),
+            headers: response.headers().clone(),
+            version: response.version(),
+            body: Body::Pending(response),
+            context_description,
+            context,
+            elapsed,
+            curl,
+            url: String::new(),
+            request_id: None,
+            tags: Vec::new(),
+            codec: Codec::Json,
+        }
+    }
+
+    pub(crate) fn replayed(
+        response: cassette::Response,
+        context_description: String,
+        context: Context,
+        curl: String,
+    ) -> RequestResult {
+        RequestResult {
+            status: response.status,
+            headers: response.headers,
+            version: Version::HTTP_11,
+            body: Body::Buffered(response.body),
+            context_description,
+            context,
+            elapsed: Duration::ZERO,
+            curl,
+            url: String::new(),
+            request_id: None,
+            tags: Vec::new(),
+            codec: Codec::Json,
+        }
+    }
+
+    pub(crate) fn with_request_id(self, request_id: Option<String>) -> RequestResult {
+        RequestResult { request_id, ..self }
+    }
+
+    pub(crate) fn with_codec(self, codec: Codec) -> RequestResult {
+        RequestResult { codec, ..self }
+    }
+
+    pub(crate) fn with_tags(self, tags: Vec<String>) -> RequestResult {
+        RequestResult { tags, ..self }
+    }
+
+    pub(crate) fn with_url(self, url: String) -> RequestResult {
+        RequestResult { url, ..self }
+    }
+
+    /// Returns the response status code.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Returns the response headers.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Returns a mutable reference to the response headers.
     ///
-    /// This method uses `serde` internally, so the output type must implement
-    /// [`DeserializeOwned`].
+    /// This is meant for [middlewares](crate::middleware), which may rewrite
+    /// the response before it is checked.
+    pub fn headers_mut(&mut self) -> &mut HeaderMap {
+        &mut self.headers
+    }
+
+    /// Replaces the response status code.
+    ///
+    /// This is meant for [middlewares](crate::middleware), which may inject
+    /// failures before the response is checked.
+    pub fn set_status(&mut self, status: StatusCode) {
+        self.status = status;
+    }
+
+    /// Returns the HTTP version of the response.
+    ///
+    /// Responses replayed from a cassette are always reported as HTTP/1.1.
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Returns the request id which was sent with the request, if the context
+    /// was created with [`with_request_id`](Context::with_request_id).
+    pub fn request_id(&self) -> Option<&str> {
+        self.request_id.as_deref()
+    }
+
+    /// Returns the tags of the request, set with
+    /// [`Context::with_tags`] and [`Request::with_tags`].
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    /// Returns a `curl` command which sends the same request again.
+    ///
+    /// This command is included in the error messages of the failed checks,
+    /// so that the request can be reproduced manually. The values of the
+    /// sensitive headers are redacted, refer to the [`log`](crate::log)
+    /// module documentation.
+    pub fn curl(&self) -> &str {
+        &self.curl
+    }
+
+    /// Checks if the response status meets an expected status code, without
+    /// consuming the result.
     ///
     /// # Panics
     ///
     /// This method panics if the server response status is not equal to
-    /// `status` or if the body can not be deserialized to the specified type.
-    #[track_caller]
-    pub async fn expect_status<T>(self, status: StatusCode) -> T
-    where
-        T: DeserializeOwned,
-    {
-        match self.ensure_status(status).await {
-            Ok(deserialized) => deserialized,
-            Err(err) => panic!("{}", err),
+    /// `status`.
+    pub async fn expect_status_code(&mut self, status: StatusCode) -> &mut RequestResult {
+        let result = self.ensure_status_code(status).await.map(|_| ());
+        report::check_soft("expect_status_code", &self.tags, result);
+
+        self
+    }
+
+    /// Checks if the response status meets an expected status code, without
+    /// consuming the result.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the server response status is not
+    /// equal to `status`. The error contains the response body.
+    pub async fn ensure_status_code(
+        &mut self,
+        status: StatusCode,
+    ) -> Result<&mut RequestResult, String> {
+        if self.status != status {
+            let body = match self.ensure_bytes().await {
+                Ok(body) => String::from_utf8_lossy(body).into_owned(),
+                Err(err) => format!("unreadable ({})", err),
+            };
+
+            return Err(format!(
+                "Unexpected server response code for request '{}': {}. Body is {}\n\nReproduce with:\n{}",
+                self.context_description, self.status, body, self.curl
+            ));
         }
+
+        Ok(self)
     }
 
-    /// Checks if the response status meets an expected status code and convert
-    /// the body to a concrete type.
+    /// Returns the response body.
     ///
-    /// This method uses `serde` internally, so the output type must implement
-    /// [`DeserializeOwned`].
+    /// The body is read the first time this method, or any other method that
+    /// reads the body, is called.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the body can not be read.
+    pub async fn bytes(&mut self) -> &[u8] {
+        match self.ensure_bytes().await {
+            Ok(body) => body,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Returns the response body.
     ///
     /// # Error
     ///
-    /// This method return an error if the server response status is not equal to
-    /// `status` or if the body can not be deserialized to the specified type.
-    #[track_caller]
-    pub async fn ensure_status<T>(self, status: StatusCode) -> Result<T, String>
-    where
-        T: DeserializeOwned,
-    {
-        if self.response.status() != status {
-            return Err(format!("Unexpected server response code for request '{}'. Body is {}",
-            self.context_description,
-            self.response.text().await.map_err(
-                |err| {
-                    format!("Unexpected server response code for request {} : {}. Unable to read response body",self.context_description, err)
+    /// This method returns an error if the body can not be read.
+    pub async fn ensure_bytes(&mut self) -> Result<&[u8], String> {
+        if let Body::Pending(_) = self.body {
+            let response = match std::mem::replace(&mut self.body, Body::Buffered(Vec::new())) {
+                Body::Pending(response) => response,
+                _ => unreachable!(),
+            };
+
+            self.body = match shaping::read(response, self.context.bandwidth_limit).await {
+                Ok(body) => {
+                    log::response_body(self.context.log_level(), &self.context_description, &body);
+                    Body::Buffered(body)
                 }
-            )?));
+                Err(err) => Body::Failed(format!(
+                    "Failed to read body for request '{}': {}",
+                    self.context_description, err
+                )),
+            };
         }
 
-        self.response.json().await.map_err(|err| {
-            format!(
-                "Failed to deserialize body for request '{}': {}",
-                self.context_description, err
-            )
-        })
+        match &self.body {
+            Body::Buffered(body) => Ok(body),
+            Body::Failed(err) => Err(err.clone()),
+            Body::Pending(_) => unreachable!(),
+        }
+    }
+
+    /// Returns the response body as text. Invalid UTF-8 sequences are
+    /// replaced with `U+FFFD REPLACEMENT CHARACTER`.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the body can not be read.
+    pub async fn text(&mut self) -> String {
+        String::from_utf8_lossy(self.bytes().await).into_owned()
     }
+
+    /// Deserializes the response body.
+    ///
+    /// Unlike [`expect_status`](RequestResult::expect_status), this method
+    /// does not check the response status, and does not consume the result.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the body can not be read or deserialized to the
+    /// specified type.
+    pub async fn json<T>(&mut self) -> T
+    where
+        T: DeserializeOwned,
+    {
+        match self.ensure_json().await {
+            Ok(body) => body,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Deserializes the response body.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the body can not be read or
+    /// deserialized to the specified type.
+    pub async fn ensure_json<T>(&mut self) -> Result<T, String>
+    where
+        T: DeserializeOwned,
+    {
+        let body = self.ensure_bytes().await?;
+
+        serde_json::from_slice(body).map_err(|err| {
+            format!(
+                "Failed to deserialize body for request '{}': {}\n\nReproduce with:\n{}",
+                self.context_description, err, self.curl
+            )
+        })
+    }
+
+    /// Deserializes the value found at a
+    /// [JSON Pointer](https://datatracker.ietf.org/doc/html/rfc6901) in the
+    /// response body, such as `/data/items/0/id`.
+    ///
+    /// Like [`json`](RequestResult::json), this method does not check the
+    /// response status, and does not consume the result. Refer to the
+    /// [`json`](crate::json) module documentation for an example.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the body is not JSON, if there is no value at
+    /// `pointer`, or if the value can not be deserialized to the specified
+    /// type.
+    pub async fn expect_pointer<T>(&mut self, pointer: &str) -> T
+    where
+        T: DeserializeOwned,
+    {
+        let result = self.ensure_pointer(pointer).await;
+        report::check("expect_pointer", &self.tags, result)
+    }
+
+    /// Deserializes the value found at a JSON Pointer in the response body.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the body is not JSON, if there is no
+    /// value at `pointer`, or if the value can not be deserialized to the
+    /// specified type.
+    pub async fn ensure_pointer<T>(&mut self, pointer: &str) -> Result<T, String>
+    where
+        T: DeserializeOwned,
+    {
+        let body = self.ensure_json::<Value>().await?;
+
+        json::ensure_pointer(&body, pointer).map_err(|err| {
+            format!(
+                "Failed to extract value for request '{}': {}",
+                self.context_description, err
+            )
+        })
+    }
+
+    /// Checks that the JSON response body is accepted by a matcher, without
+    /// consuming the result.
+    ///
+    /// Like [`json`](RequestResult::json), this method does not check the
+    /// response status.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the body is not JSON, or if it is rejected by
+    /// the matcher.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use http::StatusCode;
+    /// use restest::{
+    ///     pattern::{MatchError, Matcher},
+    ///     Context, Request,
+    /// };
+    /// use serde_json::Value;
+    ///
+    /// const CONTEXT: Context = Context::new().with_port(8080);
+    ///
+    /// /// An amount of money, such as `{ "value": "12.50", "currency": "EUR" }`.
+    /// struct Money;
+    ///
+    /// impl Matcher for Money {
+    ///     fn matches(&self, value: &Value) -> Result<(), MatchError> {
+    ///         let amount = value["value"].as_str().unwrap_or_default();
+    ///
+    ///         match amount.split_once('.') {
+    ///             Some((_, cents)) if cents.len() == 2 => Ok(()),
+    ///             _ => Err(MatchError::with_reason(format!("{} is not an amount", value))),
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// CONTEXT
+    ///     .run(Request::get("orders/42/total"))
+    ///     .await
+    ///     .expect_status_code(StatusCode::OK)
+    ///     .await
+    ///     .expect_body_matches(Money)
+    ///     .await;
+    /// # }
+    /// ```
+    pub async fn expect_body_matches(&mut self, matcher: impl Matcher) -> &mut RequestResult {
+        let result = self.ensure_body_matches(matcher).await.map(|_| ());
+        report::check_soft("expect_body_matches", &self.tags, result);
+
+        self
+    }
+
+    /// Checks that the JSON response body is accepted by a matcher, without
+    /// consuming the result.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the body is not JSON, or if it is
+    /// rejected by the matcher.
+    pub async fn ensure_body_matches(
+        &mut self,
+        matcher: impl Matcher,
+    ) -> Result<&mut RequestResult, String> {
+        let body = self.ensure_json::<Value>().await?;
+
+        matcher.matches(&body).map_err(|err| {
+            format!(
+                "Unexpected body for request '{}': {}",
+                self.context_description, err
+            )
+        })?;
+
+        Ok(self)
+    }
+
+    /// Checks that the value found at a JSON Pointer in the response body is
+    /// accepted by a matcher, without consuming the result.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the body is not JSON, if there is no value at
+    /// `pointer`, or if the value is rejected by the matcher.
+    pub async fn expect_pointer_matches(
+        &mut self,
+        pointer: &str,
+        matcher: impl Matcher,
+    ) -> &mut RequestResult {
+        let result = self
+            .ensure_pointer_matches(pointer, matcher)
+            .await
+            .map(|_| ());
+        report::check_soft("expect_pointer_matches", &self.tags, result);
+
+        self
+    }
+
+    /// Checks that the value found at a JSON Pointer in the response body is
+    /// accepted by a matcher, without consuming the result.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the body is not JSON, if there is no
+    /// value at `pointer`, or if the value is rejected by the matcher.
+    pub async fn ensure_pointer_matches(
+        &mut self,
+        pointer: &str,
+        matcher: impl Matcher,
+    ) -> Result<&mut RequestResult, String> {
+        let value = self.ensure_pointer::<Value>(pointer).await?;
+
+        matcher.matches(&value).map_err(|err| {
+            format!(
+                "Unexpected value at '{}' for request '{}': {}",
+                pointer, self.context_description, err
+            )
+        })?;
+
+        Ok(self)
+    }
+
+    /// Checks that every value selected by a JSONPath query in the response
+    /// body is accepted by a matcher, without consuming the result.
+    ///
+    /// Refer to [`json::expect_jsonpath`](crate::json::expect_jsonpath) for
+    /// the details. This method is available with the `jsonpath` feature.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the body is not JSON, if the query is invalid,
+    /// if it selects no value, or if any selected value is rejected by the
+    /// matcher.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use http::StatusCode;
+    /// use restest::{pattern::MatchError, Context, Request};
+    /// use serde_json::Value;
+    ///
+    /// const CONTEXT: Context = Context::new().with_port(8080);
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// CONTEXT
+    ///     .run(Request::get("users"))
+    ///     .await
+    ///     .expect_status_code(StatusCode::OK)
+    ///     .await
+    ///     .expect_jsonpath("$.items[?@.active == true].id", |id: &Value| match id {
+    ///         Value::Null => Err(MatchError::with_reason("id is null")),
+    ///         _ => Ok(()),
+    ///     })
+    ///     .await;
+    /// # }
+    /// ```
+    #[cfg(feature = "jsonpath")]
+    pub async fn expect_jsonpath(
+        &mut self,
+        path: &str,
+        matcher: impl Matcher,
+    ) -> &mut RequestResult {
+        let result = self.ensure_jsonpath(path, matcher).await.map(|_| ());
+        report::check_soft("expect_jsonpath", &self.tags, result);
+
+        self
+    }
+
+    /// Checks that every value selected by a JSONPath query in the response
+    /// body is accepted by a matcher, without consuming the result.
+    ///
+    /// This method is available with the `jsonpath` feature.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the body is not JSON, if the query is
+    /// invalid, if it selects no value, or if any selected value is rejected
+    /// by the matcher.
+    #[cfg(feature = "jsonpath")]
+    pub async fn ensure_jsonpath(
+        &mut self,
+        path: &str,
+        matcher: impl Matcher,
+    ) -> Result<&mut RequestResult, String> {
+        let body = self.ensure_json::<Value>().await?;
+
+        json::ensure_jsonpath(&body, path, matcher).map_err(|err| {
+            format!(
+                "Unexpected values for request '{}': {}",
+                self.context_description, err
+            )
+        })?;
+
+        Ok(self)
+    }
+
+    /// Checks that no object of the JSON response body contains the same key
+    /// twice, without consuming the result.
+    ///
+    /// Such bodies are accepted by most parsers, including the one used by
+    /// [`expect_status`](RequestResult::expect_status), which silently keep
+    /// one of the values.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the body is not JSON, or if a key is duplicated.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use http::StatusCode;
+    /// use restest::{Context, Request};
+    ///
+    /// const CONTEXT: Context = Context::new().with_port(8080);
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// CONTEXT
+    ///     .run(Request::get("users/ghopper"))
+    ///     .await
+    ///     .expect_status_code(StatusCode::OK)
+    ///     .await
+    ///     .expect_no_duplicate_keys()
+    ///     .await;
+    /// # }
+    /// ```
+    pub async fn expect_no_duplicate_keys(&mut self) -> &mut RequestResult {
+        let result = self.ensure_no_duplicate_keys().await.map(|_| ());
+        report::check_soft("expect_no_duplicate_keys", &self.tags, result);
+
+        self
+    }
+
+    /// Checks that no object of the JSON response body contains the same key
+    /// twice, without consuming the result.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the body is not JSON, or if a key is
+    /// duplicated.
+    pub async fn ensure_no_duplicate_keys(&mut self) -> Result<&mut RequestResult, String> {
+        let description = self.context_description.clone();
+        let duplicates = json::duplicate_keys(self.ensure_bytes().await?).map_err(|err| {
+            format!(
+                "Failed to parse body for request '{}': {}",
+                description, err
+            )
+        })?;
+
+        if !duplicates.is_empty() {
+            return Err(format!(
+                "Duplicate keys in body for request '{}': {}",
+                description,
+                duplicates.join(", ")
+            ));
+        }
+
+        Ok(self)
+    }
+
+    /// Stores the status code and the JSON body of the response, so that it
+    /// can be compared with another response later on, without consuming the
+    /// result.
+    ///
+    /// Refer to the [`snapshot`](crate::snapshot) module documentation for
+    /// an example.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the body can not be read, or is not JSON.
+    pub async fn expect_snapshot(&mut self) -> ResponseSnapshot {
+        let result = self.ensure_snapshot().await;
+        report::check("expect_snapshot", &self.tags, result)
+    }
+
+    /// Stores the status code and the JSON body of the response, without
+    /// consuming the result.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the body can not be read, or is not
+    /// JSON.
+    pub async fn ensure_snapshot(&mut self) -> Result<ResponseSnapshot, String> {
+        let body = self.ensure_json::<Value>().await?;
+
+        Ok(ResponseSnapshot::new(
+            self.context_description.clone(),
+            self.status,
+            body,
+            self.tags.clone(),
+        ))
+    }
+
+    /// Decodes the response body with the codec of the request.
+    ///
+    /// The codec is set with [`Request::with_codec`], and is JSON by default.
+    /// Like [`json`](RequestResult::json), this method does not check the
+    /// response status, and does not consume the result.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the body can not be read or decoded to the
+    /// specified type.
+    pub async fn decode<T>(&mut self) -> T
+    where
+        T: DeserializeOwned,
+    {
+        match self.ensure_decode().await {
+            Ok(body) => body,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Decodes the response body with the codec of the request.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the body can not be read or decoded to
+    /// the specified type.
+    pub async fn ensure_decode<T>(&mut self) -> Result<T, String>
+    where
+        T: DeserializeOwned,
+    {
+        let codec = self.codec;
+        let body = self.ensure_bytes().await?;
+
+        codec.decode(body).map_err(|err| {
+            format!(
+                "Failed to deserialize body for request '{}': {}\n\nReproduce with:\n{}",
+                self.context_description, err, self.curl
+            )
+        })
+    }
+
+    /// Checks if the response status meets an expected status code and convert
+    /// the body to a concrete type.
+    ///
+    /// This method uses `serde` internally, so the output type must implement
+    /// [`DeserializeOwned`].
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the server response status is not equal to
+    /// `status` or if the body can not be deserialized to the specified type.
+    pub async fn expect_status<T>(self, status: StatusCode) -> T
+    where
+        T: DeserializeOwned,
+    {
+        let tags = self.tags.clone();
+        report::check("expect_status", &tags, self.ensure_status(status).await)
+    }
+
+    /// Checks if the response status meets an expected status code and convert
+    /// the body to a concrete type, adding test-specific context to the
+    /// failure message.
+    ///
+    /// `context` is only called if the check fails. It receives the response,
+    /// and returns a description of the situation, such as the fixture or the
+    /// loop iteration being tested. This complements
+    /// [`Request::with_context`], which describes the request itself.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the server response status is not equal to
+    /// `status` or if the body can not be deserialized to the specified type.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use http::StatusCode;
+    /// use restest::{path, Context, Request};
+    ///
+    /// const CONTEXT: Context = Context::new().with_port(8080);
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// for (iteration, id) in ["ghopper", "alovelace"].iter().enumerate() {
+    ///     let user: String = CONTEXT
+    ///         .run(Request::get(path!["users", id]))
+    ///         .await
+    ///         .expect_status_with(StatusCode::OK, |response| {
+    ///             format!(
+    ///                 "iteration {} (user {}, request id {:?})",
+    ///                 iteration,
+    ///                 id,
+    ///                 response.request_id()
+    ///             )
+    ///         })
+    ///         .await;
+    /// }
+    /// # }
+    /// ```
+    pub async fn expect_status_with<T, F>(self, status: StatusCode, context: F) -> T
+    where
+        T: DeserializeOwned,
+        F: FnOnce(&RequestResult) -> String,
+    {
+        let tags = self.tags.clone();
+        report::check(
+            "expect_status_with",
+            &tags,
+            self.ensure_status_with(status, context).await,
+        )
+    }
+
+    /// Checks if the response status meets an expected status code and convert
+    /// the body to a concrete type, adding test-specific context to the
+    /// error message.
+    ///
+    /// `context` is only called if the check fails. It receives the response,
+    /// and returns a description of the situation.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the server response status is not
+    /// equal to `status` or if the body can not be deserialized to the
+    /// specified type.
+    pub async fn ensure_status_with<T, F>(
+        mut self,
+        status: StatusCode,
+        context: F,
+    ) -> Result<T, String>
+    where
+        T: DeserializeOwned,
+        F: FnOnce(&RequestResult) -> String,
+    {
+        let result = match self.ensure_status_code(status).await {
+            Ok(this) => this.ensure_decode().await,
+            Err(err) => Err(err),
+        };
+
+        result.map_err(|err| format!("{}\n\nContext: {}", err, context(&self)))
+    }
+
+    /// Checks if the response status meets an expected status code and convert
+    /// the body to a concrete type.
+    ///
+    /// This method uses `serde` internally, so the output type must implement
+    /// [`DeserializeOwned`].
+    ///
+    /// # Error
+    ///
+    /// This method return an error if the server response status is not equal to
+    /// `status` or if the body can not be deserialized to the specified type.
+    pub async fn ensure_status<T>(mut self, status: StatusCode) -> Result<T, String>
+    where
+        T: DeserializeOwned,
+    {
+        self.ensure_status_code(status).await?.ensure_decode().await
+    }
+
+    /// Checks if the response status meets an expected error status code,
+    /// and decodes the JSON body as a [`ProblemDetails`] (RFC 7807).
+    ///
+    /// The `status` member of the body, if present, must be equal to the
+    /// response status. The content type is not checked: it can be checked
+    /// to be `application/problem+json` with
+    /// [`expect_content_type`](RequestResult::expect_content_type). Refer to
+    /// the [`problem`](crate::problem) module documentation for an example.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the server response status is not equal to
+    /// `status`, or if the body is not a valid problem for this status.
+    pub async fn expect_problem(self, status: StatusCode) -> ProblemDetails {
+        let tags = self.tags.clone();
+        report::check("expect_problem", &tags, self.ensure_problem(status).await)
+    }
+
+    /// Checks if the response status meets an expected error status code,
+    /// and decodes the JSON body as a [`ProblemDetails`] (RFC 7807).
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the server response status is not
+    /// equal to `status`, or if the body is not a valid problem for this
+    /// status.
+    pub async fn ensure_problem(mut self, status: StatusCode) -> Result<ProblemDetails, String> {
+        let body = self
+            .ensure_status_code(status)
+            .await?
+            .ensure_bytes()
+            .await?;
+
+        let problem = serde_json::from_slice::<ProblemDetails>(body).map_err(|err| {
+            format!(
+                "Failed to deserialize problem details for request '{}': {}\n\nReproduce with:\n{}",
+                self.context_description, err, self.curl
+            )
+        })?;
+
+        match problem.status {
+            Some(member) if member != status.as_u16() => Err(format!(
+                "Problem details for request '{}' have status {}, but the response status is {}\n\nReproduce with:\n{}",
+                self.context_description, member, status, self.curl
+            )),
+            _ => Ok(problem),
+        }
+    }
+
+    /// Checks if the response status meets an expected status code, and
+    /// that the JSON body is equal to an expected value, except for some
+    /// fields.
+    ///
+    /// This is the middle ground between
+    /// [`assert_body_matches`](crate::assert_body_matches) and
+    /// [snapshots](crate::snapshot). An ignored field is either a key, which
+    /// is ignored at any depth, or a
+    /// [JSON Pointer](https://datatracker.ietf.org/doc/html/rfc6901) starting
+    /// with `/`. The order of the keys does not matter.
+    ///
+    /// Returns the whole body, including the ignored fields.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the server response status is not equal to
+    /// `status`, or if the body differs from `expected`. The panic message
+    /// lists every difference.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use http::StatusCode;
+    /// use restest::{Context, Request};
+    /// use serde_json::json;
+    ///
+    /// const CONTEXT: Context = Context::new().with_port(8080);
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let user = CONTEXT
+    ///     .run(Request::post("users").with_body("Grace Hopper"))
+    ///     .await
+    ///     .expect_status_eq_json(
+    ///         StatusCode::CREATED,
+    ///         json!({ "name": "Grace Hopper", "admin": false }),
+    ///         &["id", "created_at"],
+    ///     )
+    ///     .await;
+    ///
+    /// let id = &user["id"];
+    /// # }
+    /// ```
+    pub async fn expect_status_eq_json<E>(
+        self,
+        status: StatusCode,
+        expected: E,
+        ignoring: &[&str],
+    ) -> Value
+    where
+        E: Serialize,
+    {
+        let tags = self.tags.clone();
+        report::check(
+            "expect_status_eq_json",
+            &tags,
+            self.ensure_status_eq_json(status, expected, ignoring).await,
+        )
+    }
+
+    /// Checks if the response status meets an expected status code, and
+    /// that the JSON body is equal to an expected value, except for some
+    /// fields.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the server response status is not
+    /// equal to `status`, if the body is not JSON, or if it differs from
+    /// `expected`.
+    pub async fn ensure_status_eq_json<E>(
+        mut self,
+        status: StatusCode,
+        expected: E,
+        ignoring: &[&str],
+    ) -> Result<Value, String>
+    where
+        E: Serialize,
+    {
+        let expected = serde_json::to_value(expected)
+            .map_err(|err| format!("Failed to serialize expected body: {}", err))?;
+        let body = self
+            .ensure_status_code(status)
+            .await?
+            .ensure_json::<Value>()
+            .await?;
+
+        let differences = snapshot::differences(&expected, &body, ignoring);
+        if !differences.is_empty() {
+            return Err(format!(
+                "Unexpected body for request '{}' (expected != actual):\n{}\n\nReproduce with:\n{}",
+                self.context_description,
+                differences.join("\n"),
+                self.curl
+            ));
+        }
+
+        Ok(body)
+    }
+
+    /// Checks if the response status meets an expected status code and
+    /// converts the XML body to a concrete type.
+    ///
+    /// This method uses `quick-xml` and `serde` internally, so the output type
+    /// must implement [`DeserializeOwned`]. Refer to the
+    /// [`xml`](crate::xml) module documentation for more details.
+    ///
+    /// This method is available with the `xml` feature.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the server response status is not equal to
+    /// `status` or if the body can not be deserialized to the specified type.
+    #[cfg(feature = "xml")]
+    pub async fn expect_status_xml<T>(self, status: StatusCode) -> T
+    where
+        T: DeserializeOwned,
+    {
+        let tags = self.tags.clone();
+        report::check(
+            "expect_status_xml",
+            &tags,
+            self.ensure_status_xml(status).await,
+        )
+    }
+
+    /// Checks if the response status meets an expected status code and
+    /// converts the XML body to a concrete type.
+    ///
+    /// This method is available with the `xml` feature.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the server response status is not
+    /// equal to `status` or if the body can not be deserialized to the
+    /// specified type.
+    #[cfg(feature = "xml")]
+    pub async fn ensure_status_xml<T>(mut self, status: StatusCode) -> Result<T, String>
+    where
+        T: DeserializeOwned,
+    {
+        let body = self
+            .ensure_status_code(status)
+            .await?
+            .ensure_bytes()
+            .await?;
+        let body = String::from_utf8(body.to_vec()).map_err(|err| {
+            format!(
+                "Invalid UTF-8 body for request '{}': {}",
+                self.context_description, err
+            )
+        })?;
+
+        quick_xml::de::from_str(&body).map_err(|err| {
+            format!(
+                "Failed to deserialize XML body for request '{}': {}\n\nReproduce with:\n{}",
+                self.context_description, err, self.curl
+            )
+        })
+    }
+
+    /// Checks if the response status meets an expected status code and
+    /// parses the XML body as an untyped [`Element`](crate::xml::Element)
+    /// tree.
+    ///
+    /// This method is available with the `xml` feature.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the server response status is not equal to
+    /// `status` or if the body is not well-formed XML.
+    #[cfg(feature = "xml")]
+    pub async fn expect_status_xml_element(self, status: StatusCode) -> crate::xml::Element {
+        let tags = self.tags.clone();
+        report::check(
+            "expect_status_xml_element",
+            &tags,
+            self.ensure_status_xml_element(status).await,
+        )
+    }
+
+    /// Checks if the response status meets an expected status code and
+    /// parses the XML body as an untyped [`Element`](crate::xml::Element)
+    /// tree.
+    ///
+    /// This method is available with the `xml` feature.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the server response status is not
+    /// equal to `status` or if the body is not well-formed XML.
+    #[cfg(feature = "xml")]
+    pub async fn ensure_status_xml_element(
+        mut self,
+        status: StatusCode,
+    ) -> Result<crate::xml::Element, String> {
+        let body = self
+            .ensure_status_code(status)
+            .await?
+            .ensure_bytes()
+            .await?;
+        let body = String::from_utf8(body.to_vec()).map_err(|err| {
+            format!(
+                "Invalid UTF-8 body for request '{}': {}",
+                self.context_description, err
+            )
+        })?;
+
+        crate::xml::Element::parse(&body).map_err(|err| {
+            format!(
+                "Failed to parse XML body for request '{}': {}\n\nReproduce with:\n{}",
+                self.context_description, err, self.curl
+            )
+        })
+    }
+
+    /// Checks if the response status meets an expected status code, and
+    /// returns the deserialized body along with the response status, headers
+    /// and timing.
+    ///
+    /// This allows to inspect a single response from multiple angles without
+    /// running the request again.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the server response status is not equal to
+    /// `status` or if the body can not be deserialized to the specified type.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use http::StatusCode;
+    /// use restest::{request::ResponseParts, Context, Request};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let context = Context::new().with_port(8080);
+    ///
+    /// let ResponseParts { body, headers, elapsed, .. } = context
+    ///     .run(Request::get("users"))
+    ///     .await
+    ///     .expect_status_full::<Vec<String>>(StatusCode::OK)
+    ///     .await;
+    ///
+    /// assert!(headers.contains_key("etag"));
+    /// println!("Got {} users in {:?}", body.len(), elapsed);
+    /// # }
+    /// ```
+    pub async fn expect_status_full<T>(self, status: StatusCode) -> ResponseParts<T>
+    where
+        T: DeserializeOwned,
+    {
+        let tags = self.tags.clone();
+        report::check(
+            "expect_status_full",
+            &tags,
+            self.ensure_status_full(status).await,
+        )
+    }
+
+    /// Checks if the response status meets an expected status code, and
+    /// returns the deserialized body along with the response status, headers
+    /// and timing.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the server response status is not equal
+    /// to `status` or if the body can not be deserialized to the specified
+    /// type.
+    pub async fn ensure_status_full<T>(
+        mut self,
+        status: StatusCode,
+    ) -> Result<ResponseParts<T>, String>
+    where
+        T: DeserializeOwned,
+    {
+        let body = self
+            .ensure_status_code(status)
+            .await?
+            .ensure_decode()
+            .await?;
+
+        Ok(ResponseParts {
+            status: self.status,
+            headers: self.headers,
+            body,
+            elapsed: self.elapsed,
+        })
+    }
+
+    /// Derives a new request from the response body, and runs it with the same
+    /// [`Context`].
+    ///
+    /// The response status must be a success (`2xx`) status code. The body is
+    /// deserialized and passed to `f`, which returns the next request. This
+    /// allows to chain dependent requests fluently, for instance creating a
+    /// resource and then fetching it by id.
+    ///
+    /// The context description of the returned result contains the
+    /// description of every step of the chain, so that failures point to the
+    /// exact step that failed.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the response status is not a success status code
+    /// or if the body can not be deserialized to the specified type.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use http::StatusCode;
+    /// use restest::{path, Context, Request};
+    /// use serde::{Deserialize, Serialize};
+    ///
+    /// const CONTEXT: Context = Context::new().with_port(8080);
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let user: User = CONTEXT
+    ///     .run(Request::post("users").with_body(UserInput { year_of_birth: 1906 }))
+    ///     .await
+    ///     .then(|user: User| Request::get(path!["users", user.id]))
+    ///     .await
+    ///     .expect_status(StatusCode::OK)
+    ///     .await;
+    /// # }
+    ///
+    /// #[derive(Serialize)]
+    /// struct UserInput {
+    ///     year_of_birth: usize,
+    /// }
+    ///
+    /// #[derive(Deserialize)]
+    /// struct User {
+    ///     id: String,
+    ///     year_of_birth: usize,
+    /// }
+    /// ```
+    pub async fn then<T, B, F>(mut self, f: F) -> RequestResult
+    where
+        T: DeserializeOwned,
+        B: Serialize,
+        F: FnOnce(T) -> Request<B>,
+    {
+        let status = self.status;
+        let context = self.context.clone();
+        let previous_description = self.context_description.clone();
+
+        if !status.is_success() {
+            panic!(
+                "Unexpected server response code for request '{}': {}. Body is {}\n\nReproduce with:\n{}",
+                previous_description,
+                status,
+                self.text().await,
+                self.curl
+            );
+        }
+
+        let body = self.expect_status::<T>(status).await;
+        let request = f(body);
+
+        let mut result = context.run(&request).await;
+        result.context_description =
+            format!("{} -> {}", previous_description, result.context_description);
+
+        result
+    }
+
+    /// Checks if the response status meets an expected status code and
+    /// returns the body as a stream of chunks.
+    ///
+    /// This allows to test endpoints that send their response incrementally,
+    /// such as chunked responses or [NDJSON](http://ndjson.org/) endpoints.
+    /// The stream is consumed with the methods of [`ResponseStream`] or with
+    /// the [`assert_next_line_matches`](crate::assert_next_line_matches)
+    /// macro.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the server response status is not equal to
+    /// `status`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use http::StatusCode;
+    /// use restest::{assert_next_line_matches, Context, Request};
+    /// use serde::Deserialize;
+    ///
+    /// const CONTEXT: Context = Context::new().with_port(8080);
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut stream = CONTEXT
+    ///     .run(Request::get("users/export"))
+    ///     .await
+    ///     .expect_stream(StatusCode::OK)
+    ///     .await;
+    ///
+    /// assert_next_line_matches!(stream, User { name: "Grace Hopper", .. });
+    /// assert_next_line_matches!(stream, User { name: "Ada Lovelace", .. });
+    ///
+    /// stream.expect_end().await;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct User {
+    ///     name: String,
+    ///     year_of_birth: usize,
+    /// }
+    /// # }
+    /// ```
+    pub async fn expect_stream(self, status: StatusCode) -> ResponseStream {
+        let tags = self.tags.clone();
+        report::check("expect_stream", &tags, self.ensure_stream(status).await)
+    }
+
+    /// Checks if the response status meets an expected status code and
+    /// returns the body as a stream of chunks.
+    ///
+    /// # Error
+    ///
+    /// This method return an error if the server response status is not equal to
+    /// `status`.
+    pub async fn ensure_stream(mut self, status: StatusCode) -> Result<ResponseStream, String> {
+        self.ensure_status_code(status).await?;
+
+        // The body may have been read already, in which case the stream
+        // returns the buffered body.
+        let (response, buffer) = match self.body {
+            Body::Pending(response) => (Some(response), Vec::new()),
+            Body::Buffered(body) => (None, body),
+            Body::Failed(err) => return Err(err),
+        };
+
+        Ok(ResponseStream::new(
+            response,
+            buffer,
+            self.context_description,
+            self.context.bandwidth_limit,
+            self.tags,
+        ))
+    }
+
+    /// Checks if the response status meets an expected status code and
+    /// writes the body to a file, chunk by chunk.
+    ///
+    /// The body is never held in memory as a whole, which allows to test
+    /// export or report endpoints with very large outputs. The file is created
+    /// if needed, and truncated otherwise.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the server response status is not equal to
+    /// `status`, if the body can not be read, or if the file can not be
+    /// written.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use http::StatusCode;
+    /// use restest::{Context, Request};
+    ///
+    /// const CONTEXT: Context = Context::new().with_port(8080);
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let download = CONTEXT
+    ///     .run(Request::get("users/export"))
+    ///     .await
+    ///     .expect_status_save_to(StatusCode::OK, "target/export.csv")
+    ///     .await;
+    ///
+    /// assert_eq!(download.content_type.as_deref(), Some("text/csv"));
+    /// assert!(download.size > 0);
+    /// # }
+    /// ```
+    pub async fn expect_status_save_to(
+        self,
+        status: StatusCode,
+        path: impl AsRef<Path>,
+    ) -> Download {
+        let tags = self.tags.clone();
+        report::check(
+            "expect_status_save_to",
+            &tags,
+            self.ensure_status_save_to(status, path).await,
+        )
+    }
+
+    /// Checks if the response status meets an expected status code and
+    /// writes the body to a file, chunk by chunk.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the server response status is not
+    /// equal to `status`, if the body can not be read, or if the file can not
+    /// be written.
+    pub async fn ensure_status_save_to(
+        self,
+        status: StatusCode,
+        path: impl AsRef<Path>,
+    ) -> Result<Download, String> {
+        let path = path.as_ref().to_path_buf();
+        let content_type = self
+            .headers
+            .get(CONTENT_TYPE)
+            .map(|value| String::from_utf8_lossy(value.as_bytes()).into_owned());
+        let context_description = self.context_description.clone();
+
+        let write_error = |err: std::io::Error| {
+            format!(
+                "Failed to write body of request '{}' to '{}': {}",
+                context_description,
+                path.display(),
+                err
+            )
+        };
+
+        let mut stream = self.ensure_stream(status).await?;
+        let mut file = File::create(&path).map_err(write_error)?;
+        let mut size = 0;
+
+        while let Some(chunk) = stream.try_next_chunk().await? {
+            file.write_all(&chunk).map_err(write_error)?;
+            size += chunk.len() as u64;
+        }
+
+        file.flush().map_err(write_error)?;
+
+        Ok(Download {
+            path,
+            size,
+            content_type,
+        })
+    }
+
+    /// Checks that the response body is encoded with a specific content
+    /// coding, such as `gzip` or `br`.
+    ///
+    /// Automatic decompression must be disabled with
+    /// [`Context::with_decompression`](crate::Context::with_decompression),
+    /// otherwise the `Content-Encoding` header is removed from every
+    /// decompressed response.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the `Content-Encoding` header of the response is
+    /// missing or not equal to `encoding`.
+    #[track_caller]
+    pub fn expect_content_encoding(self, encoding: &str) -> RequestResult {
+        report::check_soft(
+            "expect_content_encoding",
+            &self.tags,
+            self.check_content_encoding(encoding),
+        );
+
+        self
+    }
+
+    /// Checks that the response body is encoded with a specific content
+    /// coding, such as `gzip` or `br`.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the `Content-Encoding` header of the
+    /// response is missing or not equal to `encoding`.
+    pub fn ensure_content_encoding(self, encoding: &str) -> Result<RequestResult, String> {
+        self.check_content_encoding(encoding)?;
+
+        Ok(self)
+    }
+
+    fn check_content_encoding(&self, encoding: &str) -> Result<(), String> {
+        let actual = self
+            .headers
+            .get(CONTENT_ENCODING)
+            .map(|value| String::from_utf8_lossy(value.as_bytes()).into_owned());
+
+        match actual {
+            Some(actual) if actual.trim().eq_ignore_ascii_case(encoding) => Ok(()),
+
+            Some(actual) => Err(format!(
+                "Unexpected content encoding for request '{}': expected {}, got {}",
+                self.context_description, encoding, actual
+            )),
+
+            None if self.context.decompression => Err(format!(
+                "Missing content encoding for request '{}': expected {}. Automatic decompression is enabled, which removes the header",
+                self.context_description, encoding
+            )),
+
+            None => Err(format!(
+                "Missing content encoding for request '{}': expected {}",
+                self.context_description, encoding
+            )),
+        }
+    }
+
+    /// Checks that the response has a specific content type.
+    ///
+    /// Media types are compared case-insensitively. Every parameter of
+    /// `content_type`, such as `charset`, must be present in the response
+    /// with the same value, but the response may have more parameters: an
+    /// `application/json` expectation is met by an
+    /// `application/json; charset=utf-8` response.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the `Content-Type` header of the response is
+    /// missing or does not match `content_type`.
+    #[track_caller]
+    pub fn expect_content_type(self, content_type: &str) -> RequestResult {
+        report::check_soft(
+            "expect_content_type",
+            &self.tags,
+            self.check_content_type(content_type),
+        );
+
+        self
+    }
+
+    /// Checks that the response has a specific content type.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the `Content-Type` header of the
+    /// response is missing or does not match `content_type`.
+    pub fn ensure_content_type(self, content_type: &str) -> Result<RequestResult, String> {
+        self.check_content_type(content_type)?;
+
+        Ok(self)
+    }
+
+    fn check_content_type(&self, content_type: &str) -> Result<(), String> {
+        let actual = self
+            .headers
+            .get(CONTENT_TYPE)
+            .map(|value| String::from_utf8_lossy(value.as_bytes()).into_owned());
+
+        match actual {
+            Some(actual) if media_type_matches(content_type, &actual) => Ok(()),
+
+            Some(actual) => Err(format!(
+                "Unexpected content type for request '{}': expected {}, got {}",
+                self.context_description, content_type, actual
+            )),
+
+            None => Err(format!(
+                "Missing content type for request '{}': expected {}",
+                self.context_description, content_type
+            )),
+        }
+    }
+
+    /// Checks that the `Content-Length` header of the response is equal to a
+    /// specific value.
+    ///
+    /// The header is absent from chunked responses, and from the responses
+    /// that were decompressed automatically (see
+    /// [`Context::with_decompression`](crate::Context::with_decompression)).
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the `Content-Length` header of the response is
+    /// missing or not equal to `length`.
+    #[track_caller]
+    pub fn expect_content_length(self, length: u64) -> RequestResult {
+        report::check_soft(
+            "expect_content_length",
+            &self.tags,
+            self.check_content_length(length),
+        );
+
+        self
+    }
+
+    /// Checks that the `Content-Length` header of the response is equal to a
+    /// specific value.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the `Content-Length` header of the
+    /// response is missing or not equal to `length`.
+    pub fn ensure_content_length(self, length: u64) -> Result<RequestResult, String> {
+        self.check_content_length(length)?;
+
+        Ok(self)
+    }
+
+    fn check_content_length(&self, length: u64) -> Result<(), String> {
+        let actual = self
+            .headers
+            .get(CONTENT_LENGTH)
+            .map(|value| String::from_utf8_lossy(value.as_bytes()).into_owned());
+
+        match actual {
+            Some(actual) if actual.trim().parse() == Ok(length) => Ok(()),
+
+            Some(actual) => Err(format!(
+                "Unexpected content length for request '{}': expected {}, got {}",
+                self.context_description, length, actual
+            )),
+
+            None => Err(format!(
+                "Missing content length for request '{}': expected {}",
+                self.context_description, length
+            )),
+        }
+    }
+
+    /// Returns the entity tag of the response, from the `ETag` header.
+    ///
+    /// The entity tag is returned verbatim, including its quotes and weakness
+    /// indicator, so that it can be passed to
+    /// [`Request::with_if_none_match`] or [`Request::with_if_match`].
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the response has no `ETag` header.
+    #[track_caller]
+    pub fn expect_etag(&self) -> String {
+        report::check("expect_etag", &self.tags, self.ensure_etag())
+    }
+
+    /// Returns the entity tag of the response, from the `ETag` header.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the response has no `ETag` header.
+    pub fn ensure_etag(&self) -> Result<String, String> {
+        self.headers
+            .get(ETAG)
+            .map(|value| String::from_utf8_lossy(value.as_bytes()).into_owned())
+            .ok_or_else(|| {
+                format!(
+                    "Missing ETag header for request '{}'",
+                    self.context_description
+                )
+            })
+    }
+
+    /// Checks that the response was sent with a given HTTP version.
+    ///
+    /// This allows to check that the server negotiates HTTP/2 correctly.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the response HTTP version is not `version`.
+    #[track_caller]
+    pub fn expect_version(self, version: Version) -> RequestResult {
+        report::check_soft("expect_version", &self.tags, self.check_version(version));
+
+        self
+    }
+
+    /// Checks that the response was sent with a given HTTP version.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the response HTTP version is not
+    /// `version`.
+    pub fn ensure_version(self, version: Version) -> Result<RequestResult, String> {
+        self.check_version(version)?;
+
+        Ok(self)
+    }
+
+    fn check_version(&self, version: Version) -> Result<(), String> {
+        if self.version != version {
+            return Err(format!(
+                "Unexpected HTTP version for request '{}': expected {:?}, got {:?}",
+                self.context_description, version, self.version
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the server echoed the request id in the response headers.
+    ///
+    /// The request id header is set with
+    /// [`with_request_id`](Context::with_request_id) or
+    /// [`with_request_id_header`](Context::with_request_id_header).
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the context does not add request ids, or if the
+    /// response does not contain the same request id.
+    #[track_caller]
+    pub fn expect_request_id_echoed(self) -> RequestResult {
+        report::check_soft(
+            "expect_request_id_echoed",
+            &self.tags,
+            self.check_request_id_echoed(),
+        );
+
+        self
+    }
+
+    /// Checks that the server echoed the request id in the response headers.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the context does not add request ids,
+    /// or if the response does not contain the same request id.
+    pub fn ensure_request_id_echoed(self) -> Result<RequestResult, String> {
+        self.check_request_id_echoed()?;
+
+        Ok(self)
+    }
+
+    fn check_request_id_echoed(&self) -> Result<(), String> {
+        let (name, expected) = match (self.context.request_id_header, &self.request_id) {
+            (Some(name), Some(expected)) => (name, expected),
+            _ => {
+                return Err(format!(
+                    "No request id was sent for request '{}'",
+                    self.context_description
+                ))
+            }
+        };
+
+        match self.headers.get(name) {
+            Some(echoed) if echoed.as_bytes() == expected.as_bytes() => Ok(()),
+            Some(echoed) => Err(format!(
+                "Request id was not echoed for request '{}': got {} header {}",
+                self.context_description,
+                name,
+                String::from_utf8_lossy(echoed.as_bytes())
+            )),
+            None => Err(format!(
+                "Request id was not echoed for request '{}': missing {} header",
+                self.context_description, name
+            )),
+        }
+    }
+
+    /// Checks that the server responded with `304 Not Modified`.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the response status is not `304 Not Modified`.
+    #[track_caller]
+    pub fn expect_not_modified(self) -> RequestResult {
+        report::check_soft("expect_not_modified", &self.tags, self.check_not_modified());
+
+        self
+    }
+
+    /// Checks that the server responded with `304 Not Modified`.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the response status is not
+    /// `304 Not Modified`.
+    pub fn ensure_not_modified(self) -> Result<RequestResult, String> {
+        self.check_not_modified()?;
+
+        Ok(self)
+    }
+
+    fn check_not_modified(&self) -> Result<(), String> {
+        if self.status != StatusCode::NOT_MODIFIED {
+            return Err(format!(
+                "Conditional request '{}' was not answered with {}: got {}",
+                self.context_description,
+                StatusCode::NOT_MODIFIED,
+                self.status
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Parses the rate limiting headers of the response.
+    ///
+    /// Refer to the [`rate_limit`](crate::rate_limit) module documentation for
+    /// the list of supported headers.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the response has no rate limiting header, or if
+    /// one of them is invalid.
+    #[track_caller]
+    pub fn expect_rate_limit(&self) -> RateLimit {
+        report::check("expect_rate_limit", &self.tags, self.ensure_rate_limit())
+    }
+
+    /// Parses the rate limiting headers of the response.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the response has no rate limiting
+    /// header, or if one of them is invalid.
+    pub fn ensure_rate_limit(&self) -> Result<RateLimit, String> {
+        let rate_limit = RateLimit::from_headers(&self.headers)
+            .map_err(|err| format!("{} for request '{}'", err, self.context_description))?;
+
+        rate_limit.ok_or_else(|| {
+            format!(
+                "Missing rate limiting headers for request '{}'",
+                self.context_description
+            )
+        })
+    }
+
+    /// Parses the caching headers of the response.
+    ///
+    /// Refer to the [`cache`](crate::cache) module documentation for the list
+    /// of supported headers.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if one of the caching headers is invalid.
+    #[track_caller]
+    pub fn expect_cache_policy(&self) -> CachePolicy {
+        report::check(
+            "expect_cache_policy",
+            &self.tags,
+            self.ensure_cache_policy(),
+        )
+    }
+
+    /// Parses the caching headers of the response.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if one of the caching headers is invalid.
+    pub fn ensure_cache_policy(&self) -> Result<CachePolicy, String> {
+        CachePolicy::from_headers(&self.headers)
+            .map_err(|err| format!("{} for request '{}'", err, self.context_description))
+    }
+
+    /// Checks that the response can be stored by shared caches, such as a
+    /// CDN, for at least `min_lifetime`.
+    ///
+    /// Refer to [`CachePolicy::is_cacheable`] for the rules applied.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the response is not cacheable, or if its
+    /// freshness lifetime is shorter than `min_lifetime`.
+    #[track_caller]
+    pub fn expect_cacheable(self, min_lifetime: Duration) -> RequestResult {
+        report::check_soft(
+            "expect_cacheable",
+            &self.tags,
+            self.check_cacheable(min_lifetime),
+        );
+
+        self
+    }
+
+    /// Checks that the response can be stored by shared caches for at least
+    /// `min_lifetime`.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the response is not cacheable, or if
+    /// its freshness lifetime is shorter than `min_lifetime`.
+    pub fn ensure_cacheable(self, min_lifetime: Duration) -> Result<RequestResult, String> {
+        self.check_cacheable(min_lifetime)?;
+
+        Ok(self)
+    }
+
+    fn check_cacheable(&self, min_lifetime: Duration) -> Result<(), String> {
+        let policy = self.ensure_cache_policy()?;
+
+        let reason = if policy.no_store {
+            "it has the `no-store` directive".to_string()
+        } else if policy.private {
+            "it has the `private` directive".to_string()
+        } else {
+            match policy.freshness_lifetime() {
+                None => "it has no `max-age` nor `s-maxage` directive".to_string(),
+                Some(lifetime) if lifetime.is_zero() || lifetime < min_lifetime => {
+                    format!("its freshness lifetime is {:?}", lifetime)
+                }
+                Some(_) => return Ok(()),
+            }
+        };
+
+        Err(format!(
+            "Response to request '{}' is not cacheable for at least {:?}: {} (Cache-Control: {})",
+            self.context_description,
+            min_lifetime,
+            reason,
+            self.cache_control()
+        ))
+    }
+
+    /// Checks that the response must not be stored by any cache, with the
+    /// `no-store` directive.
+    ///
+    /// This allows to check that responses containing personal data never
+    /// end up in a CDN.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the response has no `no-store` directive.
+    #[track_caller]
+    pub fn expect_no_store(self) -> RequestResult {
+        report::check_soft("expect_no_store", &self.tags, self.check_no_store());
+
+        self
+    }
+
+    /// Checks that the response must not be stored by any cache.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the response has no `no-store`
+    /// directive.
+    pub fn ensure_no_store(self) -> Result<RequestResult, String> {
+        self.check_no_store()?;
+
+        Ok(self)
+    }
+
+    fn check_no_store(&self) -> Result<(), String> {
+        if self.ensure_cache_policy()?.no_store {
+            return Ok(());
+        }
+
+        Err(format!(
+            "Response to request '{}' may be stored by caches: missing `no-store` directive (Cache-Control: {})",
+            self.context_description,
+            self.cache_control()
+        ))
+    }
+
+    /// Returns the `Cache-Control` headers of the response, for error
+    /// messages.
+    fn cache_control(&self) -> String {
+        let values = self
+            .headers
+            .get_all(CACHE_CONTROL)
+            .iter()
+            .map(|value| String::from_utf8_lossy(value.as_bytes()).into_owned())
+            .collect::<Vec<_>>();
+
+        if values.is_empty() {
+            return "<missing>".to_string();
+        }
+
+        values.join(", ")
+    }
+
+    /// Returns a GET request to the target of the link with the relation
+    /// `rel`, such as `self` or `next`, without consuming the result.
+    ///
+    /// The link is read from the `Link` header, or from the `_links` member of
+    /// a [HAL](https://datatracker.ietf.org/doc/html/draft-kelly-json-hal) body
+    /// if the header has no such link. Relative links are resolved against the
+    /// URL of the request. The returned request keeps the tags of the
+    /// original one.
+    ///
+    /// This function may read the body, therefore it is marked as `async`.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the response has no link with the relation
+    /// `rel`, or if the link is invalid.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use http::StatusCode;
+    /// use restest::{Context, Request};
+    /// use serde_json::Value;
+    ///
+    /// const CONTEXT: Context = Context::new().with_port(8080);
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut result = CONTEXT.run(Request::post("orders").with_body(())).await;
+    ///
+    /// let order = result
+    ///     .expect_status_code(StatusCode::CREATED)
+    ///     .await
+    ///     .follow_link("self")
+    ///     .await;
+    ///
+    /// CONTEXT
+    ///     .run(order)
+    ///     .await
+    ///     .expect_status::<Value>(StatusCode::OK)
+    ///     .await;
+    /// # }
+    /// ```
+    pub async fn follow_link(&mut self, rel: &str) -> Request<()> {
+        let result = self.ensure_follow_link(rel).await;
+        report::check("follow_link", &self.tags, result)
+    }
+
+    /// Returns a GET request to the target of the link with the relation
+    /// `rel`, without consuming the result.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the response has no link with the
+    /// relation `rel`, or if the link is invalid.
+    pub async fn ensure_follow_link(&mut self, rel: &str) -> Result<Request<()>, String> {
+        let target = match link::from_headers(&self.headers, rel) {
+            Some(target) => Some(target),
+            // A body which is not JSON has no HAL links.
+            None => match self.ensure_json::<Value>().await {
+                Ok(body) => link::from_hal(&body, rel)
+                    .map_err(|err| format!("{} for request '{}'", err, self.context_description))?,
+                Err(_) => None,
+            },
+        };
+
+        let target = target.ok_or_else(|| {
+            format!(
+                "Missing link `{}` for request '{}'\n\nReproduce with:\n{}",
+                rel, self.context_description, self.curl
+            )
+        })?;
+
+        let url = link::resolve(&self.url, &target)
+            .map_err(|err| format!("{} for request '{}'", err, self.context_description))?;
+
+        Ok(Request::get(url).with_tags(self.tags.clone()))
+    }
+
+    /// Checks that the response body is strictly smaller than a given number
+    /// of bytes, without consuming the result.
+    ///
+    /// This allows to catch payload size regressions, such as a huge nested
+    /// object accidentally embedded in a response. The size is measured after
+    /// the automatic decompression, if any.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the body can not be read, or if its size is
+    /// greater than or equal to `bytes`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use http::StatusCode;
+    /// use restest::{Context, Request};
+    ///
+    /// const CONTEXT: Context = Context::new().with_port(8080);
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut result = CONTEXT.run(Request::get("users")).await;
+    ///
+    /// result
+    ///     .expect_status_code(StatusCode::OK)
+    ///     .await
+    ///     .expect_body_smaller_than(64 * 1024)
+    ///     .await;
+    /// # }
+    /// ```
+    pub async fn expect_body_smaller_than(&mut self, bytes: usize) -> &mut RequestResult {
+        let result = self.ensure_body_smaller_than(bytes).await.map(|_| ());
+        report::check_soft("expect_body_smaller_than", &self.tags, result);
+
+        self
+    }
+
+    /// Checks that the response body is strictly smaller than a given number
+    /// of bytes, without consuming the result.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the body can not be read, or if its
+    /// size is greater than or equal to `bytes`.
+    pub async fn ensure_body_smaller_than(
+        &mut self,
+        bytes: usize,
+    ) -> Result<&mut RequestResult, String> {
+        let size = self.ensure_bytes().await?.len();
+
+        if size >= bytes {
+            return Err(format!(
+                "Body is too large for request '{}': expected less than {} bytes, got {} bytes",
+                self.context_description, bytes, size
+            ));
+        }
+
+        Ok(self)
+    }
+
+    /// Checks that the SHA-256 digest of the response body is a given
+    /// hexadecimal string, without consuming the result.
+    ///
+    /// The expected digest can be computed from a fixture file with
+    /// [`digest::sha256_file`](crate::digest::sha256_file). The comparison is
+    /// case-insensitive.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the body can not be read, or if its digest is not
+    /// `expected`.
+    pub async fn expect_body_sha256(&mut self, expected: &str) -> &mut RequestResult {
+        let result = self.ensure_body_sha256(expected).await.map(|_| ());
+        report::check_soft("expect_body_sha256", &self.tags, result);
+
+        self
+    }
+
+    /// Checks that the SHA-256 digest of the response body is a given
+    /// hexadecimal string, without consuming the result.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the body can not be read, or if its
+    /// digest is not `expected`.
+    pub async fn ensure_body_sha256(
+        &mut self,
+        expected: &str,
+    ) -> Result<&mut RequestResult, String> {
+        let body = self.ensure_bytes().await?;
+        let (size, actual) = (body.len(), digest::sha256(body));
+
+        if !actual.eq_ignore_ascii_case(expected.trim()) {
+            return Err(format!(
+                "Unexpected body digest for request '{}': expected {}, got {} ({} bytes)",
+                self.context_description, expected, actual, size
+            ));
+        }
+
+        Ok(self)
+    }
+
+    /// Checks that the server accepted the content type of the request, that
+    /// is, that it did not respond with `415 Unsupported Media Type`.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the server responded with
+    /// `415 Unsupported Media Type`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use http::StatusCode;
+    /// use restest::{Context, Request};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let context = Context::new().with_port(8080);
+    ///
+    /// context
+    ///     .run(Request::post("users").with_body("Grace Hopper"))
+    ///     .await
+    ///     .expect_request_content_type_accepted()
+    ///     .expect_status::<String>(StatusCode::CREATED)
+    ///     .await;
+    /// # }
+    /// ```
+    #[track_caller]
+    pub fn expect_request_content_type_accepted(self) -> RequestResult {
+        report::check_soft(
+            "expect_request_content_type_accepted",
+            &self.tags,
+            self.check_request_content_type_accepted(),
+        );
+
+        self
+    }
+
+    /// Checks that the server accepted the content type of the request, that
+    /// is, that it did not respond with `415 Unsupported Media Type`.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the server responded with
+    /// `415 Unsupported Media Type`. The error mentions the media types the
+    /// server accepts, if it advertised them in an `Accept` or
+    /// `Accept-Post` header.
+    pub fn ensure_request_content_type_accepted(self) -> Result<RequestResult, String> {
+        self.check_request_content_type_accepted()?;
+
+        Ok(self)
+    }
+
+    fn check_request_content_type_accepted(&self) -> Result<(), String> {
+        if self.status != StatusCode::UNSUPPORTED_MEDIA_TYPE {
+            return Ok(());
+        }
+
+        let headers = &self.headers;
+        let accepted = [ACCEPT.as_str(), "accept-post"]
+            .iter()
+            .find_map(|name| headers.get(*name))
+            .map(|value| String::from_utf8_lossy(value.as_bytes()).into_owned());
+
+        Err(match accepted {
+            Some(accepted) => format!(
+                "Content type rejected for request '{}'. Server accepts {}",
+                self.context_description, accepted
+            ),
+            None => format!(
+                "Content type rejected for request '{}'",
+                self.context_description
+            ),
+        })
+    }
+
+    /// Checks that the response does not contain a given header.
+    ///
+    /// This allows to check that the server does not leak internal
+    /// information, such as debugging headers or the software it runs.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the header is present.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use http::StatusCode;
+    /// use restest::{Context, Request};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let context = Context::new().with_port(8080);
+    ///
+    /// context
+    ///     .run(Request::get("users"))
+    ///     .await
+    ///     .expect_header_absent("x-internal-debug")
+    ///     .expect_headers_absent(&["server", "x-powered-by"])
+    ///     .expect_status::<Vec<String>>(StatusCode::OK)
+    ///     .await;
+    /// # }
+    /// ```
+    #[track_caller]
+    pub fn expect_header_absent(self, name: &str) -> RequestResult {
+        report::check_soft(
+            "expect_header_absent",
+            &self.tags,
+            self.check_header_absent(name),
+        );
+
+        self
+    }
+
+    /// Checks that the response does not contain a given header.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error containing the value of the header if it
+    /// is present.
+    pub fn ensure_header_absent(self, name: &str) -> Result<RequestResult, String> {
+        self.check_header_absent(name)?;
+
+        Ok(self)
+    }
+
+    fn check_header_absent(&self, name: &str) -> Result<(), String> {
+        self.check_headers_absent(&[name])
+    }
+
+    /// Checks that the response contains none of the given headers.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if any of the headers is present.
+    #[track_caller]
+    pub fn expect_headers_absent(self, names: &[&str]) -> RequestResult {
+        report::check_soft(
+            "expect_headers_absent",
+            &self.tags,
+            self.check_headers_absent(names),
+        );
+
+        self
+    }
+
+    /// Checks that the response contains none of the given headers.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error listing every header that is present,
+    /// along with its values.
+    pub fn ensure_headers_absent(self, names: &[&str]) -> Result<RequestResult, String> {
+        self.check_headers_absent(names)?;
+
+        Ok(self)
+    }
+
+    fn check_headers_absent(&self, names: &[&str]) -> Result<(), String> {
+        let headers = &self.headers;
+        let present = names
+            .iter()
+            .filter(|name| headers.contains_key(**name))
+            .map(|name| {
+                let values = headers
+                    .get_all(*name)
+                    .iter()
+                    .map(|value| {
+                        log::header_value(name, value, self.context.redacted_headers).into_owned()
+                    })
+                    .collect::<Vec<_>>();
+
+                format!("{} ({})", name, values.join(" | "))
+            })
+            .collect::<Vec<_>>();
+
+        if present.is_empty() {
+            return Ok(());
+        }
+
+        Err(format!(
+            "Unexpected headers for request '{}': {}",
+            self.context_description,
+            present.join(", ")
+        ))
+    }
+
+    /// Checks that the response contains the security headers of a profile,
+    /// with well-formed and secure values.
+    ///
+    /// Refer to the [`security`](crate::security) module documentation for
+    /// more details.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if any header of the profile is missing or
+    /// malformed.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use http::StatusCode;
+    /// use restest::{security::SecurityProfile, Context, Request};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let context = Context::new().with_port(8080);
+    ///
+    /// context
+    ///     .run(Request::get("users"))
+    ///     .await
+    ///     .expect_security_headers(SecurityProfile::Strict)
+    ///     .expect_status::<Vec<String>>(StatusCode::OK)
+    ///     .await;
+    /// # }
+    /// ```
+    #[track_caller]
+    pub fn expect_security_headers(self, profile: SecurityProfile) -> RequestResult {
+        report::check_soft(
+            "expect_security_headers",
+            &self.tags,
+            self.check_security_headers(profile),
+        );
+
+        self
+    }
+
+    /// Checks that the response contains the security headers of a profile,
+    /// with well-formed and secure values.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error listing every header of the profile
+    /// which is missing or malformed.
+    pub fn ensure_security_headers(
+        self,
+        profile: SecurityProfile,
+    ) -> Result<RequestResult, String> {
+        self.check_security_headers(profile)?;
+
+        Ok(self)
+    }
+
+    fn check_security_headers(&self, profile: SecurityProfile) -> Result<(), String> {
+        let problems = profile.audit(&self.headers);
+
+        if problems.is_empty() {
+            return Ok(());
+        }
+
+        Err(format!(
+            "Insecure headers for request '{}': {}",
+            self.context_description,
+            problems.join(", ")
+        ))
+    }
+}
+
+/// A response body, along with the response metadata.
+///
+/// This type is returned by [`RequestResult::expect_status_full`].
+#[derive(Debug, Clone)]
+pub struct ResponseParts<T> {
+    /// The response status code.
+    pub status: StatusCode,
+    /// The response headers.
+    pub headers: HeaderMap,
+    /// The deserialized response body.
+    pub body: T,
+    /// The time elapsed between the moment the request was sent and the
+    /// moment the response headers were received. The time spent reading the
+    /// body is not included.
+    pub elapsed: Duration,
+}
+
+/// Converts a request header, whose value may contain non-ASCII characters
+/// if `non_ascii` is set.
+///
+/// The error names the header, where it is set (such as `request 'GET:/'`)
+/// and its first invalid character.
+pub(crate) fn parse_header(
+    name: &str,
+    value: &str,
+    non_ascii: bool,
+    location: &str,
+) -> Result<(HeaderName, HeaderValue), String> {
+    // The characters allowed in tokens by RFC 9110.
+    let is_token = |c: char| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c);
+
+    if name.is_empty() {
+        return Err(format!("Empty header name in {}", location));
+    }
+
+    if let Some((position, c)) = name.char_indices().find(|&(_, c)| !is_token(c)) {
+        return Err(format!(
+            "Invalid header name '{}' in {}: invalid character {:?} at position {}",
+            name, location, c, position
+        ));
+    }
+
+    let is_valid = |c: char| match c {
+        '\t' | ' '..='~' => true,
+        _ => non_ascii && !c.is_control(),
+    };
+
+    if let Some((position, c)) = value.char_indices().find(|&(_, c)| !is_valid(c)) {
+        let hint = if non_ascii || c.is_control() {
+            ""
+        } else {
+            ". Non-ASCII values can be sent with `with_non_ascii_header`"
+        };
+
+        return Err(format!(
+            "Invalid value for header '{}' in {}: invalid character {:?} at position {}{}",
+            name, location, c, position, hint
+        ));
+    }
+
+    let invalid = |err| format!("Invalid header '{}' in {}: {}", name, location, err);
+
+    Ok((
+        HeaderName::from_bytes(name.as_bytes()).map_err(|err| invalid(err.to_string()))?,
+        HeaderValue::from_bytes(value.as_bytes()).map_err(|err| invalid(err.to_string()))?,
+    ))
+}
+
+/// Returns whether a media type has the essence of an expected one, along
+/// with all of its parameters.
+fn media_type_matches(expected: &str, actual: &str) -> bool {
+    let (expected_essence, expected_parameters) = parse_media_type(expected);
+    let (actual_essence, actual_parameters) = parse_media_type(actual);
+
+    expected_essence == actual_essence
+        && expected_parameters
+            .iter()
+            .all(|parameter| actual_parameters.contains(parameter))
+}
+
+/// Splits a media type into its lowercase essence and its parameters.
+/// Parameter names and `charset` values are lowercased, and quotes are
+/// removed from values.
+fn parse_media_type(media_type: &str) -> (String, Vec<(String, String)>) {
+    let mut parts = media_type.split(';');
+    let essence = parts.next().unwrap_or_default().trim().to_ascii_lowercase();
+
+    let parameters = parts
+        .filter_map(|parameter| parameter.split_once('='))
+        .map(|(name, value)| {
+            let name = name.trim().to_ascii_lowercase();
+            let value = value.trim().trim_matches('"');
+            let value = match name.as_str() {
+                "charset" => value.to_ascii_lowercase(),
+                _ => value.to_string(),
+            };
+
+            (name, value)
+        })
+        .collect();
+
+    (essence, parameters)
 }
@@ -0,0 +1,240 @@
+//! Audit the security headers of a response.
+//!
+//! This module provides the [`SecurityProfile`] type, which is passed to
+//! [`RequestResult::expect_security_headers`](crate::request::RequestResult::expect_security_headers).
+//! A profile is a set of [`SecurityHeader`]s which must be present in the
+//! response, each of them having a well-formed and secure value.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use http::StatusCode;
+//! use restest::{security::{SecurityHeader, SecurityProfile}, Context, Request};
+//!
+//! const CONTEXT: Context = Context::new().with_port(8080);
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! CONTEXT
+//!     .run(Request::get("users"))
+//!     .await
+//!     .expect_security_headers(SecurityProfile::Strict)
+//!     .expect_status::<Vec<String>>(StatusCode::OK)
+//!     .await;
+//!
+//! CONTEXT
+//!     .run(Request::get("health"))
+//!     .await
+//!     .expect_security_headers(SecurityProfile::Custom(&[
+//!         SecurityHeader::ContentTypeOptions,
+//!         SecurityHeader::ReferrerPolicy,
+//!     ]))
+//!     .expect_status::<()>(StatusCode::OK)
+//!     .await;
+//! # }
+//! ```
+
+use http::HeaderMap;
+
+/// The minimum `max-age` of the `Strict-Transport-Security` header, in
+/// seconds (180 days).
+const HSTS_MIN_MAX_AGE: u64 = 180 * 24 * 60 * 60;
+
+/// The `Referrer-Policy` values which do not leak full URLs to other origins.
+const SAFE_REFERRER_POLICIES: [&str; 5] = [
+    "no-referrer",
+    "same-origin",
+    "strict-origin",
+    "strict-origin-when-cross-origin",
+    "origin",
+];
+
+/// The `Referrer-Policy` values which are valid but leak full URLs.
+const UNSAFE_REFERRER_POLICIES: [&str; 3] = [
+    "no-referrer-when-downgrade",
+    "origin-when-cross-origin",
+    "unsafe-url",
+];
+
+/// A set of security headers a response must contain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecurityProfile {
+    /// The headers any response should contain: `X-Content-Type-Options`,
+    /// `X-Frame-Options` and `Referrer-Policy`.
+    Baseline,
+    /// The headers of a production HTTPS deployment: the [`Baseline`]
+    /// headers, along with `Strict-Transport-Security`,
+    /// `Content-Security-Policy` and `Cross-Origin-Opener-Policy`.
+    ///
+    /// [`Baseline`]: SecurityProfile::Baseline
+    Strict,
+    /// A custom set of headers.
+    Custom(&'static [SecurityHeader]),
+}
+
+impl SecurityProfile {
+    /// Returns the headers of the profile.
+    pub fn headers(self) -> &'static [SecurityHeader] {
+        match self {
+            SecurityProfile::Baseline => &[
+                SecurityHeader::ContentTypeOptions,
+                SecurityHeader::FrameOptions,
+                SecurityHeader::ReferrerPolicy,
+            ],
+            SecurityProfile::Strict => &[
+                SecurityHeader::StrictTransportSecurity,
+                SecurityHeader::ContentTypeOptions,
+                SecurityHeader::ContentSecurityPolicy,
+                SecurityHeader::FrameOptions,
+                SecurityHeader::ReferrerPolicy,
+                SecurityHeader::CrossOriginOpenerPolicy,
+            ],
+            SecurityProfile::Custom(headers) => headers,
+        }
+    }
+
+    /// Checks the headers of a response against the profile.
+    ///
+    /// Returns a description of every header which is missing or malformed.
+    /// An empty list means that the response complies with the profile.
+    pub fn audit(self, headers: &HeaderMap) -> Vec<String> {
+        self.headers()
+            .iter()
+            .filter_map(|header| {
+                let problem = match headers.get(header.name()) {
+                    None => "missing".to_string(),
+                    Some(value) => match value.to_str() {
+                        Ok(value) => header.check(value.trim()).err()?,
+                        Err(_) => "not valid ASCII".to_string(),
+                    },
+                };
+
+                Some(format!("{} is {}", header.name(), problem))
+            })
+            .collect()
+    }
+}
+
+/// A security header, along with the rules its value must follow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecurityHeader {
+    /// `Strict-Transport-Security`, whose `max-age` must be at least 180
+    /// days.
+    StrictTransportSecurity,
+    /// `X-Content-Type-Options`, which must be `nosniff`.
+    ContentTypeOptions,
+    /// `Content-Security-Policy`, which must not be empty, and must not
+    /// allow `'unsafe-inline'` or `'unsafe-eval'` scripts.
+    ContentSecurityPolicy,
+    /// `X-Frame-Options`, which must be `DENY` or `SAMEORIGIN`.
+    FrameOptions,
+    /// `Referrer-Policy`, which must not leak full URLs to other origins.
+    ReferrerPolicy,
+    /// `Permissions-Policy`, which must not be empty.
+    PermissionsPolicy,
+    /// `Cross-Origin-Opener-Policy`, which must not be `unsafe-none`.
+    CrossOriginOpenerPolicy,
+}
+
+impl SecurityHeader {
+    /// Returns the name of the header.
+    pub fn name(self) -> &'static str {
+        match self {
+            SecurityHeader::StrictTransportSecurity => "Strict-Transport-Security",
+            SecurityHeader::ContentTypeOptions => "X-Content-Type-Options",
+            SecurityHeader::ContentSecurityPolicy => "Content-Security-Policy",
+            SecurityHeader::FrameOptions => "X-Frame-Options",
+            SecurityHeader::ReferrerPolicy => "Referrer-Policy",
+            SecurityHeader::PermissionsPolicy => "Permissions-Policy",
+            SecurityHeader::CrossOriginOpenerPolicy => "Cross-Origin-Opener-Policy",
+        }
+    }
+
+    /// Checks the value of the header.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error describing why the value is malformed or
+    /// insecure.
+    pub fn check(self, value: &str) -> Result<(), String> {
+        let lowercase = value.to_ascii_lowercase();
+
+        match self {
+            SecurityHeader::StrictTransportSecurity => {
+                let max_age = lowercase
+                    .split(';')
+                    .find_map(|directive| directive.trim().strip_prefix("max-age="))
+                    .ok_or_else(|| format!("missing max-age ({})", value))?;
+
+                let max_age = max_age
+                    .trim_matches('"')
+                    .parse::<u64>()
+                    .map_err(|_| format!("malformed ({})", value))?;
+
+                if max_age < HSTS_MIN_MAX_AGE {
+                    return Err(format!(
+                        "too short (max-age={}, expected at least {})",
+                        max_age, HSTS_MIN_MAX_AGE
+                    ));
+                }
+            }
+
+            SecurityHeader::ContentTypeOptions => {
+                if lowercase != "nosniff" {
+                    return Err(format!("malformed ({}, expected nosniff)", value));
+                }
+            }
+
+            SecurityHeader::ContentSecurityPolicy => {
+                if lowercase.is_empty() {
+                    return Err("empty".to_string());
+                }
+
+                for keyword in ["'unsafe-inline'", "'unsafe-eval'"] {
+                    if lowercase.contains(keyword) {
+                        return Err(format!("insecure (allows {})", keyword));
+                    }
+                }
+            }
+
+            SecurityHeader::FrameOptions => {
+                if lowercase != "deny" && lowercase != "sameorigin" {
+                    return Err(format!(
+                        "malformed ({}, expected DENY or SAMEORIGIN)",
+                        value
+                    ));
+                }
+            }
+
+            SecurityHeader::ReferrerPolicy => {
+                // Browsers use the last policy they support.
+                let policy = lowercase.split(',').map(str::trim).rev().find(|policy| {
+                    SAFE_REFERRER_POLICIES.contains(policy)
+                        || UNSAFE_REFERRER_POLICIES.contains(policy)
+                });
+
+                match policy {
+                    Some(policy) if UNSAFE_REFERRER_POLICIES.contains(&policy) => {
+                        return Err(format!("insecure ({})", value))
+                    }
+                    Some(_) => {}
+                    None => return Err(format!("malformed ({})", value)),
+                }
+            }
+
+            SecurityHeader::PermissionsPolicy => {
+                if lowercase.is_empty() {
+                    return Err("empty".to_string());
+                }
+            }
+
+            SecurityHeader::CrossOriginOpenerPolicy => match lowercase.as_str() {
+                "same-origin" | "same-origin-allow-popups" | "noopener-allow-popups" => {}
+                "unsafe-none" => return Err(format!("insecure ({})", value)),
+                _ => return Err(format!("malformed ({})", value)),
+            },
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+//! Client-side bandwidth shaping and request pacing.
+//!
+//! Request bodies are sent and response bodies are read in small slices, with
+//! a pause after each slice, so that the overall throughput does not exceed
+//! the limit set with [`Context::with_bandwidth_limit`](crate::Context::with_bandwidth_limit).
+//!
+//! Requests are delayed so that each origin receives no more requests per
+//! second than the limit set with [`Context::with_rate_limit`](crate::Context::with_rate_limit).
+
+use std::{
+    collections::BTreeMap,
+    convert::Infallible,
+    sync::{Mutex, PoisonError},
+    time::Duration,
+};
+
+use ::url::Url;
+use reqwest::{Body, Response};
+use tokio::time::Instant;
+
+/// The number of slices a second of transfer is split into.
+const SLICES_PER_SECOND: u64 = 10;
+
+/// Converts a limit in kilobits per second into the number of bytes that can
+/// be transferred in one slice.
+fn slice_len(kbps: u32) -> usize {
+    let bytes_per_second = u64::from(kbps) * 1000 / 8;
+    (bytes_per_second / SLICES_PER_SECOND).max(1) as usize
+}
+
+fn slice_duration(len: usize, kbps: u32) -> Duration {
+    let bytes_per_second = (u64::from(kbps) * 1000 / 8).max(1);
+    Duration::from_secs_f64(len as f64 / bytes_per_second as f64)
+}
+
+/// Creates a request body that is sent no faster than `kbps`.
+pub(crate) fn body(bytes: Vec<u8>, kbps: Option<u32>) -> Body {
+    let kbps = match kbps {
+        Some(kbps) => kbps,
+        None => return Body::from(bytes),
+    };
+
+    let slices = bytes
+        .chunks(slice_len(kbps))
+        .map(<[u8]>::to_vec)
+        .collect::<Vec<_>>();
+
+    let stream = futures_util::stream::unfold(slices.into_iter(), move |mut slices| async move {
+        let slice = slices.next()?;
+        tokio::time::sleep(slice_duration(slice.len(), kbps)).await;
+        Some((Ok::<_, Infallible>(slice), slices))
+    });
+
+    Body::wrap_stream(stream)
+}
+
+/// Reads a response body no faster than `kbps`.
+pub(crate) async fn read(mut response: Response, kbps: Option<u32>) -> reqwest::Result<Vec<u8>> {
+    let kbps = match kbps {
+        Some(kbps) => kbps,
+        None => return response.bytes().await.map(Vec::from),
+    };
+
+    let mut buff = Vec::new();
+
+    while let Some(chunk) = response.chunk().await? {
+        for slice in chunk.chunks(slice_len(kbps)) {
+            tokio::time::sleep(slice_duration(slice.len(), kbps)).await;
+            buff.extend_from_slice(slice);
+        }
+    }
+
+    Ok(buff)
+}
+
+/// Waits for the time it takes to transfer `len` bytes at `kbps`.
+pub(crate) async fn wait_for(len: usize, kbps: Option<u32>) {
+    if let Some(kbps) = kbps {
+        tokio::time::sleep(slice_duration(len, kbps)).await;
+    }
+}
+
+/// The time at which the next request can be sent, for each origin.
+static NEXT_SLOTS: Mutex<BTreeMap<String, Instant>> = Mutex::new(BTreeMap::new());
+
+/// Waits until a request can be sent to the origin of `url` without
+/// exceeding `per_second` requests per second.
+///
+/// Slots are reserved in the order in which this function is called, so that
+/// concurrent requests are spread evenly.
+pub(crate) async fn pace(url: &str, per_second: Option<u32>) {
+    let per_second = match per_second {
+        Some(per_second) => per_second,
+        None => return,
+    };
+
+    let origin = Url::parse(url)
+        .map(|url| url.origin().ascii_serialization())
+        .unwrap_or_else(|_| url.to_string());
+
+    let interval = Duration::from_secs(1) / per_second;
+
+    let slot = {
+        let mut slots = NEXT_SLOTS.lock().unwrap_or_else(PoisonError::into_inner);
+        let next = slots.entry(origin).or_insert_with(Instant::now);
+
+        let slot = (*next).max(Instant::now());
+        *next = slot + interval;
+        slot
+    };
+
+    tokio::time::sleep_until(slot).await;
+}
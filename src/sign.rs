@@ -0,0 +1,56 @@
+//! Sign outgoing requests.
+//!
+//! This module provides the [`RequestSigner`] trait, which is called by a
+//! [`Context`](crate::Context) right before each request is sent. A signer
+//! receives the method, the full URL, the headers and the serialized body of
+//! the request, and can add or modify headers. This allows to test APIs which
+//! require HMAC signatures or AWS SigV4 authentication.
+//!
+//! The headers added by the signer are included in the logs and in the `curl`
+//! command of the error messages.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use http::{HeaderMap, HeaderValue};
+//! use restest::{Context, Request};
+//!
+//! fn sign(method: &str, url: &str, headers: &mut HeaderMap, body: &[u8]) {
+//!     let payload = [method.as_bytes(), url.as_bytes(), body].concat();
+//!     let signature = hmac_sha256(b"secret", &payload);
+//!
+//!     headers.insert("x-signature", HeaderValue::from_str(&signature).unwrap());
+//! }
+//!
+//! const CONTEXT: Context = Context::new().with_port(8080).with_signer(&sign);
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! CONTEXT.run(Request::get("users")).await;
+//! # }
+//! # fn hmac_sha256(key: &[u8], payload: &[u8]) -> String { unimplemented!() }
+//! ```
+
+use http::HeaderMap;
+
+/// Adds authentication headers to outgoing requests.
+///
+/// This trait is implemented for every function or closure with the same
+/// signature as [`sign`](RequestSigner::sign).
+pub trait RequestSigner: Send + Sync {
+    /// Signs a request, right before it is sent.
+    ///
+    /// `url` is the full URL of the request, including the query string, and
+    /// `body` is the serialized body. The `Content-Type` and `Content-Length`
+    /// headers are already set.
+    fn sign(&self, method: &str, url: &str, headers: &mut HeaderMap, body: &[u8]);
+}
+
+impl<F> RequestSigner for F
+where
+    F: Fn(&str, &str, &mut HeaderMap, &[u8]) + Send + Sync,
+{
+    fn sign(&self, method: &str, url: &str, headers: &mut HeaderMap, body: &[u8]) {
+        self(method, url, headers, body)
+    }
+}
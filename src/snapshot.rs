@@ -0,0 +1,252 @@
+//! Compare the bodies of two responses.
+//!
+//! A [`ResponseSnapshot`] is taken with
+//! [`RequestResult::expect_snapshot`](crate::request::RequestResult::expect_snapshot).
+//! It stores the status code and the JSON body of a response, so that it can
+//! be compared with another response later on, with [`assert_same_body`] or
+//! with the [`assert_same_body`](crate::assert_same_body) macro. As bodies are
+//! compared as JSON values, the formatting and the order of the keys do not
+//! matter.
+//!
+//! Fields which legitimately change between two responses, such as
+//! timestamps, can be ignored. An ignored field is either a key, which is
+//! ignored at any depth, or a [JSON Pointer](https://datatracker.ietf.org/doc/html/rfc6901)
+//! starting with `/`.
+//!
+//! This makes idempotency and read-after-write consistency tests one-liners.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use http::StatusCode;
+//! use restest::{assert_same_body, Context, Request};
+//!
+//! const CONTEXT: Context = Context::new().with_port(8080);
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let created = CONTEXT
+//!     .run(Request::post("users").with_body("Grace Hopper"))
+//!     .await
+//!     .expect_status_code(StatusCode::CREATED)
+//!     .await
+//!     .expect_snapshot()
+//!     .await;
+//!
+//! let fetched = CONTEXT
+//!     .run(Request::get("users/ghopper"))
+//!     .await
+//!     .expect_status_code(StatusCode::OK)
+//!     .await
+//!     .expect_snapshot()
+//!     .await;
+//!
+//! assert_same_body!(created, fetched, ignoring = ["updated_at", "/meta/etag"]);
+//! # }
+//! ```
+
+use http::StatusCode;
+use serde_json::Value;
+
+use crate::report;
+
+/// The status code and the JSON body of a response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResponseSnapshot {
+    description: String,
+    status: StatusCode,
+    body: Value,
+    tags: Vec<String>,
+}
+
+impl ResponseSnapshot {
+    pub(crate) fn new(
+        description: String,
+        status: StatusCode,
+        body: Value,
+        tags: Vec<String>,
+    ) -> ResponseSnapshot {
+        ResponseSnapshot {
+            description,
+            status,
+            body,
+            tags,
+        }
+    }
+
+    /// Returns the description of the request the response belongs to.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Returns the response status code.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Returns the response body.
+    pub fn body(&self) -> &Value {
+        &self.body
+    }
+
+    /// Returns the response body, without the ignored fields.
+    ///
+    /// A field is either a key, which is removed at any depth, or a JSON
+    /// Pointer starting with `/`.
+    pub fn normalized_body(&self, ignoring: &[&str]) -> Value {
+        normalize(&self.body, ignoring)
+    }
+}
+
+/// Checks that two responses have the same body, except for the ignored
+/// fields.
+///
+/// # Panics
+///
+/// This function panics if the bodies differ, unless it is run in a
+/// [`soft`](crate::soft) scope. The panic message lists every difference.
+#[track_caller]
+pub fn assert_same_body(left: &ResponseSnapshot, right: &ResponseSnapshot, ignoring: &[&str]) {
+    report::check_soft(
+        "assert_same_body",
+        &report::merge_tags(left.tags.iter().chain(&right.tags)),
+        ensure_same_body(left, right, ignoring),
+    );
+}
+
+/// Checks that two responses have the same body, except for the ignored
+/// fields.
+///
+/// # Error
+///
+/// This function returns an error listing every difference if the bodies
+/// differ.
+pub fn ensure_same_body(
+    left: &ResponseSnapshot,
+    right: &ResponseSnapshot,
+    ignoring: &[&str],
+) -> Result<(), String> {
+    let differences = differences(&left.body, &right.body, ignoring);
+
+    if differences.is_empty() {
+        return Ok(());
+    }
+
+    Err(format!(
+        "Different bodies for requests '{}' and '{}':\n{}",
+        left.description,
+        right.description,
+        differences.join("\n")
+    ))
+}
+
+/// Returns a copy of `body` without the ignored fields.
+pub(crate) fn normalize(body: &Value, ignoring: &[&str]) -> Value {
+    let mut body = body.clone();
+
+    for field in ignoring {
+        if field.starts_with('/') {
+            remove_pointer(&mut body, field);
+        } else {
+            remove_key(&mut body, field);
+        }
+    }
+
+    body
+}
+
+/// Describes every value that differs between `left` and `right`, except for
+/// the ignored fields, one per line.
+pub(crate) fn differences(left: &Value, right: &Value, ignoring: &[&str]) -> Vec<String> {
+    let mut differences = Vec::new();
+    diff(
+        "",
+        &normalize(left, ignoring),
+        &normalize(right, ignoring),
+        &mut differences,
+    );
+
+    differences
+}
+
+/// Collects the JSON Pointer of every value that differs between `left` and
+/// `right`.
+fn diff(pointer: &str, left: &Value, right: &Value, differences: &mut Vec<String>) {
+    match (left, right) {
+        (Value::Object(left), Value::Object(right)) => {
+            for (key, value) in left {
+                let pointer = format!("{}/{}", pointer, escape(key));
+                match right.get(key) {
+                    Some(other) => diff(&pointer, value, other, differences),
+                    None => differences.push(format!("  - {}: {} != <missing>", pointer, value)),
+                }
+            }
+
+            for (key, value) in right {
+                if !left.contains_key(key) {
+                    let pointer = format!("{}/{}", pointer, escape(key));
+                    differences.push(format!("  - {}: <missing> != {}", pointer, value));
+                }
+            }
+        }
+
+        (Value::Array(left_items), Value::Array(right_items))
+            if left_items.len() == right_items.len() =>
+        {
+            for (index, (left, right)) in left_items.iter().zip(right_items).enumerate() {
+                diff(&format!("{}/{}", pointer, index), left, right, differences);
+            }
+        }
+
+        (left, right) if left != right => {
+            let pointer = if pointer.is_empty() { "/" } else { pointer };
+            differences.push(format!("  - {}: {} != {}", pointer, left, right));
+        }
+
+        _ => {}
+    }
+}
+
+/// Escapes a key so that it can be used in a JSON Pointer (see RFC 6901,
+/// section 3).
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+fn remove_key(value: &mut Value, key: &str) {
+    match value {
+        Value::Object(map) => {
+            map.remove(key);
+            map.values_mut().for_each(|value| remove_key(value, key));
+        }
+
+        Value::Array(items) => items.iter_mut().for_each(|value| remove_key(value, key)),
+
+        _ => {}
+    }
+}
+
+fn remove_pointer(value: &mut Value, pointer: &str) {
+    let (parent, last) = match pointer.rsplit_once('/') {
+        Some(split) => split,
+        None => return,
+    };
+
+    let last = last.replace("~1", "/").replace("~0", "~");
+
+    match value.pointer_mut(parent) {
+        Some(Value::Object(map)) => {
+            map.remove(&last);
+        }
+
+        Some(Value::Array(items)) => {
+            if let Ok(index) = last.parse::<usize>() {
+                if index < items.len() {
+                    items.remove(index);
+                }
+            }
+        }
+
+        _ => {}
+    }
+}
@@ -0,0 +1,193 @@
+//! Server-Sent Events support.
+//!
+//! This module provides the [`EventStream`] type, which is returned by
+//! [`Context::run_sse`](crate::Context::run_sse), and the [`Event`] type,
+//! which represents a single event sent by the server.
+//!
+//! Events are parsed according to the
+//! [HTML specification](https://html.spec.whatwg.org/multipage/server-sent-events.html#event-stream-interpretation).
+
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+
+use crate::{report, stream::ResponseStream};
+
+/// The default maximum amount of time to wait for an event.
+const DEFAULT_EVENT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// An event sent by the server.
+///
+/// This type can be matched with
+/// [`assert_next_event_matches`](crate::assert_next_event_matches). As its
+/// fields are `String`s, they can be matched with string literals.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    /// The event type. Defaults to `message` when the server does not specify
+    /// it.
+    pub event: String,
+    /// The event data. Multiple `data` lines are joined with `\n`.
+    pub data: String,
+    /// The event id, if any.
+    pub id: Option<String>,
+    /// The reconnection time requested by the server, in milliseconds, if any.
+    pub retry: Option<u64>,
+}
+
+impl Event {
+    /// Deserializes the event data as a JSON value.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the data can not be deserialized to the
+    /// specified type.
+    #[track_caller]
+    pub fn json<T>(&self) -> T
+    where
+        T: DeserializeOwned,
+    {
+        match serde_json::from_str(&self.data) {
+            Ok(value) => value,
+            Err(err) => panic!(
+                "Failed to deserialize data of event '{}': {}. Data is {}",
+                self.event, err, self.data
+            ),
+        }
+    }
+}
+
+/// A stream of events sent by the server.
+pub struct EventStream {
+    stream: ResponseStream,
+    context_description: String,
+    timeout: Duration,
+}
+
+impl EventStream {
+    pub(crate) fn new(stream: ResponseStream, context_description: String) -> EventStream {
+        EventStream {
+            stream,
+            context_description,
+            timeout: DEFAULT_EVENT_TIMEOUT,
+        }
+    }
+
+    /// Sets the maximum amount of time to wait for each event.
+    ///
+    /// The default timeout is 30 seconds.
+    pub fn with_event_timeout(mut self, timeout: Duration) -> EventStream {
+        self.timeout = timeout;
+
+        self
+    }
+
+    /// Returns the next event, or `None` if the server closed the stream.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if no event is received before the event timeout, or
+    /// if the stream can not be read.
+    pub async fn next_event(&mut self) -> Option<Event> {
+        match tokio::time::timeout(self.timeout, self.read_event()).await {
+            Ok(Ok(event)) => event,
+            Ok(Err(err)) => panic!("{}", err),
+            Err(_) => panic!(
+                "No event received for request '{}' after {:?}",
+                self.context_description, self.timeout
+            ),
+        }
+    }
+
+    /// Returns the next event.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the server closed the stream, or if no event is
+    /// received before the event timeout.
+    pub async fn expect_event(&mut self) -> Event {
+        let result = self.ensure_event().await;
+        report::check("expect_event", &self.stream.tags, result)
+    }
+
+    /// Returns the next event.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the server closed the stream, or if no
+    /// event is received before the event timeout.
+    pub async fn ensure_event(&mut self) -> Result<Event, String> {
+        match tokio::time::timeout(self.timeout, self.read_event()).await {
+            Ok(Ok(Some(event))) => Ok(event),
+            Ok(Err(err)) => Err(err),
+            Ok(Ok(None)) => Err(format!(
+                "Unexpected end of event stream for request '{}'",
+                self.context_description
+            )),
+            Err(_) => Err(format!(
+                "No event received for request '{}' after {:?}",
+                self.context_description, self.timeout
+            )),
+        }
+    }
+
+    async fn read_event(&mut self) -> Result<Option<Event>, String> {
+        let mut event = None;
+        let mut data = Vec::new();
+        let mut id = None;
+        let mut retry = None;
+
+        while let Some(line) = self.stream.try_next_line().await? {
+            if line.is_empty() {
+                if data.is_empty() {
+                    event = None;
+                    continue;
+                }
+
+                return Ok(Some(Event {
+                    event: event.unwrap_or_else(|| "message".to_string()),
+                    data: data.join("\n"),
+                    id,
+                    retry,
+                }));
+            }
+
+            if line.starts_with(':') {
+                continue;
+            }
+
+            let (field, value) = match line.split_once(':') {
+                Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+                None => (line.as_str(), ""),
+            };
+
+            match field {
+                "event" => event = Some(value.to_string()),
+                "data" => data.push(value.to_string()),
+                "id" => id = Some(value.to_string()),
+                "retry" => retry = value.parse().ok().or(retry),
+                _ => {}
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Waits for the next event of an [`EventStream`], asserts that it matches a
+/// given pattern, and adds bindings to the current scope.
+///
+/// The pattern syntax is the same as in
+/// [`assert_body_matches`](crate::assert_body_matches), and is matched against
+/// an [`Event`](crate::sse::Event).
+///
+/// # Panics
+///
+/// This macro panics if the stream has ended, if no event is received before
+/// the event timeout, or if the event does not match the provided pattern.
+#[macro_export]
+macro_rules! assert_next_event_matches {
+    ( $stream:expr, $( $pattern:tt )+ ) => {
+        let __restest__event: $crate::sse::Event = $stream.expect_event().await;
+        $crate::assert_body_matches!(__restest__event, $( $pattern )+);
+    };
+}
@@ -0,0 +1,198 @@
+//! Share values between the tests of a module.
+//!
+//! A long scenario, such as creating a user, updating it and deleting it,
+//! reads best as several test functions. The resources created by one test
+//! can be handed over to the next ones with a [`Slot`]: the producer test
+//! [publishes](Slot::publish) a value, and the consumer tests
+//! [get](Slot::get) it.
+//!
+//! A slot is usually placed in a `static`, next to the tests that use it.
+//! When the producer has not run, or has failed before publishing, getting
+//! the value fails with a message naming the slot, instead of an obscure
+//! error on a request built from a missing ID. Consumers usually declare the
+//! producer as a dependency with
+//! [`restest::test`](crate::test)`(depends_on = "...")`, so that it runs
+//! first.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use http::StatusCode;
+//! use restest::{path, store::Slot, Context, Request};
+//! use serde::Deserialize;
+//! use uuid::Uuid;
+//!
+//! static USER_ID: Slot<Uuid> = Slot::new("user_id");
+//!
+//! #[derive(Deserialize)]
+//! struct User {
+//!     id: Uuid,
+//! }
+//!
+//! #[restest::test]
+//! async fn post_user(context: Context) {
+//!     let user: User = context
+//!         .run(Request::post("users").with_body(()))
+//!         .await
+//!         .expect_status(StatusCode::CREATED)
+//!         .await;
+//!
+//!     USER_ID.publish(user.id);
+//! }
+//!
+//! #[restest::test(depends_on = "post_user")]
+//! async fn delete_user(context: Context) {
+//!     context
+//!         .run(Request::delete(path!["users", USER_ID.get()]))
+//!         .await
+//!         .expect_status::<()>(StatusCode::NO_CONTENT)
+//!         .await;
+//! }
+//! ```
+
+use std::{future::Future, time::Duration};
+
+use tokio::sync::{Notify, OnceCell};
+
+/// A typed value, published once by a test and read by others.
+///
+/// The value can be read from any thread, therefore it must be `Send` and
+/// `Sync` for the slot to be placed in a `static`.
+pub struct Slot<T> {
+    name: &'static str,
+    value: OnceCell<T>,
+    published: Notify,
+}
+
+impl<T> Slot<T> {
+    /// Creates an empty slot.
+    ///
+    /// The name appears in failure messages.
+    pub const fn new(name: &'static str) -> Slot<T> {
+        Slot {
+            name,
+            value: OnceCell::const_new(),
+            published: Notify::const_new(),
+        }
+    }
+
+    /// Returns the name of the slot.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Publishes the value of the slot.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if a value was already published.
+    #[track_caller]
+    pub fn publish(&self, value: T) {
+        if let Err(err) = self.ensure_publish(value) {
+            panic!("{}", err);
+        }
+    }
+
+    /// Publishes the value of the slot.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if a value was already published.
+    pub fn ensure_publish(&self, value: T) -> Result<(), String> {
+        self.value
+            .set(value)
+            .map_err(|_| format!("Slot '{}' was already published", self.name))?;
+
+        self.published.notify_waiters();
+
+        Ok(())
+    }
+
+    /// Returns the published value.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if no value was published.
+    #[track_caller]
+    pub fn get(&self) -> &T {
+        match self.ensure_get() {
+            Ok(value) => value,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Returns the published value.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if no value was published, which
+    /// happens when the test that publishes it has not run yet, or has failed
+    /// before publishing it.
+    pub fn ensure_get(&self) -> Result<&T, String> {
+        self.value.get().ok_or_else(|| {
+            format!(
+                "Slot '{}' is empty: the test which publishes it has not run yet, or has failed. \
+                 Consider declaring it as a dependency with `#[restest::test(depends_on = \"...\")]`",
+                self.name
+            )
+        })
+    }
+
+    /// Returns the published value, or waits until it is published by a test
+    /// running concurrently, for at most `timeout`.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if no value is published in time.
+    pub async fn wait(&self, timeout: Duration) -> &T {
+        match self.ensure_wait(timeout).await {
+            Ok(value) => value,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Returns the published value, or waits until it is published by a test
+    /// running concurrently, for at most `timeout`.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if no value is published in time.
+    pub async fn ensure_wait(&self, timeout: Duration) -> Result<&T, String> {
+        let published = async {
+            loop {
+                let notified = self.published.notified();
+
+                if let Some(value) = self.value.get() {
+                    return value;
+                }
+
+                notified.await;
+            }
+        };
+
+        tokio::time::timeout(timeout, published).await.map_err(|_| {
+            format!(
+                "Slot '{}' is still empty after {:?}: the test which publishes it may have failed",
+                self.name, timeout
+            )
+        })
+    }
+
+    /// Returns the published value, or initializes the slot with `init` if
+    /// no value was published.
+    ///
+    /// Concurrent calls run `init` only once. This allows a resource to be
+    /// created lazily by the first test that needs it.
+    ///
+    /// This function may perform I/O, therefore it is marked as `async`.
+    pub async fn get_or_init<F, Fut>(&self, init: F) -> &T
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = T>,
+    {
+        let value = self.value.get_or_init(init).await;
+        self.published.notify_waiters();
+
+        value
+    }
+}
@@ -0,0 +1,268 @@
+//! Incremental consumption of response bodies.
+//!
+//! This module provides the [`ResponseStream`] type, which is returned by
+//! [`RequestResult::expect_stream`](crate::request::RequestResult::expect_stream).
+//! It allows to read a response body chunk by chunk, or line by line, as the
+//! server sends it.
+//!
+//! It also provides the [`Download`] type, which is returned by
+//! [`RequestResult::expect_status_save_to`](crate::request::RequestResult::expect_status_save_to).
+
+use std::path::PathBuf;
+
+use reqwest::Response;
+use serde::de::DeserializeOwned;
+
+use crate::{report, shaping};
+
+/// The metadata of a response body which was saved to a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Download {
+    /// The path of the file.
+    pub path: PathBuf,
+    /// The size of the body, in bytes.
+    pub size: u64,
+    /// The value of the `Content-Type` header of the response, if any.
+    pub content_type: Option<String>,
+}
+
+/// A response body that is read incrementally.
+///
+/// The body can be read as raw chunks with [`next_chunk`](ResponseStream::next_chunk),
+/// as lines with [`next_line`](ResponseStream::next_line), or as
+/// newline-delimited JSON values with
+/// [`expect_next_json`](ResponseStream::expect_next_json). These methods can
+/// be mixed: data that has been received but not returned yet is kept in an
+/// internal buffer.
+pub struct ResponseStream {
+    response: Option<Response>,
+    buffer: Vec<u8>,
+    context_description: String,
+    bandwidth_limit: Option<u32>,
+    pub(crate) tags: Vec<String>,
+}
+
+impl ResponseStream {
+    pub(crate) fn new(
+        response: Option<Response>,
+        buffer: Vec<u8>,
+        context_description: String,
+        bandwidth_limit: Option<u32>,
+        tags: Vec<String>,
+    ) -> ResponseStream {
+        ResponseStream {
+            response,
+            buffer,
+            context_description,
+            bandwidth_limit,
+            tags,
+        }
+    }
+
+    /// Returns the next chunk of the body, or `None` if the body has been
+    /// entirely read.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the connection fails while reading the body.
+    pub async fn next_chunk(&mut self) -> Option<Vec<u8>> {
+        match self.try_next_chunk().await {
+            Ok(chunk) => chunk,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Returns the next line of the body, without its line terminator, or
+    /// `None` if the body has been entirely read.
+    ///
+    /// Both `\n` and `\r\n` line terminators are accepted. The last line of the
+    /// body does not need to be terminated.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the connection fails while reading the body or
+    /// if the line is not valid UTF-8.
+    pub async fn next_line(&mut self) -> Option<String> {
+        match self.try_next_line().await {
+            Ok(line) => line,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Reads the next non-empty line of the body and deserializes it as a
+    /// JSON value.
+    ///
+    /// This allows to consume [NDJSON](http://ndjson.org/) bodies.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the body has been entirely read, or if the line
+    /// can not be deserialized to the specified type.
+    pub async fn expect_next_json<T>(&mut self) -> T
+    where
+        T: DeserializeOwned,
+    {
+        let result = self.ensure_next_json().await;
+        report::check("expect_next_json", &self.tags, result)
+    }
+
+    /// Reads the next non-empty line of the body and deserializes it as a
+    /// JSON value.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the body has been entirely read, if
+    /// the body can not be read, or if the line can not be deserialized to
+    /// the specified type.
+    pub async fn ensure_next_json<T>(&mut self) -> Result<T, String>
+    where
+        T: DeserializeOwned,
+    {
+        let line = loop {
+            match self.try_next_line().await? {
+                Some(line) if line.trim().is_empty() => continue,
+                Some(line) => break line,
+                None => {
+                    return Err(format!(
+                        "Unexpected end of stream for request '{}'",
+                        self.context_description
+                    ))
+                }
+            }
+        };
+
+        serde_json::from_str(&line).map_err(|err| {
+            format!(
+                "Failed to deserialize line for request '{}': {}. Line is {}",
+                self.context_description, err, line
+            )
+        })
+    }
+
+    /// Checks that the body has been entirely read.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the server sends any more data.
+    pub async fn expect_end(self) {
+        let tags = self.tags.clone();
+        report::check_soft("expect_end", &tags, self.ensure_end().await);
+    }
+
+    /// Checks that the body has been entirely read.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the server sends any more data, or if
+    /// the body can not be read.
+    pub async fn ensure_end(mut self) -> Result<(), String> {
+        match self.try_next_chunk().await? {
+            Some(chunk) => Err(format!(
+                "Expected end of stream for request '{}'. Received {}",
+                self.context_description,
+                String::from_utf8_lossy(&chunk)
+            )),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) async fn try_next_chunk(&mut self) -> Result<Option<Vec<u8>>, String> {
+        if !self.buffer.is_empty() {
+            return Ok(Some(std::mem::take(&mut self.buffer)));
+        }
+
+        self.read_chunk().await
+    }
+
+    pub(crate) async fn try_next_line(&mut self) -> Result<Option<String>, String> {
+        loop {
+            if let Some(idx) = self.buffer.iter().position(|b| *b == b'\n') {
+                let mut line = self.buffer.drain(..=idx).collect::<Vec<_>>();
+                line.pop();
+                if line.last() == Some(&b'\r') {
+                    line.pop();
+                }
+
+                return self.decode_line(line).map(Some);
+            }
+
+            match self.read_chunk().await? {
+                Some(chunk) => self.buffer.extend(chunk),
+                None if self.buffer.is_empty() => return Ok(None),
+                None => {
+                    let line = std::mem::take(&mut self.buffer);
+                    return self.decode_line(line).map(Some);
+                }
+            }
+        }
+    }
+
+    async fn read_chunk(&mut self) -> Result<Option<Vec<u8>>, String> {
+        let response = match &mut self.response {
+            Some(response) => response,
+            None => return Ok(None),
+        };
+
+        let chunk = match response.chunk().await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => return Ok(None),
+            Err(err) => {
+                return Err(format!(
+                    "Failed to read body for request '{}': {}",
+                    self.context_description, err
+                ))
+            }
+        };
+
+        shaping::wait_for(chunk.len(), self.bandwidth_limit).await;
+
+        Ok(Some(chunk.to_vec()))
+    }
+
+    fn decode_line(&self, line: Vec<u8>) -> Result<String, String> {
+        String::from_utf8(line).map_err(|err| {
+            format!(
+                "Invalid UTF-8 line for request '{}': {}",
+                self.context_description, err
+            )
+        })
+    }
+}
+
+/// Reads the next line of a [`ResponseStream`] as JSON, asserts that it
+/// matches a given pattern, and adds bindings to the current scope.
+///
+/// The pattern syntax is the same as in [`assert_body_matches`](crate::assert_body_matches).
+/// The type of the line is inferred from the pattern.
+///
+/// # Panics
+///
+/// This macro panics if the stream has ended, if the line can not be
+/// deserialized, or if it does not match the provided pattern.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use restest::{assert_next_line_matches, stream::ResponseStream};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Progress {
+///     done: usize,
+///     total: usize,
+/// }
+///
+/// async fn check_progress(mut stream: ResponseStream) {
+///     assert_next_line_matches!(stream, Progress { done: 0, total });
+///     assert_next_line_matches!(stream, Progress { done: 1, .. });
+///
+///     println!("The job has {} steps", total);
+/// }
+/// ```
+#[macro_export]
+macro_rules! assert_next_line_matches {
+    ( $stream:expr, $( $pattern:tt )+ ) => {
+        let __restest__line = $stream.expect_next_json().await;
+        $crate::assert_body_matches!(__restest__line, $( $pattern )+);
+    };
+}
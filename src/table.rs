@@ -0,0 +1,200 @@
+//! Run many small request cases, and report every failure at once.
+//!
+//! This module provides the [`Table`] type, which holds a list of cases. Each
+//! case is a request, the status code expected for it, and optionally a check
+//! of the response body, usually written with the
+//! [`body_matches`](crate::body_matches) macro. Every case is run, even if
+//! some fail, and the failures are aggregated in a single report.
+//!
+//! This avoids writing one test per trivial permutation of an endpoint.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use http::StatusCode;
+//! use restest::{body_matches, table::Table, Context, Request};
+//! use serde::Deserialize;
+//!
+//! const CONTEXT: Context = Context::new().with_port(8080);
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! Table::new(&CONTEXT)
+//!     .with_case_matching(Request::get("users/ghopper"), StatusCode::OK, |user: User| {
+//!         body_matches!(user, User { name: "Grace Hopper", .. })
+//!     })
+//!     .with_case(Request::get("users/unknown"), StatusCode::NOT_FOUND)
+//!     .with_case(
+//!         Request::get("users/ghopper").with_header("authorization", "invalid"),
+//!         StatusCode::UNAUTHORIZED,
+//!     )
+//!     .assert_all()
+//!     .await;
+//! # }
+//!
+//! #[derive(Deserialize)]
+//! struct User {
+//!     name: String,
+//!     year_of_birth: u16,
+//! }
+//! ```
+
+use std::fmt::Display;
+
+use http::StatusCode;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use crate::{report, Context, Request};
+
+type BodyCheck<'a> = Box<dyn Fn(&[u8]) -> Result<(), String> + Send + Sync + 'a>;
+
+/// A list of request cases, which are run against a [`Context`].
+pub struct Table<'a> {
+    context: &'a Context,
+    cases: Vec<Case<'a>>,
+}
+
+struct Case<'a> {
+    request: Request<Value>,
+    status: StatusCode,
+    check: Option<BodyCheck<'a>>,
+}
+
+impl<'a> Table<'a> {
+    /// Creates a new table, with no case.
+    pub fn new(context: &'a Context) -> Table<'a> {
+        Table {
+            context,
+            cases: Vec::new(),
+        }
+    }
+
+    /// Adds a case which only checks the response status.
+    ///
+    /// The context description of the request, which can be set with
+    /// [`Request::with_context`], identifies the case in the report.
+    pub fn with_case<I>(self, request: Request<I>, status: StatusCode) -> Table<'a>
+    where
+        I: Serialize,
+    {
+        self.push(request, status, None)
+    }
+
+    /// Adds a case which checks the response status and body.
+    ///
+    /// The body is deserialized and passed to `check`, which returns an error
+    /// if the body is not the expected one. The output of the
+    /// [`body_matches`](crate::body_matches) macro can be returned directly.
+    pub fn with_case_matching<I, T, R, E, F>(
+        self,
+        request: Request<I>,
+        status: StatusCode,
+        check: F,
+    ) -> Table<'a>
+    where
+        I: Serialize,
+        T: DeserializeOwned,
+        E: Display,
+        F: Fn(T) -> Result<R, E> + Send + Sync + 'a,
+    {
+        let check = move |body: &[u8]| {
+            let body = serde_json::from_slice::<T>(body)
+                .map_err(|err| format!("Failed to deserialize body: {}", err))?;
+
+            check(body).map(drop).map_err(|err| err.to_string())
+        };
+
+        self.push(request, status, Some(Box::new(check)))
+    }
+
+    fn push<I>(
+        mut self,
+        request: Request<I>,
+        status: StatusCode,
+        check: Option<BodyCheck<'a>>,
+    ) -> Table<'a>
+    where
+        I: Serialize,
+    {
+        let body = serde_json::to_value(&request.body).expect("Body serialization failed");
+
+        self.cases.push(Case {
+            request: request.with_body(body),
+            status,
+            check,
+        });
+
+        self
+    }
+
+    /// Runs every case, in the order they were added.
+    ///
+    /// This function performs I/O, therefore it is marked as `async`.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if at least one case fails. The panic message lists
+    /// every failed case.
+    pub async fn assert_all(&self) {
+        let tags = self
+            .cases
+            .iter()
+            .flat_map(|case| self.context.tags_of(&case.request))
+            .collect::<Vec<_>>();
+
+        report::check_soft(
+            "assert_all",
+            &report::merge_tags(&tags),
+            self.ensure_all().await,
+        );
+    }
+
+    /// Runs every case, in the order they were added.
+    ///
+    /// This function performs I/O, therefore it is marked as `async`.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if at least one case fails. The error
+    /// message lists every failed case.
+    pub async fn ensure_all(&self) -> Result<(), String> {
+        let mut failures = Vec::new();
+
+        for case in &self.cases {
+            if let Err(err) = self.run(case).await {
+                failures.push((self.context.description_of(&case.request), err));
+            }
+        }
+
+        if failures.is_empty() {
+            return Ok(());
+        }
+
+        let mut message = format!("{} of {} case(s) failed:", failures.len(), self.cases.len());
+        for (description, err) in failures {
+            message.push_str(&format!("\n  - {}: {}", description, err));
+        }
+
+        Err(message)
+    }
+
+    async fn run(&self, case: &Case<'_>) -> Result<(), String> {
+        let mut result = self.context.run(&case.request).await;
+        let status = result.status;
+
+        if status != case.status {
+            return Err(format!(
+                "expected status {}, got {}. Body is {}",
+                case.status,
+                status,
+                result.text().await
+            ));
+        }
+
+        match &case.check {
+            Some(check) => check(result.ensure_bytes().await?),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,227 @@
+//! Record when concurrent requests are sent and answered.
+//!
+//! This module provides the [`Timeline`] type, which runs groups of requests
+//! concurrently and timestamps each of them. Ordering properties between
+//! groups can then be asserted, such as "every write was acknowledged before
+//! the reads were sent". On failure, the whole timeline is printed, which
+//! makes it easier to understand what happened.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use http::StatusCode;
+//! use restest::{timeline::Timeline, Context, Request};
+//! use serde::Serialize;
+//!
+//! const CONTEXT: Context = Context::new().with_port(8080);
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let mut timeline = Timeline::new(&CONTEXT);
+//!
+//! let writes = (0..10).map(|n| Request::post("counter").with_body(Increment { by: n }));
+//! timeline.run_concurrently("writes", writes).await;
+//!
+//! let total: u32 = timeline
+//!     .run("read", Request::get("counter"))
+//!     .await
+//!     .expect_status(StatusCode::OK)
+//!     .await;
+//!
+//! timeline.assert_before("writes", "read");
+//! assert_eq!(total, 45);
+//! # }
+//!
+//! #[derive(Serialize)]
+//! struct Increment {
+//!     by: u32,
+//! }
+//! ```
+
+use std::{
+    fmt::{self, Display, Formatter},
+    time::{Duration, Instant},
+};
+
+use futures_util::future;
+use http::StatusCode;
+use serde::Serialize;
+
+use crate::{report, request::RequestResult, Context, Request};
+
+/// Runs requests against a [`Context`] and records when each of them was sent
+/// and answered.
+///
+/// Each request belongs to a group, which is used to express ordering
+/// properties.
+pub struct Timeline<'a> {
+    context: &'a Context,
+    origin: Instant,
+    entries: Vec<TimelineEntry>,
+}
+
+impl<'a> Timeline<'a> {
+    /// Creates a new, empty timeline. Timestamps are relative to the moment
+    /// this function is called.
+    pub fn new(context: &'a Context) -> Timeline<'a> {
+        Timeline {
+            context,
+            origin: Instant::now(),
+            entries: Vec::new(),
+        }
+    }
+
+    /// Runs a single request and records it in a given group.
+    ///
+    /// This function performs I/O, therefore it is marked as `async`.
+    pub async fn run<I, R>(&mut self, group: impl ToString, request: R) -> RequestResult
+    where
+        I: Serialize,
+        R: AsRef<Request<I>>,
+    {
+        self.run_concurrently(group, [request]).await.pop().unwrap()
+    }
+
+    /// Runs requests concurrently and records them in a given group.
+    ///
+    /// The results are returned in the order of the requests.
+    ///
+    /// This function performs I/O, therefore it is marked as `async`.
+    pub async fn run_concurrently<I, R>(
+        &mut self,
+        group: impl ToString,
+        requests: impl IntoIterator<Item = R>,
+    ) -> Vec<RequestResult>
+    where
+        I: Serialize,
+        R: AsRef<Request<I>>,
+    {
+        let group = group.to_string();
+        let context = self.context;
+
+        let runs = requests.into_iter().map(|request| async move {
+            let sent = Instant::now();
+            let result = context.run(request).await;
+            (sent, Instant::now(), result)
+        });
+
+        let mut results = Vec::new();
+        for (sent, received, result) in future::join_all(runs).await {
+            self.entries.push(TimelineEntry {
+                group: group.clone(),
+                endpoint: result.context_description.clone(),
+                status: result.status,
+                tags: result.tags().to_vec(),
+                sent: sent - self.origin,
+                received: received - self.origin,
+            });
+            results.push(result);
+        }
+
+        results
+    }
+
+    /// Returns every recorded request, in the order they were run.
+    pub fn entries(&self) -> &[TimelineEntry] {
+        &self.entries
+    }
+
+    /// Checks that every request of the `earlier` group was answered before
+    /// any request of the `later` group was sent.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the groups overlap, or if one of them is empty.
+    /// The panic message contains the whole timeline.
+    #[track_caller]
+    pub fn assert_before(&self, earlier: &str, later: &str) {
+        let tags = self
+            .group(earlier)
+            .chain(self.group(later))
+            .flat_map(|entry| &entry.tags);
+
+        report::check_soft(
+            "assert_before",
+            &report::merge_tags(tags),
+            self.ensure_before(earlier, later),
+        );
+    }
+
+    /// Checks that every request of the `earlier` group was answered before
+    /// any request of the `later` group was sent.
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the groups overlap, or if one of them
+    /// is empty. The error message contains the whole timeline.
+    pub fn ensure_before(&self, earlier: &str, later: &str) -> Result<(), String> {
+        let last_received = self.group(earlier).map(|entry| entry.received).max();
+        let first_sent = self.group(later).map(|entry| entry.sent).min();
+
+        match (last_received, first_sent) {
+            (Some(last_received), Some(first_sent)) if last_received <= first_sent => Ok(()),
+
+            (Some(_), Some(_)) => Err(format!(
+                "Requests of group '{}' were not all answered before group '{}' started:\n{}",
+                earlier, later, self
+            )),
+
+            (None, _) => Err(format!("No request recorded in group '{}'", earlier)),
+            (_, None) => Err(format!("No request recorded in group '{}'", later)),
+        }
+    }
+
+    fn group<'b>(&'b self, group: &'b str) -> impl Iterator<Item = &'b TimelineEntry> {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.group == group)
+    }
+}
+
+/// Renders the timeline, one line per request, sorted by the moment the
+/// requests were sent.
+impl Display for Timeline<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut entries = self.entries.iter().collect::<Vec<_>>();
+        entries.sort_by_key(|entry| entry.sent);
+
+        for entry in entries {
+            writeln!(f, "  {}", entry)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A request recorded in a [`Timeline`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimelineEntry {
+    /// The group of the request.
+    pub group: String,
+    /// The context description of the request.
+    pub endpoint: String,
+    /// The response status code.
+    pub status: StatusCode,
+    /// The tags of the request.
+    pub tags: Vec<String>,
+    /// The moment the request was sent, relative to the creation of the
+    /// timeline.
+    pub sent: Duration,
+    /// The moment the response headers were received, relative to the
+    /// creation of the timeline.
+    pub received: Duration,
+}
+
+impl Display for TimelineEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:>6}ms -> {:>6}ms  [{}] {} {}",
+            self.sent.as_millis(),
+            self.received.as_millis(),
+            self.group,
+            self.endpoint,
+            self.status.as_u16()
+        )
+    }
+}
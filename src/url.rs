@@ -1,14 +1,36 @@
+use std::fmt::Arguments;
+
 pub trait IntoUrl {
     fn into_url(self) -> String;
 }
 
 impl IntoUrl for &'static str {
     fn into_url(self) -> String {
-        if self.starts_with('/') {
-            self.to_string()
-        } else {
-            format!("/{}", self)
-        }
+        normalize(self.to_string())
+    }
+}
+
+impl IntoUrl for String {
+    fn into_url(self) -> String {
+        normalize(self)
+    }
+}
+
+impl IntoUrl for &String {
+    fn into_url(self) -> String {
+        normalize(self.clone())
+    }
+}
+
+impl IntoUrl for Arguments<'_> {
+    fn into_url(self) -> String {
+        normalize(self.to_string())
+    }
+}
+
+impl IntoUrl for ::url::Url {
+    fn into_url(self) -> String {
+        self.into()
     }
 }
 
@@ -25,3 +47,17 @@ impl IntoUrl for Vec<Box<dyn ToString>> {
         buff
     }
 }
+
+/// Returns whether `url` is a full URL, which must be used as-is instead of
+/// being appended to the [`Context`](crate::Context) host and port.
+pub(crate) fn is_absolute(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+fn normalize(url: String) -> String {
+    if url.starts_with('/') || is_absolute(&url) {
+        url
+    } else {
+        format!("/{}", url)
+    }
+}
@@ -0,0 +1,27 @@
+//! Clean up after the test process, however it exits.
+
+use std::process::{self, Command, Stdio};
+
+/// Runs a shell command once the test process has exited, however it exits.
+///
+/// A shell process polls the test process, and runs the command once it is
+/// gone. It ignores the signals sent to the process group, such as `SIGINT`
+/// when the tests are interrupted, so that it outlives the test process in
+/// every case.
+pub(crate) fn watch(command: &str) {
+    let script = format!(
+        "trap '' INT TERM HUP; while kill -0 {} 2>/dev/null; do sleep 1; done; {}",
+        process::id(),
+        command
+    );
+
+    // Failing to spawn the watchdog only leaks a process or a container,
+    // which is not worth failing the tests.
+    let _ = Command::new("sh")
+        .arg("-c")
+        .arg(script)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+}
@@ -0,0 +1,196 @@
+//! XML response bodies.
+//!
+//! XML bodies can be deserialized to a concrete type with
+//! [`RequestResult::expect_status_xml`](crate::request::RequestResult::expect_status_xml),
+//! which uses `quick-xml` and `serde` internally.
+//!
+//! When no such type is available, as is often the case for SOAP or legacy
+//! endpoints, the body can be parsed as an untyped [`Element`] tree with
+//! [`RequestResult::expect_status_xml_element`](crate::request::RequestResult::expect_status_xml_element).
+//! The tree can then be navigated, or matched with
+//! [`assert_body_matches`](crate::assert_body_matches).
+//!
+//! This module is available with the `xml` feature.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use http::StatusCode;
+//! use restest::{Context, Request};
+//!
+//! const CONTEXT: Context = Context::new().with_port(8080);
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let envelope = CONTEXT
+//!     .run(Request::get("soap/users/ghopper"))
+//!     .await
+//!     .expect_status_xml_element(StatusCode::OK)
+//!     .await;
+//!
+//! let user = envelope.find("Body/GetUserResponse/User").unwrap();
+//!
+//! assert_eq!(user.attribute("id"), Some("ghopper"));
+//! assert_eq!(user.child("Name").unwrap().text, "Grace Hopper");
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+
+use quick_xml::{
+    events::{BytesStart, Event},
+    Reader,
+};
+
+/// An untyped XML element.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Element {
+    /// The name of the element, without its namespace prefix.
+    pub name: String,
+    /// The attributes of the element, by name.
+    pub attributes: BTreeMap<String, String>,
+    /// The child elements, in document order.
+    pub children: Vec<Element>,
+    /// The text content of the element, excluding the text of its children,
+    /// with leading and trailing whitespace removed.
+    pub text: String,
+}
+
+impl Element {
+    /// Parses an XML document, and returns its root element.
+    ///
+    /// Comments, processing instructions and the XML declaration are ignored.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use restest::xml::Element;
+    ///
+    /// let root = Element::parse(r#"<user id="42"><name>Grace</name></user>"#).unwrap();
+    ///
+    /// assert_eq!(root.name, "user");
+    /// assert_eq!(root.attribute("id"), Some("42"));
+    /// assert_eq!(root.child("name").unwrap().text, "Grace");
+    /// ```
+    ///
+    /// # Error
+    ///
+    /// This method returns an error if the document is not well-formed, or if
+    /// it has no root element.
+    pub fn parse(document: &str) -> Result<Element, String> {
+        let mut reader = Reader::from_str(document);
+
+        // The elements which are not closed yet, the innermost one last.
+        let mut open: Vec<Element> = Vec::new();
+        let mut root = None;
+
+        loop {
+            let event = reader.read_event().map_err(|err| {
+                format!(
+                    "Invalid XML at position {}: {}",
+                    reader.error_position(),
+                    err
+                )
+            })?;
+
+            match event {
+                Event::Start(start) => open.push(Element::from_start(&start)?),
+                Event::Empty(start) => {
+                    let element = Element::from_start(&start)?;
+                    close(element, &mut open, &mut root)?;
+                }
+                Event::End(_) => {
+                    let element = open.pop().ok_or("Invalid XML: unexpected end tag")?;
+                    close(element, &mut open, &mut root)?;
+                }
+                Event::Text(text) => {
+                    let text = text
+                        .unescape()
+                        .map_err(|err| format!("Invalid XML: {}", err))?;
+                    push_text(&mut open, &text);
+                }
+                Event::CData(data) => {
+                    push_text(&mut open, &String::from_utf8_lossy(&data.into_inner()));
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+
+        if !open.is_empty() {
+            return Err("Invalid XML: unclosed element".to_string());
+        }
+
+        root.ok_or_else(|| "Invalid XML: no root element".to_string())
+    }
+
+    fn from_start(start: &BytesStart) -> Result<Element, String> {
+        let mut attributes = BTreeMap::new();
+
+        for attribute in start.attributes() {
+            let attribute = attribute.map_err(|err| format!("Invalid XML attribute: {}", err))?;
+            let value = attribute
+                .unescape_value()
+                .map_err(|err| format!("Invalid XML attribute: {}", err))?;
+
+            attributes.insert(
+                String::from_utf8_lossy(attribute.key.local_name().as_ref()).into_owned(),
+                value.into_owned(),
+            );
+        }
+
+        Ok(Element {
+            name: String::from_utf8_lossy(start.local_name().as_ref()).into_owned(),
+            attributes,
+            children: Vec::new(),
+            text: String::new(),
+        })
+    }
+
+    /// Returns the value of an attribute, if any.
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).map(String::as_str)
+    }
+
+    /// Returns the first child element with a given name, if any.
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    /// Returns every child element with a given name.
+    pub fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    /// Returns the first descendant element found by following a path of
+    /// element names separated by `/`, starting from the children of this
+    /// element.
+    pub fn find(&self, path: &str) -> Option<&Element> {
+        path.split('/')
+            .filter(|name| !name.is_empty())
+            .try_fold(self, |element, name| element.child(name))
+    }
+}
+
+/// Adds a closed element to its parent, or sets it as the root element.
+fn close(
+    mut element: Element,
+    open: &mut [Element],
+    root: &mut Option<Element>,
+) -> Result<(), String> {
+    element.text = element.text.trim().to_string();
+
+    match open.last_mut() {
+        Some(parent) => parent.children.push(element),
+        None if root.is_none() => *root = Some(element),
+        None => return Err("Invalid XML: multiple root elements".to_string()),
+    }
+
+    Ok(())
+}
+
+fn push_text(open: &mut [Element], text: &str) {
+    if let Some(element) = open.last_mut() {
+        element.text.push_str(text);
+    }
+}
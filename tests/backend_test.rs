@@ -0,0 +1,105 @@
+#![cfg(unix)]
+
+use std::{
+    env, fs,
+    process::{Command, Stdio},
+    time::Duration,
+};
+
+use http::StatusCode;
+use restest::{backend::Backend, Context, Request};
+use serde_json::Value;
+use warp::{reply, Filter};
+
+/// Starts a server answering every request with `500 Internal Server Error`.
+/// Returns its port.
+fn serve() -> u16 {
+    let error = warp::any()
+        .map(|| reply::with_status(reply::json(&"error"), StatusCode::INTERNAL_SERVER_ERROR));
+
+    let (address, server) = warp::serve(error).bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+
+    address.port()
+}
+
+/// Returns a backend which prints `message`, then sleeps.
+fn backend(message: &str, port: u16) -> Backend {
+    Backend::command("sh", ["-c", &format!("echo {}; exec sleep 60", message)])
+        .with_context(Context::new().with_host("http://127.0.0.1").with_port(port))
+}
+
+/// Returns the failure message of `expect_status` on a request to `port`.
+async fn failure(port: u16) -> String {
+    let context = Context::new().with_host("http://127.0.0.1").with_port(port);
+
+    let payload = tokio::spawn(async move {
+        context
+            .run(Request::get("users"))
+            .await
+            .expect_status::<Value>(StatusCode::OK)
+            .await;
+    })
+    .await
+    .unwrap_err()
+    .into_panic();
+
+    *payload.downcast::<String>().unwrap()
+}
+
+#[tokio::test]
+async fn output_of_failing_backend_only() {
+    let (first_port, second_port) = (serve(), serve());
+    let first = backend("first-backend", first_port).start().await;
+    let second = backend("second-backend", second_port).start().await;
+
+    while first.output().is_empty() || second.output().is_empty() {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    let message = failure(first_port).await;
+    assert!(message.contains("first-backend"), "{}", message);
+    assert!(!message.contains("second-backend"), "{}", message);
+
+    // A stopped backend is not reported anymore.
+    first.stop();
+    let message = failure(first_port).await;
+    assert!(!message.contains("first-backend"), "{}", message);
+}
+
+#[tokio::test]
+async fn killed_when_dropped() {
+    let pid_file = env::temp_dir().join(format!("restest-backend-{}.pid", std::process::id()));
+    let script = format!("echo $$ > {}; exec sleep 60", pid_file.display());
+
+    let process = Backend::command("sh", ["-c", &script]).start().await;
+
+    let pid = loop {
+        match fs::read_to_string(&pid_file) {
+            Ok(pid) if pid.ends_with('\n') => break pid.trim().to_string(),
+            _ => tokio::time::sleep(Duration::from_millis(10)).await,
+        }
+    };
+    fs::remove_file(&pid_file).unwrap();
+
+    drop(process);
+
+    let alive = || {
+        Command::new("kill")
+            .args(["-0", &pid])
+            .stderr(Stdio::null())
+            .status()
+            .unwrap()
+            .success()
+    };
+
+    for _ in 0..100 {
+        if !alive() {
+            return;
+        }
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    panic!("The backend is still running");
+}
@@ -0,0 +1,81 @@
+use std::{env, fs};
+
+use http::StatusCode;
+use restest::{Context, Request};
+use serde_json::{json, Value};
+use warp::{filters::method, reply, Filter};
+
+/// Returns a path to a new cassette, leaked as contexts require a static one.
+fn cassette(name: &str) -> &'static str {
+    let path = env::temp_dir().join(format!("restest-{}-{}.json", name, std::process::id()));
+    let _ = fs::remove_file(&path);
+
+    Box::leak(path.to_string_lossy().into_owned().into_boxed_str())
+}
+
+fn login() -> Request<Value> {
+    Request::post("login")
+        .with_header("Authorization", "Basic secret-credentials")
+        .with_header("X-Api-Key", "secret-key")
+        .with_header("Accept-Language", "fr-FR")
+        .with_body(json!({
+            "user": "grace",
+            "password": "secret-password",
+            "client": { "id": 1, "client_secret": "secret-client" },
+        }))
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn requests_redacted_when_recording() {
+    let login_route = warp::path!("login")
+        .and(method::post())
+        .map(|| reply::json(&json!({ "id": 1 })));
+
+    let (address, server) = warp::serve(login_route).bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+
+    let recorded = cassette("redacted");
+    let context = Context::new()
+        .with_host("http://127.0.0.1")
+        .with_port(address.port())
+        .with_redacted_headers(&["x-api-key"])
+        .with_cassette(recorded);
+
+    context
+        .run(login())
+        .await
+        .expect_status::<Value>(StatusCode::OK)
+        .await;
+
+    let content = fs::read_to_string(recorded).unwrap();
+    assert!(!content.contains("secret-"), "{}", content);
+
+    let request = &serde_json::from_str::<Value>(&content).unwrap()["interactions"][0]["request"];
+    let headers = request["headers"].as_array().unwrap();
+    assert!(headers.contains(&json!(["authorization", "<redacted>"])));
+    assert!(headers.contains(&json!(["x-api-key", "<redacted>"])));
+    assert!(headers.contains(&json!(["accept-language", "fr-FR"])));
+
+    let body = serde_json::from_str::<Value>(request["body"].as_str().unwrap()).unwrap();
+    assert_eq!(body["user"], "grace");
+    assert_eq!(body["password"], "<redacted>");
+    assert_eq!(body["client"]["client_secret"], "<redacted>");
+
+    // The cassette is replayed for the same request, whose redacted fields
+    // are not compared.
+    let replayed = cassette("replayed");
+    fs::rename(recorded, replayed).unwrap();
+
+    let context = Context::new()
+        .with_host("http://127.0.0.1")
+        .with_port(address.port())
+        .with_cassette(replayed);
+    let user = context
+        .run(login())
+        .await
+        .expect_status::<Value>(StatusCode::OK)
+        .await;
+    fs::remove_file(replayed).unwrap();
+
+    assert_eq!(user, json!({ "id": 1 }));
+}
@@ -0,0 +1,6 @@
+fn main() {
+    restest::assert_body_matches!(
+        serde_json::json!({ "source": "cache" }),
+        { source: "cache", data, .. } | { source: "origin", value },
+    );
+}
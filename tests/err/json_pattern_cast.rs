@@ -0,0 +1,7 @@
+struct User {
+    id: u32,
+}
+
+fn main() {
+    restest::assert_body_matches!(User { id: 1 }, User { id: 1 as u32 });
+}
@@ -0,0 +1,7 @@
+use serde_json::json;
+
+fn main() {
+    let body = json!({ "initial": "G" });
+
+    restest::assert_body_matches!(body, { initial: 'G' });
+}
@@ -0,0 +1,11 @@
+use serde_json::json;
+
+struct User {
+    name: String,
+}
+
+fn main() {
+    let body = json!({ "user": { "name": "Grace" } });
+
+    restest::assert_body_matches!(body, { user: User { name } });
+}
@@ -0,0 +1,107 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+
+use http::StatusCode;
+use restest::{fixture::Fixture, Context, Request};
+use serde_json::Value;
+use warp::{filters::method, reply, Filter};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn fixture_teardown_does_not_reset() {
+    let resets = Arc::new(AtomicUsize::new(0));
+    let deletions = Arc::new(AtomicUsize::new(0));
+
+    let reset = {
+        let resets = resets.clone();
+        warp::path!("admin" / "reset")
+            .and(method::post())
+            .map(move || {
+                resets.fetch_add(1, Ordering::SeqCst);
+                reply::reply()
+            })
+    };
+    let post = warp::path!("users").and(method::post()).map(|| {
+        reply::with_status(
+            reply::json(&serde_json::json!({ "id": 1 })),
+            StatusCode::CREATED,
+        )
+    });
+    let delete = {
+        let deletions = deletions.clone();
+        warp::path!("users" / u32)
+            .and(method::delete())
+            .map(move |_| {
+                deletions.fetch_add(1, Ordering::SeqCst);
+                reply::reply()
+            })
+    };
+
+    let (address, server) =
+        warp::serve(reset.or(post).or(delete)).bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+
+    let context = Context::new()
+        .with_host("http://127.0.0.1")
+        .with_port(address.port())
+        .with_reset(|| Request::post("admin/reset"));
+
+    let user = Fixture::setup(
+        &context,
+        Request::post("users"),
+        StatusCode::CREATED,
+        |_: &Value| Request::delete("users/1"),
+    )
+    .await;
+
+    // The teardown request runs on another thread.
+    drop(user);
+
+    assert_eq!(deletions.load(Ordering::SeqCst), 1);
+    assert_eq!(resets.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn fixture_teardown_keeps_raw_body() {
+    let deleted = Arc::new(Mutex::new(Vec::new()));
+
+    let post = warp::path!("users").and(method::post()).map(|| {
+        reply::with_status(
+            reply::json(&serde_json::json!({ "id": 1 })),
+            StatusCode::CREATED,
+        )
+    });
+    let delete = {
+        let deleted = deleted.clone();
+        warp::path!("users" / u32)
+            .and(method::delete())
+            .and(warp::body::bytes())
+            .map(move |_, body: warp::hyper::body::Bytes| {
+                *deleted.lock().unwrap() = body.to_vec();
+                reply::reply()
+            })
+    };
+
+    let (address, server) = warp::serve(post.or(delete)).bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+
+    let context = Context::new()
+        .with_host("http://127.0.0.1")
+        .with_port(address.port());
+
+    let user = Fixture::setup(
+        &context,
+        Request::post("users"),
+        StatusCode::CREATED,
+        |_: &Value| Request::delete("users/1").with_raw_json(r#"{ "reason": "#),
+    )
+    .await;
+
+    user.teardown()
+        .await
+        .expect_status_code(StatusCode::OK)
+        .await;
+
+    assert_eq!(*deleted.lock().unwrap(), br#"{ "reason": "#);
+}
@@ -0,0 +1,49 @@
+use serde_json::json;
+
+#[derive(Clone)]
+enum Source {
+    Cache { age: u32, data: String },
+    Origin { server: String, data: String },
+}
+
+fn main() {
+    for body in [
+        json!({ "source": "cache", "age": 3, "data": "cached" }),
+        json!({ "source": "origin", "data": "fresh" }),
+    ] {
+        restest::assert_body_matches!(
+            body,
+            { source: "cache", data, .. } | { data, source: "origin" },
+        );
+        assert!(data == "cached" || data == "fresh");
+    }
+
+    let sources = [
+        Source::Cache {
+            age: 3,
+            data: "cached".to_string(),
+        },
+        Source::Origin {
+            server: "eu-west".to_string(),
+            data: "fresh".to_string(),
+        },
+    ];
+
+    for source in sources {
+        restest::assert_body_matches!(
+            source,
+            Source::Cache { age: 0..=10, data } | Source::Origin { server: "eu-west", data },
+        );
+        assert!(data == "cached" || data == "fresh");
+    }
+
+    let body = json!({ "source": "unknown", "data": "lost" });
+    let matches = std::panic::catch_unwind(|| {
+        restest::assert_body_matches!(body, { source: "cache", data, .. } | { source: "origin", data });
+        data
+    });
+    assert!(matches.is_err());
+
+    let body = json!({ "source": "unknown" });
+    assert!(restest::body_matches!(body, { source: "cache", .. } | { source: "origin", .. }).is_err());
+}
@@ -21,4 +21,19 @@ fn main() {
     };
 
     assert_eq!(a, 101);
+
+    restest::assert_body_matches! {
+        vec![42, 101, 102],
+        [42, rest @ ..],
+    };
+
+    assert_eq!(rest, [101, 102]);
+
+    restest::assert_body_matches! {
+        vec![[1, 2], [3, 4]],
+        [[_, tail @ ..], others @ ..],
+    };
+
+    assert_eq!(tail, [2]);
+    assert_eq!(others, [[3, 4]]);
 }
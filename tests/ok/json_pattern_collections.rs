@@ -0,0 +1,45 @@
+use std::collections::VecDeque;
+
+struct Roles {
+    names: [&'static str; 2],
+    ids: VecDeque<u32>,
+    tags: Box<[u32]>,
+}
+
+fn main() {
+    restest::assert_body_matches!([1, 2, 3], [1, a, ..]);
+
+    assert_eq!(a, 2);
+
+    let ids: &[u32] = &[42, 101];
+    restest::assert_body_matches!(ids, [42, b]);
+
+    assert_eq!(b, 101);
+
+    // The deque wraps around its buffer.
+    let mut ids = VecDeque::with_capacity(4);
+    ids.extend([0, 0, 1, 2]);
+    ids.drain(..2);
+    ids.extend([3, 4]);
+
+    restest::assert_body_matches!(ids, [1, 2, c, 4]);
+
+    assert_eq!(c, 3);
+
+    let roles = Roles {
+        names: ["admin", "dev"],
+        ids: VecDeque::from(vec![7]),
+        tags: vec![1, 2].into_boxed_slice(),
+    };
+
+    restest::assert_body_matches! {
+        roles,
+        Roles {
+            names: ["admin", _],
+            ids: [d],
+            tags: [1, ..],
+        },
+    };
+
+    assert_eq!(d, 7);
+}
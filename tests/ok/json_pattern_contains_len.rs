@@ -0,0 +1,69 @@
+use serde_json::{json, Value};
+
+#[derive(Clone)]
+struct User {
+    id: u32,
+    name: String,
+}
+
+struct Listing {
+    users: Vec<User>,
+    tags: Vec<String>,
+}
+
+fn main() {
+    let listing = Listing {
+        users: (0..100)
+            .map(|id| User {
+                id,
+                name: format!("user {}", id),
+            })
+            .collect(),
+        tags: vec!["new".to_string(), "popular".to_string()],
+    };
+
+    restest::assert_body_matches! {
+        listing,
+        Listing {
+            users: contains![
+                User { id: 42, .. },
+                User { name: "user 7", .. },
+            ],
+            tags: tags @ len!(2),
+        }
+    };
+
+    assert_eq!(tags, ["new", "popular"]);
+
+    let user = User {
+        id: 1,
+        name: "Grace Hopper".to_string(),
+    };
+
+    restest::assert_body_matches!(user, User { name: contains!("Hopper"), .. });
+
+    restest::assert_body_matches!(vec![1, 2, 3], len!(1..=3));
+
+    // Each pattern needs its own element.
+    let matches = std::panic::catch_unwind(|| {
+        restest::assert_body_matches!(vec![42], contains![42, 42]);
+    });
+    assert!(matches.is_err());
+
+    let matches = std::panic::catch_unwind(|| {
+        restest::assert_body_matches!(vec![1, 2, 3], len!(2));
+    });
+    assert!(matches.is_err());
+
+    let body = json!({ "items": [{ "id": 1 }, { "id": 2 }, { "id": 3 }] });
+    restest::assert_body_matches! {
+        body,
+        Value::Object(map! {
+            "items" => Value::Array(
+                items @ contains![Value::Object(map! { "id" => Value::Number(_) })]
+            ),
+        })
+    };
+
+    restest::assert_body_not_matches!(items, len!(0));
+}
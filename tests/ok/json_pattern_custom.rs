@@ -0,0 +1,47 @@
+use restest::pattern::{MatchError, Matcher};
+use serde_json::Value;
+
+struct Prefix(&'static str);
+
+impl Matcher for Prefix {
+    fn matches(&self, value: &Value) -> Result<(), MatchError> {
+        match value.as_str() {
+            Some(value) if value.starts_with(self.0) => Ok(()),
+            _ => Err(MatchError::with_reason(format!(
+                "{} does not start with {}",
+                value, self.0
+            ))),
+        }
+    }
+}
+
+struct Account {
+    iban: String,
+    balance: f64,
+}
+
+fn main() {
+    let positive = |value: &Value| match value.as_f64() {
+        Some(value) if value >= 0.0 => Ok(()),
+        _ => Err(MatchError::with_reason("negative")),
+    };
+
+    restest::assert_body_matches! {
+        Account {
+            iban: "FR7630006000011234567890189".to_string(),
+            balance: 12.5,
+        },
+        Account {
+            iban: iban @ custom!(Prefix("FR")),
+            balance: custom!(positive),
+        }
+    };
+
+    assert_eq!(iban, "FR7630006000011234567890189");
+
+    let account = Account {
+        iban: "DE89370400440532013000".to_string(),
+        balance: 12.5,
+    };
+    assert!(restest::body_matches!(account, Account { iban: custom!(Prefix("FR")), .. }).is_err());
+}
@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+struct Event {
+    created_at: String,
+    updated_at: String,
+}
+
+fn rfc3339(datetime: OffsetDateTime) -> String {
+    datetime.format(&Rfc3339).unwrap()
+}
+
+fn now_rfc3339() -> String {
+    rfc3339(OffsetDateTime::now_utc())
+}
+
+fn main() {
+    restest::assert_body_matches! {
+        Event {
+            created_at: "2022-07-14T12:00:00.123+02:00".to_string(),
+            updated_at: now_rfc3339(),
+        },
+        Event {
+            created_at: iso8601!(),
+            updated_at: within_last!(1 min),
+        }
+    };
+
+    restest::assert_body_matches! {
+        Event {
+            created_at: now_rfc3339(),
+            updated_at: "1970-01-01T00:00:00Z".to_string(),
+        },
+        Event {
+            created_at: within_last!(Duration::from_secs(60)),
+            updated_at,
+        }
+    };
+
+    assert_eq!(updated_at, "1970-01-01T00:00:00Z");
+
+    let recent = |updated_at: String| {
+        let event = Event {
+            created_at: now_rfc3339(),
+            updated_at,
+        };
+
+        restest::body_matches!(
+            event,
+            Event {
+                updated_at: within_last!(1 min),
+                ..
+            }
+        )
+    };
+
+    let now = OffsetDateTime::now_utc();
+    assert!(recent(rfc3339(now - time::Duration::seconds(10))).is_ok());
+
+    // Stale, future and unparsable timestamps are rejected.
+    assert!(recent(rfc3339(now - time::Duration::minutes(5))).is_err());
+    assert!(recent(rfc3339(now + time::Duration::minutes(5))).is_err());
+    assert!(recent("yesterday".to_string()).is_err());
+    assert!(restest::body_matches!("2022-13-01T00:00:00Z".to_string(), iso8601!()).is_err());
+}
@@ -0,0 +1,41 @@
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+#[derive(Deserialize)]
+struct User {
+    name: String,
+    age: u8,
+}
+
+fn main() {
+    restest::assert_body_matches! {
+        json!({
+            "status": "ok",
+            "data": { "name": "Grace Hopper", "age": 85 },
+            "meta": { "page": 1 },
+        }),
+        Value::Object(map! {
+            "status" => "ok",
+            "data" => user @ deserialize!(User),
+            "meta" => meta,
+        })
+    };
+
+    assert_eq!(user.name, "Grace Hopper");
+    assert_eq!(user.age, 85);
+    assert_eq!(meta, json!({ "page": 1 }));
+
+    restest::assert_body_matches! {
+        json!([{ "name": "Ada Lovelace", "age": 36 }]),
+        Value::Array(users @ deserialize!(Vec<User>))
+    };
+
+    assert_eq!(users[0].name, "Ada Lovelace");
+
+    let body = json!({ "data": { "name": "Alan Turing" } });
+
+    let matches = std::panic::catch_unwind(|| {
+        restest::assert_body_matches!(body, Value::Object(map! { "data" => deserialize!(User) }));
+    });
+    assert!(matches.is_err());
+}
@@ -0,0 +1,34 @@
+use std::collections::HashMap;
+
+use serde_json::{json, Value};
+
+fn main() {
+    let body = json!({
+        "id": 1,
+        "name": "Grace Hopper",
+        "meta": { "page": 1 },
+    });
+
+    restest::assert_body_matches! {
+        body,
+        Value::Object(map! {
+            "id" => _,
+            "meta" => only_keys!["page", "next"],
+            ..
+        })
+    };
+
+    let body = json!({ "id": 1, "name": "Grace Hopper" });
+    restest::assert_body_matches!(body, keys!["name", "id"]);
+
+    let scores = HashMap::from([("alice".to_string(), 42)]);
+    restest::assert_body_matches!(scores, only_keys!["alice", "bob"]);
+
+    let body = json!({ "id": 1, "name": "Grace Hopper", "password": "hunter2" });
+    restest::assert_body_not_matches!(body.clone(), keys!["id", "name"]);
+    restest::assert_body_not_matches!(body, only_keys!["id", "name"]);
+
+    let body = json!({ "id": 1 });
+    restest::assert_body_not_matches!(body, keys!["id", "name"]);
+    restest::assert_body_not_matches!(json!([1]), only_keys!["id"]);
+}
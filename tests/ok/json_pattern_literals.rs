@@ -0,0 +1,45 @@
+use serde_json::json;
+
+struct Reading {
+    value: f64,
+    offset: f32,
+    valid: bool,
+    grade: char,
+    raw: Vec<u8>,
+}
+
+fn reading() -> Reading {
+    Reading {
+        value: 1.5,
+        offset: -0.25,
+        valid: true,
+        grade: 'A',
+        raw: b"ok".to_vec(),
+    }
+}
+
+fn main() {
+    restest::assert_body_matches! {
+        reading(),
+        Reading {
+            value: 1.5,
+            offset: -0.25,
+            valid: true,
+            grade: 'A',
+            raw: b"ok",
+        },
+    };
+
+    assert!(restest::body_matches!(reading(), Reading { value: 2.5, .. }).is_err());
+    assert!(restest::body_matches!(reading(), Reading { raw: b"ko", .. }).is_err());
+
+    restest::assert_body_matches! {
+        json!({ "ratio": 0.5, "active": false, "name": "sensor" }),
+        { ratio: 0.5, active: false, name: "sensor" },
+    };
+
+    assert!(restest::body_matches!(json!({ "active": true }), { active: false }).is_err());
+
+    restest::assert_body_matches!(vec![1.5, 2.5], [1.5, second]);
+    assert_eq!(second, 2.5);
+}
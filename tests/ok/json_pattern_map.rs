@@ -0,0 +1,49 @@
+use std::collections::{BTreeMap, HashMap};
+
+struct Leaderboard {
+    scores: HashMap<String, u32>,
+    teams: BTreeMap<u32, Vec<u32>>,
+}
+
+fn main() {
+    let leaderboard = Leaderboard {
+        scores: HashMap::from([("alice".to_string(), 42), ("bob".to_string(), 7)]),
+        teams: BTreeMap::from([(1, vec![10, 11])]),
+    };
+
+    restest::assert_body_matches! {
+        leaderboard,
+        Leaderboard {
+            scores: map! {
+                "alice" => 42,
+                "bob" => bob_score @ gt!(0),
+            },
+            teams: map! { 1 => [_, member], .. },
+        }
+    };
+
+    assert_eq!(bob_score, 7);
+    assert_eq!(member, 11);
+
+    let scores = HashMap::from([("alice".to_string(), 42), ("bob".to_string(), 7)]);
+    restest::assert_body_matches!(scores, map! { "alice" => alice, .. });
+    assert_eq!(alice, 42);
+
+    let nested = HashMap::from([(
+        "users".to_string(),
+        HashMap::from([("ghopper".to_string(), "Grace Hopper".to_string())]),
+    )]);
+    restest::assert_body_matches!(nested, map! { "users" => map! { "ghopper" => "Grace Hopper" } });
+
+    let scores = HashMap::from([("alice".to_string(), 42), ("bob".to_string(), 7)]);
+    let matches = std::panic::catch_unwind(|| {
+        restest::assert_body_matches!(scores, map! { "alice" => _ });
+    });
+    assert!(matches.is_err());
+
+    let scores = HashMap::from([("alice".to_string(), 42)]);
+    let matches = std::panic::catch_unwind(|| {
+        restest::assert_body_matches!(scores, map! { "bob" => _, .. });
+    });
+    assert!(matches.is_err());
+}
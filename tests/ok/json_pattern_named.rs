@@ -0,0 +1,58 @@
+use serde_json::json;
+
+#[derive(Clone)]
+struct User {
+    id: String,
+    name: String,
+    age: u8,
+}
+
+struct Team {
+    owner: User,
+    deputy: User,
+}
+
+restest::pattern! {
+    USER_SHAPE = User { id: uuid!(), name: _, age: 18..=99 };
+    ADMIN = User { name: "admin", .. };
+}
+
+mod shapes {
+    restest::pattern! {
+        EVENT = serde_json::Value::Object(map! { "kind" => "created", .. })
+    }
+}
+
+fn user(name: &str, age: u8) -> User {
+    User {
+        id: "67e55044-10b1-426f-9247-bb680e5fe0c8".to_string(),
+        name: name.to_string(),
+        age,
+    }
+}
+
+fn main() {
+    restest::assert_body_matches! {
+        Team {
+            owner: user("admin", 42),
+            deputy: user("ghopper", 85),
+        },
+        Team {
+            owner: owner @ pattern!(ADMIN),
+            deputy: pattern!(USER_SHAPE),
+        }
+    };
+
+    assert_eq!(owner.age, 42);
+
+    let team = Team {
+        owner: user("admin", 42),
+        deputy: user("minor", 12),
+    };
+    assert!(restest::body_matches!(team, Team { deputy: pattern!(USER_SHAPE), .. }).is_err());
+
+    restest::assert_body_matches! {
+        json!({ "kind": "created", "id": 1 }),
+        pattern!(shapes::EVENT),
+    };
+}
@@ -0,0 +1,25 @@
+use serde_json::{json, Value};
+
+struct User {
+    name: String,
+    role: String,
+}
+
+fn main() {
+    restest::assert_body_not_matches!(
+        User {
+            name: "Grace Hopper".to_string(),
+            role: "user".to_string(),
+        },
+        User { role: "admin", .. }
+    );
+
+    let body = json!({ "name": "Grace Hopper", "email": "ghopper@example.com" });
+    restest::assert_body_not_matches!(body, Value::Object(map! { "password" => _, .. }));
+
+    let matches = std::panic::catch_unwind(|| {
+        let body = json!({ "name": "Grace Hopper", "password": "hunter2" });
+        restest::assert_body_not_matches!(body, Value::Object(map! { "password" => _, .. }));
+    });
+    assert!(matches.is_err());
+}
@@ -0,0 +1,43 @@
+struct Account {
+    balance: f64,
+    owner: String,
+    tags: Vec<String>,
+}
+
+fn is_capitalized(value: &String) -> bool {
+    value.chars().next().is_some_and(char::is_uppercase)
+}
+
+fn main() {
+    restest::assert_body_matches! {
+        Account {
+            balance: 12.5,
+            owner: "Grace".to_string(),
+            tags: vec!["admin".to_string()],
+        },
+        Account {
+            balance: balance @ satisfies!(|b: &f64| *b >= 0.0),
+            owner: satisfies!(is_capitalized),
+            tags: satisfies!(|tags: &Vec<String>| tags.iter().any(|tag| tag == "admin")),
+        }
+    };
+
+    assert_eq!(balance, 12.5);
+
+    let account = Account {
+        balance: -1.0,
+        owner: "Grace".to_string(),
+        tags: Vec::new(),
+    };
+
+    let matches = std::panic::catch_unwind(|| {
+        restest::assert_body_matches!(
+            account,
+            Account {
+                balance: satisfies!(|b: &f64| *b >= 0.0),
+                ..
+            }
+        );
+    });
+    assert!(matches.is_err());
+}
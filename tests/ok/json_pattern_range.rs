@@ -0,0 +1,69 @@
+struct Measure {
+    count: u32,
+    ratio: f64,
+    age: u8,
+}
+
+const MAX_AGE: u8 = 99;
+
+fn main() {
+    restest::assert_body_matches! {
+        Measure {
+            count: 12,
+            ratio: 0.42,
+            age: 42,
+        },
+        Measure {
+            count: gt!(0),
+            ratio: 0.0..1.0,
+            age: 18..=MAX_AGE,
+        }
+    };
+
+    restest::assert_body_matches! {
+        vec![1.5, 2.5],
+        [first, le!(2.5)],
+    };
+
+    assert_eq!(first, 1.5);
+
+    restest::assert_body_matches! {
+        Measure {
+            count: 12,
+            ratio: 0.42,
+            age: 42,
+        },
+        Measure {
+            count: ge!(12),
+            ratio: lt!(1.0),
+            age,
+        }
+    };
+
+    assert_eq!(age, 42);
+
+    let measure = |age| Measure {
+        count: 12,
+        ratio: 0.42,
+        age,
+    };
+    let adult = |age| restest::body_matches!(measure(age), Measure { age: 18..=99, .. });
+    let minor = |age| restest::body_matches!(measure(age), Measure { age: 0..18, .. });
+
+    // Both bounds of an inclusive range are accepted, values beyond them are
+    // not.
+    assert!(adult(18).is_ok());
+    assert!(adult(99).is_ok());
+    assert!(adult(17).is_err());
+    assert!(adult(100).is_err());
+
+    // The end of an exclusive range is not accepted.
+    assert!(minor(17).is_ok());
+    assert!(minor(18).is_err());
+    assert!(restest::body_matches!(vec![1.0], [0.0..1.0]).is_err());
+
+    let panicked = std::panic::catch_unwind(|| {
+        restest::assert_body_matches!(measure(100), Measure { age: 18..=99, .. });
+    });
+    assert!(panicked.is_err());
+}
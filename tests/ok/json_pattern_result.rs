@@ -0,0 +1,36 @@
+struct User {
+    name: String,
+    age: u8,
+    tags: Vec<u32>,
+}
+
+fn user() -> User {
+    User {
+        name: "Grace Hopper".to_string(),
+        age: 85,
+        tags: vec![1, 2],
+    }
+}
+
+fn main() {
+    let (age, tag) = restest::body_matches!(
+        user(),
+        User {
+            name: "Grace Hopper",
+            age,
+            tags: [_, tag],
+        }
+    )
+    .unwrap();
+
+    assert_eq!(age, 85);
+    assert_eq!(tag, 2);
+
+    let err = restest::body_matches!(user(), User { age: gt!(100), .. }).unwrap_err();
+    assert!(err.to_string().contains("age"));
+
+    let err = restest::body_matches!(user(), User { tags: [_], .. }).unwrap_err();
+    assert!(err.pattern().unwrap().contains("tags"));
+
+    assert!(restest::body_matches!(user(), User { name: "Grace Hopper", .. }).is_ok());
+}
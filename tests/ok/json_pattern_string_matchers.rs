@@ -0,0 +1,48 @@
+struct Response {
+    status: String,
+    message: String,
+    token: String,
+}
+
+const EXTENSION: &str = ".json";
+
+fn main() {
+    restest::assert_body_matches! {
+        Response {
+            status: "OK".to_string(),
+            message: "Internal error: database is down".to_string(),
+            token: "Bearer abcd".to_string(),
+        },
+        Response {
+            status: eq_ignore_case!("ok"),
+            message: message @ contains!("error"),
+            token: starts_with!("Bearer "),
+        }
+    };
+
+    assert_eq!(message, "Internal error: database is down");
+
+    restest::assert_body_matches! {
+        Response {
+            status: "Ünïcödé".to_string(),
+            message: "users.json".to_string(),
+            token: String::new(),
+        },
+        Response {
+            status: eq_ignore_case!("üNÏCÖDÉ"),
+            message: ends_with!(EXTENSION),
+            ..
+        }
+    };
+
+    let response = Response {
+        status: "KO".to_string(),
+        message: String::new(),
+        token: String::new(),
+    };
+
+    let matches = std::panic::catch_unwind(|| {
+        restest::assert_body_matches!(response, Response { status: eq_ignore_case!("ok"), .. });
+    });
+    assert!(matches.is_err());
+}
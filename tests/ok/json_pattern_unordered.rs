@@ -0,0 +1,66 @@
+use serde_json::{json, Value};
+
+#[derive(Clone)]
+struct User {
+    name: String,
+    roles: Vec<String>,
+}
+
+fn main() {
+    restest::assert_body_matches!(vec![3, 1, 2], unordered![1, 2, 3]);
+
+    let user = User {
+        name: "Grace Hopper".to_string(),
+        roles: vec!["admin".to_string(), "dev".to_string()],
+    };
+
+    restest::assert_body_matches! {
+        user,
+        User {
+            name,
+            roles: unordered!["dev", "admin"],
+        }
+    };
+
+    assert_eq!(name, "Grace Hopper");
+
+    // `_` accepts both elements, but must leave `42` to the other pattern.
+    restest::assert_body_matches!(vec![42, 7], unordered![_, 42]);
+
+    let users = vec![
+        User {
+            name: "Ada Lovelace".to_string(),
+            roles: Vec::new(),
+        },
+        User {
+            name: "Grace Hopper".to_string(),
+            roles: vec!["admin".to_string()],
+        },
+    ];
+
+    restest::assert_body_matches! {
+        users,
+        unordered![
+            User { name: "Grace Hopper", roles: [_], },
+            User { name: starts_with!("Ada"), .. },
+        ]
+    };
+
+    let body = json!({ "tags": ["b", "a"] });
+    restest::assert_body_matches! {
+        body,
+        Value::Object(map! {
+            "tags" => Value::Array(unordered![Value::String(_), Value::String(_)]),
+        })
+    };
+
+    let matches = std::panic::catch_unwind(|| {
+        restest::assert_body_matches!(vec![1, 1], unordered![1, 2]);
+    });
+    assert!(matches.is_err());
+
+    let matches = std::panic::catch_unwind(|| {
+        restest::assert_body_matches!(vec![1, 2, 3], unordered![1, 2]);
+    });
+    assert!(matches.is_err());
+}
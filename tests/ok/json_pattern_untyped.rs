@@ -0,0 +1,38 @@
+use serde_json::json;
+
+#[derive(Clone)]
+struct Payment {
+    method: String,
+    amount: u32,
+}
+
+fn main() {
+    let card = json!({ "type": "card", "details": { "last4": "4242", "brand": "visa" } });
+    let iban = json!({ "type": "iban", "iban": "FR7630006000011234567890189" });
+
+    for body in [card.clone(), iban] {
+        restest::assert_body_matches!(
+            body,
+            { type: "card", details: { last4: _, .. } } | { type: "iban", .. },
+        );
+    }
+
+    restest::assert_body_matches!(card, { type: "card", "details": { last4, .. } });
+    assert_eq!(last4, "4242");
+
+    let cash = json!({ "type": "cash" });
+    assert!(restest::body_matches!(cash, { type: "card", .. } | { type: "iban", .. }).is_err());
+
+    let payment = Payment {
+        method: "iban".to_string(),
+        amount: 3,
+    };
+    restest::assert_body_matches!(payment, Payment { method: "card" | "iban", amount });
+    assert_eq!(amount, 3);
+
+    let payment = Payment {
+        method: "cash".to_string(),
+        amount: 3,
+    };
+    assert!(restest::body_matches!(payment, Payment { method: "card" | "iban", .. }).is_err());
+}
@@ -0,0 +1,73 @@
+use serde_json::{json, Value};
+
+fn main() {
+    let body = json!({
+        "id": 42,
+        "delta": -3,
+        "ratio": 0.5,
+        "user_id": "67e55044-10b1-426f-9247-bb680e5fe0c8",
+        "created_at": "2021-10-04T13:37:00Z",
+        "message": "internal error",
+        "tags": ["admin", "ops"],
+        "items": [{ "id": 1, "name": "first" }, { "id": 2, "name": "second" }],
+    });
+
+    // Integer literals and ranges.
+    restest::assert_body_matches!(
+        body.clone(),
+        { id: 42, delta: -10..=-1, ratio: 0.0..1.0, .. },
+    );
+
+    // Comparisons and string matchers.
+    restest::assert_body_matches!(
+        body.clone(),
+        { id: gt!(0), delta: le!(0), message: contains!("error"), .. },
+    );
+
+    // UUIDs and datetimes.
+    restest::assert_body_matches!(
+        body.clone(),
+        { user_id: user_id @ uuid!(4), created_at: iso8601!(), .. },
+    );
+    assert_eq!(user_id.get_version_num(), 4);
+
+    // Slice patterns and element matchers.
+    restest::assert_body_matches!(
+        body.clone(),
+        {
+            tags: ["admin", second],
+            items: [{ id: 1, name }, rest @ ..],
+            ..
+        },
+    );
+    assert_eq!(second, "ops");
+    assert_eq!(name, "first");
+    assert_eq!(rest, vec![json!({ "id": 2, "name": "second" })]);
+
+    restest::assert_body_matches!(
+        body.clone(),
+        { tags: contains!["ops"], items: len!(2), message: len!(1..=20), .. },
+    );
+    restest::assert_body_matches!(body.clone(), { tags: unordered!["ops", "admin"], .. });
+
+    // The same patterns on a map.
+    restest::assert_body_matches!(
+        body.clone(),
+        Value::Object(map! { "id" => 40..50, "tags" => [_, _], .. }),
+    );
+
+    // Values of the wrong type, or out of bounds, don't match.
+    assert!(restest::body_matches!(body.clone(), { id: 41, .. }).is_err());
+    assert!(restest::body_matches!(body.clone(), { message: 0..10, .. }).is_err());
+    assert!(restest::body_matches!(body.clone(), { id: 43..=100, .. }).is_err());
+    assert!(restest::body_matches!(body.clone(), { id: gt!(42), .. }).is_err());
+    assert!(restest::body_matches!(body.clone(), { id: contains!("4"), .. }).is_err());
+    assert!(restest::body_matches!(body.clone(), { message: uuid!(), .. }).is_err());
+    assert!(restest::body_matches!(body.clone(), { id: iso8601!(), .. }).is_err());
+    assert!(restest::body_matches!(body.clone(), { created_at: within_last!(1 min), .. }).is_err());
+    assert!(restest::body_matches!(body.clone(), { id: [_, ..], .. }).is_err());
+    assert!(restest::body_matches!(body.clone(), { tags: ["admin"], .. }).is_err());
+    assert!(restest::body_matches!(body, { id: len!(0..), .. }).is_err());
+
+    assert!(restest::body_matches!(json!(42), 41 | 42).is_ok());
+}
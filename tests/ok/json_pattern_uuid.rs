@@ -0,0 +1,40 @@
+struct User {
+    id: String,
+    group_id: String,
+}
+
+fn main() {
+    restest::assert_body_matches! {
+        User {
+            id: "67e55044-10b1-426f-9247-bb680e5fe0c8".to_string(),
+            group_id: "6ba7b810-9dad-11d1-80b4-00c04fd430c8".to_string(),
+        },
+        User {
+            id: id @ uuid!(4),
+            group_id: uuid!(),
+        }
+    };
+
+    assert_eq!(id.to_string(), "67e55044-10b1-426f-9247-bb680e5fe0c8");
+    assert_eq!(id.get_version_num(), 4);
+
+    let user = User {
+        id: "not-a-uuid".to_string(),
+        group_id: "6ba7b810-9dad-11d1-80b4-00c04fd430c8".to_string(),
+    };
+
+    let matches = std::panic::catch_unwind(|| {
+        restest::assert_body_matches!(user, User { id: uuid!(), .. });
+    });
+    assert!(matches.is_err());
+
+    let user = User {
+        id: "6ba7b810-9dad-11d1-80b4-00c04fd430c8".to_string(),
+        group_id: "6ba7b810-9dad-11d1-80b4-00c04fd430c8".to_string(),
+    };
+
+    let matches = std::panic::catch_unwind(|| {
+        restest::assert_body_matches!(user, User { id: uuid!(4), .. });
+    });
+    assert!(matches.is_err());
+}
@@ -0,0 +1,160 @@
+//! Runs `#[restest::test]` functions, and checks how the test harness sees
+//! them.
+
+use std::{env, process::Command};
+
+/// Prepares a new process running the ignored tests of this file whose name
+/// contains `filter`.
+fn ignored_tests(filter: &str) -> Command {
+    let mut command = Command::new(env::current_exe().unwrap());
+    // The report of this binary must not be overwritten by the new process.
+    command
+        .args(["--ignored", filter])
+        .env_remove("RESTEST_REPORT");
+    command
+}
+
+mod dependencies {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    static USER_POSTED: AtomicBool = AtomicBool::new(false);
+
+    #[restest::test]
+    async fn post_user() {
+        USER_POSTED.store(true, Ordering::SeqCst);
+    }
+
+    #[restest::test(depends_on = "post_user")]
+    async fn get_user() {
+        assert!(USER_POSTED.load(Ordering::SeqCst));
+    }
+
+    #[restest::test]
+    #[should_panic]
+    async fn failing_dependency() {
+        panic!("The dependency failed");
+    }
+
+    #[restest::test(depends_on = "failing_dependency")]
+    async fn skipped_dependent() {
+        panic!("A test whose dependency failed must be skipped");
+    }
+
+    // Neither test can run first: both fail, instead of waiting for each
+    // other forever.
+    #[restest::test(depends_on = "second_in_cycle")]
+    #[should_panic]
+    async fn first_in_cycle() {}
+
+    #[restest::test(depends_on = "first_in_cycle")]
+    #[should_panic]
+    async fn second_in_cycle() {}
+}
+
+mod environment {
+    use std::process::Output;
+
+    use http::StatusCode;
+    use restest::{
+        context::{HOST_ENV_VAR, PORT_ENV_VAR},
+        Context, Request,
+    };
+    use serde_json::Value;
+    use warp::{reply, Filter};
+
+    // The following tests are run in a separate process by the ones below,
+    // as the context given to them is read from the environment once per
+    // process.
+
+    #[restest::test]
+    #[ignore = "run by `context_from_env`"]
+    async fn get_ok(context: Context) {
+        context
+            .run(Request::get("ok"))
+            .await
+            .expect_status::<Value>(StatusCode::OK)
+            .await;
+    }
+
+    #[restest::test]
+    #[ignore = "run by `failure_reported_to_harness`"]
+    async fn get_error(context: Context) {
+        context
+            .run(Request::get("error"))
+            .await
+            .expect_status::<Value>(StatusCode::OK)
+            .await;
+    }
+
+    /// Starts a server answering `GET /ok` with `200 OK`, and `GET /error`
+    /// with `500 Internal Server Error`. Returns its port.
+    fn serve() -> u16 {
+        let ok = warp::path!("ok").map(|| reply::json(&"ok"));
+        let error = warp::path!("error")
+            .map(|| reply::with_status(reply::json(&"error"), StatusCode::INTERNAL_SERVER_ERROR));
+
+        let (address, server) =
+            warp::serve(warp::get().and(ok.or(error))).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        address.port()
+    }
+
+    /// Runs an ignored test of this file in a new process, with a context
+    /// pointing to `port`.
+    fn run_ignored(test: &str, port: u16) -> Output {
+        super::ignored_tests(test)
+            .arg("--exact")
+            .env(HOST_ENV_VAR, "http://127.0.0.1")
+            .env(PORT_ENV_VAR, port.to_string())
+            .output()
+            .unwrap()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn context_from_env() {
+        let output = run_ignored("environment::get_ok", serve());
+        let stdout = String::from_utf8_lossy(&output.stdout);
+
+        assert!(output.status.success(), "{}", stdout);
+        assert!(stdout.contains("test environment::get_ok ... ok"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn failure_reported_to_harness() {
+        let output = run_ignored("environment::get_error", serve());
+        let stdout = String::from_utf8_lossy(&output.stdout);
+
+        assert!(!output.status.success(), "{}", stdout);
+        assert!(stdout.contains("test environment::get_error ... FAILED"));
+        assert!(stdout.contains("(test get_error)"), "{}", stdout);
+        assert!(stdout.contains("500 Internal Server Error"), "{}", stdout);
+    }
+}
+
+mod cases {
+    restest::cases! {
+        #[ignore = "run by `one_test_per_case`"]
+        async fn uppercase(input: &'static str, expected: &'static str) {
+            assert_eq!(input.to_uppercase(), expected);
+        }
+
+        ("grace", "GRACE"),
+        wrong: ("ada", "ada"),
+        turing: ("alan", "ALAN"),
+    }
+
+    #[test]
+    fn one_test_per_case() {
+        let output = super::ignored_tests("cases::uppercase::").output().unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+
+        // The failing case is reported on its own, and the other cases still
+        // run.
+        assert!(!output.status.success(), "{}", stdout);
+        assert!(stdout.contains("test cases::uppercase::grace_grace ... ok"));
+        assert!(stdout.contains("test cases::uppercase::wrong ... FAILED"));
+        assert!(stdout.contains("test cases::uppercase::turing ... ok"));
+        assert!(stdout.contains("2 passed; 1 failed"), "{}", stdout);
+    }
+}